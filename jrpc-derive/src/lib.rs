@@ -0,0 +1,220 @@
+//! Derive macros for [`jrpc`](https://docs.rs/jrpc).
+//!
+//! Use them through the `derive` feature of jrpc, which re-exports them.
+
+#![allow(unknown_lints)]
+#![allow(clippy::redundant_field_names)]
+#![warn(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, Lit};
+
+/// Derive `Serialize`, `Deserialize` and `jrpc::params::DualParams` for a `params` struct
+/// accepting both positional (Array, in field order) and named (Object) params.
+///
+/// It is serialized by name, or by position with the container attribute
+/// `#[params(positional)]`. Either way, `DualParams` builds both. Field attributes:
+///
+/// - `#[params(rename = "name")]`: the member name.
+/// - `#[params(default)]`: use `Default::default()` if the field is missing.
+/// - `#[params(default = "path")]`: call `path()` if the field is missing.
+#[proc_macro_derive(Params, attributes(params))]
+pub fn derive_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Field {
+    ident: syn::Ident,
+    name: String,
+    default: Option<TokenStream2>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => return Err(not_supported(input)),
+        },
+        _ => return Err(not_supported(input)),
+    };
+    let fields = fields
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let positional = parse_container(input)?;
+
+    let ident = &input.ident;
+    let len = fields.len();
+
+    let mut de_generics = input.generics.clone();
+    let mut ser_generics = input.generics.clone();
+    for param in input.generics.type_params() {
+        let param = &param.ident;
+        de_generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote!(#param: ::jrpc::derive::DeserializeOwned));
+        ser_generics
+            .make_where_clause()
+            .predicates
+            .push(syn::parse_quote!(#param: ::jrpc::derive::Ser));
+    }
+    de_generics.params.insert(0, syn::parse_quote!('de));
+    let (de_impl, _, de_where) = de_generics.split_for_impl();
+    let (ser_impl, _, ser_where) = ser_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let take = fields.iter().enumerate().map(|(index, field)| {
+        let ident = &field.ident;
+        let name = &field.name;
+        let missing = match field.default {
+            Some(ref default) => quote!(#default),
+            None => quote!(::jrpc::derive::missing(#name).map_err(__D::Error::custom)?),
+        };
+        quote! {
+            #ident: match __fields.take(#index, #name).map_err(__D::Error::custom)? {
+                ::std::option::Option::Some(value) => value,
+                ::std::option::Option::None => #missing,
+            }
+        }
+    });
+    let insert = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let name = &field.name;
+        quote! {
+            __object.insert(#name, &self.#ident)?;
+        }
+    });
+    let push = fields.iter().map(|field| {
+        let ident = &field.ident;
+        quote! {
+            __array.push(&self.#ident)?;
+        }
+    });
+    let to_params = if positional {
+        quote!(to_positional)
+    } else {
+        quote!(to_named)
+    };
+
+    Ok(quote! {
+        impl #de_impl ::jrpc::derive::Deserialize<'de> for #ident #ty_generics #de_where {
+            fn deserialize<__D>(deserializer: __D) -> ::std::result::Result<Self, __D::Error>
+            where
+                __D: ::jrpc::derive::Deserializer<'de>,
+            {
+                use ::jrpc::derive::DeError;
+                let value = <::jrpc::Value as ::jrpc::derive::Deserialize>::deserialize(deserializer)?;
+                let mut __fields = ::jrpc::derive::Fields::new(value, #len).map_err(__D::Error::custom)?;
+                ::std::result::Result::Ok(#ident {
+                    #(#take,)*
+                })
+            }
+        }
+
+        impl #ser_impl ::jrpc::derive::Ser for #ident #ty_generics #ser_where {
+            fn serialize<__S>(&self, serializer: __S) -> ::std::result::Result<__S::Ok, __S::Error>
+            where
+                __S: ::jrpc::derive::Serializer,
+            {
+                use ::jrpc::derive::SerError;
+                let params = ::jrpc::derive::DualParams::#to_params(self).map_err(__S::Error::custom)?;
+                ::jrpc::derive::Ser::serialize(&params, serializer)
+            }
+        }
+
+        impl #ser_impl ::jrpc::derive::DualParams for #ident #ty_generics #ser_where {
+            fn to_named(&self) -> ::jrpc::derive::JsonResult<::jrpc::derive::Params> {
+                let mut __object = ::jrpc::derive::Object::new();
+                #(#insert)*
+                ::std::result::Result::Ok(__object.into_params())
+            }
+
+            fn to_positional(&self) -> ::jrpc::derive::JsonResult<::jrpc::derive::Params> {
+                let mut __array = ::jrpc::derive::Array::new(#len);
+                #(#push)*
+                ::std::result::Result::Ok(__array.into_params())
+            }
+        }
+    })
+}
+
+/// Whether the container is serialized by position.
+fn parse_container(input: &DeriveInput) -> syn::Result<bool> {
+    let mut positional = false;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("params") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("positional") {
+                positional = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `positional`"))
+            }
+        })?;
+    }
+    Ok(positional)
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Field> {
+    let ident = field.ident.clone().expect("named field");
+    let mut name = ident.to_string();
+    let mut default = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("params") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = lit_str(&meta.value()?.parse()?)?.value();
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                default = Some(if meta.input.peek(syn::Token![=]) {
+                    let path: syn::Path = lit_str(&meta.value()?.parse()?)?.parse()?;
+                    quote!(#path())
+                } else {
+                    quote!(::std::default::Default::default())
+                });
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename` or `default`"))
+            }
+        })?;
+    }
+    Ok(Field {
+        ident: ident,
+        name: name,
+        default: default,
+    })
+}
+
+fn lit_str(expr: &Expr) -> syn::Result<syn::LitStr> {
+    match *expr {
+        Expr::Lit(ref lit) => match lit.lit {
+            Lit::Str(ref s) => Ok(s.clone()),
+            _ => Err(syn::Error::new_spanned(expr, "expected a string literal")),
+        },
+        _ => Err(syn::Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+fn not_supported(input: &DeriveInput) -> syn::Error {
+    syn::Error::new_spanned(
+        &input.ident,
+        "#[derive(Params)] only supports structs with named fields",
+    )
+}
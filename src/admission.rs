@@ -0,0 +1,200 @@
+//! Bounding the calls a client has in flight.
+//!
+//! A client sending every call as soon as it is made lets a slow server grow its map of pending
+//! calls without bound. An [`Admission`](struct.Admission.html) controller sends at most
+//! `max_in_flight` calls at once and queues at most `max_queued` more. Beyond that, the
+//! [`Overflow`](enum.Overflow.html) policy either refuses the new call or evicts the oldest queued
+//! one.
+//!
+//! A refused call is handed back as [`Admit::WouldBlock`](enum.Admit.html#variant.WouldBlock),
+//! an explicit signal to apply backpressure to the caller instead of failing the call: an async
+//! wrapper keeps the call, returns `Poll::Pending` and wakes the caller once a call
+//! [completes](struct.Admission.html#method.complete). Like the rest of this crate, the
+//! controller itself does not block, store wakers or read the clock.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::admission::{Admission, AdmissionLimits, Admit, Overflow};
+//!
+//! # fn main() {
+//! let limits = AdmissionLimits::new().max_in_flight(1).max_queued(1);
+//! let mut calls = Admission::new(limits);
+//!
+//! assert_eq!(calls.submit("GetFoo"), Admit::Send("GetFoo"));
+//! assert_eq!(calls.submit("GetBar"), Admit::Queued);
+//! assert!(calls.would_block());
+//! assert_eq!(calls.submit("GetBaz"), Admit::WouldBlock("GetBaz"));
+//!
+//! // The Response to GetFoo frees its slot for GetBar.
+//! assert_eq!(calls.complete(), Some("GetBar"));
+//! assert_eq!(calls.submit("GetBaz"), Admit::Queued);
+//!
+//! // Evicting instead, e.g. for calls which are only useful while they are fresh.
+//! let mut calls = Admission::new(limits.overflow(Overflow::DropOldest));
+//! calls.submit(1);
+//! calls.submit(2);
+//! assert_eq!(calls.submit(3), Admit::Evicted(2));
+//! assert_eq!(calls.complete(), Some(3));
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use std_prelude::*;
+
+/// What to do with a call when both the in flight calls and the queue are full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Refuse the new call with [`Admit::WouldBlock`](enum.Admit.html#variant.WouldBlock).
+    #[default]
+    Reject,
+    /// Queue the new call, evicting the oldest queued call with
+    /// [`Admit::Evicted`](enum.Admit.html#variant.Evicted). Calls are refused if nothing can be
+    /// queued.
+    DropOldest,
+}
+
+/// The limits of an [`Admission`](struct.Admission.html) controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdmissionLimits {
+    /// The maximum number of calls in flight at once. Default: 64.
+    pub max_in_flight: usize,
+
+    /// The maximum number of calls waiting to be sent. Default: 0.
+    pub max_queued: usize,
+
+    /// What to do when both are full. Default: reject.
+    pub overflow: Overflow,
+}
+
+impl Default for AdmissionLimits {
+    fn default() -> Self {
+        AdmissionLimits {
+            max_in_flight: 64,
+            max_queued: 0,
+            overflow: Overflow::Reject,
+        }
+    }
+}
+
+impl AdmissionLimits {
+    /// Create the default limits.
+    pub fn new() -> Self {
+        AdmissionLimits::default()
+    }
+
+    /// Set the maximum number of calls in flight. At least one call is always allowed.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = max;
+        self
+    }
+
+    /// Set the maximum number of queued calls.
+    pub fn max_queued(mut self, max: usize) -> Self {
+        self.max_queued = max;
+        self
+    }
+
+    /// Set the overflow policy.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// The outcome of [`Admission::submit`](struct.Admission.html#method.submit).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admit<T> {
+    /// Send the call now. It is in flight until it [completes](struct.Admission.html#method.complete).
+    Send(T),
+    /// The call was queued, to be returned by [`complete`](struct.Admission.html#method.complete)
+    /// when it can be sent.
+    Queued,
+    /// The call was refused, try again after a call completed.
+    WouldBlock(T),
+    /// The call was queued, evicting the oldest queued call, which will not be sent.
+    Evicted(T),
+}
+
+/// Admits the calls of a client within its [`AdmissionLimits`](struct.AdmissionLimits.html).
+#[derive(Debug, Clone)]
+pub struct Admission<T> {
+    limits: AdmissionLimits,
+    in_flight: usize,
+    queue: VecDeque<T>,
+}
+
+impl<T> Admission<T> {
+    /// Create a controller without any calls.
+    pub fn new(limits: AdmissionLimits) -> Self {
+        Admission {
+            limits: limits,
+            in_flight: 0,
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// The limits.
+    pub fn limits(&self) -> &AdmissionLimits {
+        &self.limits
+    }
+
+    /// Submit a new call.
+    pub fn submit(&mut self, call: T) -> Admit<T> {
+        if self.in_flight < self.limits.max_in_flight.max(1) {
+            self.in_flight += 1;
+            return Admit::Send(call);
+        }
+        if self.queue.len() < self.limits.max_queued {
+            self.queue.push_back(call);
+            return Admit::Queued;
+        }
+        if self.limits.overflow == Overflow::DropOldest {
+            if let Some(evicted) = self.queue.pop_front() {
+                self.queue.push_back(call);
+                return Admit::Evicted(evicted);
+            }
+        }
+        Admit::WouldBlock(call)
+    }
+
+    /// Whether a new call would be refused with
+    /// [`Admit::WouldBlock`](enum.Admit.html#variant.WouldBlock).
+    pub fn would_block(&self) -> bool {
+        self.in_flight >= self.limits.max_in_flight.max(1)
+            && self.queue.len() >= self.limits.max_queued
+            && (self.limits.overflow == Overflow::Reject || self.limits.max_queued == 0)
+    }
+
+    /// Complete a call in flight, e.g. when its Response arrived, returning the next queued call
+    /// to send, which is then in flight.
+    pub fn complete(&mut self) -> Option<T> {
+        match self.queue.pop_front() {
+            Some(call) => Some(call),
+            None => {
+                self.in_flight = self.in_flight.saturating_sub(1);
+                None
+            }
+        }
+    }
+
+    /// The number of calls in flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// The number of queued calls.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Forget the calls in flight and return the queued calls, e.g. when the connection is lost.
+    pub fn drain(&mut self) -> Vec<T> {
+        self.in_flight = 0;
+        self.queue.drain(..).collect()
+    }
+}
@@ -0,0 +1,343 @@
+//! Session affinity through load balancers which do not understand JSON-RPC.
+//!
+//! # Convention
+//!
+//! A server issues an affinity token in the top level extension member
+//! [`AFFINITY_MEMBER`](constant.AFFINITY_MEMBER.html) of a Response, and the client echoes the
+//! latest token it received in the same member of every following Request:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "result": 19, "id": 1, "affinity": "app-3.1526000000000.9f86d081"}
+//! {"jsonrpc": "2.0", "method": "GetFoo", "id": 2, "affinity": "app-3.1526000000000.9f86d081"}
+//! ```
+//!
+//! The token is `<server>.<issued_ms>.<signature>`, the signature being the hex encoded
+//! signature of `<server>.<issued_ms>`. A load balancer which can only match a prefix or hash a
+//! member routes on it without parsing anything else, and the signature keeps clients from
+//! steering their Requests to a server of their choice.
+//!
+//! A server receiving a token of another server, e.g. after a failover, serves the Request and
+//! issues its own token. A forged or malformed token is answered with
+//! [`AFFINITY_REJECTED`](constant.AFFINITY_REJECTED.html), upon which the client drops it.
+//!
+//! This crate does not implement any cryptography. The caller supplies it by implementing
+//! [`Signer`](trait.Signer.html), e.g. an HMAC with a key shared by the servers of the pool.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::affinity::{self, Issuer, Sticky};
+//!
+//! /// A toy signature. Do not use this for anything real!
+//! fn sign(data: &[u8]) -> Vec<u8> {
+//!     vec![data.iter().fold(0x42, |acc, b| acc ^ b)]
+//! }
+//!
+//! # fn main() {
+//! let issuer = Issuer::new("app-3", sign).max_age_ms(60_000);
+//! let mut client = Sticky::new();
+//!
+//! // The first Request goes to any server, which issues a token.
+//! let mut request = json!({"jsonrpc": "2.0", "method": "GetFoo", "id": 1});
+//! client.attach(&mut request);
+//! assert_eq!(affinity::affinity_of(&request), None);
+//!
+//! let mut response = json!({"jsonrpc": "2.0", "result": 19, "id": 1});
+//! assert!(issuer.issue(&request, &mut response, 1000).unwrap());
+//! client.observe(&response);
+//!
+//! // The following Requests carry it.
+//! let mut request = json!({"jsonrpc": "2.0", "method": "GetFoo", "id": 2});
+//! client.attach(&mut request);
+//! let token = issuer.validate(affinity::affinity_of(&request).unwrap()).unwrap();
+//! assert_eq!(token.server, "app-3");
+//!
+//! // The token is still fresh, so no new one is issued.
+//! let mut response = json!({"jsonrpc": "2.0", "result": 19, "id": 2});
+//! assert!(!issuer.issue(&request, &mut response, 2000).unwrap());
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+use std::fmt::Write;
+
+use std_prelude::*;
+
+use {Error, ErrorCode, Id, Value};
+
+/// The Request and Response member carrying the affinity token.
+pub const AFFINITY_MEMBER: &str = "affinity";
+
+/// The server error code returned for forged or malformed affinity tokens.
+pub const AFFINITY_REJECTED: i64 = -32047;
+
+/// Signs affinity tokens.
+///
+/// Implemented for any `Fn(&[u8]) -> Vec<u8>`.
+pub trait Signer {
+    /// The signature of `data`.
+    fn sign(&self, data: &[u8]) -> Vec<u8>;
+}
+
+impl<F: Fn(&[u8]) -> Vec<u8>> Signer for F {
+    fn sign(&self, data: &[u8]) -> Vec<u8> {
+        self(data)
+    }
+}
+
+/// The claims of a valid affinity token.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Token {
+    /// The id of the server which issued the token.
+    pub server: String,
+
+    /// When the token was issued, in milliseconds since the unix epoch.
+    pub issued_ms: u64,
+}
+
+/// Mints and validates the affinity tokens of a server.
+#[derive(Debug, Clone)]
+pub struct Issuer<S> {
+    server: String,
+    signer: S,
+    max_age_ms: Option<u64>,
+}
+
+impl<S: Signer> Issuer<S> {
+    /// Create the issuer of the server `server`, signing its tokens with `signer`.
+    ///
+    /// # Panics
+    ///
+    /// If `server` is empty or contains a `.`.
+    pub fn new<T: Into<String>>(server: T, signer: S) -> Self {
+        let server = server.into();
+        assert!(
+            !server.is_empty() && !server.contains('.'),
+            "invalid server id `{}`",
+            server
+        );
+        Issuer {
+            server: server,
+            signer: signer,
+            max_age_ms: None,
+        }
+    }
+
+    /// Issue a new token once the current one is older than `max_age_ms`. Default: never.
+    pub fn max_age_ms(mut self, max_age_ms: u64) -> Self {
+        self.max_age_ms = Some(max_age_ms);
+        self
+    }
+
+    /// The id of the server.
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    /// Mint a token issued at `now_ms`.
+    pub fn mint(&self, now_ms: u64) -> String {
+        let claims = format!("{}.{}", self.server, now_ms);
+        let signature = hex(&self.signer.sign(claims.as_bytes()));
+        format!("{}.{}", claims, signature)
+    }
+
+    /// Validate a token, which may have been issued by another server of the pool.
+    pub fn validate(&self, token: &str) -> Result<Token, AffinityError> {
+        let malformed = || AffinityError::Malformed(token.to_string());
+        let mut parts = token.rsplitn(2, '.');
+        let signature = parts.next().ok_or_else(malformed)?;
+        let claims = parts.next().ok_or_else(malformed)?;
+        let mut parts = claims.rsplitn(2, '.');
+        let issued_ms = parts
+            .next()
+            .and_then(|issued_ms| issued_ms.parse().ok())
+            .ok_or_else(malformed)?;
+        let server = match parts.next() {
+            Some(server) if !server.is_empty() => server,
+            _ => return Err(malformed()),
+        };
+        let expected = hex(&self.signer.sign(claims.as_bytes()));
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(AffinityError::BadSignature(token.to_string()));
+        }
+        Ok(Token {
+            server: server.to_string(),
+            issued_ms: issued_ms,
+        })
+    }
+
+    /// Whether `token` is a token of this server which does not need to be renewed at `now_ms`.
+    pub fn is_current(&self, token: &Token, now_ms: u64) -> bool {
+        token.server == self.server
+            && match self.max_age_ms {
+                Some(max_age_ms) => now_ms.saturating_sub(token.issued_ms) < max_age_ms,
+                None => true,
+            }
+    }
+
+    /// Issue a token in `response` to the Request `request`, unless the Request already carries
+    /// a current token of this server. Returns whether a token was issued.
+    ///
+    /// Fails if the Request carries a forged or malformed token, in which case `response` is
+    /// left unchanged and should be replaced by the error.
+    ///
+    /// # Panics
+    ///
+    /// If `response` is not an Object.
+    pub fn issue(
+        &self,
+        request: &Value,
+        response: &mut Value,
+        now_ms: u64,
+    ) -> Result<bool, AffinityError> {
+        if let Some(token) = affinity_of(request) {
+            if self.is_current(&self.validate(token)?, now_ms) {
+                return Ok(false);
+            }
+        }
+        set(response, self.mint(now_ms));
+        Ok(true)
+    }
+}
+
+/// Get the affinity token of a Request or Response, if any.
+pub fn affinity_of(message: &Value) -> Option<&str> {
+    message.get(AFFINITY_MEMBER).and_then(Value::as_str)
+}
+
+/// Set the affinity token of a Request or Response.
+///
+/// # Panics
+///
+/// If `message` is not an Object.
+pub fn set<T: Into<String>>(message: &mut Value, token: T) {
+    message
+        .as_object_mut()
+        .expect("message must be an Object")
+        .insert(AFFINITY_MEMBER.to_string(), Value::String(token.into()));
+}
+
+/// Remove the affinity token from a Request or Response and return it.
+pub fn strip(message: &mut Value) -> Option<String> {
+    match message
+        .as_object_mut()
+        .and_then(|o| o.remove(AFFINITY_MEMBER))
+    {
+        Some(Value::String(token)) => Some(token),
+        _ => None,
+    }
+}
+
+/// The client side of the convention: remembers the latest token and attaches it to Requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sticky {
+    token: Option<String>,
+}
+
+impl Sticky {
+    /// Create without a token, until the first Response carrying one.
+    pub fn new() -> Self {
+        Sticky::default()
+    }
+
+    /// The current token, if any.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Observe a Response, or a batch of Responses, keeping the latest token it carries.
+    ///
+    /// A Response rejecting the token with [`AFFINITY_REJECTED`](constant.AFFINITY_REJECTED.html)
+    /// drops it.
+    pub fn observe(&mut self, response: &Value) {
+        if let Value::Array(ref responses) = *response {
+            for response in responses {
+                self.observe(response);
+            }
+            return;
+        }
+        if let Some(token) = affinity_of(response) {
+            self.token = Some(token.to_string());
+        } else if response.pointer("/error/code").and_then(Value::as_i64) == Some(AFFINITY_REJECTED)
+        {
+            self.token = None;
+        }
+    }
+
+    /// Attach the current token, if any, to a Request, or to every Request of a batch.
+    ///
+    /// # Panics
+    ///
+    /// If a Request is not an Object.
+    pub fn attach(&self, request: &mut Value) {
+        let token = match self.token {
+            Some(ref token) => token,
+            None => return,
+        };
+        match *request {
+            Value::Array(ref mut requests) => {
+                for request in requests {
+                    set(request, token.clone());
+                }
+            }
+            ref mut request => set(request, token.clone()),
+        }
+    }
+
+    /// Forget the token, e.g. when reconnecting to a different pool.
+    pub fn reset(&mut self) {
+        self.token = None;
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(out, "{:02x}", byte).unwrap();
+    }
+    out
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The error returned for an invalid affinity token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AffinityError {
+    /// The token is not of the form `<server>.<issued_ms>.<signature>`.
+    Malformed(String),
+    /// The signature of the token does not match.
+    BadSignature(String),
+}
+
+impl AffinityError {
+    /// The error code to respond with.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::ServerError(AFFINITY_REJECTED)
+    }
+
+    /// Convert into an error response for the request `id`.
+    pub fn into_error(self, id: Id) -> Error<Value> {
+        Error::new(id, self.code(), self.to_string(), None)
+    }
+}
+
+impl fmt::Display for AffinityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AffinityError::Malformed(ref token) => {
+                write!(f, "malformed affinity token `{}`", token)
+            }
+            AffinityError::BadSignature(ref token) => {
+                write!(f, "invalid signature of affinity token `{}`", token)
+            }
+        }
+    }
+}
+
+impl error::Error for AffinityError {}
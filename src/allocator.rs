@@ -0,0 +1,354 @@
+//! Allocating application error codes to the subsystems of a large application.
+//!
+//! An [`ErrorCodeAllocator`](struct.ErrorCodeAllocator.html) gives each subsystem (or team) its
+//! own range of codes and hands out the codes of a range in order, so two subsystems can never
+//! pick the same number. The allocator is its own manifest: it is serde serializable and is meant
+//! to be checked in next to the code, so allocations are stable between builds.
+//!
+//! At startup, [`verify`](struct.ErrorCodeAllocator.html#method.verify) compares the codes the
+//! application actually uses against the manifest and lists every
+//! [`Mismatch`](enum.Mismatch.html).
+//!
+//! Ranges may not overlap each other, nor the codes the specification reserves for pre-defined
+//! errors (`-32768` to `-32100`). The server error range (`-32099` to `-32000`) and any code
+//! outside of the reserved range may be allocated.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::allocator::{Allocation, ErrorCodeAllocator, Mismatch};
+//!
+//! # fn main() {
+//! let mut allocator = ErrorCodeAllocator::new();
+//! allocator.reserve("billing", -32099..-32090).unwrap();
+//! allocator.reserve("search", 1000..1100).unwrap();
+//! assert!(allocator.reserve("auth", -32095..-32080).is_err());
+//!
+//! assert_eq!(allocator.allocate("billing", "InsufficientFunds"), Ok(-32099));
+//! assert_eq!(allocator.allocate("billing", "CardExpired"), Ok(-32098));
+//! assert_eq!(allocator.allocate("search", "BadQuery"), Ok(1000));
+//! // Allocating again returns the same code.
+//! assert_eq!(allocator.allocate("billing", "InsufficientFunds"), Ok(-32099));
+//!
+//! // Persist the manifest and load it on the next build.
+//! let manifest = serde_json::to_string(&allocator).unwrap();
+//! let allocator: ErrorCodeAllocator = serde_json::from_str(&manifest).unwrap();
+//!
+//! let live = [
+//!     Allocation::new(-32099, "billing", "InsufficientFunds"),
+//!     Allocation::new(-32097, "billing", "CardExpired"),
+//! ];
+//! assert_eq!(
+//!     allocator.verify(&live),
+//!     vec![Mismatch::CodeChanged {
+//!         subsystem: "billing".to_string(),
+//!         name: "CardExpired".to_string(),
+//!         manifest: -32098,
+//!         live: -32097,
+//!     }],
+//! );
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+use std::ops::Range;
+
+use std_prelude::*;
+
+/// The codes the specification reserves for pre-defined errors.
+const RESERVED: Range<i64> = -32768..-32099;
+
+/// The range of codes of a subsystem.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subsystem {
+    /// The name of the subsystem.
+    pub name: String,
+
+    /// The codes of the subsystem.
+    pub codes: Range<i64>,
+}
+
+/// An error code allocated to a subsystem.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Allocation {
+    /// The error code.
+    pub code: i64,
+
+    /// The subsystem the code belongs to.
+    pub subsystem: String,
+
+    /// The name of the error, unique within the subsystem.
+    pub name: String,
+}
+
+impl Allocation {
+    /// Create an allocation.
+    pub fn new<S: Into<String>, N: Into<String>>(code: i64, subsystem: S, name: N) -> Self {
+        Allocation {
+            code: code,
+            subsystem: subsystem.into(),
+            name: name.into(),
+        }
+    }
+}
+
+/// Hands out non-colliding error codes from the ranges of subsystems.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCodeAllocator {
+    /// The subsystems, in the order they were reserved.
+    #[serde(default)]
+    pub subsystems: Vec<Subsystem>,
+
+    /// The allocated codes, in the order they were allocated.
+    #[serde(default)]
+    pub allocations: Vec<Allocation>,
+}
+
+impl ErrorCodeAllocator {
+    /// Create an allocator without subsystems.
+    pub fn new() -> Self {
+        ErrorCodeAllocator::default()
+    }
+
+    /// Reserve a range of codes for a subsystem.
+    pub fn reserve<S: Into<String>>(
+        &mut self,
+        name: S,
+        codes: Range<i64>,
+    ) -> Result<(), AllocError> {
+        let name = name.into();
+        if codes.start >= codes.end {
+            return Err(AllocError::EmptyRange(name));
+        }
+        if overlaps(&codes, &RESERVED) {
+            return Err(AllocError::Reserved(name));
+        }
+        for subsystem in &self.subsystems {
+            if subsystem.name == name {
+                return Err(AllocError::DuplicateSubsystem(name));
+            }
+            if overlaps(&codes, &subsystem.codes) {
+                return Err(AllocError::Overlap {
+                    subsystem: name,
+                    other: subsystem.name.clone(),
+                });
+            }
+        }
+        self.subsystems.push(Subsystem {
+            name: name,
+            codes: codes,
+        });
+        Ok(())
+    }
+
+    /// Allocate the next free code of a subsystem to an error.
+    ///
+    /// Allocating an error which already has a code returns that code.
+    pub fn allocate(&mut self, subsystem: &str, name: &str) -> Result<i64, AllocError> {
+        if let Some(code) = self.code_of(subsystem, name) {
+            return Ok(code);
+        }
+        let codes = match self.get_subsystem(subsystem) {
+            Some(s) => s.codes.clone(),
+            None => return Err(AllocError::UnknownSubsystem(subsystem.to_string())),
+        };
+        let code = codes
+            .into_iter()
+            .find(|&c| self.get(c).is_none())
+            .ok_or_else(|| AllocError::Exhausted(subsystem.to_string()))?;
+        self.allocations.push(Allocation::new(code, subsystem, name));
+        Ok(code)
+    }
+
+    /// Get a subsystem.
+    pub fn get_subsystem(&self, name: &str) -> Option<&Subsystem> {
+        self.subsystems.iter().find(|s| s.name == name)
+    }
+
+    /// Get the allocation of a code.
+    pub fn get(&self, code: i64) -> Option<&Allocation> {
+        self.allocations.iter().find(|a| a.code == code)
+    }
+
+    /// The code allocated to an error of a subsystem.
+    pub fn code_of(&self, subsystem: &str, name: &str) -> Option<i64> {
+        self.allocations
+            .iter()
+            .find(|a| a.subsystem == subsystem && a.name == name)
+            .map(|a| a.code)
+    }
+
+    /// Compare the codes used by the application with the manifest.
+    ///
+    /// Codes of the manifest which are not used anymore are not reported: they stay allocated so
+    /// they are never reused for a different error.
+    pub fn verify(&self, live: &[Allocation]) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        for (index, allocation) in live.iter().enumerate() {
+            if let Some(first) = live[..index].iter().find(|a| a.code == allocation.code) {
+                mismatches.push(Mismatch::Collision {
+                    code: allocation.code,
+                    first: first.clone(),
+                    second: allocation.clone(),
+                });
+                continue;
+            }
+            let range = match self.get_subsystem(&allocation.subsystem) {
+                Some(s) => &s.codes,
+                None => {
+                    mismatches.push(Mismatch::UnknownSubsystem(allocation.clone()));
+                    continue;
+                }
+            };
+            match self.code_of(&allocation.subsystem, &allocation.name) {
+                Some(code) if code != allocation.code => {
+                    mismatches.push(Mismatch::CodeChanged {
+                        subsystem: allocation.subsystem.clone(),
+                        name: allocation.name.clone(),
+                        manifest: code,
+                        live: allocation.code,
+                    });
+                }
+                Some(_) => {}
+                None if !range.contains(&allocation.code) => {
+                    mismatches.push(Mismatch::OutOfRange(allocation.clone()));
+                }
+                None => match self.get(allocation.code) {
+                    Some(owner) => mismatches.push(Mismatch::Collision {
+                        code: allocation.code,
+                        first: owner.clone(),
+                        second: allocation.clone(),
+                    }),
+                    None => mismatches.push(Mismatch::Unallocated(allocation.clone())),
+                },
+            }
+        }
+        mismatches
+    }
+}
+
+fn overlaps(a: &Range<i64>, b: &Range<i64>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// A difference between the codes used by the application and the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mismatch {
+    /// The subsystem of the code is not in the manifest.
+    UnknownSubsystem(Allocation),
+    /// The error has no code in the manifest, and its code is outside of its subsystem's range.
+    OutOfRange(Allocation),
+    /// The error has no code in the manifest.
+    Unallocated(Allocation),
+    /// The error uses a different code than the one in the manifest.
+    CodeChanged {
+        /// The subsystem.
+        subsystem: String,
+        /// The name of the error.
+        name: String,
+        /// The code in the manifest.
+        manifest: i64,
+        /// The code used by the application.
+        live: i64,
+    },
+    /// Two errors use the same code.
+    Collision {
+        /// The code.
+        code: i64,
+        /// The error which owns the code.
+        first: Allocation,
+        /// The other error using the code.
+        second: Allocation,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Mismatch::UnknownSubsystem(ref a) => write!(
+                f,
+                "{}.{} ({}) belongs to unknown subsystem",
+                a.subsystem, a.name, a.code
+            ),
+            Mismatch::OutOfRange(ref a) => write!(
+                f,
+                "{}.{} ({}) is outside of the subsystem's range",
+                a.subsystem, a.name, a.code
+            ),
+            Mismatch::Unallocated(ref a) => write!(
+                f,
+                "{}.{} ({}) is not in the manifest",
+                a.subsystem, a.name, a.code
+            ),
+            Mismatch::CodeChanged {
+                ref subsystem,
+                ref name,
+                manifest,
+                live,
+            } => write!(
+                f,
+                "{}.{} uses {} but the manifest allocates {}",
+                subsystem, name, live, manifest
+            ),
+            Mismatch::Collision {
+                code,
+                ref first,
+                ref second,
+            } => write!(
+                f,
+                "{}.{} and {}.{} both use {}",
+                first.subsystem, first.name, second.subsystem, second.name, code
+            ),
+        }
+    }
+}
+
+/// The error returned by the allocator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllocError {
+    /// The range of the subsystem is empty.
+    EmptyRange(String),
+    /// The range of the subsystem overlaps the codes reserved by the specification.
+    Reserved(String),
+    /// The subsystem was already reserved.
+    DuplicateSubsystem(String),
+    /// The range of the subsystem overlaps the range of another one.
+    Overlap {
+        /// The subsystem.
+        subsystem: String,
+        /// The subsystem owning the overlapped range.
+        other: String,
+    },
+    /// The subsystem was never reserved.
+    UnknownSubsystem(String),
+    /// Every code of the subsystem is allocated.
+    Exhausted(String),
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AllocError::EmptyRange(ref s) => write!(f, "subsystem `{}` has an empty range", s),
+            AllocError::Reserved(ref s) => write!(
+                f,
+                "subsystem `{}` overlaps the codes reserved by the specification",
+                s
+            ),
+            AllocError::DuplicateSubsystem(ref s) => {
+                write!(f, "subsystem `{}` is already reserved", s)
+            }
+            AllocError::Overlap {
+                ref subsystem,
+                ref other,
+            } => write!(f, "subsystem `{}` overlaps `{}`", subsystem, other),
+            AllocError::UnknownSubsystem(ref s) => write!(f, "unknown subsystem `{}`", s),
+            AllocError::Exhausted(ref s) => write!(f, "subsystem `{}` has no free code", s),
+        }
+    }
+}
+
+impl error::Error for AllocError {}
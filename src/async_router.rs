@@ -0,0 +1,220 @@
+//! Dispatching Requests to async handlers.
+//!
+//! > Only available with the `async` feature, or its alias `futures`.
+//!
+//! An [`AsyncRouter`](struct.AsyncRouter.html) is the async counterpart of the
+//! [`Router`](../router/struct.Router.html), with the same handling of invalid json, malformed
+//! Requests, unknown methods, invalid params, batches and Notifications. Its handlers return
+//! futures, which it boxes so handlers of any type can be registered together.
+//!
+//! This crate does not depend on an async runtime: [`handle`](struct.AsyncRouter.html#method.handle)
+//! returns a [`Handling`](struct.Handling.html) future, which the server awaits on its own
+//! runtime. The handlers of the entries of a batch run concurrently, and the future resolves to
+//! the json to send back, if any, once they all completed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use std::future::{self, Future};
+//! use std::pin::Pin;
+//! use std::task::{Context, Poll, Waker};
+//!
+//! use jrpc::async_router::AsyncRouter;
+//!
+//! # fn main() {
+//! let mut router = AsyncRouter::new();
+//! router.add::<(i64, i64), i64, _, _>("subtract", |(a, b)| future::ready(Ok(a - b)));
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#;
+//! let mut handling = router.handle(json);
+//! let mut cx = Context::from_waker(Waker::noop());
+//! match Pin::new(&mut handling).poll(&mut cx) {
+//!     Poll::Ready(response) => {
+//!         assert_eq!(response.unwrap(), r#"{"jsonrpc":"2.0","result":19,"id":1}"#);
+//!     }
+//!     Poll::Pending => unreachable!(),
+//! }
+//! # }
+//! ```
+
+use std::fmt;
+use std::future::{self, Future};
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std_prelude::*;
+
+use router::{decode_params, encode_result, join, respond, split};
+use {request_from_value, Error, ErrorObject, Id, IdReq, Response, Value};
+
+/// A boxed future, as returned by the handlers once registered.
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+type Handler = Box<dyn Fn(Option<Value>) -> BoxFuture<Result<Value, Error<Value>>> + Send + Sync>;
+
+/// Maps method names to async handlers, answering Requests with their Responses.
+#[derive(Default)]
+pub struct AsyncRouter {
+    methods: BTreeMap<String, Handler>,
+}
+
+impl fmt::Debug for AsyncRouter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncRouter")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl AsyncRouter {
+    /// Create a router without any methods.
+    pub fn new() -> Self {
+        AsyncRouter::default()
+    }
+
+    /// Register the handler of a method, replacing any previous one.
+    ///
+    /// See [`Router::add`](../router/struct.Router.html#method.add). The handler is not called
+    /// if the params do not deserialize.
+    pub fn add<P, R, F, Fut>(&mut self, method: &str, handler: F) -> &mut Self
+    where
+        P: DeserializeOwned,
+        R: Serialize + 'static,
+        F: Fn(P) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, ErrorObject<Value>>> + Send + 'static,
+    {
+        let handler = move |params: Option<Value>| -> BoxFuture<Result<Value, Error<Value>>> {
+            match decode_params(params) {
+                Ok(params) => Box::pin(Encode {
+                    inner: Box::pin(handler(params)),
+                }),
+                Err(error) => Box::pin(future::ready(Err(error))),
+            }
+        };
+        self.methods.insert(method.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Whether a handler is registered for `method`.
+    pub fn contains(&self, method: &str) -> bool {
+        self.methods.contains_key(method)
+    }
+
+    /// Handle the json of a Request, a Notification or a batch, returning a future resolving to
+    /// the json of the Response or batch of Responses, or `None` if there is nothing to send back.
+    ///
+    /// The handlers are called right away, and their futures polled by the returned future.
+    pub fn handle(&self, json: &str) -> Handling {
+        let (batch, values) = match split(json) {
+            Ok(input) => input,
+            Err(error) => {
+                return Handling {
+                    batch: false,
+                    entries: Vec::new(),
+                    answer: Some(error),
+                }
+            }
+        };
+        let entries = values
+            .into_iter()
+            .map(|value| match request_from_value::<String>(value) {
+                Ok(request) => {
+                    let future = match self.methods.get(&request.method) {
+                        Some(handler) => handler(request.params),
+                        None => Box::pin(future::ready(Err(Error::method_not_found(Id::Null)))),
+                    };
+                    Entry::Pending(request.id, future)
+                }
+                Err(error) => Entry::Done(Some(Response::Err(error))),
+            })
+            .collect();
+        Handling {
+            batch: batch,
+            entries: entries,
+            answer: None,
+        }
+    }
+}
+
+/// Serializes the result of a handler's future.
+struct Encode<R> {
+    inner: BoxFuture<Result<R, ErrorObject<Value>>>,
+}
+
+impl<R: Serialize> Future for Encode<R> {
+    type Output = Result<Value, Error<Value>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.inner.as_mut().poll(cx).map(encode_result)
+    }
+}
+
+enum Entry {
+    Pending(IdReq, BoxFuture<Result<Value, Error<Value>>>),
+    Done(Option<Response<Value>>),
+}
+
+/// The future returned by [`AsyncRouter::handle`](struct.AsyncRouter.html#method.handle).
+pub struct Handling {
+    batch: bool,
+    entries: Vec<Entry>,
+    /// The error answering the whole input, e.g. invalid json.
+    answer: Option<String>,
+}
+
+impl fmt::Debug for Handling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pending = self
+            .entries
+            .iter()
+            .filter(|entry| match **entry {
+                Entry::Pending(..) => true,
+                Entry::Done(_) => false,
+            })
+            .count();
+        f.debug_struct("Handling")
+            .field("batch", &self.batch)
+            .field("entries", &self.entries.len())
+            .field("pending", &pending)
+            .finish()
+    }
+}
+
+impl Future for Handling {
+    type Output = Option<String>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<String>> {
+        if let Some(answer) = self.answer.take() {
+            return Poll::Ready(Some(answer));
+        }
+        let mut pending = false;
+        for entry in &mut self.entries {
+            let response = match *entry {
+                Entry::Pending(ref id, ref mut future) => match future.as_mut().poll(cx) {
+                    Poll::Ready(result) => respond(id.clone(), result),
+                    Poll::Pending => {
+                        pending = true;
+                        continue;
+                    }
+                },
+                Entry::Done(_) => continue,
+            };
+            *entry = Entry::Done(response);
+        }
+        if pending {
+            return Poll::Pending;
+        }
+        let responses = mem::take(&mut self.entries)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Entry::Done(response) => response,
+                Entry::Pending(..) => None,
+            })
+            .collect();
+        Poll::Ready(join(self.batch, responses))
+    }
+}
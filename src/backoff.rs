@@ -0,0 +1,227 @@
+//! Backoff signalling from overloaded servers.
+//!
+//! An overloaded server answers calls with the [`OVERLOADED`](constant.OVERLOADED.html) error,
+//! whose `data` is a [`Backoff`](struct.Backoff.html): how long to wait and, optionally, which
+//! methods are affected. It may also broadcast the same hint to all its clients with the
+//! [`rpc.backoff`](constant.BACKOFF_METHOD.html) notification, before they hit the error.
+//!
+//! Clients feed Responses and notifications to a [`Throttle`](struct.Throttle.html) and hold
+//! their calls back for [`delay_ms`](struct.Throttle.html#method.delay_ms). Repeated overloaded
+//! errors without a successful Response in between double the pause, up to a maximum.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{Id, Response, Value};
+//! use jrpc::backoff::{self, Backoff, Throttle};
+//!
+//! # fn main() {
+//! // The server.
+//! let error = backoff::overloaded(Id::from(1), &Backoff::new(500).method("search"));
+//!
+//! // The client.
+//! let mut throttle = Throttle::new();
+//! assert!(throttle.observe(&Response::Err(error), 1000));
+//! assert_eq!(throttle.delay_ms("search", 1200), 300);
+//! assert_eq!(throttle.delay_ms("get", 1200), 0);
+//!
+//! // A broadcast hint applies to every method.
+//! let notification = backoff::notification(&Backoff::new(100));
+//! assert!(throttle.observe_notification(&notification, 2000));
+//! assert_eq!(throttle.delay_ms("get", 2000), 100);
+//!
+//! throttle.observe(&Response::success(Id::from(2), Value::from(true)), 2100);
+//! assert!(throttle.may_send("get", 2100));
+//! # }
+//! ```
+
+use serde_json;
+use std_prelude::*;
+
+use {Error, ErrorCode, Id, IdReq, Request, Response, Value};
+
+/// The server error code returned by overloaded servers. Its `data` is a
+/// [`Backoff`](struct.Backoff.html).
+pub const OVERLOADED: i64 = -32045;
+
+/// The method name of the broadcast backoff notification. Its `params` are a
+/// [`Backoff`](struct.Backoff.html).
+pub const BACKOFF_METHOD: &str = "rpc.backoff";
+
+/// The default maximum pause of a [`Throttle`](struct.Throttle.html): one minute.
+pub const DEFAULT_MAX_PAUSE_MS: u64 = 60_000;
+
+/// How long a client should hold its calls back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Backoff {
+    /// The pause, in milliseconds.
+    pub retry_after_ms: u64,
+
+    /// The affected methods, or all methods if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+}
+
+impl Backoff {
+    /// Back off from all methods for `retry_after_ms`.
+    pub fn new(retry_after_ms: u64) -> Self {
+        Backoff {
+            retry_after_ms: retry_after_ms,
+            methods: Vec::new(),
+        }
+    }
+
+    /// Restrict the backoff to a method (may be called multiple times).
+    pub fn method<S: Into<String>>(mut self, method: S) -> Self {
+        self.methods.push(method.into());
+        self
+    }
+}
+
+/// Create the overloaded error response to the Request `id`.
+pub fn overloaded(id: Id, backoff: &Backoff) -> Error<Value> {
+    Error::new(
+        id,
+        ErrorCode::ServerError(OVERLOADED),
+        format!(
+            "server overloaded, retry after {}ms",
+            backoff.retry_after_ms
+        ),
+        Some(serde_json::to_value(backoff).expect("backoff is serializable")),
+    )
+}
+
+/// Create the `rpc.backoff` notification.
+pub fn notification(backoff: &Backoff) -> Request<String, Value> {
+    Request::with_params(
+        IdReq::Notification,
+        BACKOFF_METHOD.to_string(),
+        serde_json::to_value(backoff).expect("backoff is serializable"),
+    )
+}
+
+/// Get the backoff of an error response, if it is an overloaded error.
+pub fn backoff_of(error: &Error<Value>) -> Option<Backoff> {
+    if error.error.code != ErrorCode::ServerError(OVERLOADED) {
+        return None;
+    }
+    match error.error.data {
+        Some(ref data) => serde_json::from_value(data.clone()).ok(),
+        None => Some(Backoff::new(0)),
+    }
+}
+
+/// Slows a client down according to the backoff hints of its server.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    max_pause_ms: u64,
+    /// Until when all methods are paused.
+    all_until_ms: u64,
+    /// Until when each method is paused.
+    methods: BTreeMap<String, u64>,
+    /// The overloaded errors since the last successful Response.
+    consecutive: u32,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle::new()
+    }
+}
+
+impl Throttle {
+    /// Create a throttle without pauses.
+    pub fn new() -> Self {
+        Throttle {
+            max_pause_ms: DEFAULT_MAX_PAUSE_MS,
+            all_until_ms: 0,
+            methods: BTreeMap::new(),
+            consecutive: 0,
+        }
+    }
+
+    /// Set the maximum pause, which also bounds the hints of the server.
+    pub fn max_pause_ms(mut self, max_pause_ms: u64) -> Self {
+        self.max_pause_ms = max_pause_ms;
+        self
+    }
+
+    /// Handle a Response received at `now_ms`. Returns whether it is an overloaded error.
+    pub fn observe(&mut self, response: &Response<Value>, now_ms: u64) -> bool {
+        let backoff = match *response {
+            Response::Ok(_) => {
+                self.consecutive = 0;
+                return false;
+            }
+            Response::Err(ref error) => match backoff_of(error) {
+                Some(backoff) => backoff,
+                None => return false,
+            },
+        };
+        let factor = 1u64 << self.consecutive.min(16);
+        self.consecutive = self.consecutive.saturating_add(1);
+        self.pause(
+            &backoff,
+            backoff.retry_after_ms.saturating_mul(factor),
+            now_ms,
+        );
+        true
+    }
+
+    /// Handle a notification received at `now_ms`. Returns whether it is an `rpc.backoff`
+    /// notification.
+    pub fn observe_notification<M: AsRef<str>>(
+        &mut self,
+        request: &Request<M, Value>,
+        now_ms: u64,
+    ) -> bool {
+        if request.method.as_ref() != BACKOFF_METHOD {
+            return false;
+        }
+        let backoff: Backoff = match request
+            .params
+            .clone()
+            .and_then(|p| serde_json::from_value(p).ok())
+        {
+            Some(backoff) => backoff,
+            None => return false,
+        };
+        self.pause(&backoff, backoff.retry_after_ms, now_ms);
+        true
+    }
+
+    fn pause(&mut self, backoff: &Backoff, pause_ms: u64, now_ms: u64) {
+        let until = now_ms.saturating_add(pause_ms.min(self.max_pause_ms));
+        if backoff.methods.is_empty() {
+            self.all_until_ms = self.all_until_ms.max(until);
+        } else {
+            for method in &backoff.methods {
+                let entry = self.methods.entry(method.clone()).or_insert(0);
+                *entry = (*entry).max(until);
+            }
+        }
+        let all_until_ms = self.all_until_ms;
+        self.methods
+            .retain(|_, until| *until > now_ms && *until > all_until_ms);
+    }
+
+    /// How long to hold back a call of `method` at `now_ms`, 0 if it may be sent.
+    pub fn delay_ms(&self, method: &str, now_ms: u64) -> u64 {
+        let until = self
+            .methods
+            .get(method)
+            .map_or(self.all_until_ms, |m| (*m).max(self.all_until_ms));
+        until.saturating_sub(now_ms)
+    }
+
+    /// Whether a call of `method` may be sent at `now_ms`.
+    pub fn may_send(&self, method: &str, now_ms: u64) -> bool {
+        self.delay_ms(method, now_ms) == 0
+    }
+
+    /// The overloaded errors received since the last successful Response.
+    pub fn consecutive(&self) -> u32 {
+        self.consecutive
+    }
+}
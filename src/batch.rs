@@ -0,0 +1,403 @@
+//! Batches of Requests and Responses.
+//!
+//! A [`BatchRequest`](struct.BatchRequest.html) and a [`BatchResponse`](struct.BatchResponse.html)
+//! serialize as a json Array. The specification does not allow empty batches, so they can not
+//! be constructed nor deserialized empty: an empty Array sent by a client must be answered with
+//! a single `InvalidRequest` error, and a server with no Response to send (i.e. the batch held
+//! only notifications) must send nothing at all.
+//!
+//! Responses may arrive in any order, so the client matches them to their Requests by `id` with
+//! [`BatchResponse::pair`](struct.BatchResponse.html#method.pair).
+//!
+//! # Positions
+//!
+//! The error for an entry which is not a valid Request has a `null` id, since its id could not
+//! be read, so the client can not tell which entry failed. A server parsing the batch with
+//! [`LenientBatch::parse`](struct.LenientBatch.html#method.parse) gets one outcome per entry,
+//! in order, and the errors with a `null` id carry the position of their entry in the
+//! [`INDEX_MEMBER`](constant.INDEX_MEMBER.html) member of their `data`:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "error": {"code": -32600, "message": "...", "data": {"batch_index": 1}}, "id": null}
+//! ```
+//!
+//! The client gets the [`Outcome`](enum.Outcome.html) of every entry it sent with
+//! [`BatchResponse::by_position`](struct.BatchResponse.html#method.by_position).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{Id, IdReq, Request, Value};
+//! use jrpc::batch::{BatchRequest, BatchResponse};
+//!
+//! # fn main() {
+//! let batch = BatchRequest::new(vec![
+//!     Request::with_params(Id::from(1), "sum".to_string(), vec![1, 2]),
+//!     Request::with_params(IdReq::Notification, "log".to_string(), vec![3]),
+//!     Request::with_params(Id::from(2), "sum".to_string(), vec![4, 5]),
+//! ]).unwrap();
+//! assert_eq!(batch.ids(), vec![Id::from(1), Id::from(2)]);
+//!
+//! let json = r#"[
+//!     {"jsonrpc": "2.0", "result": 9, "id": 2},
+//!     {"jsonrpc": "2.0", "result": 3, "id": 1}
+//! ]"#;
+//! let responses: BatchResponse<u32> = serde_json::from_str(json).unwrap();
+//! let pairs = responses.pair(&batch);
+//! assert_eq!(pairs.len(), 2);
+//! assert_eq!(pairs[0].request.params, Some(vec![1, 2]));
+//! assert_eq!(pairs[0].response.unwrap().id(), &Id::from(1));
+//!
+//! assert!(BatchRequest::<String, Value>::new(vec![]).is_err());
+//! assert!(serde_json::from_str::<BatchResponse<u32>>("[]").is_err());
+//! # }
+//! ```
+//!
+//! Correlating the failed entries of a batch:
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{ErrorCode, Id, Request, Response, Value};
+//! use jrpc::batch::{BatchRequest, BatchResponse, LenientBatch, Outcome};
+//!
+//! # fn main() {
+//! let sent: BatchRequest<String, Value> = BatchRequest::new(vec![
+//!     Request::with_params(Id::from(1), "sum".to_string(), Value::from(vec![1, 2])),
+//!     Request::with_params(Id::from(2), "sum".to_string(), Value::from(vec![3])),
+//! ]).unwrap();
+//!
+//! // The server fails to parse the second entry, e.g. after a proxy mangled it.
+//! let json = r#"[{"jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 1}, {"method": 1}]"#;
+//! let batch = LenientBatch::<String>::parse(json).unwrap();
+//! let responses: Vec<Response<Value>> = batch
+//!     .into_entries()
+//!     .into_iter()
+//!     .map(|entry| match entry {
+//!         Ok(request) => Response::success(request.id.to_id().unwrap(), Value::from(3)),
+//!         Err(error) => Response::Err(error),
+//!     })
+//!     .collect();
+//!
+//! // The client knows which entry failed.
+//! let responses = BatchResponse::new(responses).unwrap();
+//! let outcomes = responses.by_position(&sent);
+//! match outcomes[1] {
+//!     Outcome::Answered(&Response::Err(ref error)) => {
+//!         assert_eq!(error.error.code, ErrorCode::InvalidRequest)
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+use std::ops::Deref;
+
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {request_from_value, Error, ErrorCode, Id, IdReq, Request, Response, Value};
+
+/// The member of the error `data` carrying the position of the failed entry in the batch.
+pub const INDEX_MEMBER: &str = "batch_index";
+
+/// The error returned when constructing an empty batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyBatch;
+
+impl fmt::Display for EmptyBatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a batch must not be empty")
+    }
+}
+
+impl error::Error for EmptyBatch {}
+
+fn non_empty<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let entries = Vec::<T>::deserialize(deserializer)?;
+    if entries.is_empty() {
+        return Err(de::Error::invalid_length(0, &"a non-empty batch"));
+    }
+    Ok(entries)
+}
+
+/// A non-empty batch of Requests, which may include notifications.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct BatchRequest<M, T>(Vec<Request<M, T>>);
+
+impl<'de, M, T> Deserialize<'de> for BatchRequest<M, T>
+where
+    Request<M, T>: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        non_empty(deserializer).map(BatchRequest)
+    }
+}
+
+impl<M, T> BatchRequest<M, T> {
+    /// Create a batch.
+    pub fn new(requests: Vec<Request<M, T>>) -> Result<Self, EmptyBatch> {
+        if requests.is_empty() {
+            Err(EmptyBatch)
+        } else {
+            Ok(BatchRequest(requests))
+        }
+    }
+
+    /// The Requests, in order.
+    pub fn requests(&self) -> &[Request<M, T>] {
+        &self.0
+    }
+
+    /// Convert into the Requests.
+    pub fn into_inner(self) -> Vec<Request<M, T>> {
+        self.0
+    }
+
+    /// The ids of the Requests which are not notifications, in order.
+    pub fn ids(&self) -> Vec<Id> {
+        self.0.iter().filter_map(|r| r.id.clone().to_id()).collect()
+    }
+
+    /// Whether the server must answer the batch, i.e. it is not only notifications.
+    pub fn expects_response(&self) -> bool {
+        self.0.iter().any(|r| r.id != IdReq::Notification)
+    }
+}
+
+impl<M, T> Deref for BatchRequest<M, T> {
+    type Target = [Request<M, T>];
+
+    fn deref(&self) -> &[Request<M, T>] {
+        &self.0
+    }
+}
+
+/// A non-empty batch of Responses.
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct BatchResponse<T, E = Value>(Vec<Response<T, E>>);
+
+impl<'de, T, E> Deserialize<'de> for BatchResponse<T, E>
+where
+    Response<T, E>: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        non_empty(deserializer).map(BatchResponse)
+    }
+}
+
+impl<T, E> BatchResponse<T, E> {
+    /// Create a batch.
+    ///
+    /// A server whose batch produced no Responses gets `EmptyBatch`, and must not reply at all.
+    pub fn new(responses: Vec<Response<T, E>>) -> Result<Self, EmptyBatch> {
+        if responses.is_empty() {
+            Err(EmptyBatch)
+        } else {
+            Ok(BatchResponse(responses))
+        }
+    }
+
+    /// The Responses, in the order they were sent.
+    pub fn responses(&self) -> &[Response<T, E>] {
+        &self.0
+    }
+
+    /// Convert into the Responses.
+    pub fn into_inner(self) -> Vec<Response<T, E>> {
+        self.0
+    }
+}
+
+impl<T, E> BatchResponse<T, E>
+where
+    T: Serialize + DeserializeOwned,
+    E: Serialize + DeserializeOwned,
+{
+    /// Get the Response with `id`. If several have it, the first one.
+    pub fn get(&self, id: &Id) -> Option<&Response<T, E>> {
+        self.0.iter().find(|r| r.id() == id)
+    }
+
+    /// Match every Request which is not a notification with its Response, in the order of the
+    /// Requests.
+    pub fn pair<'a, M, P>(&'a self, requests: &'a BatchRequest<M, P>) -> Vec<Pair<'a, M, P, T, E>> {
+        requests
+            .iter()
+            .filter_map(|r| {
+                r.id.clone().to_id().map(|id| Pair {
+                    request: r,
+                    response: self.get(&id),
+                })
+            })
+            .collect()
+    }
+
+    /// The outcome of every entry of `requests`, in order.
+    ///
+    /// Responses are matched by `id`, or by the [`INDEX_MEMBER`](constant.INDEX_MEMBER.html)
+    /// of the `data` of errors with a `null` id.
+    pub fn by_position<'a, M, P>(
+        &'a self,
+        requests: &BatchRequest<M, P>,
+    ) -> Vec<Outcome<'a, T, E>> {
+        requests
+            .iter()
+            .enumerate()
+            .map(|(index, request)| {
+                let by_id = match request.id.clone().to_id() {
+                    None => return Outcome::Notification,
+                    Some(Id::Null) => None,
+                    Some(id) => self.get(&id),
+                };
+                let by_index = || self.0.iter().find(|r| index_of(r) == Some(index));
+                match by_id.or_else(by_index) {
+                    Some(response) => Outcome::Answered(response),
+                    None => Outcome::Missing,
+                }
+            })
+            .collect()
+    }
+
+    /// The Responses which answer none of `requests`, i.e. errors with a `null` id sent for
+    /// entries the server could not parse.
+    pub fn unmatched<M, P>(&self, requests: &BatchRequest<M, P>) -> Vec<&Response<T, E>> {
+        let ids = requests.ids();
+        self.0.iter().filter(|r| !ids.contains(r.id())).collect()
+    }
+}
+
+/// The position carried by an error with a `null` id.
+fn index_of<T, E: Serialize>(response: &Response<T, E>) -> Option<usize> {
+    let error = match *response {
+        Response::Err(ref error) if error.id == Id::Null => error,
+        _ => return None,
+    };
+    let data = serde_json::to_value(error.error.data.as_ref()?).ok()?;
+    data.get(INDEX_MEMBER)?.as_u64().map(|i| i as usize)
+}
+
+/// The outcome of an entry of a batch, returned by
+/// [`BatchResponse::by_position`](struct.BatchResponse.html#method.by_position).
+#[derive(Debug)]
+pub enum Outcome<'a, T: 'a, E: 'a> {
+    /// The Response of the entry.
+    Answered(&'a Response<T, E>),
+    /// The server did not answer the entry.
+    Missing,
+    /// The entry is a notification, which is never answered.
+    Notification,
+}
+
+/// A batch parsed entry by entry, so entries which are not valid Requests do not fail the
+/// whole batch.
+#[derive(Debug)]
+pub struct LenientBatch<M> {
+    entries: Vec<Result<Request<M, Value>, Error<Value>>>,
+}
+
+impl<M: Serialize + DeserializeOwned> LenientBatch<M> {
+    /// Parse a batch.
+    ///
+    /// Fails with the single error to reply with if `json` is not valid json (`ParseError`), or
+    /// not a non-empty Array (`InvalidRequest`). Otherwise every entry is parsed as with
+    /// [`parse_request`](../fn.parse_request.html), and the errors with a `null` id carry the
+    /// position of their entry.
+    pub fn parse(json: &str) -> Result<Self, Error<Value>> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|err| Error::new(Id::Null, ErrorCode::ParseError, err.to_string(), None))?;
+        let values = match value {
+            Value::Array(ref values) if values.is_empty() => {
+                return Err(Error::new(
+                    Id::Null,
+                    ErrorCode::InvalidRequest,
+                    "empty batch",
+                    None,
+                ))
+            }
+            Value::Array(values) => values,
+            _ => {
+                return Err(Error::new(
+                    Id::Null,
+                    ErrorCode::InvalidRequest,
+                    "not a batch",
+                    None,
+                ))
+            }
+        };
+        let entries = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| request_from_value(value).map_err(|err| with_index(err, index)))
+            .collect();
+        Ok(LenientBatch { entries: entries })
+    }
+}
+
+impl<M> LenientBatch<M> {
+    /// The outcome of every entry, in order.
+    pub fn entries(&self) -> &[Result<Request<M, Value>, Error<Value>>] {
+        &self.entries
+    }
+
+    /// Convert into the outcome of every entry, in order.
+    pub fn into_entries(self) -> Vec<Result<Request<M, Value>, Error<Value>>> {
+        self.entries
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Always `false`: batches are never empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Add the position to an error with a `null` id. `data` which is neither absent nor an Object
+/// is left unchanged.
+fn with_index(mut error: Error<Value>, index: usize) -> Error<Value> {
+    if error.id != Id::Null {
+        return error;
+    }
+    let data = error
+        .error
+        .data
+        .get_or_insert_with(|| Value::Object(Default::default()));
+    if let Some(object) = data.as_object_mut() {
+        object.insert(INDEX_MEMBER.to_string(), Value::from(index));
+    }
+    error
+}
+
+/// A Request and its Response, returned by
+/// [`BatchResponse::pair`](struct.BatchResponse.html#method.pair).
+#[derive(Debug)]
+pub struct Pair<'a, M: 'a, P: 'a, T: 'a, E: 'a> {
+    /// The Request.
+    pub request: &'a Request<M, P>,
+    /// Its Response, `None` if the server did not answer it.
+    pub response: Option<&'a Response<T, E>>,
+}
+
+impl<T, E> Deref for BatchResponse<T, E> {
+    type Target = [Response<T, E>];
+
+    fn deref(&self) -> &[Response<T, E>] {
+        &self.0
+    }
+}
@@ -0,0 +1,372 @@
+//! Zero-copy borrowed variants of the types.
+//!
+//! Deserializing a [`Request`](../struct.Request.html) allocates a `String` for its method and
+//! every String id, and an [`ErrorObject`](../struct.ErrorObject.html) one for its message. The
+//! types of this module hold a `Cow<'a, str>` instead, which borrows from the json being
+//! deserialized whenever the string contains no escapes, so a server handling many small
+//! messages does not allocate for them.
+//!
+//! Borrowing requires deserializing from a `&'a str` or `&'a [u8]` (`serde_json::from_str`,
+//! `from_slice`), not from a reader. Every type converts to its owned counterpart with
+//! `to_owned` or `into_owned`, and serializes exactly like it.
+//!
+//! The views, [`RequestView`](struct.RequestView.html) and
+//! [`ResponseView`](struct.ResponseView.html), borrow the members of an owned message instead, so
+//! a chain of middleware can inspect it without cloning it. They are created with `as_view`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use std::borrow::Cow;
+//! use jrpc::{Id, Request, Value};
+//! use jrpc::borrowed::{IdReqRef, RequestRef};
+//!
+//! # fn main() {
+//! let json = r#"{"jsonrpc": "2.0", "method": "CreateFoo", "params": [1], "id": "a1"}"#;
+//! let request: RequestRef<Value> = serde_json::from_str(json).unwrap();
+//! assert_eq!(request.method, "CreateFoo");
+//! match (&request.method, &request.id) {
+//!     (&Cow::Borrowed(_), &IdReqRef::String(Cow::Borrowed(_))) => {}
+//!     other => panic!("allocated: {:?}", other),
+//! }
+//!
+//! let owned: Request<String, Value> = request.to_owned();
+//! assert_eq!(owned.id, Id::from("a1").into());
+//! assert_eq!(owned.to_string(), serde_json::to_string(&request).unwrap());
+//!
+//! // Inspecting an owned Request without cloning it.
+//! let view = owned.as_view();
+//! assert_eq!(view.method, "CreateFoo");
+//! assert_eq!(view.params, Some(&Value::from(vec![1])));
+//! assert_eq!(serde_json::to_string(&view).unwrap(), owned.to_string());
+//! assert_eq!(view.to_owned(), owned);
+//! # }
+//! ```
+
+use std_prelude::*;
+
+use {Error, ErrorCode, ErrorObject, Id, IdReq, Request, Response, Success, V2_0};
+
+/// The borrowed counterpart of [`Id`](../enum.Id.html).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IdRef<'a> {
+    /// An String id
+    String(#[serde(borrow)] Cow<'a, str>),
+    /// An Number id that must be an integer.
+    Int(i64),
+    /// A null id
+    Null,
+}
+
+impl<'a> IdRef<'a> {
+    /// Convert to an [`Id`](../enum.Id.html), copying a borrowed String.
+    pub fn to_owned(&self) -> Id {
+        self.clone().into_owned()
+    }
+
+    /// Convert into an [`Id`](../enum.Id.html).
+    pub fn into_owned(self) -> Id {
+        match self {
+            IdRef::String(s) => Id::String(s.into_owned()),
+            IdRef::Int(i) => Id::Int(i),
+            IdRef::Null => Id::Null,
+        }
+    }
+}
+
+impl<'a> From<&'a Id> for IdRef<'a> {
+    fn from(id: &'a Id) -> Self {
+        match *id {
+            Id::String(ref s) => IdRef::String(Cow::Borrowed(s)),
+            Id::Int(i) => IdRef::Int(i),
+            Id::Null => IdRef::Null,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for IdRef<'a> {
+    fn from(s: &'a str) -> Self {
+        IdRef::String(Cow::Borrowed(s))
+    }
+}
+
+impl<'a> From<i64> for IdRef<'a> {
+    fn from(v: i64) -> Self {
+        IdRef::Int(v)
+    }
+}
+
+/// The borrowed counterpart of [`IdReq`](../enum.IdReq.html).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IdReqRef<'a> {
+    /// An String id
+    String(#[serde(borrow)] Cow<'a, str>),
+    /// An Number id that must be an integer.
+    Int(i64),
+    /// A null id
+    Null,
+    /// The notification id, i.e. the id is absent.
+    Notification,
+}
+
+impl<'a> IdReqRef<'a> {
+    /// Convert to an [`IdReq`](../enum.IdReq.html), copying a borrowed String.
+    pub fn to_owned(&self) -> IdReq {
+        self.clone().into_owned()
+    }
+
+    /// Convert into an [`IdReq`](../enum.IdReq.html).
+    pub fn into_owned(self) -> IdReq {
+        match self {
+            IdReqRef::String(s) => IdReq::String(s.into_owned()),
+            IdReqRef::Int(i) => IdReq::Int(i),
+            IdReqRef::Null => IdReq::Null,
+            IdReqRef::Notification => IdReq::Notification,
+        }
+    }
+
+    /// Attempt to convert to an [`IdRef`](enum.IdRef.html).
+    ///
+    /// Returns `None` if this Id is a `Notification`.
+    pub fn to_id(self) -> Option<IdRef<'a>> {
+        let out = match self {
+            IdReqRef::String(s) => IdRef::String(s),
+            IdReqRef::Int(i) => IdRef::Int(i),
+            IdReqRef::Null => IdRef::Null,
+            IdReqRef::Notification => return None,
+        };
+        Some(out)
+    }
+}
+
+impl<'a> From<IdRef<'a>> for IdReqRef<'a> {
+    fn from(id: IdRef<'a>) -> Self {
+        match id {
+            IdRef::String(s) => IdReqRef::String(s),
+            IdRef::Int(i) => IdReqRef::Int(i),
+            IdRef::Null => IdReqRef::Null,
+        }
+    }
+}
+
+impl<'a> From<&'a IdReq> for IdReqRef<'a> {
+    fn from(id: &'a IdReq) -> Self {
+        match *id {
+            IdReq::String(ref s) => IdReqRef::String(Cow::Borrowed(s)),
+            IdReq::Int(i) => IdReqRef::Int(i),
+            IdReq::Null => IdReqRef::Null,
+            IdReq::Notification => IdReqRef::Notification,
+        }
+    }
+}
+
+fn notification<'a>() -> IdReqRef<'a> {
+    IdReqRef::Notification
+}
+
+/// The borrowed counterpart of a [`Request`](../struct.Request.html) with a String method.
+///
+/// The params are of any type, which may borrow too, e.g. a `&'a RawValue` or a struct with
+/// `Cow<'a, str>` fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestRef<'a, T> {
+    /// A String specifying the version of the JSON-RPC protocol. MUST be exactly "2.0".
+    pub jsonrpc: V2_0,
+
+    /// The name of the method to be invoked.
+    #[serde(borrow)]
+    pub method: Cow<'a, str>,
+
+    /// The parameter values to be used during the invocation of the method.
+    #[serde(default = "Option::default")]
+    pub params: Option<T>,
+
+    /// The `id`. See [`IdReqRef`](enum.IdReqRef.html)
+    #[serde(borrow, default = "notification")]
+    pub id: IdReqRef<'a>,
+}
+
+impl<'a, T> RequestRef<'a, T> {
+    /// Convert to a [`Request`](../struct.Request.html), copying the borrowed strings.
+    pub fn to_owned(&self) -> Request<String, T>
+    where
+        T: Clone,
+    {
+        self.clone().into_owned()
+    }
+
+    /// Convert into a [`Request`](../struct.Request.html).
+    pub fn into_owned(self) -> Request<String, T> {
+        Request {
+            jsonrpc: V2_0,
+            method: self.method.into_owned(),
+            params: self.params,
+            id: self.id.into_owned(),
+        }
+    }
+}
+
+impl<'a, M: AsRef<str>, T: Clone> From<&'a Request<M, T>> for RequestRef<'a, T> {
+    fn from(request: &'a Request<M, T>) -> Self {
+        RequestRef {
+            jsonrpc: V2_0,
+            method: Cow::Borrowed(request.method.as_ref()),
+            params: request.params.clone(),
+            id: IdReqRef::from(&request.id),
+        }
+    }
+}
+
+/// The borrowed counterpart of [`ErrorObject`](../struct.ErrorObject.html).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorObjectRef<'a, T> {
+    /// The error code. See [`ErrorCode`](../enum.ErrorCode.html)
+    pub code: ErrorCode,
+
+    /// A String providing a short description of the error.
+    #[serde(borrow)]
+    pub message: Cow<'a, str>,
+
+    /// A Primitive or Structured value that contains additional information about the error.
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+}
+
+impl<'a, T> ErrorObjectRef<'a, T> {
+    /// Convert to an [`ErrorObject`](../struct.ErrorObject.html), copying the message if it is
+    /// borrowed.
+    pub fn to_owned(&self) -> ErrorObject<T>
+    where
+        T: Clone,
+    {
+        self.clone().into_owned()
+    }
+
+    /// Convert into an [`ErrorObject`](../struct.ErrorObject.html).
+    pub fn into_owned(self) -> ErrorObject<T> {
+        ErrorObject {
+            code: self.code,
+            message: self.message.into_owned(),
+            data: self.data,
+        }
+    }
+}
+
+/// A view of a Request, borrowing its members, so middleware can inspect and serialize it
+/// without cloning it.
+///
+/// It serializes exactly like the Request it views.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RequestView<'a, T: 'a> {
+    /// Always "2.0".
+    pub jsonrpc: V2_0,
+
+    /// The name of the method to be invoked.
+    pub method: &'a str,
+
+    /// The parameter values to be used during the invocation of the method.
+    pub params: Option<&'a T>,
+
+    /// The `id`. See [`IdReq`](../enum.IdReq.html)
+    pub id: &'a IdReq,
+}
+
+impl<'a, T> Clone for RequestView<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for RequestView<'a, T> {}
+
+impl<'a, T: Clone> RequestView<'a, T> {
+    /// Convert to a [`Request`](../struct.Request.html), cloning the members.
+    pub fn to_owned(&self) -> Request<String, T> {
+        Request {
+            jsonrpc: V2_0,
+            method: self.method.to_string(),
+            params: self.params.cloned(),
+            id: self.id.clone(),
+        }
+    }
+}
+
+impl<'a, M: AsRef<str>, T> From<&'a Request<M, T>> for RequestView<'a, T> {
+    fn from(request: &'a Request<M, T>) -> Self {
+        RequestView {
+            jsonrpc: V2_0,
+            method: request.method.as_ref(),
+            params: request.params.as_ref(),
+            id: &request.id,
+        }
+    }
+}
+
+impl<M: AsRef<str>, T> Request<M, T> {
+    /// A view of the Request. See [`RequestView`](borrowed/struct.RequestView.html).
+    pub fn as_view(&self) -> RequestView<'_, T> {
+        RequestView::from(self)
+    }
+}
+
+/// A view of a Response, borrowing its members.
+#[derive(Debug, PartialEq)]
+pub struct ResponseView<'a, T: 'a, E: 'a> {
+    /// The `id` of the Request the Response answers.
+    pub id: &'a Id,
+
+    /// The `result`, or the `error` object.
+    pub result: Result<&'a T, &'a ErrorObject<E>>,
+}
+
+impl<'a, T, E> Clone for ResponseView<'a, T, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, E> Copy for ResponseView<'a, T, E> {}
+
+impl<'a, T: Clone, E: Clone> ResponseView<'a, T, E> {
+    /// Convert to a [`Response`](../enum.Response.html), cloning the members.
+    pub fn to_owned(&self) -> Response<T, E> {
+        match self.result {
+            Ok(result) => Response::Ok(Success {
+                jsonrpc: V2_0,
+                result: result.clone(),
+                id: self.id.clone(),
+            }),
+            Err(error) => Response::Err(Error {
+                jsonrpc: V2_0,
+                error: error.clone(),
+                id: self.id.clone(),
+            }),
+        }
+    }
+}
+
+impl<'a, T, E> From<&'a Response<T, E>> for ResponseView<'a, T, E> {
+    fn from(response: &'a Response<T, E>) -> Self {
+        match *response {
+            Response::Ok(ref success) => ResponseView {
+                id: &success.id,
+                result: Ok(&success.result),
+            },
+            Response::Err(ref error) => ResponseView {
+                id: &error.id,
+                result: Err(&error.error),
+            },
+        }
+    }
+}
+
+impl<T, E> Response<T, E> {
+    /// A view of the Response. See [`ResponseView`](borrowed/struct.ResponseView.html).
+    pub fn as_view(&self) -> ResponseView<'_, T, E> {
+        ResponseView::from(self)
+    }
+}
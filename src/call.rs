@@ -0,0 +1,140 @@
+//! Pairing a Request with the type of its result.
+//!
+//! A client sending a `Request<M, P>` knows which result type it expects, but nothing ties the
+//! Response it receives later to that type or to that Request. A [`Call`](struct.Call.html),
+//! created with [`Request::expecting`](../struct.Request.html#method.expecting), remembers the
+//! `id` of the Request and the result type `R`, and
+//! [`parse_response`](struct.Call.html#method.parse_response) decodes the Response as an `R`
+//! after checking that it answers this Request.
+//!
+//! A Response which does not answer the Request is reported as an `InternalError` whose `data`
+//! holds the `expected` and `received` ids. An error Response with a Null `id`, which a Server
+//! sends when it could not read the `id` of the Request, is returned as is.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::{ErrorCode, Id, Request};
+//!
+//! # fn main() {
+//! let request = Request::with_params(Id::from(1), "subtract".to_string(), (42, 23));
+//! let call = request.expecting::<i64>();
+//!
+//! let json = r#"{"jsonrpc": "2.0", "result": 19, "id": 1}"#;
+//! assert_eq!(call.parse_response(json), Ok(19));
+//!
+//! let json = r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 1}"#;
+//! assert_eq!(call.parse_response(json).unwrap_err().code, ErrorCode::MethodNotFound);
+//!
+//! // The Response to another Request.
+//! let json = r#"{"jsonrpc": "2.0", "result": 19, "id": 2}"#;
+//! let error = call.parse_response(json).unwrap_err();
+//! assert_eq!(error.code, ErrorCode::InternalError);
+//! assert_eq!(error.data, Some(json!({"expected": 1, "received": 2})));
+//! # }
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde_json;
+use std_prelude::*;
+
+use {ErrorCode, ErrorObject, Id, Request, Response, Value};
+
+/// The `id` of a sent Request with params `P`, and the type `R` of its result.
+pub struct Call<P, R> {
+    id: Id,
+    types: PhantomData<fn() -> (P, R)>,
+}
+
+// Not derived, which would require `P` and `R` to implement the traits as well.
+impl<P, R> Clone for Call<P, R> {
+    fn clone(&self) -> Self {
+        Call::new(self.id.clone())
+    }
+}
+
+impl<P, R> PartialEq for Call<P, R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<P, R> Eq for Call<P, R> {}
+
+impl<P, R> fmt::Debug for Call<P, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Call").field("id", &self.id).finish()
+    }
+}
+
+impl<M, T> Request<M, T> {
+    /// The [`Call`](call/struct.Call.html) decoding the Response to this Request as an `R`.
+    ///
+    /// A Request with an [`IdReq::Notification`](enum.IdReq.html#variant.Notification) `id` is
+    /// serialized with a Null `id`, so it expects the Response to the `id` Null.
+    pub fn expecting<R>(&self) -> Call<T, R> {
+        Call::new(self.id.clone().to_id().unwrap_or(Id::Null))
+    }
+}
+
+impl<P, R> Call<P, R> {
+    /// The Call of the Request `id`.
+    pub fn new(id: Id) -> Self {
+        Call {
+            id: id,
+            types: PhantomData,
+        }
+    }
+
+    /// The `id` of the Request.
+    pub fn id(&self) -> &Id {
+        &self.id
+    }
+}
+
+impl<P, R: DeserializeOwned> Call<P, R> {
+    /// Parse the json of the Response, returning its `result` or `error` object.
+    ///
+    /// Invalid json or a Response whose `result` is not an `R` is reported as a `ParseError`.
+    pub fn parse_response(&self, json: &str) -> Result<R, ErrorObject<Value>> {
+        let response: Response<R> = serde_json::from_str(json).map_err(|err| ErrorObject {
+            code: ErrorCode::ParseError,
+            message: err.to_string(),
+            data: None,
+        })?;
+        self.result(response)
+    }
+
+    /// Return the `result` or `error` object of the Response, if it answers this Request.
+    pub fn result(&self, response: Response<R>) -> Result<R, ErrorObject<Value>> {
+        match response {
+            Response::Ok(ref success) if success.id != self.id => Err(self.mismatch(&success.id)),
+            Response::Err(ref error) if error.id != self.id && error.id != Id::Null => {
+                Err(self.mismatch(&error.id))
+            }
+            Response::Ok(success) => Ok(success.result),
+            Response::Err(error) => Err(error.error),
+        }
+    }
+
+    fn mismatch(&self, received: &Id) -> ErrorObject<Value> {
+        let mut data = serde_json::Map::new();
+        data.insert("expected".to_string(), id_value(&self.id));
+        data.insert("received".to_string(), id_value(received));
+        ErrorObject {
+            code: ErrorCode::InternalError,
+            message: "the Response does not answer the Request".to_string(),
+            data: Some(Value::Object(data)),
+        }
+    }
+}
+
+fn id_value(id: &Id) -> Value {
+    serde_json::to_value(id).expect("ids are always serializable")
+}
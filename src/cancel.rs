@@ -0,0 +1,337 @@
+//! Cancellation of requests.
+//!
+//! # Protocol
+//!
+//! A client cancels a Request it sent with a `$/cancelRequest` Notification carrying the `id`
+//! of the Request, as in the Language Server Protocol:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "method": "$/cancelRequest", "params": {"id": 4}}
+//! ```
+//!
+//! The server still answers the Request, either with its result if it completed anyway, or with
+//! the [`REQUEST_CANCELLED`](constant.REQUEST_CANCELLED.html) error. Clients create the
+//! Notification with [`Notification::cancel`](../struct.Notification.html#method.cancel) and
+//! servers recognize it in the incoming [`Message`](../enum.Message.html)s with
+//! [`cancelled_id`](fn.cancelled_id.html):
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{Id, Message, Notification};
+//! use jrpc::cancel;
+//!
+//! # fn main() {
+//! let json = Notification::cancel(Id::from(4)).to_string();
+//! assert_eq!(json, r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":4}}"#);
+//!
+//! let message: Message = serde_json::from_str(&json).unwrap();
+//! assert_eq!(cancel::cancelled_id(&message), Some(Id::from(4)));
+//! # }
+//! ```
+//!
+//! # Cancelling handlers
+//!
+//! > Only available with the `async` feature.
+//!
+//! A [`CancelToken`](struct.CancelToken.html) is shared between the connection, which cancels
+//! it, e.g. when the client sends `$/cancelRequest` or disconnects, and the handler, which is
+//! wrapped with [`cancellable`](fn.cancellable.html). The wrapped handler:
+//!
+//! - resolves to the [`REQUEST_CANCELLED`](constant.REQUEST_CANCELLED.html) error as soon as the
+//!   token is cancelled, dropping the handler's future,
+//! - runs its [`on_cancel`](struct.Cancellable.html#method.on_cancel) closures when it is
+//!   cancelled, or when it is dropped before completing (e.g. by a `select` on a timeout), so
+//!   the cleanup is not skipped by whoever drops it,
+//! - with [`run_to_completion`](struct.Cancellable.html#method.run_to_completion), ignores the
+//!   token once the handler has started, for handlers which must not be interrupted halfway.
+//!
+//! Handler authors therefore do not have to race their work against the token themselves.
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! # #[cfg(feature = "async")]
+//! # fn main() {
+//! use std::future::{self, Future};
+//! use std::pin::Pin;
+//! use std::sync::Arc;
+//! use std::sync::atomic::{AtomicBool, Ordering};
+//! use std::task::{Context, Poll, Waker};
+//!
+//! use jrpc::ErrorCode;
+//! use jrpc::cancel::{self, CancelToken, REQUEST_CANCELLED};
+//!
+//! let mut cx = Context::from_waker(Waker::noop());
+//! let token = CancelToken::new();
+//! let cleaned = Arc::new(AtomicBool::new(false));
+//!
+//! let flag = cleaned.clone();
+//! let mut handler = cancel::cancellable(future::pending::<u32>(), &token)
+//!     .on_cancel(move || flag.store(true, Ordering::SeqCst));
+//! assert!(Pin::new(&mut handler).poll(&mut cx).is_pending());
+//!
+//! token.cancel();
+//! match Pin::new(&mut handler).poll(&mut cx) {
+//!     Poll::Ready(Err(error)) => assert_eq!(error.code, ErrorCode::ServerError(REQUEST_CANCELLED)),
+//!     _ => unreachable!(),
+//! }
+//! assert!(cleaned.load(Ordering::SeqCst));
+//!
+//! // Handlers which complete are not cleaned up.
+//! let mut handler = cancel::cancellable(future::ready(4), &CancelToken::new())
+//!     .on_cancel(|| panic!("not cancelled"));
+//! match Pin::new(&mut handler).poll(&mut cx) {
+//!     Poll::Ready(Ok(4)) => {}
+//!     _ => unreachable!(),
+//! }
+//! # }
+//! # #[cfg(not(feature = "async"))]
+//! # fn main() {}
+//! ```
+
+#[cfg(feature = "async")]
+use std::fmt;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::mem;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+
+use serde_json;
+use std_prelude::*;
+
+use {ErrorCode, ErrorObject, Id, Message, Notification, Value};
+
+/// The method of the Notification cancelling a Request.
+pub const CANCEL_METHOD: &str = "$/cancelRequest";
+
+/// The error code of cancelled requests, as in the Language Server Protocol.
+pub const REQUEST_CANCELLED: i64 = -32800;
+
+/// The error a cancelled handler resolves to.
+pub fn cancelled_error() -> ErrorObject<Value> {
+    ErrorObject {
+        code: ErrorCode::ServerError(REQUEST_CANCELLED),
+        message: "request cancelled".to_string(),
+        data: None,
+    }
+}
+
+/// The params of the [`CANCEL_METHOD`](constant.CANCEL_METHOD.html) Notification.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CancelParams {
+    /// The `id` of the Request to cancel.
+    pub id: Id,
+}
+
+impl Notification<String, CancelParams> {
+    /// Create the Notification cancelling the Request with `id`.
+    pub fn cancel(id: Id) -> Self {
+        Notification::with_params(CANCEL_METHOD.to_string(), CancelParams { id: id })
+    }
+}
+
+/// The `id` of the Request cancelled by `message`, if it is a cancellation Notification.
+///
+/// Cancellations with malformed params are not recognized, so they are ignored like any other
+/// unknown Notification.
+pub fn cancelled_id(message: &Message) -> Option<Id> {
+    match *message {
+        Message::Notification(ref notification) if notification.method == CANCEL_METHOD => {
+            let params = notification.params.clone()?;
+            serde_json::from_value::<CancelParams>(params)
+                .ok()
+                .map(|params| params.id)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(feature = "async")]
+struct State {
+    cancelled: bool,
+    wakers: Vec<Waker>,
+}
+
+#[cfg(feature = "async")]
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Signals the cancellation of one or more handlers. Clones share the same state.
+#[cfg(feature = "async")]
+#[derive(Clone)]
+pub struct CancelToken {
+    state: Arc<Mutex<State>>,
+}
+
+#[cfg(feature = "async")]
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CancelToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+#[cfg(feature = "async")]
+impl Default for CancelToken {
+    fn default() -> Self {
+        CancelToken::new()
+    }
+}
+
+#[cfg(feature = "async")]
+impl CancelToken {
+    /// Create a token which is not cancelled.
+    pub fn new() -> Self {
+        CancelToken {
+            state: Arc::new(Mutex::new(State {
+                cancelled: false,
+                wakers: Vec::new(),
+            })),
+        }
+    }
+
+    /// Cancel the handlers of the token, waking them.
+    pub fn cancel(&self) {
+        let wakers = {
+            let mut state = lock(&self.state);
+            state.cancelled = true;
+            mem::take(&mut state.wakers)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        lock(&self.state).cancelled
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut state = lock(&self.state);
+        if !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+type OnCancel = Box<dyn FnOnce() + Send>;
+
+/// A handler which resolves to the [`REQUEST_CANCELLED`](constant.REQUEST_CANCELLED.html) error
+/// when its token is cancelled. Created by [`cancellable`](fn.cancellable.html).
+#[cfg(feature = "async")]
+pub struct Cancellable<F> {
+    future: Option<Pin<Box<F>>>,
+    token: CancelToken,
+    on_cancel: Vec<OnCancel>,
+    run_to_completion: bool,
+    started: bool,
+}
+
+#[cfg(feature = "async")]
+impl<F> fmt::Debug for Cancellable<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cancellable")
+            .field("token", &self.token)
+            .field("done", &self.future.is_none())
+            .field("run_to_completion", &self.run_to_completion)
+            .finish()
+    }
+}
+
+/// Wrap a handler, cancelling it with `token`.
+#[cfg(feature = "async")]
+pub fn cancellable<F: Future>(future: F, token: &CancelToken) -> Cancellable<F> {
+    Cancellable {
+        future: Some(Box::pin(future)),
+        token: token.clone(),
+        on_cancel: Vec::new(),
+        run_to_completion: false,
+        started: false,
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F: Future> Cancellable<F> {
+    /// Call `f` if the handler is cancelled or dropped before it completes. Closures are called
+    /// in the reverse order they were added, after the handler's future is dropped.
+    pub fn on_cancel<C: FnOnce() + Send + 'static>(mut self, f: C) -> Self {
+        self.on_cancel.push(Box::new(f));
+        self
+    }
+
+    /// Ignore the token once the handler was polled, so it always runs to completion. A token
+    /// cancelled before the first poll still cancels the handler.
+    ///
+    /// Dropping the handler still drops its future, and calls the `on_cancel` closures.
+    pub fn run_to_completion(mut self) -> Self {
+        self.run_to_completion = true;
+        self
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F> Cancellable<F> {
+    fn cancel(&mut self) {
+        self.future = None;
+        while let Some(f) = self.on_cancel.pop() {
+            f();
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F: Future> Future for Cancellable<F> {
+    type Output = Result<F::Output, ErrorObject<Value>>;
+
+    /// # Panics
+    ///
+    /// If polled after it resolved.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.future.is_none() {
+            panic!("Cancellable polled after it resolved");
+        }
+        let shielded = this.run_to_completion && this.started;
+        if !shielded && this.token.is_cancelled() {
+            this.cancel();
+            return Poll::Ready(Err(cancelled_error()));
+        }
+        this.started = true;
+        let output = match this.future {
+            Some(ref mut future) => future.as_mut().poll(cx),
+            None => unreachable!(),
+        };
+        match output {
+            Poll::Ready(output) => {
+                this.future = None;
+                this.on_cancel.clear();
+                Poll::Ready(Ok(output))
+            }
+            Poll::Pending => {
+                if !this.run_to_completion {
+                    this.token.register(cx.waker());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F> Drop for Cancellable<F> {
+    fn drop(&mut self) {
+        if self.future.is_some() {
+            self.cancel();
+        }
+    }
+}
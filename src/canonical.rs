@@ -0,0 +1,186 @@
+//! Deterministic ordering of batch entries.
+//!
+//! The spec allows the entries of a batch to be processed and returned in any order. Systems
+//! where every node must produce byte-for-byte identical batches (e.g. for consensus or
+//! signatures) can instead agree on the canonical order implemented here:
+//!
+//! 1. by `id`: notifications first, then Null, then Numbers in ascending order, then Strings in
+//!    lexicographic (byte) order. Any other (invalid) `id` sorts last, by its json text.
+//! 2. then by `method` (Responses have no method and compare equal on it).
+//!
+//! Sorting is stable, so entries with equal keys keep their relative order. Together with
+//! serde_json's sorted Object members this makes [`to_string`](fn.to_string.html) deterministic.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{Id, Request};
+//! use jrpc::canonical;
+//!
+//! # fn main() {
+//! let mut batch = vec![
+//!     Request::new(Id::from("b"), "Foo".to_string()),
+//!     Request::new(Id::from(2), "Foo".to_string()),
+//!     Request::new(Id::from(1), "Foo".to_string()),
+//! ];
+//! assert_eq!(canonical::check_sorted(&batch), Err(canonical::Unsorted { index: 1 }));
+//!
+//! let json = canonical::to_string(&mut batch);
+//! assert_eq!(batch[0].id, Id::from(1).into());
+//! assert_eq!(batch[2].id, Id::from("b").into());
+//! assert!(json.starts_with(r#"[{"jsonrpc":"2.0","method":"Foo","params":null,"id":1},"#));
+//! assert_eq!(canonical::check_sorted(&batch), Ok(()));
+//! # }
+//! ```
+
+use std::cmp::Ordering;
+use std::error;
+use std::fmt;
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {Id, IdReq, Request, Response, Value};
+
+/// The `id` part of the canonical sort key. The variants are in canonical order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IdKey {
+    /// The Request is a notification.
+    Notification,
+    /// A Null id.
+    Null,
+    /// A Number id.
+    Int(i64),
+    /// A String id.
+    String(String),
+    /// Any other value, as its json text.
+    Other(String),
+}
+
+impl<'a> From<&'a Id> for IdKey {
+    fn from(id: &'a Id) -> Self {
+        match *id {
+            Id::Null => IdKey::Null,
+            Id::Int(v) => IdKey::Int(v),
+            Id::String(ref s) => IdKey::String(s.clone()),
+        }
+    }
+}
+
+impl<'a> From<&'a IdReq> for IdKey {
+    fn from(id: &'a IdReq) -> Self {
+        match *id {
+            IdReq::Notification => IdKey::Notification,
+            IdReq::Null => IdKey::Null,
+            IdReq::Int(v) => IdKey::Int(v),
+            IdReq::String(ref s) => IdKey::String(s.clone()),
+        }
+    }
+}
+
+/// The canonical sort key of a batch entry.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SortKey {
+    /// The entry's `id`.
+    pub id: IdKey,
+    /// The entry's `method`, empty for Responses. Non-String methods use their json text.
+    pub method: String,
+}
+
+/// A message which can be part of a canonically ordered batch.
+pub trait BatchEntry {
+    /// The canonical sort key of the entry.
+    fn sort_key(&self) -> SortKey;
+}
+
+impl<M: Serialize, T> BatchEntry for Request<M, T> {
+    fn sort_key(&self) -> SortKey {
+        let method = serde_json::to_value(&self.method).unwrap_or(Value::Null);
+        SortKey {
+            id: IdKey::from(&self.id),
+            method: method_text(&method),
+        }
+    }
+}
+
+impl<T, E> BatchEntry for Response<T, E> {
+    fn sort_key(&self) -> SortKey {
+        let id = match *self {
+            Response::Ok(ref r) => &r.id,
+            Response::Err(ref e) => &e.id,
+        };
+        SortKey {
+            id: IdKey::from(id),
+            method: String::new(),
+        }
+    }
+}
+
+/// Raw messages, which need not be valid.
+impl BatchEntry for Value {
+    fn sort_key(&self) -> SortKey {
+        let id = match self.get("id") {
+            None => IdKey::Notification,
+            Some(Value::Null) => IdKey::Null,
+            Some(Value::String(s)) => IdKey::String(s.clone()),
+            Some(id) => match id.as_i64() {
+                Some(v) => IdKey::Int(v),
+                None => IdKey::Other(id.to_string()),
+            },
+        };
+        SortKey {
+            id: id,
+            method: self.get("method").map(method_text).unwrap_or_default(),
+        }
+    }
+}
+
+fn method_text(method: &Value) -> String {
+    match *method {
+        Value::String(ref s) => s.clone(),
+        ref other => other.to_string(),
+    }
+}
+
+/// Sort a batch into canonical order.
+pub fn sort<E: BatchEntry>(batch: &mut [E]) {
+    batch.sort_by_cached_key(BatchEntry::sort_key);
+}
+
+/// Verify that a received batch is in canonical order.
+pub fn check_sorted<E: BatchEntry>(batch: &[E]) -> Result<(), Unsorted> {
+    let mut previous: Option<SortKey> = None;
+    for (index, entry) in batch.iter().enumerate() {
+        let key = entry.sort_key();
+        if let Some(ref previous) = previous {
+            if previous.cmp(&key) == Ordering::Greater {
+                return Err(Unsorted { index: index });
+            }
+        }
+        previous = Some(key);
+    }
+    Ok(())
+}
+
+/// Sort a batch into canonical order and serialize it as compact json.
+pub fn to_string<E: BatchEntry + Serialize>(batch: &mut [E]) -> String {
+    sort(batch);
+    serde_json::to_string(batch).expect("batch entries are always serializable")
+}
+
+/// The error returned for a batch which is not in canonical order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unsorted {
+    /// The index of the first entry which sorts before its predecessor.
+    pub index: usize,
+}
+
+impl fmt::Display for Unsorted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "batch entry {} is out of canonical order", self.index)
+    }
+}
+
+impl error::Error for Unsorted {}
@@ -0,0 +1,318 @@
+//! A machine readable catalog of methods and the errors they return.
+//!
+//! An [`ErrorCatalog`](struct.ErrorCatalog.html) documents every error code a server uses and,
+//! for every method, which of them it may return and the schemas of its `params`, `result`
+//! and error `data`. It is serde serializable, for client SDK generators and documentation
+//! sites.
+//!
+//! [`ErrorCatalog::standard`](struct.ErrorCatalog.html#method.standard) starts with the codes of
+//! the specification and those used by this crate's extensions.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::catalog::{ErrorCatalog, ErrorDoc, MethodDoc};
+//!
+//! # fn main() {
+//! let catalog = ErrorCatalog::standard()
+//!     .error(
+//!         ErrorDoc::new(-32001, "InsufficientFunds", "The account balance is too low.")
+//!             .data_schema(json!({"type": "object", "properties": {"balance": {"type": "integer"}}})),
+//!     )
+//!     .method(
+//!         MethodDoc::new("transfer")
+//!             .params_schema(json!({"type": "array"}))
+//!             .errors(&[-32602, -32001]),
+//!     );
+//! catalog.validate().unwrap();
+//!
+//! let value = serde_json::to_value(&catalog).unwrap();
+//! assert_eq!(value["methods"][0]["errors"], json!([-32602, -32001]));
+//! assert_eq!(catalog.errors_of("transfer")[1].name, "InsufficientFunds");
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use std_prelude::*;
+
+use method::Method;
+use {affinity, backoff, deadline, resume, tenant, via, ErrorCode, Value};
+
+/// The documentation of an error code.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorDoc {
+    /// The error code.
+    pub code: i64,
+
+    /// A short identifier, e.g. for an SDK's error type.
+    pub name: String,
+
+    /// What the error means.
+    pub description: String,
+
+    /// The JSON Schema of the error's `data`, if it has any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_schema: Option<Value>,
+}
+
+impl ErrorDoc {
+    /// Document an error code.
+    pub fn new<N: Into<String>, D: Into<String>>(code: i64, name: N, description: D) -> Self {
+        ErrorDoc {
+            code: code,
+            name: name.into(),
+            description: description.into(),
+            data_schema: None,
+        }
+    }
+
+    /// Set the schema of the error's `data`.
+    pub fn data_schema(mut self, schema: Value) -> Self {
+        self.data_schema = Some(schema);
+        self
+    }
+}
+
+/// The documentation of a method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MethodDoc {
+    /// The method name.
+    pub name: String,
+
+    /// The JSON Schema of the `params`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params_schema: Option<Value>,
+
+    /// The JSON Schema of the `result`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_schema: Option<Value>,
+
+    /// The codes the method may return, documented in the catalog's `errors`.
+    #[serde(default)]
+    pub errors: Vec<i64>,
+}
+
+impl MethodDoc {
+    /// Document a method.
+    pub fn new<N: Into<String>>(name: N) -> Self {
+        MethodDoc {
+            name: name.into(),
+            params_schema: None,
+            result_schema: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Document a typed [`Method`](../method/trait.Method.html).
+    pub fn of<M: Method>() -> Self {
+        MethodDoc::new(M::name().into_owned())
+    }
+
+    /// Set the schema of the `params`.
+    pub fn params_schema(mut self, schema: Value) -> Self {
+        self.params_schema = Some(schema);
+        self
+    }
+
+    /// Set the schema of the `result`.
+    pub fn result_schema(mut self, schema: Value) -> Self {
+        self.result_schema = Some(schema);
+        self
+    }
+
+    /// Add error codes the method may return.
+    pub fn errors(mut self, codes: &[i64]) -> Self {
+        self.errors.extend_from_slice(codes);
+        self
+    }
+}
+
+/// The catalog of a server's error codes and methods.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ErrorCatalog {
+    /// The error codes, in the order they were added.
+    pub errors: Vec<ErrorDoc>,
+
+    /// The methods, in the order they were added.
+    pub methods: Vec<MethodDoc>,
+}
+
+impl ErrorCatalog {
+    /// Create an empty catalog.
+    pub fn new() -> Self {
+        ErrorCatalog::default()
+    }
+
+    /// Create a catalog with the error codes of the specification and of this crate's
+    /// extensions.
+    pub fn standard() -> Self {
+        let spec = [
+            (
+                ErrorCode::ParseError,
+                "ParseError",
+                "Invalid JSON was received by the server.",
+            ),
+            (
+                ErrorCode::InvalidRequest,
+                "InvalidRequest",
+                "The JSON sent is not a valid Request object.",
+            ),
+            (
+                ErrorCode::MethodNotFound,
+                "MethodNotFound",
+                "The method does not exist / is not available.",
+            ),
+            (
+                ErrorCode::InvalidParams,
+                "InvalidParams",
+                "Invalid method parameter(s).",
+            ),
+            (
+                ErrorCode::InternalError,
+                "InternalError",
+                "Internal JSON-RPC error.",
+            ),
+        ];
+        let extensions = [
+            (
+                tenant::UNKNOWN_TENANT,
+                "UnknownTenant",
+                "The tenant is not known to the server.",
+            ),
+            (
+                tenant::LIMIT_EXCEEDED,
+                "LimitExceeded",
+                "The tenant exceeded its limits.",
+            ),
+            (
+                via::LOOP_DETECTED,
+                "LoopDetected",
+                "The Request was already forwarded by this gateway.",
+            ),
+            (
+                resume::RESUME_FAILED,
+                "ResumeFailed",
+                "The session can not be resumed.",
+            ),
+            (
+                backoff::OVERLOADED,
+                "Overloaded",
+                "The server is overloaded; retry after the pause in the data.",
+            ),
+            (
+                deadline::DEADLINE_EXCEEDED,
+                "DeadlineExceeded",
+                "The deadline of the call passed.",
+            ),
+            (
+                affinity::AFFINITY_REJECTED,
+                "AffinityRejected",
+                "The affinity token is forged or malformed; drop it.",
+            ),
+        ];
+        let mut catalog = ErrorCatalog::new();
+        for &(code, name, description) in &spec {
+            catalog = catalog.error(ErrorDoc::new(code.code(), name, description));
+        }
+        for &(code, name, description) in &extensions {
+            catalog = catalog.error(ErrorDoc::new(code, name, description));
+        }
+        #[cfg(feature = "async")]
+        {
+            catalog = catalog.error(ErrorDoc::new(
+                ::stream::LAGGED,
+                "Lagged",
+                "Notifications of the subscription were dropped.",
+            ));
+        }
+        catalog
+    }
+
+    /// Add an error code, replacing an existing one with the same code.
+    pub fn error(mut self, doc: ErrorDoc) -> Self {
+        match self.errors.iter().position(|e| e.code == doc.code) {
+            Some(i) => self.errors[i] = doc,
+            None => self.errors.push(doc),
+        }
+        self
+    }
+
+    /// Add a method, replacing an existing one with the same name.
+    pub fn method(mut self, doc: MethodDoc) -> Self {
+        match self.methods.iter().position(|m| m.name == doc.name) {
+            Some(i) => self.methods[i] = doc,
+            None => self.methods.push(doc),
+        }
+        self
+    }
+
+    /// Get the documentation of an error code.
+    pub fn get_error(&self, code: i64) -> Option<&ErrorDoc> {
+        self.errors.iter().find(|e| e.code == code)
+    }
+
+    /// Get the documentation of a method.
+    pub fn get_method(&self, name: &str) -> Option<&MethodDoc> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    /// The documented errors a method may return, in the method's order. Undocumented codes
+    /// are skipped.
+    pub fn errors_of(&self, method: &str) -> Vec<&ErrorDoc> {
+        self.get_method(method)
+            .map(|m| m.errors.iter().filter_map(|&c| self.get_error(c)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Check that the codes are valid and every code referenced by a method is documented.
+    pub fn validate(&self) -> Result<(), CatalogError> {
+        for doc in &self.errors {
+            if !ErrorCode::from(doc.code).is_valid() {
+                return Err(CatalogError::InvalidCode(doc.code));
+            }
+        }
+        for method in &self.methods {
+            for &code in &method.errors {
+                if self.get_error(code).is_none() {
+                    return Err(CatalogError::Undocumented {
+                        method: method.name.clone(),
+                        code: code,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The error returned by [`ErrorCatalog::validate`](struct.ErrorCatalog.html#method.validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogError {
+    /// The code is neither one of the specification nor a valid server error code.
+    InvalidCode(i64),
+    /// The method references an undocumented code.
+    Undocumented {
+        /// The method name.
+        method: String,
+        /// The code.
+        code: i64,
+    },
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CatalogError::InvalidCode(code) => write!(f, "{} is not a valid error code", code),
+            CatalogError::Undocumented { ref method, code } => {
+                write!(f, "method `{}` returns undocumented code {}", method, code)
+            }
+        }
+    }
+}
+
+impl error::Error for CatalogError {}
@@ -0,0 +1,54 @@
+//! Encoding messages as CBOR.
+//!
+//! > Only available with the `cbor` feature.
+//!
+//! The messages are encoded with [`ciborium`](https://docs.rs/ciborium), with the members of
+//! Objects by name, so a CBOR message has the same shape as its json. Any message type of this
+//! crate, or a batch of them, can be encoded and decoded.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! # #[cfg(feature = "cbor")]
+//! # fn main() {
+//! use jrpc::{Id, Request, Response, Value};
+//! use jrpc::cbor;
+//!
+//! let request = Request::with_params(Id::from(1), "subtract".to_string(), vec![42, 23]);
+//! let bytes = cbor::to_vec(&request).unwrap();
+//! let decoded: Request<String, Vec<u32>> = cbor::from_slice(&bytes).unwrap();
+//! assert_eq!(decoded, request);
+//!
+//! let response: Response<u32> = Response::success(Id::from(1), 19);
+//! let bytes = cbor::to_vec(&response).unwrap();
+//! assert_eq!(cbor::from_slice::<Response<u32>>(&bytes).unwrap(), response);
+//! # }
+//! # #[cfg(not(feature = "cbor"))]
+//! # fn main() {}
+//! ```
+
+use std::io;
+
+use ciborium;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std_prelude::*;
+
+/// The error returned when encoding a message fails.
+pub type EncodeError = ciborium::ser::Error<io::Error>;
+
+/// The error returned when decoding a message fails.
+pub type DecodeError = ciborium::de::Error<io::Error>;
+
+/// Encode a message as CBOR.
+pub fn to_vec<T: Serialize + ?Sized>(message: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(message, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decode a message from CBOR.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    ciborium::from_reader(bytes)
+}
@@ -0,0 +1,139 @@
+//! Coalescing of identical concurrent calls on the client.
+//!
+//! Read heavy clients often issue the same query several times before the first answer arrives.
+//! A [`Coalescer`](struct.Coalescer.html) sends only the first of a group of identical calls
+//! (same method and params) and fans its Response out to every caller, each with the `id` of its
+//! own Request.
+//!
+//! Calls are compared by the compact json serialization of their method and params, in which
+//! Object members are sorted, so member order does not matter. The json itself is the key, not a
+//! hash of it, so different calls are never coalesced. Only coalesce methods without side
+//! effects.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::{Id, Request, Response, Value};
+//! use jrpc::coalesce::Coalescer;
+//!
+//! # fn main() {
+//! let mut coalescer = Coalescer::new();
+//!
+//! let first = Request::with_params(Id::from(1), "get".to_string(), json!({"a": 1, "b": 2}));
+//! let second = Request::with_params(Id::from(2), "get".to_string(), json!({"b": 2, "a": 1}));
+//! assert!(coalescer.call(&first));
+//! assert!(!coalescer.call(&second)); // joined the first, do not send it
+//!
+//! let responses = coalescer.complete(Response::success(Id::from(1), Value::from("x")));
+//! assert_eq!(responses.len(), 2);
+//! assert_eq!(responses[1].id(), &Id::from(2));
+//! assert_eq!(responses[1].to_string(), r#"{"jsonrpc":"2.0","result":"x","id":2}"#);
+//! assert_eq!(coalescer.in_flight(), 0);
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use serde_json;
+use std_prelude::*;
+
+use {Error, Id, Request, Response, Value};
+
+/// Identifies a group of identical calls: the compact json of the method and params.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CallKey(pub String);
+
+impl CallKey {
+    /// The key of a call.
+    pub fn new(method: &str, params: Option<&Value>) -> Self {
+        CallKey(serde_json::to_string(&(method, params)).expect("calls are always serializable"))
+    }
+}
+
+/// Tracks in-flight calls and their waiters.
+#[derive(Debug, Default)]
+pub struct Coalescer {
+    /// The waiters of each in-flight call, the sent Request first.
+    waiters: HashMap<CallKey, Vec<Id>>,
+    /// The key of each sent Request, by its `id`.
+    sent: HashMap<Id, CallKey>,
+}
+
+impl Coalescer {
+    /// Create a coalescer without calls in flight.
+    pub fn new() -> Self {
+        Coalescer::default()
+    }
+
+    /// Register a call and return whether it must be sent.
+    ///
+    /// Returns `false` if an identical call is in flight, whose Response will also answer this
+    /// one. Notifications are never coalesced.
+    pub fn call<M: AsRef<str>>(&mut self, request: &Request<M, Value>) -> bool {
+        let id = match request.id.clone().to_id() {
+            Some(id) => id,
+            None => return true,
+        };
+        let key = CallKey::new(request.method.as_ref(), request.params.as_ref());
+        let waiters = self.waiters.entry(key.clone()).or_default();
+        waiters.push(id.clone());
+        if waiters.len() == 1 {
+            self.sent.insert(id, key);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Fan the Response to a sent Request out to all its waiters, each with its own `id`.
+    ///
+    /// A Response which does not belong to a sent Request is returned as is.
+    pub fn complete(&mut self, response: Response<Value>) -> Vec<Response<Value>> {
+        let waiters = self
+            .sent
+            .remove(response.id())
+            .and_then(|key| self.waiters.remove(&key));
+        let waiters = match waiters {
+            Some(waiters) => waiters,
+            None => return vec![response],
+        };
+        waiters
+            .into_iter()
+            .map(|id| with_id(&response, id))
+            .collect()
+    }
+
+    /// Forget a sent Request which will not be answered, e.g. after a timeout. Returns the ids
+    /// of all its waiters.
+    pub fn abandon(&mut self, id: &Id) -> Vec<Id> {
+        self.sent
+            .remove(id)
+            .and_then(|key| self.waiters.remove(&key))
+            .unwrap_or_default()
+    }
+
+    /// The number of sent Requests awaiting a Response.
+    pub fn in_flight(&self) -> usize {
+        self.sent.len()
+    }
+
+    /// The number of callers waiting for the call with `key`, including the one which sent it.
+    pub fn waiting(&self, key: &CallKey) -> usize {
+        self.waiters.get(key).map_or(0, Vec::len)
+    }
+}
+
+pub(crate) fn with_id(response: &Response<Value>, id: Id) -> Response<Value> {
+    match *response {
+        Response::Ok(ref success) => Response::success(id, success.result.clone()),
+        Response::Err(ref error) => Response::Err(Error::new(
+            id,
+            error.error.code,
+            error.error.message.clone(),
+            error.error.data.clone(),
+        )),
+    }
+}
@@ -0,0 +1,136 @@
+//! Well known error codes of the protocols built on JSON-RPC.
+//!
+//! > Only available with the `lsp-codes` and/or `eth-codes` features.
+//!
+//! Each protocol allocates its own codes outside of those of the specification, and the same
+//! number may mean different things in different protocols (e.g. `-32002` is
+//! `ServerNotInitialized` in the Language Server Protocol and `ResourceUnavailable` for
+//! Ethereum). The enums of this module convert into an [`ErrorCode`](../enum.ErrorCode.html)
+//! with `From` and back with `TryFrom`, so the numbers do not have to be hardcoded.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! # #[cfg(feature = "lsp-codes")]
+//! # fn main() {
+//! use std::convert::TryFrom;
+//! use jrpc::ErrorCode;
+//! use jrpc::codes::LspErrorCode;
+//!
+//! let code = ErrorCode::from(LspErrorCode::RequestCancelled);
+//! assert_eq!(code, ErrorCode::ServerError(-32800));
+//! assert_eq!(LspErrorCode::try_from(code), Ok(LspErrorCode::RequestCancelled));
+//! assert!(LspErrorCode::try_from(ErrorCode::InvalidParams).is_err());
+//! # }
+//! # #[cfg(not(feature = "lsp-codes"))]
+//! # fn main() {}
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use ErrorCode;
+
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[doc = $doc:expr])* $variant:ident = $code:expr,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[doc = $doc])* $variant,)*
+        }
+
+        impl $name {
+            /// All codes.
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            /// The numeric value of the code.
+            pub fn code(self) -> i64 {
+                match self {
+                    $($name::$variant => $code,)*
+                }
+            }
+
+            /// The code with a numeric value, if there is one.
+            pub fn from_code(code: i64) -> Option<$name> {
+                $name::ALL.iter().find(|c| c.code() == code).cloned()
+            }
+
+            /// The name of the code.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant),)*
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{} ({})", self.name(), self.code())
+            }
+        }
+
+        impl From<$name> for ErrorCode {
+            fn from(code: $name) -> ErrorCode {
+                ErrorCode::from(code.code())
+            }
+        }
+
+        impl TryFrom<ErrorCode> for $name {
+            type Error = ErrorCode;
+
+            fn try_from(code: ErrorCode) -> Result<$name, ErrorCode> {
+                $name::from_code(code.code()).ok_or(code)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "lsp-codes")]
+error_codes! {
+    /// The error codes of the Language Server Protocol.
+    ///
+    /// > Only available with the `lsp-codes` feature.
+    pub enum LspErrorCode {
+        /// `-32002`: a request was received before the `initialize` request.
+        ServerNotInitialized = -32002,
+        /// `-32001`: an unknown error.
+        UnknownErrorCode = -32001,
+        /// `-32803`: a syntactically correct request failed.
+        RequestFailed = -32803,
+        /// `-32802`: the server cancelled the request.
+        ServerCancelled = -32802,
+        /// `-32801`: the content of a document changed during the request.
+        ContentModified = -32801,
+        /// `-32800`: the client cancelled the request.
+        RequestCancelled = -32800,
+    }
+}
+
+#[cfg(feature = "eth-codes")]
+error_codes! {
+    /// The error codes of the Ethereum JSON-RPC API (EIP-1474).
+    ///
+    /// > Only available with the `eth-codes` feature.
+    pub enum EthErrorCode {
+        /// `-32000`: missing or invalid parameters.
+        InvalidInput = -32000,
+        /// `-32001`: the requested resource was not found.
+        ResourceNotFound = -32001,
+        /// `-32002`: the requested resource is not available.
+        ResourceUnavailable = -32002,
+        /// `-32003`: the transaction creation failed.
+        TransactionRejected = -32003,
+        /// `-32004`: the method is not implemented.
+        MethodNotSupported = -32004,
+        /// `-32005`: the request exceeds a defined limit.
+        LimitExceeded = -32005,
+        /// `-32006`: the version of the JSON-RPC protocol is not supported.
+        VersionNotSupported = -32006,
+    }
+}
@@ -0,0 +1,191 @@
+//! Checks that `params` and `result` types can evolve without downtime.
+//!
+//! During a rolling upgrade old and new peers talk to each other, so a changed type must be
+//! compatible in both directions:
+//!
+//! - **backward**: the new type decodes what old peers send, and re-encodes it such that the
+//!   old type decodes the same value (new members need `#[serde(default)]`).
+//! - **forward**: the old type decodes what new peers send (old peers must ignore new members,
+//!   i.e. no `#[serde(deny_unknown_fields)]`).
+//!
+//! The `assert_*` functions panic with a description of the incompatibility, for use in tests.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_derive;
+//! use jrpc::compat;
+//!
+//! #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+//! struct SearchV1 {
+//!     query: String,
+//! }
+//!
+//! #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+//! struct SearchV2 {
+//!     query: String,
+//!     #[serde(default)]
+//!     limit: Option<u32>,
+//! }
+//!
+//! #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+//! struct SearchV3 {
+//!     query: String,
+//!     limit: u32,
+//! }
+//!
+//! # fn main() {
+//! compat::assert_compatible::<SearchV1, SearchV2>();
+//! compat::assert_compatible_samples(
+//!     &[SearchV1 { query: "rust".into() }],
+//!     &[SearchV2 { query: "rust".into(), limit: Some(10) }],
+//! );
+//!
+//! // `limit` is required, so V3 can not decode what V1 peers send.
+//! assert!(compat::check_backward::<SearchV1, SearchV3>(&SearchV1::default()).is_err());
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use Value;
+
+/// Check that `New` decodes `old`, and that re-encoding the result decodes as `old` again.
+pub fn check_backward<Old, New>(old: &Old) -> Result<(), CompatError>
+where
+    Old: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+    New: Serialize + DeserializeOwned,
+{
+    let json = to_value(old);
+    let new: New = from_value(Direction::Backward, &json)?;
+    let round_trip: Old = from_value(Direction::Backward, &to_value(&new))?;
+    if round_trip != *old {
+        return Err(CompatError::Lost {
+            sent: json,
+            round_trip: format!("{:?}", round_trip),
+        });
+    }
+    Ok(())
+}
+
+/// Check that `Old` decodes `new`.
+pub fn check_forward<Old, New>(new: &New) -> Result<(), CompatError>
+where
+    Old: DeserializeOwned,
+    New: Serialize,
+{
+    from_value::<Old>(Direction::Forward, &to_value(new)).map(|_| ())
+}
+
+/// Assert that the default values of `Old` and `New` are compatible in both directions.
+///
+/// # Panics
+///
+/// If they are not compatible.
+pub fn assert_compatible<Old, New>()
+where
+    Old: Serialize + DeserializeOwned + PartialEq + fmt::Debug + Default,
+    New: Serialize + DeserializeOwned + Default,
+{
+    assert_compatible_samples::<Old, New>(&[Old::default()], &[New::default()]);
+}
+
+/// Assert that `Old` and `New` are compatible in both directions for the given samples.
+///
+/// # Panics
+///
+/// If a sample is not compatible.
+pub fn assert_compatible_samples<Old, New>(old: &[Old], new: &[New])
+where
+    Old: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+    New: Serialize + DeserializeOwned,
+{
+    for sample in old {
+        if let Err(err) = check_backward::<Old, New>(sample) {
+            panic!("not backward compatible: {}", err);
+        }
+    }
+    for sample in new {
+        if let Err(err) = check_forward::<Old, New>(sample) {
+            panic!("not forward compatible: {}", err);
+        }
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).expect("samples must be serializable")
+}
+
+fn from_value<T: DeserializeOwned>(direction: Direction, json: &Value) -> Result<T, CompatError> {
+    serde_json::from_value(json.clone()).map_err(|err| CompatError::Decode {
+        direction: direction,
+        sent: json.clone(),
+        message: err.to_string(),
+    })
+}
+
+/// The direction of a compatibility check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// New peers receiving from old peers.
+    Backward,
+    /// Old peers receiving from new peers.
+    Forward,
+}
+
+/// An incompatibility found by a check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompatError {
+    /// The receiving type failed to decode the json sent.
+    Decode {
+        /// The direction of the check.
+        direction: Direction,
+        /// The json sent.
+        sent: Value,
+        /// The decoding error.
+        message: String,
+    },
+    /// The json sent by an old peer did not survive the round trip through the new type.
+    Lost {
+        /// The json sent.
+        sent: Value,
+        /// The old value decoded after the round trip.
+        round_trip: String,
+    },
+}
+
+impl fmt::Display for CompatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompatError::Decode {
+                direction,
+                ref sent,
+                ref message,
+            } => {
+                let receiver = match direction {
+                    Direction::Backward => "new",
+                    Direction::Forward => "old",
+                };
+                write!(
+                    f,
+                    "the {} type can not decode {}: {}",
+                    receiver, sent, message
+                )
+            }
+            CompatError::Lost {
+                ref sent,
+                ref round_trip,
+            } => write!(f, "{} changed to {} through the new type", sent, round_trip),
+        }
+    }
+}
+
+impl error::Error for CompatError {}
@@ -0,0 +1,273 @@
+//! Deciding whether to compress outbound frames.
+//!
+//! Every transport of a server should compress the same frames, so the decision is made by a
+//! shared [`CompressionPolicy`](struct.CompressionPolicy.html) rather than by each transport.
+//! A frame is compressed if:
+//!
+//! - it is at least [`min_size`](struct.CompressionPolicy.html#structfield.min_size) bytes:
+//!   compressing small frames costs more than it saves,
+//! - its content type is not one of the
+//!   [`incompressible`](struct.CompressionPolicy.html#structfield.incompressible) types (images,
+//!   archives, ... are already compressed),
+//! - its byte entropy is at most
+//!   [`max_entropy`](struct.CompressionPolicy.html#structfield.max_entropy) bits per byte: data
+//!   close to 8 bits per byte is random or already compressed, e.g. base64 decoded blobs.
+//!
+//! The [`Decision`](struct.Decision.html) holds the reason and the measured
+//! [`FrameStats`](struct.FrameStats.html), and [`CompressionStats`](struct.CompressionStats.html)
+//! aggregates decisions for metrics. This module does not compress anything itself.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::compress::{CompressionPolicy, CompressionStats, Reason};
+//!
+//! # fn main() {
+//! let policy = CompressionPolicy::default();
+//! let mut stats = CompressionStats::new();
+//!
+//! let small = br#"{"jsonrpc":"2.0","result":1,"id":1}"#;
+//! let decision = policy.decide(small, "application/json");
+//! assert!(!decision.compress);
+//! assert_eq!(decision.reason, Reason::TooSmall);
+//! stats.record(&decision);
+//!
+//! let large = format!(r#"{{"jsonrpc":"2.0","result":[{}],"id":1}}"#, vec!["1"; 1000].join(","));
+//! let decision = policy.decide(large.as_bytes(), "application/json");
+//! assert!(decision.compress);
+//! assert!(decision.stats.entropy < 2.0);
+//! stats.record(&decision);
+//!
+//! let decision = policy.decide(large.as_bytes(), "image/png");
+//! assert_eq!(decision.reason, Reason::ContentType);
+//! stats.record(&decision);
+//!
+//! assert_eq!(stats.compressed, 1);
+//! assert_eq!(stats.skipped["too_small"], 1);
+//! # }
+//! ```
+
+use std_prelude::*;
+
+/// Whether and why a frame should be compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    /// The frame should be compressed.
+    Compressible,
+    /// The frame is smaller than the minimum size.
+    TooSmall,
+    /// The content type is already compressed.
+    ContentType,
+    /// The bytes of the frame look random or already compressed.
+    HighEntropy,
+    /// Compression is disabled.
+    Disabled,
+}
+
+impl Reason {
+    /// The name of the reason, as serialized.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Reason::Compressible => "compressible",
+            Reason::TooSmall => "too_small",
+            Reason::ContentType => "content_type",
+            Reason::HighEntropy => "high_entropy",
+            Reason::Disabled => "disabled",
+        }
+    }
+}
+
+/// Measurements of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FrameStats {
+    /// The size of the frame, in bytes.
+    pub size: usize,
+
+    /// The Shannon entropy of the bytes of the frame, in bits per byte (`0` to `8`). Only
+    /// measured for frames which pass the size and content type checks, `0` otherwise.
+    pub entropy: f64,
+}
+
+/// The decision for a single frame.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    /// Whether to compress the frame.
+    pub compress: bool,
+
+    /// Why.
+    pub reason: Reason,
+
+    /// The measurements the decision is based on.
+    pub stats: FrameStats,
+}
+
+/// Decides whether frames should be compressed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionPolicy {
+    /// Whether to compress at all. Default: true.
+    pub enabled: bool,
+
+    /// The size, in bytes, from which frames are compressed. Default: 1024.
+    pub min_size: usize,
+
+    /// The entropy, in bits per byte, above which frames are not compressed. Default: 7.5.
+    pub max_entropy: f64,
+
+    /// Content types which are never compressed. Entries ending with `/*` match every subtype,
+    /// parameters such as `; charset=utf-8` are ignored. Default: images, audio, video and
+    /// common archive formats.
+    pub incompressible: Vec<String>,
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> CompressionPolicy {
+        CompressionPolicy {
+            enabled: true,
+            min_size: 1024,
+            max_entropy: 7.5,
+            incompressible: [
+                "image/*",
+                "audio/*",
+                "video/*",
+                "application/gzip",
+                "application/zip",
+                "application/zstd",
+                "application/x-bzip2",
+                "application/x-xz",
+            ]
+            .iter()
+            .map(|t| t.to_string())
+            .collect(),
+        }
+    }
+}
+
+impl CompressionPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        CompressionPolicy::default()
+    }
+
+    /// Set the minimum size.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Set the maximum entropy.
+    pub fn max_entropy(mut self, max_entropy: f64) -> Self {
+        self.max_entropy = max_entropy;
+        self
+    }
+
+    /// Never compress a content type.
+    pub fn incompressible<S: Into<String>>(mut self, content_type: S) -> Self {
+        self.incompressible.push(content_type.into());
+        self
+    }
+
+    /// Whether the content type is never compressed.
+    pub fn is_incompressible(&self, content_type: &str) -> bool {
+        let content_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        self.incompressible
+            .iter()
+            .any(|pattern| match pattern.strip_suffix("/*") {
+                Some(kind) => content_type.split('/').next() == Some(kind),
+                None => *pattern == content_type,
+            })
+    }
+
+    /// Decide whether to compress a serialized frame.
+    pub fn decide(&self, frame: &[u8], content_type: &str) -> Decision {
+        let mut stats = FrameStats {
+            size: frame.len(),
+            entropy: 0.0,
+        };
+        let reason = if !self.enabled {
+            Reason::Disabled
+        } else if frame.len() < self.min_size {
+            Reason::TooSmall
+        } else if self.is_incompressible(content_type) {
+            Reason::ContentType
+        } else {
+            stats.entropy = entropy(frame);
+            if stats.entropy > self.max_entropy {
+                Reason::HighEntropy
+            } else {
+                Reason::Compressible
+            }
+        };
+        Decision {
+            compress: reason == Reason::Compressible,
+            reason: reason,
+            stats: stats,
+        }
+    }
+}
+
+/// The Shannon entropy of `bytes`, in bits per byte.
+fn entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    let len = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Aggregated compression decisions, e.g. for the metrics of a transport.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionStats {
+    /// The number of frames to compress.
+    pub compressed: u64,
+
+    /// The bytes of the frames to compress, before compression.
+    pub compressed_bytes: u64,
+
+    /// The number of frames not to compress, per reason.
+    #[serde(default)]
+    pub skipped: BTreeMap<String, u64>,
+
+    /// The bytes of the frames not to compress.
+    pub skipped_bytes: u64,
+}
+
+impl CompressionStats {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        CompressionStats::default()
+    }
+
+    /// Record a decision.
+    pub fn record(&mut self, decision: &Decision) {
+        let size = decision.stats.size as u64;
+        if decision.compress {
+            self.compressed += 1;
+            self.compressed_bytes += size;
+        } else {
+            *self
+                .skipped
+                .entry(decision.reason.as_str().to_string())
+                .or_insert(0) += 1;
+            self.skipped_bytes += size;
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! A single configuration struct for every tunable of the RPC layer.
+//!
+//! [`Config`](struct.Config.html) is a plain serde struct, so embedding applications can keep it
+//! as a section of their existing configuration files. Every field has a sensible default and
+//! missing fields are filled in from [`Config::default()`](struct.Config.html#impl-Default).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use std::time::Duration;
+//! use jrpc::config::{Config, Strictness};
+//!
+//! # fn main() {
+//! let config = Config::from_value(json!({
+//!     "strictness": "lenient",
+//!     "limits": { "max_batch_size": 10 },
+//!     "timeouts": { "request_ms": 500 },
+//! })).unwrap();
+//!
+//! assert_eq!(config.strictness, Strictness::Lenient);
+//! assert_eq!(config.limits.max_batch_size, 10);
+//! assert_eq!(config.limits.max_message_bytes, Config::default().limits.max_message_bytes);
+//! assert_eq!(config.timeouts.request(), Duration::from_millis(500));
+//!
+//! // Invalid values are rejected.
+//! let err = Config::from_value(json!({"limits": {"max_batch_size": 0}})).unwrap_err();
+//! assert_eq!(err.to_string(), "invalid config `limits.max_batch_size`: must be at least 1");
+//!
+//! // And so are unknown fields, which are almost always typos.
+//! assert!(Config::from_value(json!({"limts": {}})).is_err());
+//! # }
+//! ```
+//!
+//! # Live reconfiguration
+//!
+//! Some values can be changed while the RPC layer is running. [`Config::diff`] lists what changed
+//! between two configs and [`Config::apply_to`] pushes safe changes to anything implementing
+//! [`Reconfigure`], refusing the whole update if any change requires a restart.
+//!
+//! [`Config::diff`]: struct.Config.html#method.diff
+//! [`Config::apply_to`]: struct.Config.html#method.apply_to
+//! [`Reconfigure`]: trait.Reconfigure.html
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::config::{Config, ConfigChange, Reconfigure, Strictness};
+//!
+//! struct Server {
+//!     max_batch_size: usize,
+//! }
+//!
+//! impl Reconfigure for Server {
+//!     fn reconfigure(&mut self, config: &Config) {
+//!         self.max_batch_size = config.limits.max_batch_size;
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut server = Server { max_batch_size: 100 };
+//! let mut live = Config::default();
+//!
+//! let mut new = live.clone();
+//! new.limits.max_batch_size = 10;
+//! let changes = live.apply_to(new, &mut [&mut server]).unwrap();
+//! assert_eq!(changes, vec![ConfigChange::MaxBatchSize { old: 100, new: 10 }]);
+//! assert_eq!(server.max_batch_size, 10);
+//! assert_eq!(live.limits.max_batch_size, 10);
+//!
+//! // Changing how messages are interpreted requires a restart.
+//! let mut new = live.clone();
+//! new.limits.max_batch_size = 20;
+//! new.strictness = Strictness::Strict;
+//! assert!(live.apply_to(new, &mut [&mut server]).is_err());
+//! assert_eq!(server.max_batch_size, 10);
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use serde_json;
+use std_prelude::*;
+
+use Value;
+
+/// Every tunable of the RPC layer.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Size limits on inbound messages.
+    pub limits: Limits,
+
+    /// How strictly inbound messages are checked against the spec.
+    pub strictness: Strictness,
+
+    /// Which versions of the protocol are accepted.
+    pub dialect: Dialect,
+
+    /// Timeouts, in milliseconds.
+    pub timeouts: Timeouts,
+
+    /// Optional rate limiting of inbound calls.
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// Size limits on inbound messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// The maximum size of a single serialized message, in bytes. Default: 1 MiB.
+    pub max_message_bytes: usize,
+
+    /// The maximum number of entries in a batch. Default: 100.
+    pub max_batch_size: usize,
+
+    /// The maximum number of calls being processed at the same time. Default: 64.
+    pub max_in_flight: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_message_bytes: 1024 * 1024,
+            max_batch_size: 100,
+            max_in_flight: 64,
+        }
+    }
+}
+
+/// How strictly inbound messages are checked against the spec.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Strictness {
+    /// Reject anything that is not spec compliant.
+    Strict,
+    /// Accept what the types in this crate accept. This is the default.
+    #[default]
+    Standard,
+    /// Additionally accept common deviations from the spec, for tooling and debugging.
+    Lenient,
+}
+
+/// Which versions of the protocol are accepted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    /// Only JSON-RPC 2.0. This is the default.
+    #[default]
+    V2,
+    /// Both JSON-RPC 1.0 and 2.0 on the same endpoint. Requires the `v1-compat` feature to be
+    /// acted upon, see the `negotiate` module.
+    Dual,
+}
+
+/// Timeouts, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeouts {
+    /// How long a single call may take before it is answered with an error. Default: 30s.
+    pub request_ms: u64,
+
+    /// How long a connection may be idle before it is closed. Default: 5 minutes.
+    pub idle_ms: u64,
+}
+
+impl Timeouts {
+    /// The request timeout as a `Duration`.
+    pub fn request(&self) -> Duration {
+        Duration::from_millis(self.request_ms)
+    }
+
+    /// The idle timeout as a `Duration`.
+    pub fn idle(&self) -> Duration {
+        Duration::from_millis(self.idle_ms)
+    }
+}
+
+impl Default for Timeouts {
+    fn default() -> Timeouts {
+        Timeouts {
+            request_ms: 30_000,
+            idle_ms: 300_000,
+        }
+    }
+}
+
+/// Token bucket rate limiting of inbound calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// The sustained number of calls allowed per second.
+    pub per_second: u32,
+
+    /// The number of calls allowed in a burst.
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+impl Config {
+    /// Load a `Config` from a json `Value`, filling in defaults and validating the result.
+    pub fn from_value(value: Value) -> Result<Config, ConfigError> {
+        let config: Config = serde_json::from_value(value).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load a `Config` from environment variables, starting from the defaults.
+    ///
+    /// This does not read the environment itself: pass in `std::env::vars()` (or any other list
+    /// of key/value pairs). Only variables starting with `prefix` are considered. The recognized
+    /// names (after the prefix) are:
+    ///
+    /// - `MAX_MESSAGE_BYTES`, `MAX_BATCH_SIZE`, `MAX_IN_FLIGHT`
+    /// - `STRICTNESS` (`strict`, `standard` or `lenient`)
+    /// - `DIALECT` (`v2` or `dual`)
+    /// - `REQUEST_TIMEOUT_MS`, `IDLE_TIMEOUT_MS`
+    /// - `RATE_LIMIT_PER_SECOND`, `RATE_LIMIT_BURST`
+    ///
+    /// Unrecognized names with the prefix are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::config::Config;
+    ///
+    /// # fn main() {
+    /// let vars = vec![
+    ///     ("JRPC_MAX_BATCH_SIZE".to_string(), "5".to_string()),
+    ///     ("JRPC_RATE_LIMIT_PER_SECOND".to_string(), "20".to_string()),
+    ///     ("HOME".to_string(), "/root".to_string()),
+    /// ];
+    /// let config = Config::from_env_vars("JRPC_", vars).unwrap();
+    /// assert_eq!(config.limits.max_batch_size, 5);
+    /// assert_eq!(config.rate_limit.unwrap().per_second, 20);
+    /// # }
+    /// ```
+    pub fn from_env_vars<I>(prefix: &str, vars: I) -> Result<Config, ConfigError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut config = Config::default();
+        let mut rate_limit = RateLimit {
+            per_second: 0,
+            burst: default_burst(),
+        };
+        let mut rate_limited = false;
+
+        for (key, value) in vars {
+            if !key.starts_with(prefix) {
+                continue;
+            }
+            let value = value.trim();
+            match &key[prefix.len()..] {
+                "MAX_MESSAGE_BYTES" => {
+                    config.limits.max_message_bytes = parse_var("limits.max_message_bytes", value)?
+                }
+                "MAX_BATCH_SIZE" => {
+                    config.limits.max_batch_size = parse_var("limits.max_batch_size", value)?
+                }
+                "MAX_IN_FLIGHT" => {
+                    config.limits.max_in_flight = parse_var("limits.max_in_flight", value)?
+                }
+                "STRICTNESS" => {
+                    config.strictness = serde_json::from_value(Value::String(value.into()))
+                        .map_err(|e| ConfigError::invalid("strictness", e.to_string()))?
+                }
+                "DIALECT" => {
+                    config.dialect = serde_json::from_value(Value::String(value.into()))
+                        .map_err(|e| ConfigError::invalid("dialect", e.to_string()))?
+                }
+                "REQUEST_TIMEOUT_MS" => {
+                    config.timeouts.request_ms = parse_var("timeouts.request_ms", value)?
+                }
+                "IDLE_TIMEOUT_MS" => config.timeouts.idle_ms = parse_var("timeouts.idle_ms", value)?,
+                "RATE_LIMIT_PER_SECOND" => {
+                    rate_limited = true;
+                    rate_limit.per_second = parse_var("rate_limit.per_second", value)?;
+                }
+                "RATE_LIMIT_BURST" => {
+                    rate_limited = true;
+                    rate_limit.burst = parse_var("rate_limit.burst", value)?;
+                }
+                _ => return Err(ConfigError::invalid("<env>", format!("unknown variable {}", key))),
+            }
+        }
+
+        if rate_limited {
+            config.rate_limit = Some(rate_limit);
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check that every value is within its allowed range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        at_least_one("limits.max_message_bytes", self.limits.max_message_bytes as u64)?;
+        at_least_one("limits.max_batch_size", self.limits.max_batch_size as u64)?;
+        at_least_one("limits.max_in_flight", self.limits.max_in_flight as u64)?;
+        at_least_one("timeouts.request_ms", self.timeouts.request_ms)?;
+        at_least_one("timeouts.idle_ms", self.timeouts.idle_ms)?;
+        if let Some(ref rate_limit) = self.rate_limit {
+            at_least_one("rate_limit.per_second", u64::from(rate_limit.per_second))?;
+            at_least_one("rate_limit.burst", u64::from(rate_limit.burst))?;
+        }
+        Ok(())
+    }
+
+    /// List every value that differs between `old` and `new`.
+    pub fn diff(old: &Config, new: &Config) -> Vec<ConfigChange> {
+        let mut out = Vec::new();
+        macro_rules! changed {
+            ($variant:ident, $($field:ident).+) => {
+                if old.$($field).+ != new.$($field).+ {
+                    out.push(ConfigChange::$variant {
+                        old: old.$($field).+,
+                        new: new.$($field).+,
+                    });
+                }
+            };
+        }
+        changed!(MaxMessageBytes, limits.max_message_bytes);
+        changed!(MaxBatchSize, limits.max_batch_size);
+        changed!(MaxInFlight, limits.max_in_flight);
+        changed!(Strictness, strictness);
+        changed!(Dialect, dialect);
+        changed!(RequestTimeoutMs, timeouts.request_ms);
+        changed!(IdleTimeoutMs, timeouts.idle_ms);
+        changed!(RateLimit, rate_limit);
+        out
+    }
+
+    /// Replace this (live) config with `new`, then let every target pick up the new values.
+    ///
+    /// `new` is validated first. If any of the changes is not
+    /// [safe at runtime](enum.ConfigChange.html#method.is_safe) then nothing is applied and
+    /// `ConfigError::Unsafe` is returned. On success the applied changes are returned; targets are
+    /// not called if nothing changed.
+    pub fn apply_to(
+        &mut self,
+        new: Config,
+        targets: &mut [&mut dyn Reconfigure],
+    ) -> Result<Vec<ConfigChange>, ConfigError> {
+        new.validate()?;
+        let changes = Config::diff(self, &new);
+        if let Some(change) = changes.iter().find(|c| !c.is_safe()) {
+            return Err(ConfigError::Unsafe(*change));
+        }
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+        *self = new;
+        for target in targets.iter_mut() {
+            target.reconfigure(self);
+        }
+        Ok(changes)
+    }
+}
+
+/// Something which can pick up a new [`Config`](struct.Config.html) while running.
+///
+/// See [`Config::apply_to`](struct.Config.html#method.apply_to).
+pub trait Reconfigure {
+    /// Apply the (already validated) config.
+    ///
+    /// This is only called with configs whose changes are all
+    /// [safe at runtime](enum.ConfigChange.html#method.is_safe).
+    fn reconfigure(&mut self, config: &Config);
+}
+
+/// A single value that differs between two [`Config`](struct.Config.html)s.
+///
+/// Returned by [`Config::diff`](struct.Config.html#method.diff).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigChange {
+    /// `limits.max_message_bytes` changed.
+    MaxMessageBytes {
+        /// The previous value.
+        old: usize,
+        /// The new value.
+        new: usize,
+    },
+    /// `limits.max_batch_size` changed.
+    MaxBatchSize {
+        /// The previous value.
+        old: usize,
+        /// The new value.
+        new: usize,
+    },
+    /// `limits.max_in_flight` changed.
+    MaxInFlight {
+        /// The previous value.
+        old: usize,
+        /// The new value.
+        new: usize,
+    },
+    /// `strictness` changed.
+    Strictness {
+        /// The previous value.
+        old: Strictness,
+        /// The new value.
+        new: Strictness,
+    },
+    /// `dialect` changed.
+    Dialect {
+        /// The previous value.
+        old: Dialect,
+        /// The new value.
+        new: Dialect,
+    },
+    /// `timeouts.request_ms` changed.
+    RequestTimeoutMs {
+        /// The previous value.
+        old: u64,
+        /// The new value.
+        new: u64,
+    },
+    /// `timeouts.idle_ms` changed.
+    IdleTimeoutMs {
+        /// The previous value.
+        old: u64,
+        /// The new value.
+        new: u64,
+    },
+    /// `rate_limit` changed.
+    RateLimit {
+        /// The previous value.
+        old: Option<RateLimit>,
+        /// The new value.
+        new: Option<RateLimit>,
+    },
+}
+
+impl ConfigChange {
+    /// The dotted path of the changed field, e.g. `limits.max_batch_size`.
+    pub fn field(&self) -> &'static str {
+        match *self {
+            ConfigChange::MaxMessageBytes { .. } => "limits.max_message_bytes",
+            ConfigChange::MaxBatchSize { .. } => "limits.max_batch_size",
+            ConfigChange::MaxInFlight { .. } => "limits.max_in_flight",
+            ConfigChange::Strictness { .. } => "strictness",
+            ConfigChange::Dialect { .. } => "dialect",
+            ConfigChange::RequestTimeoutMs { .. } => "timeouts.request_ms",
+            ConfigChange::IdleTimeoutMs { .. } => "timeouts.idle_ms",
+            ConfigChange::RateLimit { .. } => "rate_limit",
+        }
+    }
+
+    /// Whether the change can be applied to a running system.
+    ///
+    /// Limits, timeouts and rate limits are safe. Anything which changes how messages are
+    /// interpreted (`strictness` and `dialect`) is not, since peers may already rely on the old
+    /// behavior.
+    pub fn is_safe(&self) -> bool {
+        !matches!(
+            *self,
+            ConfigChange::Strictness { .. } | ConfigChange::Dialect { .. }
+        )
+    }
+}
+
+/// The error returned when loading or validating a [`Config`](struct.Config.html).
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The value could not be deserialized into a `Config`.
+    Parse(serde_json::Error),
+    /// A field has a value outside of its allowed range.
+    Invalid {
+        /// The dotted path of the offending field, e.g. `limits.max_batch_size`.
+        field: &'static str,
+        /// Why the value is invalid.
+        reason: String,
+    },
+    /// A change cannot be applied at runtime.
+    Unsafe(ConfigChange),
+}
+
+impl ConfigError {
+    fn invalid<S: Into<String>>(field: &'static str, reason: S) -> ConfigError {
+        ConfigError::Invalid {
+            field: field,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Parse(ref err) => write!(f, "invalid config: {}", err),
+            ConfigError::Invalid { field, ref reason } => {
+                write!(f, "invalid config `{}`: {}", field, reason)
+            }
+            ConfigError::Unsafe(ref change) => write!(
+                f,
+                "config `{}` cannot be changed at runtime",
+                change.field()
+            ),
+        }
+    }
+}
+
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ConfigError::Parse(ref err) => Some(err),
+            ConfigError::Invalid { .. } | ConfigError::Unsafe(_) => None,
+        }
+    }
+}
+
+fn at_least_one(field: &'static str, value: u64) -> Result<(), ConfigError> {
+    if value == 0 {
+        return Err(ConfigError::invalid(field, "must be at least 1"));
+    }
+    Ok(())
+}
+
+fn parse_var<T: FromStr>(field: &'static str, value: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| ConfigError::invalid(field, format!("expected a number, got {:?}", value)))
+}
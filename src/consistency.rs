@@ -0,0 +1,141 @@
+//! Session consistency tokens, for read-your-writes over eventually consistent backends.
+//!
+//! A server backed by replicas returns a [`ConsistencyToken`](struct.ConsistencyToken.html) in
+//! the `consistency_token` member of Object results, e.g. the log position of a write. The client
+//! echoes the latest token it has seen in the `consistency_token` member of the Object params of
+//! its next calls, and the server answers them from a replica at least as fresh as the token.
+//!
+//! Tokens are opaque to clients. A [`ConsistencySession`](struct.ConsistencySession.html)
+//! captures them from Responses and injects them into Requests.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::{Id, Request, Response};
+//! use jrpc::consistency::{self, ConsistencySession, ConsistencyToken};
+//!
+//! # fn main() {
+//! let mut session = ConsistencySession::new();
+//!
+//! // The server tags the result of a write.
+//! let result = consistency::with_token(json!({"ok": true}), &ConsistencyToken::new("lsn-42"));
+//! session.capture(&Response::success(Id::from(1), result));
+//!
+//! // The token is echoed in the next call.
+//! let mut request = Request::with_params(Id::from(2), "get".to_string(), json!({"key": "a"}));
+//! assert!(session.inject(&mut request));
+//! assert_eq!(request.params.as_ref().unwrap()["consistency_token"], "lsn-42");
+//!
+//! // The server reads it back.
+//! let token = consistency::token_of(request.params.as_ref().unwrap()).unwrap();
+//! assert_eq!(token.as_str(), "lsn-42");
+//! # }
+//! ```
+
+use std_prelude::*;
+
+use {Request, Response, Value};
+
+/// The member of results and params carrying the token.
+pub const TOKEN_MEMBER: &str = "consistency_token";
+
+/// An opaque token denoting a point in a backend's history.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConsistencyToken(String);
+
+impl ConsistencyToken {
+    /// Create a token.
+    pub fn new<S: Into<String>>(token: S) -> Self {
+        ConsistencyToken(token.into())
+    }
+
+    /// The token as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Get the token of a result or params Object, if any.
+pub fn token_of(value: &Value) -> Option<ConsistencyToken> {
+    value
+        .get(TOKEN_MEMBER)
+        .and_then(Value::as_str)
+        .map(ConsistencyToken::new)
+}
+
+/// Add the token to a result or params Object. Values which are not Objects are returned
+/// unchanged, since there is nowhere to put the token.
+pub fn with_token(mut value: Value, token: &ConsistencyToken) -> Value {
+    set_token(&mut value, token);
+    value
+}
+
+fn set_token(value: &mut Value, token: &ConsistencyToken) -> bool {
+    match value.as_object_mut() {
+        Some(object) => {
+            object.insert(TOKEN_MEMBER.to_string(), Value::from(token.as_str()));
+            true
+        }
+        None => false,
+    }
+}
+
+/// The latest token seen by a client session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsistencySession {
+    token: Option<ConsistencyToken>,
+}
+
+impl ConsistencySession {
+    /// Create a session which has not seen a token.
+    pub fn new() -> Self {
+        ConsistencySession::default()
+    }
+
+    /// The latest token.
+    pub fn token(&self) -> Option<&ConsistencyToken> {
+        self.token.as_ref()
+    }
+
+    /// Set the latest token, e.g. to restore a session.
+    pub fn set_token(&mut self, token: ConsistencyToken) {
+        self.token = Some(token);
+    }
+
+    /// Capture the token of a successful Response, if it has one. Returns whether it did.
+    pub fn capture(&mut self, response: &Response<Value>) -> bool {
+        let token = match *response {
+            Response::Ok(ref success) => token_of(&success.result),
+            Response::Err(_) => None,
+        };
+        match token {
+            Some(token) => {
+                self.token = Some(token);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Inject the latest token into the params of a Request. Missing params become an Object.
+    ///
+    /// Returns `false` if there is no token, or the params are not an Object (positional params
+    /// have no place for it).
+    pub fn inject<M>(&self, request: &mut Request<M, Value>) -> bool {
+        let token = match self.token {
+            Some(ref token) => token,
+            None => return false,
+        };
+        let params = request
+            .params
+            .get_or_insert_with(|| Value::Object(Default::default()));
+        if params.is_null() {
+            *params = Value::Object(Default::default());
+        }
+        set_token(params, token)
+    }
+}
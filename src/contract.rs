@@ -0,0 +1,207 @@
+//! Contract tests between the client and the server of a service.
+//!
+//! The server exports its method table, with the schemas of their params and results, as a
+//! [`Contract`](struct.Contract.html) artifact (e.g. a json file checked into the client's
+//! repository or published by CI). The client's tests load it and check every Request they
+//! construct with [`check_request`](struct.Contract.html#method.check_request), so drift
+//! between the two is caught before they are deployed.
+//!
+//! Schemas are validated with [`schema::validate`](../schema/fn.validate.html). Methods without
+//! a schema accept anything. Omitted params are checked as `null`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::{Id, Request};
+//! use jrpc::catalog::MethodDoc;
+//! use jrpc::contract::{Contract, ContractError};
+//!
+//! # fn main() {
+//! // The server exports its contract.
+//! let contract = Contract::new("accounts").method(
+//!     MethodDoc::new("transfer")
+//!         .params_schema(json!({"type": "object", "required": ["amount"]}))
+//!         .result_schema(json!({"type": "integer"})),
+//! );
+//! let artifact = serde_json::to_string(&contract).unwrap();
+//!
+//! // The client's tests load it.
+//! let contract: Contract = serde_json::from_str(&artifact).unwrap();
+//! let request = Request::with_params(Id::from(1), "transfer".to_string(), json!({"amount": 5}));
+//! assert!(contract.check_request(&request).is_ok());
+//!
+//! let request = Request::with_params(Id::from(2), "transfer".to_string(), json!({"amuont": 5}));
+//! match contract.check_request(&request) {
+//!     Err(ContractError::Params { violations, .. }) => assert_eq!(violations.len(), 1),
+//!     _ => unreachable!(),
+//! }
+//! let request = Request::with_params(Id::from(3), "withdraw".to_string(), json!({}));
+//! assert_eq!(
+//!     contract.check_request(&request),
+//!     Err(ContractError::UnknownMethod("withdraw".to_string()))
+//! );
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use catalog::{ErrorCatalog, MethodDoc};
+use method::Method;
+use schema::{self, Violation};
+use {Request, Value};
+
+/// The method table of a service.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Contract {
+    /// The name of the service.
+    pub service: String,
+
+    /// The methods, in the order they were added.
+    #[serde(default)]
+    pub methods: Vec<MethodDoc>,
+}
+
+impl Contract {
+    /// Create a contract without methods.
+    pub fn new<S: Into<String>>(service: S) -> Self {
+        Contract {
+            service: service.into(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// Create the contract of the methods of a catalog.
+    pub fn from_catalog<S: Into<String>>(service: S, catalog: &ErrorCatalog) -> Self {
+        Contract {
+            service: service.into(),
+            methods: catalog.methods.clone(),
+        }
+    }
+
+    /// Add a method, replacing an existing one with the same name.
+    pub fn method(mut self, doc: MethodDoc) -> Self {
+        match self.methods.iter().position(|m| m.name == doc.name) {
+            Some(index) => self.methods[index] = doc,
+            None => self.methods.push(doc),
+        }
+        self
+    }
+
+    /// Get a method.
+    pub fn get(&self, name: &str) -> Option<&MethodDoc> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    /// Check that a Request calls a declared method with valid params.
+    pub fn check_request<M, T>(&self, request: &Request<M, T>) -> Result<(), ContractError>
+    where
+        M: AsRef<str>,
+        T: Serialize,
+    {
+        let params = match request.params {
+            Some(ref params) => {
+                serde_json::to_value(params).map_err(|e| ContractError::Serialize(e.to_string()))?
+            }
+            None => Value::Null,
+        };
+        self.check_params(request.method.as_ref(), &params)
+    }
+
+    /// Check the params of a typed method call.
+    pub fn check_call<M: Method>(&self, params: &M::Params) -> Result<(), ContractError> {
+        let params =
+            serde_json::to_value(params).map_err(|e| ContractError::Serialize(e.to_string()))?;
+        self.check_params(&M::name(), &params)
+    }
+
+    /// Check that `method` is declared and accepts `params`.
+    pub fn check_params(&self, method: &str, params: &Value) -> Result<(), ContractError> {
+        let doc = self.declared(method)?;
+        match doc.params_schema {
+            Some(ref schema) => match schema::validate(schema, params) {
+                ref violations if violations.is_empty() => Ok(()),
+                violations => Err(ContractError::Params {
+                    method: method.to_string(),
+                    violations: violations,
+                }),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Check that `method` is declared and may return `result`, e.g. for the fixtures of a
+    /// client's tests.
+    pub fn check_result(&self, method: &str, result: &Value) -> Result<(), ContractError> {
+        let doc = self.declared(method)?;
+        match doc.result_schema {
+            Some(ref schema) => match schema::validate(schema, result) {
+                ref violations if violations.is_empty() => Ok(()),
+                violations => Err(ContractError::Result {
+                    method: method.to_string(),
+                    violations: violations,
+                }),
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn declared(&self, method: &str) -> Result<&MethodDoc, ContractError> {
+        self.get(method)
+            .ok_or_else(|| ContractError::UnknownMethod(method.to_string()))
+    }
+}
+
+/// A call which breaks a [`Contract`](struct.Contract.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractError {
+    /// The method is not declared.
+    UnknownMethod(String),
+    /// The params do not match their schema.
+    Params {
+        /// The method.
+        method: String,
+        /// The violations of the schema.
+        violations: Vec<Violation>,
+    },
+    /// The result does not match its schema.
+    Result {
+        /// The method.
+        method: String,
+        /// The violations of the schema.
+        violations: Vec<Violation>,
+    },
+    /// The params could not be serialized.
+    Serialize(String),
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (what, method, violations) = match *self {
+            ContractError::UnknownMethod(ref m) => return write!(f, "undeclared method `{}`", m),
+            ContractError::Serialize(ref e) => return write!(f, "invalid params: {}", e),
+            ContractError::Params {
+                ref method,
+                ref violations,
+            } => ("params", method, violations),
+            ContractError::Result {
+                ref method,
+                ref violations,
+            } => ("result", method, violations),
+        };
+        write!(f, "invalid {} of `{}`", what, method)?;
+        for (i, violation) in violations.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { ";" }, violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ContractError {}
@@ -0,0 +1,226 @@
+//! Matching Responses to the calls a client has in flight.
+//!
+//! Every client has to give its Requests unique ids and find the call a Response answers. An
+//! [`IdGenerator`](trait.IdGenerator.html) produces the ids: [`Sequential`](struct.Sequential.html)
+//! Integers, or [`Uuids`](struct.Uuids.html) for ids which must not be guessable from the number
+//! of calls made. A [`Correlation`](struct.Correlation.html) holds the state of every call in
+//! flight by its id, rejects colliding ids, and hands the state back when the Response arrives.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{Id, Response, Value};
+//! use jrpc::correlation::{Correlation, CorrelationError, Sequential};
+//!
+//! # fn main() {
+//! let mut ids = Sequential::new();
+//! let mut calls = Correlation::new();
+//! let first = calls.start(&mut ids, "GetFoo").unwrap();
+//! let second = calls.start(&mut ids, "GetBar").unwrap();
+//! assert_eq!((first.clone(), second), (Id::Int(1), Id::Int(2)));
+//!
+//! // Ids chosen elsewhere are checked for collisions.
+//! assert_eq!(calls.insert(Id::Int(2), "GetBaz"), Err(CorrelationError::Duplicate(Id::Int(2))));
+//!
+//! let json = r#"{"jsonrpc": "2.0", "result": 7, "id": 1}"#;
+//! let response: Response<Value> = serde_json::from_str(json).unwrap();
+//! assert_eq!(calls.complete(response.id()), Ok("GetFoo"));
+//! assert_eq!(calls.complete(response.id()), Err(CorrelationError::Unknown(Id::Int(1))));
+//! assert_eq!(calls.len(), 1);
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use std_prelude::*;
+
+use loadgen::SeededRng;
+use Id;
+
+/// The number of ids drawn by [`Correlation::start`](struct.Correlation.html#method.start) before
+/// giving up on finding an unused one.
+pub const MAX_ATTEMPTS: usize = 8;
+
+/// Produces the ids of outgoing Requests.
+pub trait IdGenerator {
+    /// The next id.
+    fn next_id(&mut self) -> Id;
+}
+
+/// Integer ids, incrementing from `1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequential {
+    next: i64,
+}
+
+impl Default for Sequential {
+    fn default() -> Self {
+        Sequential { next: 1 }
+    }
+}
+
+impl Sequential {
+    /// Start at `1`.
+    pub fn new() -> Self {
+        Sequential::default()
+    }
+
+    /// Start at `next`, e.g. to continue after a restart.
+    pub fn starting_at(next: i64) -> Self {
+        Sequential { next: next }
+    }
+}
+
+impl IdGenerator for Sequential {
+    fn next_id(&mut self) -> Id {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+        Id::Int(id)
+    }
+}
+
+/// Random (version 4) UUID String ids, e.g. `"0f6c3a0e-5b2d-4e8a-9c41-7d2e90b1f3a6"`.
+///
+/// The ids are drawn from a [`SeededRng`](../loadgen/struct.SeededRng.html): seed it from a
+/// random source, as two clients with the same seed produce the same ids. They are not
+/// cryptographically secure, so must not be used as secrets.
+#[derive(Debug, Clone)]
+pub struct Uuids {
+    rng: SeededRng,
+}
+
+impl Uuids {
+    /// Draw the ids from a generator seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Uuids {
+            rng: SeededRng::new(seed),
+        }
+    }
+}
+
+impl IdGenerator for Uuids {
+    fn next_id(&mut self) -> Id {
+        let high = self.rng.next_u64();
+        let low = self.rng.next_u64();
+        // The version in the 13th hex digit, the variant in the 17th.
+        let high = (high & !0xF000) | 0x4000;
+        let low = (low & !(0b11 << 62)) | (0b10 << 62);
+        Id::String(format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xFFFF,
+            high & 0xFFFF,
+            low >> 48,
+            low & 0xFFFF_FFFF_FFFF,
+        ))
+    }
+}
+
+/// Why a call could not be started or completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CorrelationError {
+    /// A call with the id is already in flight.
+    Duplicate(Id),
+    /// No call with the id is in flight.
+    Unknown(Id),
+    /// The generator produced [`MAX_ATTEMPTS`](constant.MAX_ATTEMPTS.html) ids in flight in a
+    /// row.
+    Exhausted,
+}
+
+impl fmt::Display for CorrelationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CorrelationError::Duplicate(ref id) => write!(f, "id {:?} is already in flight", id),
+            CorrelationError::Unknown(ref id) => write!(f, "no call with id {:?} in flight", id),
+            CorrelationError::Exhausted => {
+                write!(f, "no unused id in {} attempts", MAX_ATTEMPTS)
+            }
+        }
+    }
+}
+
+impl error::Error for CorrelationError {}
+
+/// The calls in flight, by id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correlation<T> {
+    calls: BTreeMap<Id, T>,
+}
+
+impl<T> Default for Correlation<T> {
+    fn default() -> Self {
+        Correlation {
+            calls: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> Correlation<T> {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Correlation::default()
+    }
+
+    /// Start a call with an id from `ids`, returning the id to send the Request with. Ids
+    /// already in flight are skipped.
+    pub fn start<G: IdGenerator>(&mut self, ids: &mut G, call: T) -> Result<Id, CorrelationError> {
+        for _ in 0..MAX_ATTEMPTS {
+            let id = ids.next_id();
+            if !self.calls.contains_key(&id) {
+                self.calls.insert(id.clone(), call);
+                return Ok(id);
+            }
+        }
+        Err(CorrelationError::Exhausted)
+    }
+
+    /// Start a call with an id chosen by the caller.
+    pub fn insert(&mut self, id: Id, call: T) -> Result<(), CorrelationError> {
+        if self.calls.contains_key(&id) {
+            return Err(CorrelationError::Duplicate(id));
+        }
+        self.calls.insert(id, call);
+        Ok(())
+    }
+
+    /// Complete the call a Response with `id` answers, returning its state.
+    pub fn complete(&mut self, id: &Id) -> Result<T, CorrelationError> {
+        self.calls
+            .remove(id)
+            .ok_or_else(|| CorrelationError::Unknown(id.clone()))
+    }
+
+    /// The state of the call with `id`.
+    pub fn get(&self, id: &Id) -> Option<&T> {
+        self.calls.get(id)
+    }
+
+    /// Whether a call with `id` is in flight.
+    pub fn contains(&self, id: &Id) -> bool {
+        self.calls.contains_key(id)
+    }
+
+    /// The ids of the calls in flight, in order.
+    pub fn ids(&self) -> impl Iterator<Item = &Id> {
+        self.calls.keys()
+    }
+
+    /// The number of calls in flight.
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Whether no call is in flight.
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Abandon every call in flight, e.g. when the connection is lost, returning them.
+    pub fn drain(&mut self) -> Vec<(Id, T)> {
+        ::std::mem::take(&mut self.calls).into_iter().collect()
+    }
+}
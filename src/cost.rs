@@ -0,0 +1,222 @@
+//! Labeling requests with their consumer, for cost attribution.
+//!
+//! # Convention
+//!
+//! The client labels each Request with a [`CostLabel`](struct.CostLabel.html) in the top level
+//! extension member [`COST_MEMBER`](constant.COST_MEMBER.html): the team making the call and,
+//! optionally, the feature it is made for and the cost center to charge it to.
+//!
+//! ```json
+//! {
+//!     "jsonrpc": "2.0",
+//!     "method": "Search",
+//!     "id": 1,
+//!     "cost": {"team": "growth", "feature": "recommendations", "cost_center": "cc-104"}
+//! }
+//! ```
+//!
+//! Servers aggregate their traffic per label with a [`CostLedger`](struct.CostLedger.html), so
+//! platform teams can attribute it to its consumers. Labels are informative only: a missing or
+//! malformed label never causes a Request to be rejected, its traffic is recorded as
+//! unattributed instead.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use std::time::Duration;
+//! use jrpc::{Id, Request, Value};
+//! use jrpc::cost::{self, CostLabel, CostLedger};
+//!
+//! # fn main() {
+//! let label = CostLabel::new("growth").feature("recommendations");
+//! let request: Request<String, Value> = Request::with_params(
+//!     Id::from(1),
+//!     "Search".to_string(),
+//!     Value::from("shoes"),
+//! );
+//! let request = cost::with_label(&request, &label);
+//!
+//! let mut ledger = CostLedger::new();
+//! let received = CostLabel::of(&request);
+//! assert_eq!(received.as_ref(), Some(&label));
+//! ledger.record(received.as_ref(), "Search", 120, Duration::from_millis(8));
+//! ledger.record(None, "Search", 80, Duration::from_millis(2));
+//!
+//! let usage = ledger.usage(Some(&label)).unwrap();
+//! assert_eq!(usage.requests, 1);
+//! assert_eq!(usage.bytes, 120);
+//! assert_eq!(ledger.by_team()["growth"].time_ms, 8);
+//! assert_eq!(ledger.usage(None).unwrap().requests, 1);
+//! # }
+//! ```
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use Value;
+
+/// The Request member carrying the cost label.
+pub const COST_MEMBER: &str = "cost";
+
+/// The consumer a Request is made on behalf of.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CostLabel {
+    /// The team making the call.
+    pub team: String,
+
+    /// The feature the call is made for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature: Option<String>,
+
+    /// The cost center to charge the call to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_center: Option<String>,
+}
+
+impl CostLabel {
+    /// Create a label for a team.
+    pub fn new<S: Into<String>>(team: S) -> Self {
+        CostLabel {
+            team: team.into(),
+            feature: None,
+            cost_center: None,
+        }
+    }
+
+    /// Set the feature.
+    pub fn feature<S: Into<String>>(mut self, feature: S) -> Self {
+        self.feature = Some(feature.into());
+        self
+    }
+
+    /// Set the cost center.
+    pub fn cost_center<S: Into<String>>(mut self, cost_center: S) -> Self {
+        self.cost_center = Some(cost_center.into());
+        self
+    }
+
+    /// Read the label of a Request. Returns `None` if it is missing or malformed.
+    pub fn of(request: &Value) -> Option<CostLabel> {
+        let label = request.get(COST_MEMBER)?;
+        serde_json::from_value(label.clone()).ok()
+    }
+}
+
+/// Serialize a Request (or any Object) with the cost label added.
+///
+/// # Panics
+///
+/// If `request` does not serialize to an Object.
+pub fn with_label<T: Serialize>(request: &T, label: &CostLabel) -> Value {
+    let mut value = serde_json::to_value(request).expect("request is serializable");
+    let label = serde_json::to_value(label).expect("CostLabel is always serializable");
+    value
+        .as_object_mut()
+        .expect("request must be an Object")
+        .insert(COST_MEMBER.to_string(), label);
+    value
+}
+
+/// The traffic of a single consumer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostUsage {
+    /// The number of calls.
+    pub requests: u64,
+
+    /// The size of the calls, as reported to the ledger (e.g. request plus response bytes).
+    pub bytes: u64,
+
+    /// The time spent handling the calls, in milliseconds.
+    pub time_ms: u64,
+
+    /// The number of calls, per method.
+    #[serde(default)]
+    pub methods: BTreeMap<String, u64>,
+}
+
+impl CostUsage {
+    /// Add the traffic of `other`.
+    pub fn add(&mut self, other: &CostUsage) {
+        self.requests += other.requests;
+        self.bytes += other.bytes;
+        self.time_ms += other.time_ms;
+        for (method, count) in &other.methods {
+            *self.methods.entry(method.clone()).or_insert(0) += count;
+        }
+    }
+}
+
+/// The traffic of a consumer, as reported by [`CostLedger::snapshot`].
+///
+/// [`CostLedger::snapshot`]: struct.CostLedger.html#method.snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostEntry {
+    /// The consumer, or `None` for unattributed traffic.
+    pub label: Option<CostLabel>,
+
+    /// Its traffic.
+    pub usage: CostUsage,
+}
+
+/// Aggregates the traffic of a server per [`CostLabel`](struct.CostLabel.html).
+#[derive(Debug, Clone, Default)]
+pub struct CostLedger {
+    usage: BTreeMap<Option<CostLabel>, CostUsage>,
+}
+
+impl CostLedger {
+    /// Create an empty ledger.
+    pub fn new() -> Self {
+        CostLedger::default()
+    }
+
+    /// Record a call to `method` made on behalf of `label`, `None` if the call had no label.
+    pub fn record(
+        &mut self,
+        label: Option<&CostLabel>,
+        method: &str,
+        bytes: u64,
+        elapsed: Duration,
+    ) {
+        let usage = self.usage.entry(label.cloned()).or_default();
+        usage.requests += 1;
+        usage.bytes += bytes;
+        usage.time_ms += elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis());
+        *usage.methods.entry(method.to_string()).or_insert(0) += 1;
+    }
+
+    /// The traffic of `label`, `None` for unattributed traffic.
+    pub fn usage(&self, label: Option<&CostLabel>) -> Option<&CostUsage> {
+        self.usage.get(&label.cloned())
+    }
+
+    /// The traffic per team, with the features and cost centers of each team merged.
+    /// Unattributed traffic is left out.
+    pub fn by_team(&self) -> BTreeMap<String, CostUsage> {
+        let mut teams: BTreeMap<String, CostUsage> = BTreeMap::new();
+        for (label, usage) in &self.usage {
+            if let Some(ref label) = *label {
+                teams.entry(label.team.clone()).or_default().add(usage);
+            }
+        }
+        teams
+    }
+
+    /// The traffic per label, unattributed traffic first.
+    pub fn snapshot(&self) -> Vec<CostEntry> {
+        self.usage
+            .iter()
+            .map(|(label, usage)| CostEntry {
+                label: label.clone(),
+                usage: usage.clone(),
+            })
+            .collect()
+    }
+
+    /// Reset every counter.
+    pub fn clear(&mut self) {
+        self.usage.clear();
+    }
+}
@@ -0,0 +1,185 @@
+//! Per-request deadlines, propagated into the context of handlers.
+//!
+//! # Convention
+//!
+//! A client which gives up on a call after some time tells the server when, in milliseconds
+//! since the unix epoch, in the top level extension member
+//! [`DEADLINE_MEMBER`](constant.DEADLINE_MEMBER.html):
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "method": "Search", "id": 1, "deadline_ms": 1526000000250}
+//! ```
+//!
+//! The server builds the [`Ctx`](struct.Ctx.html) of the call from the member and from its own
+//! [request timeout](../config/struct.Timeouts.html), whichever is earlier. Handlers budget the
+//! timeouts of their downstream calls with [`remaining`](struct.Ctx.html#method.remaining), and
+//! [`run`](fn.run.html) answers with the [`DEADLINE_EXCEEDED`](constant.DEADLINE_EXCEEDED.html)
+//! error instead of calling a handler whose deadline already passed, or instead of a result
+//! produced too late for the client to still be waiting for it.
+//!
+//! Like the rest of this crate, this module never reads the clock: the current time is passed in.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use std::cell::Cell;
+//! use std::time::Duration;
+//! use jrpc::ErrorCode;
+//! use jrpc::config::Timeouts;
+//! use jrpc::deadline::{self, Ctx, DEADLINE_EXCEEDED};
+//!
+//! # fn main() {
+//! let request = json!({"jsonrpc": "2.0", "method": "Search", "id": 1, "deadline_ms": 1250});
+//! let ctx = Ctx::from_request(&request, &Timeouts::default(), 1000);
+//! assert_eq!(ctx.deadline_ms, Some(1250));
+//! assert_eq!(ctx.remaining(1100), Some(Duration::from_millis(150)));
+//!
+//! let now = Cell::new(1100);
+//! let result = deadline::run(&ctx, || now.get(), |ctx| {
+//!     // Leave 50ms to answer.
+//!     assert_eq!(ctx.budget(now.get(), Duration::from_millis(50)), Some(Duration::from_millis(100)));
+//!     Ok(json!(["shoes"]))
+//! });
+//! assert_eq!(result.unwrap(), json!(["shoes"]));
+//!
+//! // The handler finished too late.
+//! let error = deadline::run(&ctx, || now.get(), |_| {
+//!     now.set(1300);
+//!     Ok(json!([]))
+//! }).unwrap_err();
+//! assert_eq!(error.code, ErrorCode::ServerError(DEADLINE_EXCEEDED));
+//! # }
+//! ```
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use config::Timeouts;
+use {ErrorCode, ErrorObject, Value};
+
+/// The Request member carrying the deadline, in milliseconds since the unix epoch.
+pub const DEADLINE_MEMBER: &str = "deadline_ms";
+
+/// The server error code returned when the deadline of a call passed. Its `data` is the
+/// deadline, in milliseconds since the unix epoch.
+pub const DEADLINE_EXCEEDED: i64 = -32046;
+
+/// Get the deadline of a Request, if any.
+pub fn deadline_of(request: &Value) -> Option<u64> {
+    request.get(DEADLINE_MEMBER).and_then(Value::as_u64)
+}
+
+/// Serialize a Request (or any Object) with the deadline added.
+///
+/// # Panics
+///
+/// If `request` does not serialize to an Object.
+pub fn with_deadline<T: Serialize>(request: &T, deadline_ms: u64) -> Value {
+    let mut value = serde_json::to_value(request).expect("request is serializable");
+    value
+        .as_object_mut()
+        .expect("request must be an Object")
+        .insert(DEADLINE_MEMBER.to_string(), Value::from(deadline_ms));
+    value
+}
+
+/// The error returned when the deadline passed.
+pub fn exceeded(deadline_ms: u64) -> ErrorObject<Value> {
+    ErrorObject {
+        code: ErrorCode::ServerError(DEADLINE_EXCEEDED),
+        message: "deadline exceeded".to_string(),
+        data: Some(Value::from(deadline_ms)),
+    }
+}
+
+/// The context of a handler call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ctx {
+    /// When the Request was received, in milliseconds since the unix epoch.
+    pub received_ms: u64,
+
+    /// When the call must be answered by, in milliseconds since the unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline_ms: Option<u64>,
+}
+
+impl Ctx {
+    /// Create the context of a call without a deadline.
+    pub fn new(received_ms: u64) -> Self {
+        Ctx {
+            received_ms: received_ms,
+            deadline_ms: None,
+        }
+    }
+
+    /// Set the deadline, keeping the earlier one if there already is one.
+    pub fn deadline(mut self, deadline_ms: u64) -> Self {
+        self.deadline_ms = Some(match self.deadline_ms {
+            Some(current) => current.min(deadline_ms),
+            None => deadline_ms,
+        });
+        self
+    }
+
+    /// Create the context of a Request received at `now_ms`, with the earlier of its deadline
+    /// and the server's request timeout. A request timeout of `0` is no timeout.
+    pub fn from_request(request: &Value, timeouts: &Timeouts, now_ms: u64) -> Self {
+        let mut ctx = Ctx::new(now_ms);
+        if timeouts.request_ms > 0 {
+            ctx = ctx.deadline(now_ms.saturating_add(timeouts.request_ms));
+        }
+        if let Some(deadline_ms) = deadline_of(request) {
+            ctx = ctx.deadline(deadline_ms);
+        }
+        ctx
+    }
+
+    /// The time left until the deadline, zero once it passed. `None` if there is no deadline.
+    pub fn remaining(&self, now_ms: u64) -> Option<Duration> {
+        self.deadline_ms
+            .map(|deadline| Duration::from_millis(deadline.saturating_sub(now_ms)))
+    }
+
+    /// The time left for a downstream call, keeping `reserve` to answer the Request.
+    pub fn budget(&self, now_ms: u64, reserve: Duration) -> Option<Duration> {
+        self.remaining(now_ms)
+            .map(|remaining| remaining.checked_sub(reserve).unwrap_or_default())
+    }
+
+    /// Whether the deadline passed.
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        match self.deadline_ms {
+            Some(deadline) => now_ms >= deadline,
+            None => false,
+        }
+    }
+
+    /// Fail with the [`DEADLINE_EXCEEDED`](constant.DEADLINE_EXCEEDED.html) error if the
+    /// deadline passed.
+    pub fn check(&self, now_ms: u64) -> Result<(), ErrorObject<Value>> {
+        match self.deadline_ms {
+            Some(deadline) if now_ms >= deadline => Err(exceeded(deadline)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Call `handler` with `ctx`, enforcing its deadline.
+///
+/// The handler is not called if the deadline already passed, and its result is replaced by the
+/// [`DEADLINE_EXCEEDED`](constant.DEADLINE_EXCEEDED.html) error if it passed while the handler
+/// ran. `now_ms` returns the current time.
+pub fn run<T, C, F>(ctx: &Ctx, mut now_ms: C, handler: F) -> Result<T, ErrorObject<Value>>
+where
+    C: FnMut() -> u64,
+    F: FnOnce(&Ctx) -> Result<T, ErrorObject<Value>>,
+{
+    ctx.check(now_ms())?;
+    let result = handler(ctx);
+    ctx.check(now_ms())?;
+    result
+}
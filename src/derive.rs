@@ -0,0 +1,103 @@
+//! Runtime support for `#[derive(Params)]`. Not public API.
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use Value;
+
+pub use params::{DualParams, Params};
+pub use serde::de::{DeserializeOwned, Error as DeError};
+pub use serde::ser::Error as SerError;
+pub use serde::{Deserialize, Deserializer, Serialize as Ser, Serializer};
+pub use serde_json::Result as JsonResult;
+
+/// The `params` being decoded, either by position or by name.
+pub enum Fields {
+    Positional(Vec<Option<Value>>),
+    Named(serde_json::Map<String, Value>),
+}
+
+impl Fields {
+    /// Accept an Array of at most `len` elements or an Object. Null (omitted `params`) is an
+    /// empty Object.
+    pub fn new(value: Value, len: usize) -> Result<Fields, String> {
+        match value {
+            Value::Array(values) => {
+                if values.len() > len {
+                    return Err(format!(
+                        "expected at most {} positional params, found {}",
+                        len,
+                        values.len()
+                    ));
+                }
+                Ok(Fields::Positional(values.into_iter().map(Some).collect()))
+            }
+            Value::Object(map) => Ok(Fields::Named(map)),
+            Value::Null => Ok(Fields::Named(serde_json::Map::new())),
+            _ => Err("params must be an Array or an Object".to_string()),
+        }
+    }
+
+    /// Take and decode the field at `index` or named `name`, or `None` if it is missing.
+    pub fn take<T: DeserializeOwned>(
+        &mut self,
+        index: usize,
+        name: &str,
+    ) -> Result<Option<T>, String> {
+        let value = match *self {
+            Fields::Positional(ref mut values) => values.get_mut(index).and_then(Option::take),
+            Fields::Named(ref mut map) => map.remove(name),
+        };
+        match value {
+            Some(value) => serde_json::from_value(value)
+                .map(Some)
+                .map_err(|err| format!("invalid field `{}`: {}", name, err)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The value of a missing field without a default: `None` for an `Option`, otherwise an error
+/// naming the field.
+pub fn missing<T: DeserializeOwned>(name: &str) -> Result<T, String> {
+    serde_json::from_value(Value::Null).map_err(|_| format!("missing field `{}`", name))
+}
+
+/// Builds the named (Object) representation.
+#[derive(Default)]
+pub struct Object(serde_json::Map<String, Value>);
+
+impl Object {
+    pub fn new() -> Self {
+        Object::default()
+    }
+
+    pub fn insert<T: Serialize>(&mut self, name: &str, value: &T) -> serde_json::Result<()> {
+        self.0
+            .insert(name.to_string(), serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    pub fn into_params(self) -> Params {
+        Params::Map(self.0)
+    }
+}
+
+/// Builds the positional (Array) representation.
+pub struct Array(Vec<Value>);
+
+impl Array {
+    pub fn new(len: usize) -> Self {
+        Array(Vec::with_capacity(len))
+    }
+
+    pub fn push<T: Serialize>(&mut self, value: &T) -> serde_json::Result<()> {
+        self.0.push(serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    pub fn into_params(self) -> Params {
+        Params::Array(self.0)
+    }
+}
@@ -0,0 +1,383 @@
+//! A differential parsing oracle, for testing gateways.
+//!
+//! > Only available with the `differential` feature.
+//!
+//! A gateway which validates a message with one json parser and forwards it to a backend using
+//! another is vulnerable if the parsers disagree, e.g. on which of two duplicate `method` members
+//! counts. [`compare`](fn.compare.html) parses the input with serde_json and with an independent,
+//! strict RFC 8259 parser in this module, and reports where they disagree on the members
+//! relevant to JSON-RPC.
+//!
+//! The reference parser differs from serde_json in the ways other parsers commonly do:
+//!
+//! - for duplicate members the **first** one wins (serde_json keeps the last),
+//! - lone UTF-16 surrogates in escapes are replaced with U+FFFD (serde_json rejects them),
+//! - nesting is limited to 512 levels (serde_json allows 128),
+//! - `-0` is the integer 0 (serde_json reads it as the float -0.0).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::differential::{self, Outcome};
+//!
+//! # fn main() {
+//! let json = r#"{"jsonrpc": "2.0", "method": "get", "id": 1}"#;
+//! assert_eq!(differential::compare(json), Outcome::Agree);
+//!
+//! // The gateway sees `get`, a backend with the other behavior executes `delete`.
+//! let json = r#"{"jsonrpc": "2.0", "method": "get", "method": "delete", "id": 1}"#;
+//! match differential::compare(json) {
+//!     Outcome::Disagree(disagreements) => {
+//!         assert_eq!(disagreements[0].member, "method");
+//!     }
+//!     outcome => panic!("{:?}", outcome),
+//! }
+//! # }
+//! ```
+
+use serde_json;
+use std_prelude::*;
+
+use Value;
+
+/// The members compared in each message.
+pub const MEMBERS: [&str; 6] = ["jsonrpc", "method", "params", "id", "result", "error"];
+
+/// The maximum nesting depth of the reference parser.
+pub const MAX_DEPTH: usize = 512;
+
+/// A member on which the parsers disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disagreement {
+    /// The index of the message in a batch, 0 for a single message.
+    pub index: usize,
+
+    /// The member name.
+    pub member: String,
+
+    /// The value according to serde_json.
+    pub serde_json: Option<Value>,
+
+    /// The value according to the reference parser.
+    pub reference: Option<Value>,
+}
+
+/// The result of comparing the parsers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// Both parsers reject the input.
+    BothReject,
+    /// The parsers agree on every relevant member.
+    Agree,
+    /// Only serde_json accepts the input. The reference parser's error is given.
+    OnlySerdeJson(String),
+    /// Only the reference parser accepts the input. serde_json's error is given.
+    OnlyReference(String),
+    /// Both accept the input but disagree on some members.
+    Disagree(Vec<Disagreement>),
+}
+
+impl Outcome {
+    /// Whether the input is safe to pass between the two parsers.
+    pub fn is_consistent(&self) -> bool {
+        matches!(*self, Outcome::BothReject | Outcome::Agree)
+    }
+}
+
+/// Parse `json` with both parsers and compare the JSON-RPC members of the message, or of each
+/// message of a batch.
+pub fn compare(json: &str) -> Outcome {
+    let ours = parse(json);
+    let theirs = serde_json::from_str::<Value>(json);
+    let (ours, theirs) = match (ours, theirs) {
+        (Err(_), Err(_)) => return Outcome::BothReject,
+        (Err(err), Ok(_)) => return Outcome::OnlySerdeJson(err),
+        (Ok(_), Err(err)) => return Outcome::OnlyReference(err.to_string()),
+        (Ok(ours), Ok(theirs)) => (ours, theirs),
+    };
+    let mut disagreements = Vec::new();
+    match (&theirs, &ours) {
+        (Value::Array(theirs), Value::Array(ours)) if theirs.len() == ours.len() => {
+            for (index, (theirs, ours)) in theirs.iter().zip(ours).enumerate() {
+                compare_message(index, theirs, ours, &mut disagreements);
+            }
+        }
+        (Value::Array(_), _) | (_, Value::Array(_)) => disagreements.push(Disagreement {
+            index: 0,
+            member: String::new(),
+            serde_json: Some(theirs.clone()),
+            reference: Some(ours.clone()),
+        }),
+        _ => compare_message(0, &theirs, &ours, &mut disagreements),
+    }
+    if disagreements.is_empty() {
+        Outcome::Agree
+    } else {
+        Outcome::Disagree(disagreements)
+    }
+}
+
+fn compare_message(index: usize, theirs: &Value, ours: &Value, out: &mut Vec<Disagreement>) {
+    for member in &MEMBERS {
+        let a = theirs.get(member);
+        let b = ours.get(member);
+        if a != b {
+            out.push(Disagreement {
+                index: index,
+                member: member.to_string(),
+                serde_json: a.cloned(),
+                reference: b.cloned(),
+            });
+        }
+    }
+}
+
+/// Parse `json` with the reference parser.
+pub fn parse(json: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        input: json.as_bytes(),
+        pos: 0,
+    };
+    parser.whitespace();
+    let value = parser.value(0)?;
+    parser.whitespace();
+    if parser.pos != parser.input.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at byte {}", message, self.pos)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).cloned()
+    }
+
+    fn whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", byte as char)))
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Value, String> {
+        if self.input[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth >= MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect(b'{')?;
+        let mut map = serde_json::Map::new();
+        self.whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(map));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.whitespace();
+            self.expect(b':')?;
+            self.whitespace();
+            let value = self.value(depth + 1)?;
+            map.entry(key).or_insert(value);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(map));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            self.whitespace();
+            values.push(self.value(depth + 1)?);
+            self.whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while let Some(byte) = self.peek() {
+                if byte == b'"' || byte == b'\\' || byte < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            // The input is a str and the run ends at an ASCII byte, so it is valid UTF-8.
+            out.push_str(str::from_utf8(&self.input[start..self.pos]).expect("utf-8"));
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    self.escape(&mut out)?;
+                }
+                Some(_) => return Err(self.error("control character in string")),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn escape(&mut self, out: &mut String) -> Result<(), String> {
+        let byte = self
+            .peek()
+            .ok_or_else(|| self.error("unterminated escape"))?;
+        self.pos += 1;
+        let c = match byte {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let unit = self.hex4()?;
+                if (0xd800..0xdc00).contains(&unit) && self.input[self.pos..].starts_with(b"\\u") {
+                    let save = self.pos;
+                    self.pos += 2;
+                    let low = self.hex4()?;
+                    if (0xdc00..0xe000).contains(&low) {
+                        let c = 0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00);
+                        char::from_u32(c).unwrap_or('\u{fffd}')
+                    } else {
+                        self.pos = save;
+                        '\u{fffd}'
+                    }
+                } else {
+                    char::from_u32(unit).unwrap_or('\u{fffd}')
+                }
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+        out.push(c);
+        Ok(())
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| str::from_utf8(d).ok())
+            .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("hex digits"))
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => self.digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+        let mut integer = true;
+        if self.peek() == Some(b'.') {
+            integer = false;
+            self.pos += 1;
+            if !self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            integer = false;
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if !self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+        let text = str::from_utf8(&self.input[start..self.pos]).expect("ascii");
+        if integer {
+            if let Ok(n) = text.parse::<i64>() {
+                return Ok(Value::from(n));
+            }
+            if let Ok(n) = text.parse::<u64>() {
+                return Ok(Value::from(n));
+            }
+        }
+        let n: f64 = text.parse().map_err(|_| self.error("invalid number"))?;
+        serde_json::Number::from_f64(n)
+            .map(Value::Number)
+            .ok_or_else(|| self.error("number out of range"))
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+    }
+}
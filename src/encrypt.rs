@@ -0,0 +1,589 @@
+//! A conventional envelope for end-to-end encrypted `params` and `result` values.
+//!
+//! This crate does not implement any cryptography. The caller supplies it by implementing
+//! [`Cipher`](trait.Cipher.html); [`Encrypted<T>`](struct.Encrypted.html) only defines how the
+//! sealed value is represented, so that deployments agree on the shape of the envelope:
+//!
+//! ```json
+//! {
+//!     "alg": "A256GCM",
+//!     "kid": "2018-05-key-1",
+//!     "nonce": "<base64>",
+//!     "ciphertext": "<base64>"
+//! }
+//! ```
+//!
+//! The plaintext is the json serialization of `T`. Binary members use standard base64 with
+//! padding.
+//!
+//! # Key rotation
+//!
+//! Keys are rotated with the [`rpc.rekey`](constant.REKEY_METHOD.html) method. The initiator
+//! proposes a new key id, the algorithms it supports (in order of preference) and a validity
+//! window in [`RekeyParams`](struct.RekeyParams.html); the peer answers with the chosen algorithm
+//! in a [`RekeyResult`](struct.RekeyResult.html). How the key material itself is agreed upon is up
+//! to the cipher. Both sides track the known keys in a [`KeySchedule`](struct.KeySchedule.html).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{Id, Request};
+//! use jrpc::encrypt::{Cipher, Encrypted, Sealed};
+//!
+//! /// A toy cipher. Do not use this for anything real!
+//! struct Xor(u8);
+//!
+//! impl Cipher for Xor {
+//!     type Error = String;
+//!
+//!     fn algorithm(&self) -> &str { "XOR" }
+//!     fn key_id(&self) -> &str { "key-1" }
+//!
+//!     fn encrypt(&self, plaintext: &[u8]) -> Result<Sealed, String> {
+//!         Ok(Sealed {
+//!             nonce: vec![0; 4],
+//!             ciphertext: plaintext.iter().map(|b| b ^ self.0).collect(),
+//!         })
+//!     }
+//!
+//!     fn decrypt(&self, key_id: &str, _nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+//!         if key_id != "key-1" {
+//!             return Err(format!("unknown key {}", key_id));
+//!         }
+//!         Ok(ciphertext.iter().map(|b| b ^ self.0).collect())
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let cipher = Xor(0x42);
+//! let params = vec!["secret".to_string()];
+//! let request = Request::with_params(
+//!     Id::from(1),
+//!     "Store".to_string(),
+//!     Encrypted::seal(&params, &cipher).unwrap(),
+//! );
+//!
+//! let json = request.to_string();
+//! assert!(!json.contains("secret"));
+//! assert!(json.contains(r#""alg":"XOR","kid":"key-1""#));
+//!
+//! let request: Request<String, Encrypted<Vec<String>>> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(request.params.unwrap().open(&cipher).unwrap(), params);
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+/// The output of [`Cipher::encrypt`](trait.Cipher.html#tymethod.encrypt).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sealed {
+    /// The nonce (or IV) used, if the algorithm has one. May be empty.
+    pub nonce: Vec<u8>,
+    /// The encrypted bytes, including any authentication tag.
+    pub ciphertext: Vec<u8>,
+}
+
+/// The cryptography used to seal and open [`Encrypted`](struct.Encrypted.html) envelopes.
+pub trait Cipher {
+    /// The error returned when encryption or decryption fails.
+    type Error;
+
+    /// The algorithm tag written to (and required in) the envelope, e.g. `"A256GCM"`.
+    fn algorithm(&self) -> &str;
+
+    /// The id of the key used by [`encrypt`](#tymethod.encrypt).
+    fn key_id(&self) -> &str;
+
+    /// Encrypt `plaintext` with the current key.
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Sealed, Self::Error>;
+
+    /// Decrypt `ciphertext`, which was encrypted with the key `key_id`.
+    ///
+    /// Implementations supporting key rotation should look up older keys by id.
+    fn decrypt(
+        &self,
+        key_id: &str,
+        nonce: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// An encrypted `T`.
+///
+/// Use this as the `params` of a Request or the `result` of a Response. See the
+/// [module documentation](index.html) for the serialized shape and an example.
+#[derive(Serialize, Deserialize)]
+pub struct Encrypted<T> {
+    /// The algorithm tag.
+    pub alg: String,
+
+    /// The id of the key the value was encrypted with.
+    pub kid: String,
+
+    /// The nonce, base64 encoded when serialized.
+    #[serde(with = "base64")]
+    pub nonce: Vec<u8>,
+
+    /// The encrypted json serialization of `T`, base64 encoded when serialized.
+    #[serde(with = "base64")]
+    pub ciphertext: Vec<u8>,
+
+    #[serde(skip)]
+    value: PhantomData<T>,
+}
+
+impl<T> fmt::Debug for Encrypted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Encrypted")
+            .field("alg", &self.alg)
+            .field("kid", &self.kid)
+            .field("nonce", &self.nonce)
+            .field("ciphertext", &self.ciphertext)
+            .finish()
+    }
+}
+
+impl<T> Clone for Encrypted<T> {
+    fn clone(&self) -> Self {
+        Encrypted {
+            alg: self.alg.clone(),
+            kid: self.kid.clone(),
+            nonce: self.nonce.clone(),
+            ciphertext: self.ciphertext.clone(),
+            value: PhantomData,
+        }
+    }
+}
+
+/// An empty envelope, which no cipher will open.
+///
+/// Only needed so `Encrypted<T>` can be used as the `params` of a
+/// [`Request`](../struct.Request.html).
+impl<T> Default for Encrypted<T> {
+    fn default() -> Self {
+        Encrypted {
+            alg: String::new(),
+            kid: String::new(),
+            nonce: Vec::new(),
+            ciphertext: Vec::new(),
+            value: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Encrypted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.alg == other.alg
+            && self.kid == other.kid
+            && self.nonce == other.nonce
+            && self.ciphertext == other.ciphertext
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Encrypted<T> {
+    /// Serialize and encrypt `value`.
+    pub fn seal<C: Cipher>(value: &T, cipher: &C) -> Result<Self, EncryptError<C::Error>> {
+        let plaintext = serde_json::to_vec(value).map_err(EncryptError::Json)?;
+        let sealed = cipher.encrypt(&plaintext).map_err(EncryptError::Cipher)?;
+        Ok(Encrypted {
+            alg: cipher.algorithm().to_string(),
+            kid: cipher.key_id().to_string(),
+            nonce: sealed.nonce,
+            ciphertext: sealed.ciphertext,
+            value: PhantomData,
+        })
+    }
+
+    /// Decrypt and deserialize the value.
+    ///
+    /// Fails if the envelope's algorithm does not match the cipher's.
+    pub fn open<C: Cipher>(&self, cipher: &C) -> Result<T, EncryptError<C::Error>> {
+        if self.alg != cipher.algorithm() {
+            return Err(EncryptError::Algorithm {
+                expected: cipher.algorithm().to_string(),
+                found: self.alg.clone(),
+            });
+        }
+        let plaintext = cipher
+            .decrypt(&self.kid, &self.nonce, &self.ciphertext)
+            .map_err(EncryptError::Cipher)?;
+        serde_json::from_slice(&plaintext).map_err(EncryptError::Json)
+    }
+}
+
+/// The error returned when sealing or opening an [`Encrypted`](struct.Encrypted.html) value.
+#[derive(Debug)]
+pub enum EncryptError<E> {
+    /// The plaintext could not be (de)serialized.
+    Json(serde_json::Error),
+    /// The cipher failed.
+    Cipher(E),
+    /// The envelope was sealed with a different algorithm than the cipher's.
+    Algorithm {
+        /// The cipher's algorithm.
+        expected: String,
+        /// The envelope's algorithm.
+        found: String,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for EncryptError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncryptError::Json(ref err) => write!(f, "invalid plaintext: {}", err),
+            EncryptError::Cipher(ref err) => write!(f, "cipher failed: {}", err),
+            EncryptError::Algorithm {
+                ref expected,
+                ref found,
+            } => write!(
+                f,
+                "envelope algorithm {:?} does not match cipher algorithm {:?}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> error::Error for EncryptError<E> {}
+
+/// The method name of the key rotation handshake.
+pub const REKEY_METHOD: &str = "rpc.rekey";
+
+/// The `params` of an [`rpc.rekey`](constant.REKEY_METHOD.html) Request.
+///
+/// All times are milliseconds since the unix epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RekeyParams {
+    /// The id of the proposed key.
+    pub key_id: String,
+
+    /// The algorithms the initiator supports, most preferred first.
+    pub algorithms: Vec<String>,
+
+    /// The time from which the new key is used for encryption.
+    pub not_before_ms: u64,
+
+    /// The time after which the new key can no longer be used at all, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after_ms: Option<u64>,
+}
+
+/// The `result` of an [`rpc.rekey`](constant.REKEY_METHOD.html) Request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RekeyResult {
+    /// The id of the accepted key, which must equal the proposed one.
+    pub key_id: String,
+
+    /// The algorithm chosen from the proposed ones.
+    pub algorithm: String,
+}
+
+/// A key known to a [`KeySchedule`](struct.KeySchedule.html).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyEpoch {
+    /// The key id.
+    pub key_id: String,
+
+    /// The algorithm used with this key.
+    pub algorithm: String,
+
+    /// The time from which the key is used for encryption.
+    pub not_before_ms: u64,
+
+    /// The time after which the key can no longer be used at all, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_after_ms: Option<u64>,
+}
+
+impl KeyEpoch {
+    fn is_expired(&self, now_ms: u64) -> bool {
+        self.not_after_ms.is_some_and(|t| now_ms >= t)
+    }
+}
+
+/// The state of a key at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyState {
+    /// The key is known but not valid yet.
+    Pending,
+    /// The key is used for encryption.
+    Current,
+    /// A newer key is current. The key may still be used to open messages in flight.
+    Retired,
+    /// The key's validity window has passed.
+    Expired,
+}
+
+/// Tracks the keys of a secure channel through rotations.
+///
+/// The schedule is a pure state tracker: the caller supplies the current time and keeps its
+/// [`Cipher`](trait.Cipher.html) in sync with it.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// use jrpc::encrypt::{KeyEpoch, KeySchedule, KeyState, RekeyParams};
+///
+/// # fn main() {
+/// let mut schedule = KeySchedule::new(KeyEpoch {
+///     key_id: "key-1".into(),
+///     algorithm: "A256GCM".into(),
+///     not_before_ms: 0,
+///     not_after_ms: None,
+/// });
+/// let mut peer = schedule.clone();
+///
+/// // The initiator proposes a rotation at t=1000 ...
+/// let params = RekeyParams {
+///     key_id: "key-2".into(),
+///     algorithms: vec!["C20P".into(), "A256GCM".into()],
+///     not_before_ms: 1000,
+///     not_after_ms: None,
+/// };
+///
+/// // ... which the peer accepts, choosing the first algorithm it supports.
+/// let result = peer.respond(&params, &["A256GCM"]).unwrap();
+/// assert_eq!(result.algorithm, "A256GCM");
+/// schedule.accept(&params, &result).unwrap();
+/// assert_eq!(peer, schedule);
+///
+/// assert_eq!(schedule.current(500).unwrap().key_id, "key-1");
+/// assert_eq!(schedule.current(1000).unwrap().key_id, "key-2");
+/// assert_eq!(schedule.state("key-1", 1000), Some(KeyState::Retired));
+/// assert!(schedule.can_decrypt("key-1", 1000));
+///
+/// // Once messages sealed with the old key have drained, it can be dropped.
+/// assert_eq!(schedule.prune(5000, 2000).len(), 1);
+/// assert!(!schedule.can_decrypt("key-1", 5000));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySchedule {
+    /// Sorted by `not_before_ms`.
+    epochs: Vec<KeyEpoch>,
+}
+
+impl KeySchedule {
+    /// Create a schedule starting with the `initial` key.
+    pub fn new(initial: KeyEpoch) -> Self {
+        KeySchedule {
+            epochs: vec![initial],
+        }
+    }
+
+    /// Look up a key by id.
+    pub fn get(&self, key_id: &str) -> Option<&KeyEpoch> {
+        self.epochs.iter().find(|e| e.key_id == key_id)
+    }
+
+    /// Iterate over all known keys, oldest first.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, KeyEpoch> {
+        self.epochs.iter()
+    }
+
+    /// The key to encrypt with at `now_ms`: the newest key which is neither pending nor expired.
+    pub fn current(&self, now_ms: u64) -> Option<&KeyEpoch> {
+        self.epochs
+            .iter()
+            .rev()
+            .find(|e| e.not_before_ms <= now_ms && !e.is_expired(now_ms))
+    }
+
+    /// The state of the key `key_id` at `now_ms`, or `None` if the key is unknown.
+    pub fn state(&self, key_id: &str, now_ms: u64) -> Option<KeyState> {
+        let epoch = self.get(key_id)?;
+        let state = if epoch.is_expired(now_ms) {
+            KeyState::Expired
+        } else if epoch.not_before_ms > now_ms {
+            KeyState::Pending
+        } else if self.current(now_ms).map(|e| &e.key_id) == Some(&epoch.key_id) {
+            KeyState::Current
+        } else {
+            KeyState::Retired
+        };
+        Some(state)
+    }
+
+    /// Whether a message sealed with `key_id` may be opened at `now_ms`.
+    pub fn can_decrypt(&self, key_id: &str, now_ms: u64) -> bool {
+        matches!(
+            self.state(key_id, now_ms),
+            Some(KeyState::Current) | Some(KeyState::Retired)
+        )
+    }
+
+    /// Answer a rekey proposal as the peer, adding the new key to the schedule.
+    ///
+    /// The chosen algorithm is the first proposed one contained in `supported`.
+    pub fn respond(
+        &mut self,
+        params: &RekeyParams,
+        supported: &[&str],
+    ) -> Result<RekeyResult, RekeyError> {
+        let algorithm = params
+            .algorithms
+            .iter()
+            .find(|a| supported.contains(&a.as_str()))
+            .ok_or(RekeyError::NoCommonAlgorithm)?;
+        let result = RekeyResult {
+            key_id: params.key_id.clone(),
+            algorithm: algorithm.clone(),
+        };
+        self.accept(params, &result)?;
+        Ok(result)
+    }
+
+    /// Record the peer's answer to a rekey proposal as the initiator, adding the new key to the
+    /// schedule.
+    pub fn accept(&mut self, params: &RekeyParams, result: &RekeyResult) -> Result<(), RekeyError> {
+        if result.key_id != params.key_id {
+            return Err(RekeyError::KeyIdMismatch);
+        }
+        if !params.algorithms.contains(&result.algorithm) {
+            return Err(RekeyError::NoCommonAlgorithm);
+        }
+        if self.get(&params.key_id).is_some() {
+            return Err(RekeyError::DuplicateKeyId);
+        }
+        if params
+            .not_after_ms
+            .is_some_and(|t| t <= params.not_before_ms)
+        {
+            return Err(RekeyError::InvalidWindow);
+        }
+        let epoch = KeyEpoch {
+            key_id: params.key_id.clone(),
+            algorithm: result.algorithm.clone(),
+            not_before_ms: params.not_before_ms,
+            not_after_ms: params.not_after_ms,
+        };
+        let index = self
+            .epochs
+            .iter()
+            .position(|e| e.not_before_ms > epoch.not_before_ms)
+            .unwrap_or(self.epochs.len());
+        self.epochs.insert(index, epoch);
+        Ok(())
+    }
+
+    /// Drop the keys which are no longer needed at `now_ms` and return them.
+    ///
+    /// These are the expired keys, plus the retired keys once `grace_ms` have passed since the
+    /// current key took over.
+    pub fn prune(&mut self, now_ms: u64, grace_ms: u64) -> Vec<KeyEpoch> {
+        let current = self.current(now_ms).map(|e| e.not_before_ms);
+        let (dropped, kept) = self.epochs.drain(..).partition(|e: &KeyEpoch| {
+            let retired_out = current
+                .is_some_and(|c| e.not_before_ms < c && now_ms >= c.saturating_add(grace_ms));
+            e.is_expired(now_ms) || retired_out
+        });
+        self.epochs = kept;
+        dropped
+    }
+}
+
+/// The error returned when a rekey proposal cannot be accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RekeyError {
+    /// None of the proposed algorithms is supported.
+    NoCommonAlgorithm,
+    /// The result names a different key than the proposal.
+    KeyIdMismatch,
+    /// The proposed key id is already in use.
+    DuplicateKeyId,
+    /// The validity window ends before it starts.
+    InvalidWindow,
+}
+
+impl fmt::Display for RekeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            RekeyError::NoCommonAlgorithm => "no common algorithm",
+            RekeyError::KeyIdMismatch => "accepted key id does not match the proposed one",
+            RekeyError::DuplicateKeyId => "key id is already in use",
+            RekeyError::InvalidWindow => "key validity window ends before it starts",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl error::Error for RekeyError {}
+
+/// Standard base64 with padding, for `#[serde(with = "base64")]`.
+mod base64 {
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+    use std_prelude::*;
+
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    pub fn encode(bytes: &[u8]) -> String {
+        let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                chunk.get(1).cloned().unwrap_or(0),
+                chunk.get(2).cloned().unwrap_or(0),
+            ];
+            let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    pub fn decode(text: &str) -> Option<Vec<u8>> {
+        let text = text.as_bytes();
+        if !text.len().is_multiple_of(4) {
+            return None;
+        }
+        let mut out = Vec::with_capacity(text.len() / 4 * 3);
+        for chunk in text.chunks(4) {
+            let padding = chunk.iter().rev().take_while(|b| **b == b'=').count();
+            if padding > 2 {
+                return None;
+            }
+            let mut n = 0u32;
+            for (i, b) in chunk.iter().enumerate() {
+                let value = if i >= 4 - padding {
+                    0
+                } else {
+                    ALPHABET.iter().position(|a| a == b)? as u32
+                };
+                n = (n << 6) | value;
+            }
+            out.push((n >> 16) as u8);
+            if padding < 2 {
+                out.push((n >> 8) as u8);
+            }
+            if padding < 1 {
+                out.push(n as u8);
+            }
+        }
+        Some(out)
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        decode(&text).ok_or_else(|| de::Error::custom("invalid base64"))
+    }
+}
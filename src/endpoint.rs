@@ -0,0 +1,201 @@
+//! Failover between a primary endpoint and its replicas.
+//!
+//! An [`EndpointSet`](struct.EndpointSet.html) decides where a client sends each Request. Calls
+//! go to the primary while it is up and to the first replica which is up otherwise.
+//! Subscriptions are sticky: all their Requests go to the endpoint they were first routed to, for
+//! as long as it is up.
+//!
+//! The caller feeds the health of the endpoints, e.g. from connection errors or health checks.
+//! Marking an endpoint as down returns a [`Failover`](struct.Failover.html) listing the pending
+//! calls to resend and the subscriptions to re-establish, each with its new endpoint.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::Id;
+//! use jrpc::endpoint::{EndpointSet, Health};
+//!
+//! # fn main() {
+//! let mut endpoints = EndpointSet::new("primary");
+//! endpoints.add_replica("replica-1");
+//!
+//! let endpoint = endpoints.route().unwrap().to_string();
+//! assert_eq!(endpoint, "primary");
+//! endpoints.sent(Id::from(1), &endpoint);
+//! assert_eq!(endpoints.route_sticky("prices").unwrap(), "primary");
+//!
+//! let failover = endpoints.set_health("primary", Health::Down);
+//! assert_eq!(failover.calls, vec![(Id::from(1), Some("replica-1".to_string()))]);
+//! assert_eq!(failover.subscriptions, vec![("prices".to_string(), Some("replica-1".to_string()))]);
+//! assert_eq!(endpoints.route(), Some("replica-1"));
+//!
+//! // Subscriptions stay on the replica when the primary recovers.
+//! endpoints.set_health("primary", Health::Up);
+//! assert_eq!(endpoints.route(), Some("primary"));
+//! assert_eq!(endpoints.route_sticky("prices"), Some("replica-1"));
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use std_prelude::*;
+
+use Id;
+
+/// The health of an endpoint, as observed by the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    /// Requests may be sent to the endpoint.
+    Up,
+    /// The endpoint is unreachable.
+    Down,
+}
+
+/// What to do after an endpoint went down.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Failover {
+    /// The pending calls sent to the endpoint, in no particular order, with the endpoint to
+    /// resend them to, or `None` if every endpoint is down.
+    pub calls: Vec<(Id, Option<String>)>,
+
+    /// The subscriptions routed to the endpoint, with their new endpoint, or `None` if every
+    /// endpoint is down.
+    pub subscriptions: Vec<(String, Option<String>)>,
+}
+
+impl Failover {
+    /// Whether nothing needs to be moved.
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty() && self.subscriptions.is_empty()
+    }
+}
+
+/// A primary endpoint and its replicas, in order of preference.
+#[derive(Debug, Clone)]
+pub struct EndpointSet {
+    /// The endpoints, the primary first.
+    endpoints: Vec<(String, Health)>,
+    pending: HashMap<Id, String>,
+    subscriptions: BTreeMap<String, String>,
+}
+
+impl EndpointSet {
+    /// Create a set with a primary endpoint, which is up.
+    pub fn new<S: Into<String>>(primary: S) -> Self {
+        EndpointSet {
+            endpoints: vec![(primary.into(), Health::Up)],
+            pending: HashMap::new(),
+            subscriptions: BTreeMap::new(),
+        }
+    }
+
+    /// Add a replica, which is up, after the existing ones.
+    pub fn add_replica<S: Into<String>>(&mut self, replica: S) {
+        let replica = replica.into();
+        if self.health(&replica).is_none() {
+            self.endpoints.push((replica, Health::Up));
+        }
+    }
+
+    /// The primary endpoint.
+    pub fn primary(&self) -> &str {
+        &self.endpoints[0].0
+    }
+
+    /// The health of an endpoint, or `None` if it is not in the set.
+    pub fn health(&self, endpoint: &str) -> Option<Health> {
+        self.endpoints.iter().find(|e| e.0 == endpoint).map(|e| e.1)
+    }
+
+    /// Record the health of an endpoint.
+    ///
+    /// If it went down, its pending calls and subscriptions are moved to the preferred endpoint
+    /// which is still up, and returned to be resent.
+    pub fn set_health(&mut self, endpoint: &str, health: Health) -> Failover {
+        let previous = match self.endpoints.iter_mut().find(|e| e.0 == endpoint) {
+            Some(entry) => ::std::mem::replace(&mut entry.1, health),
+            None => return Failover::default(),
+        };
+        if health != Health::Down || previous == Health::Down {
+            return Failover::default();
+        }
+        let target = self.route().map(str::to_string);
+        let mut failover = Failover::default();
+        for (id, to) in &self.pending {
+            if to == endpoint {
+                failover.calls.push((id.clone(), target.clone()));
+            }
+        }
+        for (subscription, to) in &self.subscriptions {
+            if to == endpoint {
+                failover
+                    .subscriptions
+                    .push((subscription.clone(), target.clone()));
+            }
+        }
+        match target {
+            Some(ref target) => {
+                for (id, _) in &failover.calls {
+                    self.pending.insert(id.clone(), target.clone());
+                }
+                for (subscription, _) in &failover.subscriptions {
+                    self.subscriptions
+                        .insert(subscription.clone(), target.clone());
+                }
+            }
+            None => {
+                for (id, _) in &failover.calls {
+                    self.pending.remove(id);
+                }
+                for (subscription, _) in &failover.subscriptions {
+                    self.subscriptions.remove(subscription);
+                }
+            }
+        }
+        failover
+    }
+
+    /// The endpoint to send a call to: the primary if it is up, otherwise the first replica
+    /// which is up.
+    pub fn route(&self) -> Option<&str> {
+        self.endpoints
+            .iter()
+            .find(|e| e.1 == Health::Up)
+            .map(|e| e.0.as_str())
+    }
+
+    /// The endpoint of a subscription. A new subscription, or one whose endpoint is down, is
+    /// routed like a call and stays there.
+    pub fn route_sticky(&mut self, subscription: &str) -> Option<&str> {
+        let current = self.subscriptions.get(subscription).cloned();
+        let endpoint = match current {
+            Some(ref endpoint) if self.health(endpoint) == Some(Health::Up) => endpoint.clone(),
+            _ => self.route()?.to_string(),
+        };
+        self.subscriptions
+            .insert(subscription.to_string(), endpoint);
+        self.subscriptions.get(subscription).map(String::as_str)
+    }
+
+    /// Stop routing a subscription.
+    pub fn unsubscribe(&mut self, subscription: &str) -> bool {
+        self.subscriptions.remove(subscription).is_some()
+    }
+
+    /// Record that the call `id` was sent to `endpoint`.
+    pub fn sent(&mut self, id: Id, endpoint: &str) {
+        self.pending.insert(id, endpoint.to_string());
+    }
+
+    /// Record that the call `id` was answered, returning the endpoint it was sent to.
+    pub fn complete(&mut self, id: &Id) -> Option<String> {
+        self.pending.remove(id)
+    }
+
+    /// The number of pending calls sent to `endpoint`.
+    pub fn pending(&self, endpoint: &str) -> usize {
+        self.pending.values().filter(|e| *e == endpoint).count()
+    }
+}
@@ -0,0 +1,252 @@
+//! Type-erased messages, to store heterogeneous messages together.
+//!
+//! A gateway holding the pending messages of many services would otherwise need a collection
+//! per combination of method, params and result types. [`ErasedRequest`](struct.ErasedRequest.html)
+//! and [`ErasedResponse`](struct.ErasedResponse.html) hold any typed message with its payload
+//! serialized to a `Value`, and implement the object-safe [`AnyMessage`](trait.AnyMessage.html),
+//! so they can be stored as `Box<dyn AnyMessage>` and processed uniformly. The typed message is
+//! decoded back when it is needed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{Id, Request, Response};
+//! use jrpc::erased::{AnyMessage, ErasedRequest, ErasedResponse, MessageKind};
+//!
+//! # fn main() {
+//! let request = Request::with_params(Id::from(1), "add".to_string(), (2, 3));
+//! let response: Response<u64> = Response::success(Id::from(1), 5);
+//!
+//! let pending: Vec<Box<dyn AnyMessage>> = vec![
+//!     Box::new(ErasedRequest::new(&request).unwrap()),
+//!     Box::new(ErasedResponse::new(&response).unwrap()),
+//! ];
+//! let kinds: Vec<MessageKind> = pending.iter().map(|m| m.kind()).collect();
+//! assert_eq!(kinds, vec![MessageKind::Request, MessageKind::Response]);
+//! assert_eq!(pending[0].method(), Some("add"));
+//! assert_eq!(pending[1].id(), Some(Id::from(1)));
+//! assert_eq!(pending[0].to_json().unwrap(), request.to_string());
+//!
+//! // Decoding back.
+//! let request = ErasedRequest::new(&request).unwrap().decode::<(u64, u64)>().unwrap();
+//! assert_eq!(request.params, Some((2, 3)));
+//! # }
+//! ```
+
+use core::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use prelude::*;
+
+use {Error, ErrorObject, Id, IdReq, Request, Response, Success, Value, V2_0};
+
+/// The kind of a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageKind {
+    /// A Request with an `id`, even Null.
+    Request,
+    /// A Request without an `id`.
+    Notification,
+    /// A Response.
+    Response,
+}
+
+/// A message of any type. Object safe.
+pub trait AnyMessage: fmt::Debug {
+    /// The kind of the message.
+    fn kind(&self) -> MessageKind;
+
+    /// The `id`, `None` for notifications.
+    fn id(&self) -> Option<Id>;
+
+    /// The method, `None` for Responses.
+    fn method(&self) -> Option<&str>;
+
+    /// Serialize the message to a `Value`.
+    fn to_value(&self) -> serde_json::Result<Value>;
+
+    /// Serialize the message to json.
+    fn to_json(&self) -> serde_json::Result<String>;
+}
+
+/// A Request of any method and params type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErasedRequest {
+    inner: Request<String, Value>,
+}
+
+impl ErasedRequest {
+    /// Erase the types of a Request, serializing its params.
+    pub fn new<M, T>(request: &Request<M, T>) -> serde_json::Result<Self>
+    where
+        M: AsRef<str>,
+        T: Serialize,
+    {
+        let params = match request.params {
+            Some(ref params) => Some(serde_json::to_value(params)?),
+            None => None,
+        };
+        Ok(ErasedRequest {
+            inner: Request {
+                jsonrpc: V2_0,
+                method: request.method.as_ref().to_string(),
+                params: params,
+                id: request.id.clone(),
+            },
+        })
+    }
+
+    /// The erased Request.
+    pub fn request(&self) -> &Request<String, Value> {
+        &self.inner
+    }
+
+    /// Convert into the erased Request.
+    pub fn into_inner(self) -> Request<String, Value> {
+        self.inner
+    }
+
+    /// Decode the params into their type.
+    pub fn decode<T: DeserializeOwned>(self) -> serde_json::Result<Request<String, T>> {
+        self.inner.try_map_params(serde_json::from_value)
+    }
+}
+
+impl From<Request<String, Value>> for ErasedRequest {
+    fn from(request: Request<String, Value>) -> Self {
+        ErasedRequest { inner: request }
+    }
+}
+
+impl AnyMessage for ErasedRequest {
+    fn kind(&self) -> MessageKind {
+        match self.inner.id {
+            IdReq::Notification => MessageKind::Notification,
+            _ => MessageKind::Request,
+        }
+    }
+
+    fn id(&self) -> Option<Id> {
+        self.inner.id.clone().to_id()
+    }
+
+    fn method(&self) -> Option<&str> {
+        Some(&self.inner.method)
+    }
+
+    fn to_value(&self) -> serde_json::Result<Value> {
+        serde_json::to_value(&self.inner)
+    }
+
+    fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.inner)
+    }
+}
+
+/// A Response of any result and error data type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErasedResponse {
+    inner: Response<Value, Value>,
+}
+
+impl ErasedResponse {
+    /// Erase the types of a Response, serializing its result or error data.
+    pub fn new<T, E>(response: &Response<T, E>) -> serde_json::Result<Self>
+    where
+        T: Serialize,
+        E: Serialize,
+    {
+        let inner = match *response {
+            Response::Ok(ref success) => Response::Ok(Success {
+                jsonrpc: V2_0,
+                result: serde_json::to_value(&success.result)?,
+                id: success.id.clone(),
+            }),
+            Response::Err(ref error) => {
+                let data = match error.error.data {
+                    Some(ref data) => Some(serde_json::to_value(data)?),
+                    None => None,
+                };
+                Response::Err(Error::new(
+                    error.id.clone(),
+                    error.error.code,
+                    error.error.message.clone(),
+                    data,
+                ))
+            }
+        };
+        Ok(ErasedResponse { inner: inner })
+    }
+
+    /// The erased Response.
+    pub fn response(&self) -> &Response<Value, Value> {
+        &self.inner
+    }
+
+    /// Convert into the erased Response.
+    pub fn into_inner(self) -> Response<Value, Value> {
+        self.inner
+    }
+
+    /// Decode the result or error data into their types.
+    pub fn decode<T, E>(self) -> serde_json::Result<Response<T, E>>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+    {
+        Ok(match self.inner {
+            Response::Ok(success) => Response::Ok(Success {
+                jsonrpc: V2_0,
+                result: serde_json::from_value(success.result)?,
+                id: success.id,
+            }),
+            Response::Err(error) => {
+                let data = match error.error.data {
+                    Some(data) => Some(serde_json::from_value(data)?),
+                    None => None,
+                };
+                Response::Err(Error {
+                    jsonrpc: V2_0,
+                    error: ErrorObject {
+                        code: error.error.code,
+                        message: error.error.message,
+                        data: data,
+                    },
+                    id: error.id,
+                })
+            }
+        })
+    }
+}
+
+impl From<Response<Value, Value>> for ErasedResponse {
+    fn from(response: Response<Value, Value>) -> Self {
+        ErasedResponse { inner: response }
+    }
+}
+
+impl AnyMessage for ErasedResponse {
+    fn kind(&self) -> MessageKind {
+        MessageKind::Response
+    }
+
+    fn id(&self) -> Option<Id> {
+        Some(self.inner.id().clone())
+    }
+
+    fn method(&self) -> Option<&str> {
+        None
+    }
+
+    fn to_value(&self) -> serde_json::Result<Value> {
+        serde_json::to_value(&self.inner)
+    }
+
+    fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.inner)
+    }
+}
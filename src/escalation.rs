@@ -0,0 +1,188 @@
+//! Escalating responses to peers which keep violating the protocol.
+//!
+//! A server keeps one [`FaultPolicy`](struct.FaultPolicy.html) per connection and records every
+//! [`Violation`](enum.Violation.html) of its peer. The policy counts the violations of a sliding
+//! window and recommends an [`Action`](enum.Action.html) by comparing the count with its
+//! [`FaultThresholds`](struct.FaultThresholds.html): a broken client is warned, then throttled,
+//! then disconnected. A peer which behaves for a whole window is forgiven.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::ErrorCode;
+//! use jrpc::escalation::{Action, FaultPolicy, FaultThresholds, Violation};
+//!
+//! # fn main() {
+//! let thresholds = FaultThresholds::new(1, 3, 5).window_ms(10_000);
+//! let mut policy = FaultPolicy::new(thresholds);
+//!
+//! assert_eq!(policy.record(Violation::Parse, 0), Action::Warn);
+//! assert_eq!(policy.record(Violation::Parse, 100), Action::Warn);
+//! let violation = Violation::of(ErrorCode::InvalidRequest).unwrap();
+//! assert_eq!(policy.record(violation, 200), Action::Throttle);
+//!
+//! // Ten seconds later all is forgiven.
+//! assert_eq!(policy.action(10_200), Action::Allow);
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use std_prelude::*;
+
+use ErrorCode;
+
+/// A protocol violation by a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Violation {
+    /// The peer sent invalid json.
+    Parse,
+    /// The peer sent json which is not a valid Request.
+    InvalidRequest,
+    /// The peer sent a message over the size limit.
+    Oversized,
+}
+
+impl Violation {
+    /// The violation an error code is answering, if it denotes one. Errors of a valid Request,
+    /// like `MethodNotFound`, are not violations.
+    pub fn of(code: ErrorCode) -> Option<Violation> {
+        match code {
+            ErrorCode::ParseError => Some(Violation::Parse),
+            ErrorCode::InvalidRequest => Some(Violation::InvalidRequest),
+            _ => None,
+        }
+    }
+}
+
+/// What to do about a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Serve the peer normally.
+    Allow,
+    /// Log a warning.
+    Warn,
+    /// Slow the peer down, e.g. delay reading from it.
+    Throttle,
+    /// Close the connection.
+    Disconnect,
+}
+
+/// The number of violations within a window at which each action is recommended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultThresholds {
+    /// Warn at this many violations.
+    pub warn: u32,
+
+    /// Throttle at this many violations.
+    pub throttle: u32,
+
+    /// Disconnect at this many violations.
+    pub disconnect: u32,
+
+    /// The length of the window, in milliseconds. Default: one minute.
+    #[serde(default = "default_window_ms")]
+    pub window_ms: u64,
+}
+
+fn default_window_ms() -> u64 {
+    60_000
+}
+
+impl Default for FaultThresholds {
+    /// Warn at the first violation, throttle at 5 and disconnect at 20 within a minute.
+    fn default() -> Self {
+        FaultThresholds::new(1, 5, 20)
+    }
+}
+
+impl FaultThresholds {
+    /// Create thresholds with a window of one minute.
+    pub fn new(warn: u32, throttle: u32, disconnect: u32) -> Self {
+        FaultThresholds {
+            warn: warn,
+            throttle: throttle,
+            disconnect: disconnect,
+            window_ms: default_window_ms(),
+        }
+    }
+
+    /// Set the length of the window.
+    pub fn window_ms(mut self, window_ms: u64) -> Self {
+        self.window_ms = window_ms;
+        self
+    }
+
+    /// The action recommended for `count` violations.
+    pub fn action(&self, count: usize) -> Action {
+        let count = count as u64;
+        if self.disconnect > 0 && count >= u64::from(self.disconnect) {
+            Action::Disconnect
+        } else if self.throttle > 0 && count >= u64::from(self.throttle) {
+            Action::Throttle
+        } else if self.warn > 0 && count >= u64::from(self.warn) {
+            Action::Warn
+        } else {
+            Action::Allow
+        }
+    }
+}
+
+/// Tracks the violations of one peer.
+#[derive(Debug, Clone, Default)]
+pub struct FaultPolicy {
+    thresholds: FaultThresholds,
+    /// The time and kind of the violations within the window, oldest first.
+    violations: VecDeque<(u64, Violation)>,
+}
+
+impl FaultPolicy {
+    /// Create a policy for a peer without violations.
+    pub fn new(thresholds: FaultThresholds) -> Self {
+        FaultPolicy {
+            thresholds: thresholds,
+            violations: VecDeque::new(),
+        }
+    }
+
+    /// The thresholds.
+    pub fn thresholds(&self) -> &FaultThresholds {
+        &self.thresholds
+    }
+
+    /// Record a violation at `now_ms` and return the recommended action.
+    pub fn record(&mut self, violation: Violation, now_ms: u64) -> Action {
+        self.violations.push_back((now_ms, violation));
+        self.action(now_ms)
+    }
+
+    /// The recommended action at `now_ms`.
+    pub fn action(&mut self, now_ms: u64) -> Action {
+        self.expire(now_ms);
+        self.thresholds.action(self.violations.len())
+    }
+
+    /// The number of violations of a kind within the window.
+    pub fn count(&self, violation: Violation) -> usize {
+        self.violations.iter().filter(|v| v.1 == violation).count()
+    }
+
+    /// Forget all violations, e.g. after the peer authenticated.
+    pub fn reset(&mut self) {
+        self.violations.clear();
+    }
+
+    fn expire(&mut self, now_ms: u64) {
+        let window_ms = self.thresholds.window_ms;
+        while let Some(&(at, _)) = self.violations.front() {
+            if now_ms.saturating_sub(at) < window_ms {
+                break;
+            }
+            self.violations.pop_front();
+        }
+    }
+}
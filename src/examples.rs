@@ -0,0 +1,189 @@
+//! Example Request and Response pairs, generated from the documented methods.
+//!
+//! [`render`](fn.render.html) produces one [`ExampleExchange`](struct.ExampleExchange.html) per
+//! [`MethodDoc`](../catalog/struct.MethodDoc.html), with sequential ids and params and results
+//! built from their schemas by [`example`](fn.example.html), so that documentation sites and
+//! READMEs embed examples which cannot drift from the catalog.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::catalog::MethodDoc;
+//! use jrpc::examples;
+//!
+//! # fn main() {
+//! let methods = vec![
+//!     MethodDoc::new("transfer")
+//!         .params_schema(json!({
+//!             "type": "object",
+//!             "properties": {
+//!                 "to": {"type": "string", "default": "acct-42"},
+//!                 "amount": {"type": "integer", "minimum": 10},
+//!             },
+//!         }))
+//!         .result_schema(json!({"type": "string", "examples": ["tx-1"]})),
+//!     MethodDoc::new("ping"),
+//! ];
+//!
+//! let exchanges = examples::render(&methods);
+//! assert_eq!(exchanges[0].request, json!({
+//!     "jsonrpc": "2.0",
+//!     "method": "transfer",
+//!     "params": {"to": "acct-42", "amount": 10},
+//!     "id": 1,
+//! }));
+//! assert_eq!(exchanges[0].response, json!({"jsonrpc": "2.0", "result": "tx-1", "id": 1}));
+//! assert_eq!(exchanges[1].request["id"], 2);
+//! assert_eq!(exchanges[1].response["result"], json!(null));
+//! # }
+//! ```
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use catalog::MethodDoc;
+use {Id, Request, Response, Value};
+
+/// An example call of a method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExampleExchange {
+    /// The method name.
+    pub method: String,
+
+    /// The Request.
+    pub request: Value,
+
+    /// The Response to `request`.
+    pub response: Value,
+}
+
+/// The longest String and Array [`example`](fn.example.html) builds, whatever the `minLength`
+/// or `minItems` of the schema, so large or hostile schemas can not exhaust memory.
+pub const MAX_EXAMPLE_LEN: usize = 64;
+
+/// Render an example exchange for every method, with the ids `1`, `2`, ... in order.
+pub fn render(methods: &[MethodDoc]) -> Vec<ExampleExchange> {
+    methods
+        .iter()
+        .enumerate()
+        .map(|(i, doc)| {
+            let id = Id::Int(i as i64 + 1);
+            let params = doc.params_schema.as_ref().map(example).unwrap_or(Value::Null);
+            let request = Request::with_params(id.clone(), doc.name.clone(), params);
+            let result = doc.result_schema.as_ref().map(example).unwrap_or(Value::Null);
+            ExampleExchange {
+                method: doc.name.clone(),
+                request: to_value(&request),
+                response: to_value(&Response::success(id, result)),
+            }
+        })
+        .collect()
+}
+
+/// Serialize a message, without `"params": null`.
+fn to_value<T: Serialize>(message: &T) -> Value {
+    let mut value = serde_json::to_value(message).expect("messages are always serializable");
+    if let Some(object) = value.as_object_mut() {
+        if object.get("params") == Some(&Value::Null) {
+            object.remove("params");
+        }
+    }
+    value
+}
+
+/// Build an example value matching `schema`.
+///
+/// In order of preference this is the schema's `default`, its first `examples`, its `const`, its
+/// first `enum` value or its first `anyOf` alternative. Otherwise a value of its (first non
+/// null) `type` is built: Objects with an example of every property, Arrays of `minItems`
+/// examples of `items` (at least one), and Strings and Numbers within the schema's bounds.
+/// Strings and Arrays are at most [`MAX_EXAMPLE_LEN`](constant.MAX_EXAMPLE_LEN.html) long.
+pub fn example(schema: &Value) -> Value {
+    let object = match schema.as_object() {
+        Some(object) => object,
+        None => return Value::Null,
+    };
+    if let Some(default) = object.get("default") {
+        return default.clone();
+    }
+    if let Some(first) = object
+        .get("examples")
+        .and_then(Value::as_array)
+        .and_then(|e| e.first())
+    {
+        return first.clone();
+    }
+    if let Some(value) = object.get("const") {
+        return value.clone();
+    }
+    if let Some(first) = object.get("enum").and_then(Value::as_array).and_then(|e| e.first()) {
+        return first.clone();
+    }
+    if let Some(first) = object.get("anyOf").and_then(Value::as_array).and_then(|e| e.first()) {
+        return example(first);
+    }
+    let kind = match object.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|k| *k != "null")
+            .unwrap_or("null"),
+        _ if object.contains_key("properties") => "object",
+        _ if object.contains_key("items") => "array",
+        _ => "null",
+    };
+    let bound = |key: &str| object.get(key).and_then(Value::as_f64);
+    let length = |key: &str| object.get(key).and_then(Value::as_u64).map(|n| n as usize);
+    match kind {
+        "object" => {
+            let properties = object.get("properties").and_then(Value::as_object);
+            Value::Object(
+                properties
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, schema)| (name.clone(), example(schema)))
+                    .collect(),
+            )
+        }
+        "array" => {
+            let item = object.get("items").map(example).unwrap_or(Value::Null);
+            let mut len = length("minItems").unwrap_or(0).clamp(1, MAX_EXAMPLE_LEN);
+            if let Some(max) = length("maxItems") {
+                len = len.min(max);
+            }
+            Value::Array(vec![item; len])
+        }
+        "string" => {
+            let mut s = "string".to_string();
+            if let Some(min) = length("minLength") {
+                let min = min.min(MAX_EXAMPLE_LEN);
+                s.push_str(&"x".repeat(min.saturating_sub(s.len())));
+            }
+            if let Some(max) = length("maxLength") {
+                s = s.chars().take(max).collect();
+            }
+            Value::String(s)
+        }
+        "integer" | "number" => {
+            let mut n: f64 = if kind == "integer" { 1.0 } else { 1.5 };
+            if let Some(min) = bound("minimum") {
+                n = n.max(min);
+            }
+            if let Some(max) = bound("maximum") {
+                n = n.min(max);
+            }
+            if kind == "integer" {
+                Value::from(n.ceil() as i64)
+            } else {
+                Value::from(n)
+            }
+        }
+        "boolean" => Value::Bool(true),
+        _ => Value::Null,
+    }
+}
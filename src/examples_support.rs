@@ -0,0 +1,238 @@
+//! A complete calculator service, as a reference for wiring the crate together.
+//!
+//! The service defines its methods as typed [`Method`](../method/trait.Method.html)s, serves
+//! them from a [`Router`](../router/struct.Router.html), and reports its errors with
+//! [`ErrorDetails`](../struct.ErrorDetails.html) under application defined codes. The
+//! [`Calculator`](struct.Calculator.html) client numbers its Requests with a
+//! [`Sequential`](../correlation/struct.Sequential.html) id generator, sends them one at a time
+//! or as a [`BatchRequest`](../batch/struct.BatchRequest.html), and decodes the results and
+//! errors with the methods' types.
+//!
+//! There is no network code: the client hands the json of its Requests directly to the router,
+//! where a real client would write it to its transport. Copy the pieces you need.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::examples_support::{self, Add, Calculator, Divide, Sum};
+//! use jrpc::ErrorCode;
+//!
+//! # fn main() {
+//! let mut calculator = Calculator::new();
+//! assert_eq!(calculator.call::<Add>((40, 2)), Ok(42));
+//!
+//! let error = calculator.call::<Divide>((1, 0)).unwrap_err();
+//! assert_eq!(error.code, ErrorCode::from(examples_support::DIVISION_BY_ZERO));
+//! assert_eq!(error.data.unwrap().field.unwrap(), "1");
+//!
+//! // Batches are answered in one round trip, and decoded per Request.
+//! let sum = calculator.request::<Sum>(vec![1, 2, 3]);
+//! let quotient = calculator.request::<Divide>((7, 2));
+//! let responses = calculator.batch(vec![sum.clone(), quotient.clone()]).unwrap();
+//! assert_eq!(Calculator::decode::<Sum>(&responses, &sum), Ok(6));
+//! assert_eq!(Calculator::decode::<Divide>(&responses, &quotient), Ok(3));
+//! # }
+//! ```
+
+use serde_json;
+use std_prelude::*;
+
+use batch::{BatchRequest, BatchResponse};
+use correlation::{IdGenerator, Sequential};
+use method::Method;
+use router::Router;
+use {ErrorCode, ErrorDetails, ErrorObject, Request, Response, Value};
+
+/// The code of the error returned when dividing by zero.
+pub const DIVISION_BY_ZERO: i64 = 1;
+
+/// The code of the error returned when a result does not fit into an `i64`.
+pub const OVERFLOW: i64 = 2;
+
+/// `add`: the sum of two integers.
+#[derive(Debug, Clone, Copy)]
+pub struct Add;
+
+impl Method for Add {
+    type Params = (i64, i64);
+    type Result = i64;
+    type ErrorData = ErrorDetails;
+
+    fn name() -> Cow<'static, str> {
+        "add".into()
+    }
+}
+
+/// `subtract`: the difference of two integers.
+#[derive(Debug, Clone, Copy)]
+pub struct Subtract;
+
+impl Method for Subtract {
+    type Params = (i64, i64);
+    type Result = i64;
+    type ErrorData = ErrorDetails;
+
+    fn name() -> Cow<'static, str> {
+        "subtract".into()
+    }
+}
+
+/// `divide`: the integer quotient of two integers.
+#[derive(Debug, Clone, Copy)]
+pub struct Divide;
+
+impl Method for Divide {
+    type Params = (i64, i64);
+    type Result = i64;
+    type ErrorData = ErrorDetails;
+
+    fn name() -> Cow<'static, str> {
+        "divide".into()
+    }
+}
+
+/// `sum`: the sum of any number of integers.
+#[derive(Debug, Clone, Copy)]
+pub struct Sum;
+
+impl Method for Sum {
+    type Params = Vec<i64>;
+    type Result = i64;
+    type ErrorData = ErrorDetails;
+
+    fn name() -> Cow<'static, str> {
+        "sum".into()
+    }
+}
+
+/// The router serving the calculator's methods.
+pub fn router() -> Router {
+    let mut router = Router::new();
+    serve::<Add, _>(&mut router, |(a, b)| a.checked_add(b).ok_or_else(overflow));
+    serve::<Subtract, _>(&mut router, |(a, b)| a.checked_sub(b).ok_or_else(overflow));
+    serve::<Divide, _>(&mut router, |(a, b)| {
+        if b == 0 {
+            let details = ErrorDetails::new("the divisor is zero").field("1");
+            return Err(error(DIVISION_BY_ZERO, "Division by zero", details));
+        }
+        a.checked_div(b).ok_or_else(overflow)
+    });
+    serve::<Sum, _>(&mut router, |values| {
+        values
+            .iter()
+            .try_fold(0i64, |sum, value| sum.checked_add(*value))
+            .ok_or_else(overflow)
+    });
+    router
+}
+
+/// Register the handler of a typed method, converting its error `data` to json.
+fn serve<M, F>(router: &mut Router, handler: F)
+where
+    M: Method + 'static,
+    F: Fn(M::Params) -> Result<M::Result, ErrorObject<ErrorDetails>> + Send + Sync + 'static,
+{
+    router.add::<M::Params, M::Result, _>(&M::name(), move |params| {
+        handler(params).map_err(|error| {
+            error.map_data(|details| {
+                serde_json::to_value(details).expect("ErrorDetails is always serializable")
+            })
+        })
+    });
+}
+
+fn error(code: i64, message: &str, details: ErrorDetails) -> ErrorObject<ErrorDetails> {
+    ErrorObject::with_details(code, message, details).expect("application codes are not reserved")
+}
+
+fn overflow() -> ErrorObject<ErrorDetails> {
+    error(
+        OVERFLOW,
+        "Overflow",
+        ErrorDetails::new("the result does not fit into an i64"),
+    )
+}
+
+/// A client of the calculator, connected to an in-process [`router`](fn.router.html).
+#[derive(Debug)]
+pub struct Calculator {
+    server: Router,
+    ids: Sequential,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Calculator {
+            server: router(),
+            ids: Sequential::new(),
+        }
+    }
+}
+
+impl Calculator {
+    /// Create a client, numbering its Requests from `1`.
+    pub fn new() -> Self {
+        Calculator::default()
+    }
+
+    /// Create the Request calling `M` with the next id.
+    pub fn request<M: Method>(&mut self, params: M::Params) -> Request<String, Value> {
+        let params = serde_json::to_value(params).expect("params are always serializable");
+        Request::with_params(self.ids.next_id(), M::name().into_owned(), params)
+    }
+
+    /// Call `M`, returning its result or error.
+    ///
+    /// A Response which can not be parsed is reported as a `ParseError`.
+    pub fn call<M: Method>(
+        &mut self,
+        params: M::Params,
+    ) -> Result<M::Result, ErrorObject<M::ErrorData>> {
+        let request = self.request::<M>(params);
+        let json = self
+            .send(&request.to_string())
+            .expect("Requests are always answered");
+        let response: Response<Value> = serde_json::from_str(&json).map_err(|err| ErrorObject {
+            code: ErrorCode::ParseError,
+            message: err.to_string(),
+            data: None,
+        })?;
+        M::decode_result(response)
+    }
+
+    /// Send a batch of Requests, returning the Responses, or `None` if the batch is empty.
+    ///
+    /// # Panics
+    ///
+    /// If the Responses can not be parsed.
+    pub fn batch(&self, requests: Vec<Request<String, Value>>) -> Option<BatchResponse<Value>> {
+        let batch = BatchRequest::new(requests).ok()?;
+        let json = serde_json::to_string(&batch).expect("Requests are always serializable");
+        self.send(&json)
+            .map(|json| serde_json::from_str(&json).expect("the router answers valid Responses"))
+    }
+
+    /// Decode the Response to `request` from a batch of Responses.
+    ///
+    /// A missing Response is reported as an `InternalError`.
+    pub fn decode<M: Method>(
+        responses: &BatchResponse<Value>,
+        request: &Request<String, Value>,
+    ) -> Result<M::Result, ErrorObject<M::ErrorData>> {
+        let response = request.id.clone().to_id().and_then(|id| responses.get(&id));
+        match response {
+            Some(response) => M::decode_result(response.clone()),
+            None => Err(ErrorObject {
+                code: ErrorCode::InternalError,
+                message: "missing Response".to_string(),
+                data: None,
+            }),
+        }
+    }
+
+    /// The transport: hand the json to the server and return its answer.
+    fn send(&self, json: &str) -> Option<String> {
+        self.server.handle(json)
+    }
+}
@@ -0,0 +1,156 @@
+//! Translation between `ErrorObject`s and generic (SOAP style) faults.
+//!
+//! Gateways fronting legacy SOAP systems need to turn faults into JSON-RPC errors and back.
+//! [`Fault`](struct.Fault.html) is a format agnostic representation of a fault (code, reason and
+//! a detail map); rendering it as XML is left to the gateway.
+//!
+//! # Conventions
+//!
+//! - Errors caused by the client (`ParseError`, `InvalidRequest`, `MethodNotFound` and
+//!   `InvalidParams`) have the fault code [`SENDER`](constant.SENDER.html), all others
+//!   [`RECEIVER`](constant.RECEIVER.html). The SOAP 1.1 names `Client` and `Server` are also
+//!   understood when converting back.
+//! - The exact JSON-RPC code is kept in the detail entry
+//!   [`DETAIL_CODE`](constant.DETAIL_CODE.html) and the error `data` in
+//!   [`DETAIL_DATA`](constant.DETAIL_DATA.html).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{ErrorCode, ErrorObject, Value};
+//! use jrpc::fault::{self, Fault};
+//!
+//! # fn main() {
+//! let error: ErrorObject<Value> = ErrorObject {
+//!     code: ErrorCode::InvalidParams,
+//!     message: "missing field `name`".into(),
+//!     data: Some(Value::from("name")),
+//! };
+//!
+//! let fault = Fault::from_error(&error);
+//! assert_eq!(fault.code, fault::SENDER);
+//! assert_eq!(fault.reason, "missing field `name`");
+//! let back = fault.into_error();
+//! assert_eq!(back.code, error.code);
+//! assert_eq!(back.data, error.data);
+//!
+//! // A fault coming from a legacy system, without any JSON-RPC details.
+//! let mut legacy = Fault::new("soap:Server", "Database unavailable");
+//! legacy.detail.insert("retryAfter".into(), Value::from(30));
+//! let error = legacy.into_error();
+//! assert_eq!(error.code, ErrorCode::InternalError);
+//! assert_eq!(error.data.unwrap()["retryAfter"], 30);
+//! # }
+//! ```
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {ErrorCode, ErrorObject, Value};
+
+/// The fault code for errors caused by the sender of the request.
+pub const SENDER: &str = "Sender";
+
+/// The fault code for errors caused by the receiver of the request.
+pub const RECEIVER: &str = "Receiver";
+
+/// The detail entry holding the exact JSON-RPC error code.
+pub const DETAIL_CODE: &str = "jsonrpcCode";
+
+/// The detail entry holding the JSON-RPC error `data`.
+pub const DETAIL_DATA: &str = "jsonrpcData";
+
+/// A generic fault.
+///
+/// See the [module documentation](index.html) for the conventions used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fault {
+    /// The fault code, e.g. `Sender` or `Receiver`. May carry a namespace prefix such as
+    /// `soap:Sender`.
+    pub code: String,
+
+    /// A human readable explanation of the fault.
+    pub reason: String,
+
+    /// Application specific details.
+    #[serde(default)]
+    pub detail: BTreeMap<String, Value>,
+}
+
+impl Fault {
+    /// Create a fault without any detail.
+    pub fn new<C, R>(code: C, reason: R) -> Self
+    where
+        C: Into<String>,
+        R: Into<String>,
+    {
+        Fault {
+            code: code.into(),
+            reason: reason.into(),
+            detail: BTreeMap::new(),
+        }
+    }
+
+    /// Translate an error into a fault.
+    pub fn from_error<T: Serialize>(error: &ErrorObject<T>) -> Self {
+        let code = match error.code {
+            ErrorCode::ParseError
+            | ErrorCode::InvalidRequest
+            | ErrorCode::MethodNotFound
+            | ErrorCode::InvalidParams => SENDER,
+            ErrorCode::InternalError | ErrorCode::ServerError(_) | ErrorCode::Reserved(_) => {
+                RECEIVER
+            }
+        };
+        let mut fault = Fault::new(code, error.message.clone());
+        fault
+            .detail
+            .insert(DETAIL_CODE.to_string(), Value::from(error.code.code()));
+        if let Some(ref data) = error.data {
+            let data = serde_json::to_value(data).unwrap_or(Value::Null);
+            fault.detail.insert(DETAIL_DATA.to_string(), data);
+        }
+        fault
+    }
+
+    /// Whether this fault was caused by the sender of the request.
+    ///
+    /// Any namespace prefix of the code is ignored.
+    pub fn is_sender(&self) -> bool {
+        let code = self.code.rsplit(':').next().unwrap_or("");
+        code == SENDER || code == "Client"
+    }
+
+    /// Translate the fault into an error.
+    ///
+    /// The code is taken from the [`DETAIL_CODE`](constant.DETAIL_CODE.html) entry if present,
+    /// otherwise sender faults become `InvalidRequest` and all others `InternalError`. The data is
+    /// taken from the [`DETAIL_DATA`](constant.DETAIL_DATA.html) entry if present, otherwise any
+    /// remaining detail entries become the data Object.
+    pub fn into_error(mut self) -> ErrorObject<Value> {
+        let code = self
+            .detail
+            .remove(DETAIL_CODE)
+            .and_then(|c| c.as_i64())
+            .map(ErrorCode::from);
+        let code = match code {
+            Some(code) => code,
+            None if self.is_sender() => ErrorCode::InvalidRequest,
+            None => ErrorCode::InternalError,
+        };
+
+        let data = match self.detail.remove(DETAIL_DATA) {
+            Some(data) => Some(data),
+            None if self.detail.is_empty() => None,
+            None => Some(Value::Object(self.detail.into_iter().collect())),
+        };
+
+        ErrorObject {
+            code: code,
+            message: self.reason,
+            data: data,
+        }
+    }
+}
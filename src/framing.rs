@@ -0,0 +1,398 @@
+//! Framing messages on byte streams.
+//!
+//! Like the rest of this crate, this module does no I/O: the encoders return the bytes to write,
+//! and the decoders are fed the bytes as they are read, in chunks of any size, and yield complete
+//! messages.
+//!
+//! # `Content-Length`
+//!
+//! As used by the Language Server Protocol over stdio, each message is preceded by a header part,
+//! of `Name: value` lines terminated by `\r\n`, and an empty line:
+//!
+//! ```text
+//! Content-Length: 33\r\n
+//! \r\n
+//! {"jsonrpc":"2.0","method":"exit"}
+//! ```
+//!
+//! `Content-Length` is the length of the content in bytes and is required. Other headers, e.g.
+//! `Content-Type`, are accepted and ignored. [`encode_framed`](fn.encode_framed.html) frames a
+//! message and a [`FrameDecoder`](struct.FrameDecoder.html) yields the payloads.
+//!
+//! # Newline-delimited json
+//!
+//! Each message is on its own line, terminated by `\n` (or `\r\n`). A
+//! [`StreamParser`](struct.StreamParser.html) yields the parsed messages. Unlike with
+//! `Content-Length`, the stream resynchronizes after an invalid message: it is reported as a
+//! `ParseError`, or an `InvalidRequest` if it is valid json of the wrong shape, and parsing goes
+//! on with the next line.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{ErrorCode, Id, Request, Value};
+//! use jrpc::framing::{self, FrameDecoder, StreamParser};
+//!
+//! # fn main() {
+//! let request = Request::new(Id::from(1), "shutdown".to_string());
+//! let bytes = framing::encode_framed(&request).unwrap();
+//! assert!(bytes.starts_with(b"Content-Length: 58\r\n\r\n{"));
+//!
+//! // The bytes arrive in arbitrary chunks.
+//! let mut decoder = FrameDecoder::new();
+//! decoder.push(&bytes[..10]);
+//! assert_eq!(decoder.decode().unwrap(), None);
+//! decoder.push(&bytes[10..]);
+//! let payload = decoder.decode().unwrap().unwrap();
+//! let request: Request<String, Value> = serde_json::from_slice(&payload).unwrap();
+//! assert_eq!(request.method, "shutdown");
+//! assert_eq!(decoder.buffered(), 0);
+//!
+//! // Newline-delimited.
+//! let mut parser = StreamParser::<Request<String, Value>>::new();
+//! parser.push(b"{\"jsonrpc\": \"2.0\", \"method\": \"a\"}\n{\"jsonrpc\": \"2.0\", ");
+//! assert_eq!(parser.next().unwrap().unwrap().method, "a");
+//! assert!(parser.next().is_none());
+//! parser.push(b"\"method\": \"b\"}\n{oops}\n");
+//! assert_eq!(parser.next().unwrap().unwrap().method, "b");
+//! assert_eq!(parser.next().unwrap().unwrap_err().error.code, ErrorCode::ParseError);
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {Error, ErrorCode, Id, Value};
+
+/// The header carrying the length of the content.
+pub const CONTENT_LENGTH: &str = "Content-Length";
+
+/// The default maximum length of the content of a frame: 16 MiB.
+pub const DEFAULT_MAX_CONTENT_LENGTH: usize = 16 * 1024 * 1024;
+
+/// The default maximum length of the header part of a frame: 8 KiB.
+pub const DEFAULT_MAX_HEADER_LENGTH: usize = 8 * 1024;
+
+const SEPARATOR: &[u8] = b"\r\n\r\n";
+
+/// Frame a json payload.
+pub fn encode(payload: &[u8]) -> Vec<u8> {
+    let header = format!("{}: {}\r\n\r\n", CONTENT_LENGTH, payload.len());
+    let mut frame = Vec::with_capacity(header.len() + payload.len());
+    frame.extend_from_slice(header.as_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Serialize a message and frame it.
+pub fn encode_framed<T: Serialize>(message: &T) -> serde_json::Result<Vec<u8>> {
+    Ok(encode(&serde_json::to_vec(message)?))
+}
+
+/// Why the byte stream could not be decoded.
+///
+/// The stream can not be resynchronized after an error: the connection should be closed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FrameError {
+    /// A header is not valid UTF-8, or not of the form `Name: value`.
+    InvalidHeader(String),
+    /// The header part has no `Content-Length`.
+    MissingContentLength,
+    /// The `Content-Length` is not a non-negative integer.
+    InvalidContentLength(String),
+    /// The `Content-Length` is above the maximum.
+    ContentTooLong {
+        /// The announced length.
+        len: usize,
+        /// The maximum length.
+        max: usize,
+    },
+    /// The header part is longer than the maximum, without being terminated.
+    HeaderTooLong {
+        /// The maximum length.
+        max: usize,
+    },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrameError::InvalidHeader(ref line) => write!(f, "invalid header {:?}", line),
+            FrameError::MissingContentLength => write!(f, "missing {} header", CONTENT_LENGTH),
+            FrameError::InvalidContentLength(ref value) => {
+                write!(f, "invalid {} {:?}", CONTENT_LENGTH, value)
+            }
+            FrameError::ContentTooLong { len, max } => {
+                write!(f, "content of {} bytes, the maximum is {}", len, max)
+            }
+            FrameError::HeaderTooLong { max } => {
+                write!(f, "header part longer than {} bytes", max)
+            }
+        }
+    }
+}
+
+impl error::Error for FrameError {}
+
+/// An incremental decoder of `Content-Length` framed payloads.
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    /// The length of the header part and of the content of the frame at the start of the
+    /// buffer, once its header part is complete.
+    frame: Option<(usize, usize)>,
+    max_content_length: usize,
+    max_header_length: usize,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        FrameDecoder {
+            buffer: Vec::new(),
+            frame: None,
+            max_content_length: DEFAULT_MAX_CONTENT_LENGTH,
+            max_header_length: DEFAULT_MAX_HEADER_LENGTH,
+        }
+    }
+}
+
+impl FrameDecoder {
+    /// Create a decoder with the default limits.
+    pub fn new() -> Self {
+        FrameDecoder::default()
+    }
+
+    /// Set the maximum length of the content of a frame.
+    pub fn max_content_length(mut self, max: usize) -> Self {
+        self.max_content_length = max;
+        self
+    }
+
+    /// Set the maximum length of the header part of a frame, including the empty line.
+    pub fn max_header_length(mut self, max: usize) -> Self {
+        self.max_header_length = max;
+        self
+    }
+
+    /// Append bytes read from the stream.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The number of bytes pushed but not yet returned in a payload.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Return the next complete payload, or `None` if more bytes are needed. Call it until it
+    /// returns `None` after every [`push`](#method.push).
+    pub fn decode(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let (header, content) = match self.frame {
+            Some(frame) => frame,
+            None => match self.header()? {
+                Some(frame) => {
+                    self.frame = Some(frame);
+                    frame
+                }
+                None => return Ok(None),
+            },
+        };
+        if self.buffer.len() < header + content {
+            return Ok(None);
+        }
+        self.frame = None;
+        let payload = self.buffer[header..header + content].to_vec();
+        self.buffer.drain(..header + content);
+        Ok(Some(payload))
+    }
+
+    /// Parse the header part at the start of the buffer, if it is complete.
+    fn header(&self) -> Result<Option<(usize, usize)>, FrameError> {
+        let searched = &self.buffer[..self.buffer.len().min(self.max_header_length)];
+        let end = match searched
+            .windows(SEPARATOR.len())
+            .position(|w| w == SEPARATOR)
+        {
+            Some(end) => end,
+            None if searched.len() < self.max_header_length => return Ok(None),
+            None => {
+                return Err(FrameError::HeaderTooLong {
+                    max: self.max_header_length,
+                })
+            }
+        };
+        let headers = str::from_utf8(&self.buffer[..end]).map_err(|_| {
+            FrameError::InvalidHeader(String::from_utf8_lossy(&self.buffer[..end]).into_owned())
+        })?;
+        let mut content = None;
+        for line in headers.split("\r\n") {
+            let mut parts = line.splitn(2, ':');
+            let (name, value) = match (parts.next(), parts.next()) {
+                (Some(name), Some(value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+                _ => return Err(FrameError::InvalidHeader(line.to_string())),
+            };
+            if name.eq_ignore_ascii_case(CONTENT_LENGTH) {
+                let len = value
+                    .parse::<usize>()
+                    .map_err(|_| FrameError::InvalidContentLength(value.to_string()))?;
+                if len > self.max_content_length {
+                    return Err(FrameError::ContentTooLong {
+                        len: len,
+                        max: self.max_content_length,
+                    });
+                }
+                content = Some(len);
+            }
+        }
+        match content {
+            Some(content) => Ok(Some((end + SEPARATOR.len(), content))),
+            None => Err(FrameError::MissingContentLength),
+        }
+    }
+}
+
+/// Serialize a message on its own line, for newline-delimited json.
+pub fn encode_line<T: Serialize>(message: &T) -> serde_json::Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+/// An incremental parser of newline-delimited json messages.
+///
+/// It is an `Iterator` of the messages of the complete lines pushed so far, or of the errors to
+/// reply with; iterate again after every [`push`](#method.push). Blank lines are skipped.
+pub struct StreamParser<T> {
+    buffer: Vec<u8>,
+    /// Where to resume looking for the end of the line.
+    scanned: usize,
+    /// Whether the rest of an overlong line is being dropped.
+    discarding: bool,
+    max_line_length: usize,
+    _message: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for StreamParser<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StreamParser")
+            .field("buffered", &self.buffer.len())
+            .field("discarding", &self.discarding)
+            .field("max_line_length", &self.max_line_length)
+            .finish()
+    }
+}
+
+impl<T> Default for StreamParser<T> {
+    fn default() -> Self {
+        StreamParser {
+            buffer: Vec::new(),
+            scanned: 0,
+            discarding: false,
+            max_line_length: DEFAULT_MAX_CONTENT_LENGTH,
+            _message: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> StreamParser<T> {
+    /// Create a parser with the default maximum line length.
+    pub fn new() -> Self {
+        StreamParser::default()
+    }
+
+    /// Set the maximum length of a line. Longer lines are reported as a `ParseError` and
+    /// dropped.
+    pub fn max_line_length(mut self, max: usize) -> Self {
+        self.max_line_length = max;
+        self
+    }
+
+    /// Append bytes read from the stream.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The number of bytes pushed but not yet parsed.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Parse the last line, not terminated by a newline, once the stream ended.
+    pub fn finish(&mut self) -> Option<Result<T, Error<Value>>> {
+        let line = ::std::mem::take(&mut self.buffer);
+        self.scanned = 0;
+        if ::std::mem::replace(&mut self.discarding, false) {
+            return None;
+        }
+        parse_line(&line)
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for StreamParser<T> {
+    type Item = Result<T, Error<Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let end = match self.buffer[self.scanned..].iter().position(|&b| b == b'\n') {
+                Some(end) => self.scanned + end,
+                None => {
+                    if self.buffer.len() <= self.max_line_length && !self.discarding {
+                        self.scanned = self.buffer.len();
+                        return None;
+                    }
+                    // Drop the overlong line as it arrives, reporting it once.
+                    self.buffer.clear();
+                    self.scanned = 0;
+                    if self.discarding {
+                        return None;
+                    }
+                    self.discarding = true;
+                    return Some(Err(too_long(self.max_line_length)));
+                }
+            };
+            let line: Vec<u8> = self.buffer.drain(..end + 1).collect();
+            self.scanned = 0;
+            if ::std::mem::replace(&mut self.discarding, false) {
+                continue;
+            }
+            if line.len() - 1 > self.max_line_length {
+                return Some(Err(too_long(self.max_line_length)));
+            }
+            if let Some(result) = parse_line(&line[..end]) {
+                return Some(result);
+            }
+        }
+    }
+}
+
+fn too_long(max: usize) -> Error<Value> {
+    let message = format!("line longer than {} bytes", max);
+    Error::new(Id::Null, ErrorCode::ParseError, message, None)
+}
+
+/// Parse a line without its newline, `None` if it is blank.
+fn parse_line<T: DeserializeOwned>(line: &[u8]) -> Option<Result<T, Error<Value>>> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return None;
+    }
+    let value: Value = match serde_json::from_slice(line) {
+        Ok(value) => value,
+        Err(err) => {
+            let error = Error::new(Id::Null, ErrorCode::ParseError, err.to_string(), None);
+            return Some(Err(error));
+        }
+    };
+    Some(
+        serde_json::from_value(value)
+            .map_err(|err| Error::new(Id::Null, ErrorCode::InvalidRequest, err.to_string(), None)),
+    )
+}
@@ -0,0 +1,98 @@
+//! Invariants of the parsing surface, packaged as fuzz targets.
+//!
+//! Each function takes arbitrary bytes, ignores input which is not UTF-8, and panics if an
+//! invariant is violated, so it can be wired directly into
+//! [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz):
+//!
+//! ```text
+//! #![no_main]
+//! #[macro_use] extern crate libfuzzer_sys;
+//! extern crate jrpc;
+//!
+//! fuzz_target!(|data: &[u8]| {
+//!     jrpc::fuzz::parse_never_panics(data);
+//!     jrpc::fuzz::roundtrip(data);
+//! });
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! # fn main() {
+//! let corpus: &[&[u8]] = &[
+//!     br#"{"jsonrpc": "2.0", "method": "sum", "params": [1, 2], "id": 1}"#,
+//!     br#"{"jsonrpc": "2.0", "result": 3, "id": 1}"#,
+//!     br#"[{"jsonrpc": "2.0"}, 1, "#,
+//!     b"\xff\xfe",
+//! ];
+//! for data in corpus {
+//!     jrpc::fuzz::parse_never_panics(data);
+//!     jrpc::fuzz::roundtrip(data);
+//! }
+//! # }
+//! ```
+
+use serde_json;
+use std_prelude::*;
+
+use {parse_many, parse_request, Request, Response, Value};
+
+/// Feed the input to every parser of the crate. The results are discarded; only panics are of
+/// interest.
+///
+/// Also checks that the spans reported by [`parse_many`](../fn.parse_many.html) are in order and
+/// within the input.
+pub fn parse_never_panics(data: &[u8]) {
+    let s = match str::from_utf8(data) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let _ = parse_request::<String>(s);
+    let _ = serde_json::from_str::<Request<String, Value>>(s);
+    let _ = serde_json::from_str::<Response<Value>>(s);
+    let _ = serde_json::from_str::<Vec<Response<Value>>>(s);
+
+    let mut end = 0;
+    for parsed in parse_many::<Request<String, Value>>(s) {
+        assert!(parsed.span.start >= end, "span {:?} overlaps", parsed.span);
+        assert!(
+            parsed.span.end <= s.len(),
+            "span {:?} out of bounds",
+            parsed.span
+        );
+        assert!(s.is_char_boundary(parsed.span.start) && s.is_char_boundary(parsed.span.end));
+        end = parsed.span.end;
+    }
+}
+
+/// Check that messages which parse survive a serialization round trip.
+///
+/// A parsed Request or Response is serialized and parsed again, which must succeed and
+/// serialize identically. The pretty ordered serialization must denote the same json.
+pub fn roundtrip(data: &[u8]) {
+    let s = match str::from_utf8(data) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    if let Ok(request) = parse_request::<String>(s) {
+        let json = request.to_string();
+        let again = parse_request::<String>(&json)
+            .unwrap_or_else(|err| panic!("{} does not parse again: {:?}", json, err));
+        assert_eq!(again.to_string(), json, "Request round trip");
+        assert_same_json(&json, &request.to_string_pretty_ordered());
+    }
+    if let Ok(response) = serde_json::from_str::<Response<Value>>(s) {
+        let json = response.to_string();
+        let again: Response<Value> = serde_json::from_str(&json)
+            .unwrap_or_else(|err| panic!("{} does not parse again: {}", json, err));
+        assert_eq!(again.to_string(), json, "Response round trip");
+        assert_same_json(&json, &response.to_string_pretty_ordered());
+    }
+}
+
+fn assert_same_json(compact: &str, pretty: &str) {
+    let compact: Value = serde_json::from_str(compact).expect("compact json");
+    let pretty: Value = serde_json::from_str(pretty).expect("pretty json");
+    assert_eq!(compact, pretty, "pretty ordered serialization");
+}
@@ -0,0 +1,255 @@
+//! Mapping between JSON-RPC error codes and gRPC status codes.
+//!
+//! Bridges between JSON-RPC services and gRPC services need to agree on what each error means on
+//! the other side. This module contains only tables and pure functions; it does not depend on any
+//! gRPC library.
+//!
+//! # Default mapping
+//!
+//! | JSON-RPC                      | gRPC                  |
+//! |-------------------------------|-----------------------|
+//! | `-32700` ParseError           | `3` INVALID_ARGUMENT  |
+//! | `-32600` InvalidRequest       | `3` INVALID_ARGUMENT  |
+//! | `-32601` MethodNotFound       | `12` UNIMPLEMENTED    |
+//! | `-32602` InvalidParams        | `3` INVALID_ARGUMENT  |
+//! | `-32603` InternalError        | `13` INTERNAL         |
+//! | `-32001` to `-32016`          | the status `-32000 - code`, see below |
+//! | any other code                | `2` UNKNOWN           |
+//!
+//! In the other direction, INVALID_ARGUMENT maps to InvalidParams, UNIMPLEMENTED to
+//! MethodNotFound and INTERNAL to InternalError. Every other status `s` maps to the server error
+//! `-32000 - s`, which lies within the range reserved for implementation-defined server errors,
+//! so that the status survives a round trip through a JSON-RPC hop.
+//!
+//! # Metadata
+//!
+//! Since the mapping is lossy, bridges SHOULD also carry the exact JSON-RPC error in the gRPC
+//! metadata (trailers), using the keys [`METADATA_CODE`](constant.METADATA_CODE.html) and
+//! [`METADATA_DATA`](constant.METADATA_DATA.html). The gRPC status message carries the JSON-RPC
+//! error message.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{ErrorCode, ErrorObject, Value};
+//! use jrpc::grpc::{GrpcMapping, GrpcStatus};
+//!
+//! # fn main() {
+//! let mapping = GrpcMapping::new().with_code(-32050, GrpcStatus::NotFound);
+//!
+//! assert_eq!(mapping.to_grpc(ErrorCode::MethodNotFound), GrpcStatus::Unimplemented);
+//! assert_eq!(mapping.to_grpc(ErrorCode::ServerError(-32050)), GrpcStatus::NotFound);
+//! assert_eq!(mapping.from_grpc(GrpcStatus::NotFound), ErrorCode::ServerError(-32050));
+//! assert_eq!(mapping.from_grpc(GrpcStatus::Unavailable), ErrorCode::ServerError(-32014));
+//!
+//! // Carrying the exact error through gRPC metadata.
+//! let error: ErrorObject<Value> = ErrorObject {
+//!     code: ErrorCode::ServerError(-32099),
+//!     message: "BadIndexes".into(),
+//!     data: Some(Value::from(vec![1, 2, 3])),
+//! };
+//! let (status, message, metadata) = mapping.to_status(&error);
+//! assert_eq!(status, GrpcStatus::Unknown);
+//! let back = mapping.from_status(status, &message, &metadata);
+//! assert_eq!(back.code, error.code);
+//! assert_eq!(back.message, error.message);
+//! assert_eq!(back.data, error.data);
+//! # }
+//! ```
+
+use serde_json;
+use std_prelude::*;
+
+use {ErrorCode, ErrorObject, Value};
+
+/// The metadata key carrying the exact JSON-RPC error code, as a decimal string.
+pub const METADATA_CODE: &str = "jsonrpc-error-code";
+
+/// The metadata key carrying the JSON-RPC error `data`, serialized as json.
+///
+/// The `-bin` suffix marks it as binary metadata, so gRPC libraries take care of encoding
+/// arbitrary bytes.
+pub const METADATA_DATA: &str = "jsonrpc-error-data-bin";
+
+/// The canonical gRPC status codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GrpcStatus {
+    /// `0`
+    Ok,
+    /// `1`
+    Cancelled,
+    /// `2`
+    Unknown,
+    /// `3`
+    InvalidArgument,
+    /// `4`
+    DeadlineExceeded,
+    /// `5`
+    NotFound,
+    /// `6`
+    AlreadyExists,
+    /// `7`
+    PermissionDenied,
+    /// `8`
+    ResourceExhausted,
+    /// `9`
+    FailedPrecondition,
+    /// `10`
+    Aborted,
+    /// `11`
+    OutOfRange,
+    /// `12`
+    Unimplemented,
+    /// `13`
+    Internal,
+    /// `14`
+    Unavailable,
+    /// `15`
+    DataLoss,
+    /// `16`
+    Unauthenticated,
+}
+
+const STATUSES: [GrpcStatus; 17] = [
+    GrpcStatus::Ok,
+    GrpcStatus::Cancelled,
+    GrpcStatus::Unknown,
+    GrpcStatus::InvalidArgument,
+    GrpcStatus::DeadlineExceeded,
+    GrpcStatus::NotFound,
+    GrpcStatus::AlreadyExists,
+    GrpcStatus::PermissionDenied,
+    GrpcStatus::ResourceExhausted,
+    GrpcStatus::FailedPrecondition,
+    GrpcStatus::Aborted,
+    GrpcStatus::OutOfRange,
+    GrpcStatus::Unimplemented,
+    GrpcStatus::Internal,
+    GrpcStatus::Unavailable,
+    GrpcStatus::DataLoss,
+    GrpcStatus::Unauthenticated,
+];
+
+impl GrpcStatus {
+    /// The numeric status code.
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Look up a status by its numeric code.
+    pub fn from_code(code: i32) -> Option<GrpcStatus> {
+        if code < 0 {
+            return None;
+        }
+        STATUSES.get(code as usize).cloned()
+    }
+}
+
+/// The mapping between JSON-RPC error codes and gRPC statuses.
+///
+/// [`GrpcMapping::new()`](#method.new) uses the [default mapping](index.html#default-mapping);
+/// application specific codes can be added with [`with_code`](#method.with_code).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrpcMapping {
+    custom: BTreeMap<i64, GrpcStatus>,
+}
+
+impl GrpcMapping {
+    /// Create the default mapping.
+    pub fn new() -> Self {
+        GrpcMapping::default()
+    }
+
+    /// Map the application error `code` to `status`, in both directions.
+    ///
+    /// If several codes map to the same status, `from_grpc` returns the one closest to zero (the
+    /// negative one if two are as close).
+    pub fn with_code<C: Into<ErrorCode>>(mut self, code: C, status: GrpcStatus) -> Self {
+        self.custom.insert(code.into().code(), status);
+        self
+    }
+
+    /// Convert a JSON-RPC error code into a gRPC status.
+    pub fn to_grpc<C: Into<ErrorCode>>(&self, code: C) -> GrpcStatus {
+        let code = code.into();
+        if let Some(status) = self.custom.get(&code.code()) {
+            return *status;
+        }
+        match code {
+            ErrorCode::ParseError | ErrorCode::InvalidRequest | ErrorCode::InvalidParams => {
+                GrpcStatus::InvalidArgument
+            }
+            ErrorCode::MethodNotFound => GrpcStatus::Unimplemented,
+            ErrorCode::InternalError => GrpcStatus::Internal,
+            ErrorCode::ServerError(v) if (-32016..=-32001).contains(&v) => {
+                GrpcStatus::from_code((-32000 - v) as i32).unwrap_or(GrpcStatus::Unknown)
+            }
+            ErrorCode::ServerError(_) => GrpcStatus::Unknown,
+            ErrorCode::Reserved(_) => GrpcStatus::Unknown,
+        }
+    }
+
+    /// Convert a gRPC status into a JSON-RPC error code.
+    ///
+    /// `GrpcStatus::Ok` is not an error, but is mapped to `-32000` for completeness.
+    pub fn from_grpc(&self, status: GrpcStatus) -> ErrorCode {
+        let custom = self
+            .custom
+            .iter()
+            .filter(|&(_, s)| *s == status)
+            .min_by_key(|&(code, _)| code.unsigned_abs());
+        if let Some((code, _)) = custom {
+            return ErrorCode::from(*code);
+        }
+        match status {
+            GrpcStatus::InvalidArgument => ErrorCode::InvalidParams,
+            GrpcStatus::Unimplemented => ErrorCode::MethodNotFound,
+            GrpcStatus::Internal => ErrorCode::InternalError,
+            _ => ErrorCode::ServerError(-32000 - i64::from(status.code())),
+        }
+    }
+
+    /// Convert an error into a gRPC status, status message and metadata.
+    pub fn to_status<T>(
+        &self,
+        error: &ErrorObject<T>,
+    ) -> (GrpcStatus, String, BTreeMap<String, String>)
+    where
+        T: ::serde::Serialize,
+    {
+        let mut metadata = BTreeMap::new();
+        metadata.insert(METADATA_CODE.to_string(), error.code.code().to_string());
+        if let Some(ref data) = error.data {
+            if let Ok(data) = serde_json::to_string(data) {
+                metadata.insert(METADATA_DATA.to_string(), data);
+            }
+        }
+        (self.to_grpc(error.code), error.message.clone(), metadata)
+    }
+
+    /// Convert a gRPC status, status message and metadata into an error.
+    ///
+    /// The exact code and data are taken from the metadata if present (and valid), otherwise
+    /// the code is derived from `status` and there is no data.
+    pub fn from_status(
+        &self,
+        status: GrpcStatus,
+        message: &str,
+        metadata: &BTreeMap<String, String>,
+    ) -> ErrorObject<Value> {
+        let code = metadata
+            .get(METADATA_CODE)
+            .and_then(|c| c.trim().parse::<i64>().ok())
+            .map(ErrorCode::from)
+            .unwrap_or_else(|| self.from_grpc(status));
+        let data = metadata
+            .get(METADATA_DATA)
+            .and_then(|d| serde_json::from_str(d).ok());
+        ErrorObject {
+            code: code,
+            message: message.to_string(),
+            data: data,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! Hedged requests: speculative retries of slow calls.
+//!
+//! When a call enabled for hedging has not been answered within its
+//! [`HedgePolicy`](struct.HedgePolicy.html)'s delay, the client sends a duplicate with a new `id`,
+//! usually to a secondary target. The first Response wins and is returned with the `id` of the
+//! original Request; the other attempts should be cancelled and their late Responses are ignored.
+//!
+//! The [`Hedger`](struct.Hedger.html) only keeps the state: the caller supplies the time, new ids
+//! and chooses the targets. Only hedge idempotent methods.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{Id, Request, Response, Value};
+//! use jrpc::hedge::{Completion, HedgePolicy, Hedger};
+//!
+//! # fn main() {
+//! let mut hedger = Hedger::new();
+//! hedger.enable("get", HedgePolicy::new(50));
+//!
+//! let request = Request::with_params(Id::from(1), "get".to_string(), Value::from(7));
+//! hedger.start(&request, 1000);
+//! assert!(hedger.due(1020).is_empty());
+//!
+//! // No Response after 50ms: send a hedge to the secondary.
+//! assert_eq!(hedger.due(1050), vec![Id::from(1)]);
+//! let hedge = hedger.hedge(&Id::from(1), Id::from(2), 1050).unwrap();
+//! assert_eq!(hedge.params, Some(Value::from(7)));
+//!
+//! // The hedge wins; cancel the original attempt.
+//! match hedger.complete(Response::success(Id::from(2), Value::from("x"))) {
+//!     Completion::Winner { response, losers } => {
+//!         assert_eq!(response.id(), &Id::from(1));
+//!         assert_eq!(losers, vec![Id::from(1)]);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//!
+//! // The original arrives late and is ignored.
+//! match hedger.complete(Response::success(Id::from(1), Value::from("x"))) {
+//!     Completion::Ignored => {}
+//!     _ => unreachable!(),
+//! }
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use std_prelude::*;
+
+use coalesce::with_id;
+use {Id, Request, Response, Value};
+
+/// When to hedge the calls of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HedgePolicy {
+    /// How long to wait for a Response before each hedge, in milliseconds.
+    pub delay_ms: u64,
+
+    /// The maximum number of hedges per call. Default: 1.
+    #[serde(default = "default_max_hedges")]
+    pub max_hedges: u32,
+}
+
+fn default_max_hedges() -> u32 {
+    1
+}
+
+impl HedgePolicy {
+    /// Hedge once after `delay_ms`.
+    pub fn new(delay_ms: u64) -> Self {
+        HedgePolicy {
+            delay_ms: delay_ms,
+            max_hedges: default_max_hedges(),
+        }
+    }
+
+    /// Set the maximum number of hedges per call.
+    pub fn max_hedges(mut self, max_hedges: u32) -> Self {
+        self.max_hedges = max_hedges;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Call {
+    method: String,
+    params: Option<Value>,
+    policy: HedgePolicy,
+    /// The ids of all attempts, the original first.
+    attempts: Vec<Id>,
+    /// When the last attempt was sent.
+    last_sent_ms: u64,
+}
+
+impl Call {
+    fn due_ms(&self) -> Option<u64> {
+        if self.attempts.len() > self.policy.max_hedges as usize {
+            None
+        } else {
+            Some(self.last_sent_ms.saturating_add(self.policy.delay_ms))
+        }
+    }
+}
+
+/// The outcome of [`Hedger::complete`](struct.Hedger.html#method.complete).
+#[derive(Debug)]
+pub enum Completion {
+    /// The first Response of a hedged call, with the `id` of the original Request.
+    Winner {
+        /// The Response.
+        response: Response<Value>,
+        /// The ids of the other attempts, to cancel.
+        losers: Vec<Id>,
+    },
+    /// The Response of an attempt which already lost.
+    Ignored,
+    /// The Response does not belong to a hedged call.
+    Unknown(Response<Value>),
+}
+
+/// Tracks hedged calls.
+#[derive(Debug, Default)]
+pub struct Hedger {
+    policies: BTreeMap<String, HedgePolicy>,
+    /// The calls, by the id of the original Request.
+    calls: HashMap<Id, Call>,
+    /// The original id of every attempt.
+    attempts: HashMap<Id, Id>,
+    losers: HashSet<Id>,
+}
+
+impl Hedger {
+    /// Create a hedger with hedging disabled for all methods.
+    pub fn new() -> Self {
+        Hedger::default()
+    }
+
+    /// Enable hedging for a method.
+    pub fn enable(&mut self, method: &str, policy: HedgePolicy) {
+        self.policies.insert(method.to_string(), policy);
+    }
+
+    /// Disable hedging for a method. Calls already started are still hedged.
+    pub fn disable(&mut self, method: &str) {
+        self.policies.remove(method);
+    }
+
+    /// The policy of a method, if hedging is enabled for it.
+    pub fn policy(&self, method: &str) -> Option<&HedgePolicy> {
+        self.policies.get(method)
+    }
+
+    /// Track a Request sent at `now_ms`. Returns whether it will be hedged: notifications and
+    /// methods without a policy are not.
+    pub fn start(&mut self, request: &Request<String, Value>, now_ms: u64) -> bool {
+        let policy = match self.policies.get(&request.method) {
+            Some(policy) => *policy,
+            None => return false,
+        };
+        let id = match request.id.clone().to_id() {
+            Some(id) => id,
+            None => return false,
+        };
+        self.attempts.insert(id.clone(), id.clone());
+        self.calls.insert(
+            id.clone(),
+            Call {
+                method: request.method.clone(),
+                params: request.params.clone(),
+                policy: policy,
+                attempts: vec![id],
+                last_sent_ms: now_ms,
+            },
+        );
+        true
+    }
+
+    /// The original ids of the calls due for a hedge at `now_ms`.
+    pub fn due(&self, now_ms: u64) -> Vec<Id> {
+        self.calls
+            .iter()
+            .filter(|&(_, call)| call.due_ms().is_some_and(|due| due <= now_ms))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// The earliest time a hedge is due, to set a timer.
+    pub fn next_due_ms(&self) -> Option<u64> {
+        self.calls.values().filter_map(Call::due_ms).min()
+    }
+
+    /// Create the next hedge of the call `original`, with the new `id`, sent at `now_ms`.
+    ///
+    /// Returns `None` if the call is not in flight or has used all its hedges.
+    pub fn hedge(&mut self, original: &Id, id: Id, now_ms: u64) -> Option<Request<String, Value>> {
+        let call = self.calls.get_mut(original)?;
+        call.due_ms()?;
+        call.attempts.push(id.clone());
+        call.last_sent_ms = now_ms;
+        self.attempts.insert(id.clone(), original.clone());
+        Some(Request {
+            jsonrpc: ::V2_0,
+            method: call.method.clone(),
+            params: call.params.clone(),
+            id: id.into(),
+        })
+    }
+
+    /// The number of attempts sent for the call `original`, including the original.
+    pub fn attempts(&self, original: &Id) -> usize {
+        self.calls.get(original).map_or(0, |c| c.attempts.len())
+    }
+
+    /// Handle a Response.
+    pub fn complete(&mut self, response: Response<Value>) -> Completion {
+        if self.losers.remove(response.id()) {
+            return Completion::Ignored;
+        }
+        let call = self
+            .attempts
+            .get(response.id())
+            .cloned()
+            .and_then(|original| self.calls.remove(&original).map(|c| (original, c)));
+        let (original, call) = match call {
+            Some(call) => call,
+            None => return Completion::Unknown(response),
+        };
+        let mut losers = Vec::new();
+        for attempt in call.attempts {
+            self.attempts.remove(&attempt);
+            if attempt != *response.id() {
+                self.losers.insert(attempt.clone());
+                losers.push(attempt);
+            }
+        }
+        Completion::Winner {
+            response: with_id(&response, original),
+            losers: losers,
+        }
+    }
+
+    /// Stop tracking the call `original`, e.g. after it timed out. Returns the ids of all its
+    /// attempts.
+    pub fn abandon(&mut self, original: &Id) -> Vec<Id> {
+        let call = match self.calls.remove(original) {
+            Some(call) => call,
+            None => return Vec::new(),
+        };
+        for attempt in &call.attempts {
+            self.attempts.remove(attempt);
+        }
+        call.attempts
+    }
+
+    /// Forget the losers whose Responses have not arrived, e.g. because they were cancelled.
+    pub fn forget_losers(&mut self) {
+        self.losers.clear();
+    }
+
+    /// The number of hedged calls in flight.
+    pub fn in_flight(&self) -> usize {
+        self.calls.len()
+    }
+}
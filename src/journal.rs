@@ -0,0 +1,303 @@
+//! A journal of Responses, for replying to retried Requests after a crash.
+//!
+//! A server records each Response under the [content hash](fn.content_hash.html) of its Request
+//! and persists the journal (it is serde serializable). After a restart, a Request retried by a
+//! client is answered from the journal instead of being executed a second time.
+//!
+//! The hash is only 64 bits, so each entry also keeps the compact json of its Request: a Request
+//! is only answered from an entry whose json is identical, never from a colliding one.
+//!
+//! Retention is bounded both by age and by the number of entries; call
+//! [`compact`](struct.ResponseJournal.html#method.compact) periodically, e.g. before persisting.
+//! It returns a [`GcReport`](struct.GcReport.html) for monitoring; the limits can be tuned while
+//! the journal is in use.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use std::time::Duration;
+//! use jrpc::{Id, Request, Response};
+//! use jrpc::journal::{self, ResponseJournal};
+//!
+//! # fn main() {
+//! let mut journal = ResponseJournal::new(1000, Duration::from_secs(600));
+//!
+//! let request = Request::with_params(Id::from(1), "Transfer".to_string(), vec![100]);
+//! journal.record(&request, &Response::success(Id::from(1), "tx-1".to_string()), 5_000);
+//!
+//! // ... crash, restart and reload the journal ...
+//! let json = serde_json::to_string(&journal).unwrap();
+//! let journal: ResponseJournal = serde_json::from_str(&json).unwrap();
+//!
+//! let retried = Request::with_params(Id::from(1), "Transfer".to_string(), vec![100]);
+//! let response: Response<String> = journal.replay(&retried).unwrap();
+//! assert_eq!(response.to_string(), r#"{"jsonrpc":"2.0","result":"tx-1","id":1}"#);
+//! assert!(journal.get(&journal::content_hash(&retried)).is_some());
+//! # }
+//! ```
+
+use core::convert::TryFrom;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use memory::MemoryUsage;
+use {Response, Value};
+
+/// Compute the content hash of a Request (or any message).
+///
+/// This is the 64 bit FNV-1a hash of the compact json serialization, as 16 lowercase hex
+/// digits. It is stable across processes and versions of this crate, so it can be persisted. The
+/// `id` is part of the content: a retry only matches if it reuses the original `id`.
+pub fn content_hash<R: Serialize>(request: &R) -> String {
+    json_hash(&request_json(request))
+}
+
+/// The compact json of a Request, as hashed by `content_hash`.
+fn request_json<R: Serialize>(request: &R) -> String {
+    serde_json::to_string(request).expect("requests are always serializable")
+}
+
+fn json_hash(json: &str) -> String {
+    format!("{:016x}", fnv1a(json.as_bytes()))
+}
+
+/// The 64 bit FNV-1a hash.
+pub(crate) fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in data {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// What a [`compact`](struct.ResponseJournal.html#method.compact) dropped and what remains.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcReport {
+    /// The number of entries dropped because they were older than the retention period.
+    pub expired: usize,
+
+    /// The number of entries evicted because the journal was over `max_entries`.
+    pub evicted: usize,
+
+    /// An estimate of the memory reclaimed, in bytes: the size of the dropped hashes and the
+    /// compact json of their Requests and Responses.
+    pub reclaimed_bytes: usize,
+
+    /// The age of the oldest remaining entry, in milliseconds, or `None` if the journal is empty.
+    pub oldest_age_ms: Option<u64>,
+}
+
+impl GcReport {
+    /// The total number of entries dropped.
+    pub fn dropped(&self) -> usize {
+        self.expired + self.evicted
+    }
+}
+
+/// A recorded Response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// When the Response was recorded, in milliseconds since the unix epoch.
+    pub recorded_ms: u64,
+
+    /// The compact json of the Request, compared on a hit before replaying.
+    pub request: String,
+
+    /// The Response.
+    pub response: Value,
+}
+
+/// Maps Request content hashes to their Responses, with bounded retention.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseJournal {
+    max_entries: usize,
+    retention_ms: u64,
+    entries: BTreeMap<String, JournalEntry>,
+}
+
+impl ResponseJournal {
+    /// Create an empty journal keeping at most `max_entries` Responses, each for at most
+    /// `retention`.
+    pub fn new(max_entries: usize, retention: Duration) -> Self {
+        ResponseJournal {
+            max_entries: max_entries,
+            retention_ms: duration_ms(retention),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// The maximum number of entries.
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Change the maximum number of entries. Takes effect on the next `record` or `compact`.
+    pub fn set_max_entries(&mut self, max_entries: usize) {
+        self.max_entries = max_entries;
+    }
+
+    /// How long entries are kept.
+    pub fn retention(&self) -> Duration {
+        Duration::from_millis(self.retention_ms)
+    }
+
+    /// Change how long entries are kept. Takes effect on the next `compact`.
+    pub fn set_retention(&mut self, retention: Duration) {
+        self.retention_ms = duration_ms(retention);
+    }
+
+    /// Record the Response to `request` under its content hash, at `now_ms`.
+    ///
+    /// If the journal is full, the oldest entry is evicted.
+    pub fn record<R, T>(&mut self, request: &R, response: &Response<T>, now_ms: u64)
+    where
+        R: Serialize,
+        T: Serialize,
+    {
+        let request = request_json(request);
+        let response = serde_json::to_value(response).expect("responses are always serializable");
+        self.entries.insert(
+            json_hash(&request),
+            JournalEntry {
+                recorded_ms: now_ms,
+                request: request,
+                response: response,
+            },
+        );
+        while self.entries.len() > self.max_entries {
+            self.evict_oldest();
+        }
+    }
+
+    /// Get the entry recorded for `hash`.
+    pub fn get(&self, hash: &str) -> Option<&JournalEntry> {
+        self.entries.get(hash)
+    }
+
+    /// Get the Response recorded for `request`, if it deserializes as `Response<T>`.
+    ///
+    /// An entry recorded for another Request with the same content hash is not replayed.
+    pub fn replay<R, T>(&self, request: &R) -> Option<Response<T>>
+    where
+        R: Serialize,
+        T: Serialize + DeserializeOwned,
+    {
+        let request = request_json(request);
+        let entry = self.entries.get(&json_hash(&request))?;
+        if entry.request != request {
+            return None;
+        }
+        serde_json::from_value(entry.response.clone()).ok()
+    }
+
+    /// Remove the entry recorded for `hash`.
+    pub fn remove(&mut self, hash: &str) -> Option<JournalEntry> {
+        self.entries.remove(hash)
+    }
+
+    /// Drop all entries older than the retention period at `now_ms`, then the oldest entries
+    /// beyond `max_entries`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use std::time::Duration;
+    /// use jrpc::{Id, Request, Response};
+    /// use jrpc::journal::ResponseJournal;
+    ///
+    /// # fn main() {
+    /// let mut journal = ResponseJournal::new(10, Duration::from_secs(60));
+    /// let a = Request::new(Id::from(1), "a".to_string());
+    /// let b = Request::new(Id::from(2), "b".to_string());
+    /// journal.record(&a, &Response::success(Id::from(1), 1), 0);
+    /// journal.record(&b, &Response::success(Id::from(2), 2), 30_000);
+    ///
+    /// let report = journal.compact(70_000);
+    /// assert_eq!(report.expired, 1);
+    /// assert_eq!(report.oldest_age_ms, Some(40_000));
+    ///
+    /// // Tighten the retention of a long running server.
+    /// journal.set_retention(Duration::from_secs(30));
+    /// assert_eq!(journal.compact(70_000).expired, 1);
+    /// assert!(journal.is_empty());
+    /// # }
+    /// ```
+    pub fn compact(&mut self, now_ms: u64) -> GcReport {
+        let mut report = GcReport::default();
+        let retention_ms = self.retention_ms;
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|&(_, e)| now_ms.saturating_sub(e.recorded_ms) >= retention_ms)
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        for hash in expired {
+            if let Some(entry) = self.entries.remove(&hash) {
+                report.expired += 1;
+                report.reclaimed_bytes += entry_size(&hash, &entry);
+            }
+        }
+        while self.entries.len() > self.max_entries {
+            if let Some(bytes) = self.evict_oldest() {
+                report.evicted += 1;
+                report.reclaimed_bytes += bytes;
+            }
+        }
+        report.oldest_age_ms = self
+            .entries
+            .values()
+            .map(|e| now_ms.saturating_sub(e.recorded_ms))
+            .max();
+        report
+    }
+
+    /// The number of recorded Responses.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the journal is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Evict the oldest entry, returning its estimated size.
+    fn evict_oldest(&mut self) -> Option<usize> {
+        let hash = self
+            .entries
+            .iter()
+            .min_by_key(|&(_, e)| e.recorded_ms)
+            .map(|(hash, _)| hash.clone())?;
+        let entry = self.entries.remove(&hash)?;
+        Some(entry_size(&hash, &entry))
+    }
+}
+
+impl MemoryUsage for ResponseJournal {
+    fn memory_usage(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(hash, entry)| entry_size(hash, entry))
+            .sum()
+    }
+
+    fn shed_oldest(&mut self) -> Option<usize> {
+        self.evict_oldest()
+    }
+}
+
+fn entry_size(hash: &str, entry: &JournalEntry) -> usize {
+    let response = serde_json::to_vec(&entry.response).map_or(0, |data| data.len());
+    hash.len() + entry.request.len() + response
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,268 @@
+//! Dependency-light latency tracking.
+//!
+//! [`LatencyRecorder`](struct.LatencyRecorder.html) keeps one
+//! [`Histogram`](struct.Histogram.html) per method. Histograms use HDR-style log-linear buckets:
+//! values are grouped by powers of two and each group is split into 16 linear sub-buckets, which
+//! bounds the relative error of any reported percentile to roughly 6% while using a small, fixed
+//! amount of memory regardless of how many samples are recorded.
+//!
+//! This crate never measures time itself: the caller (typically whatever drives the dispatch of
+//! requests) records the elapsed `Duration` of each call.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use std::time::Duration;
+//! use jrpc::latency::LatencyRecorder;
+//!
+//! # fn main() {
+//! let mut recorder = LatencyRecorder::new();
+//! for ms in 1..101 {
+//!     recorder.record("CreateFoo", Duration::from_millis(ms));
+//! }
+//! recorder.record("DeleteFoo", Duration::from_millis(3));
+//!
+//! let summary = recorder.summary("CreateFoo").unwrap();
+//! assert_eq!(summary.count, 100);
+//! assert!(summary.p50_us >= 48_000 && summary.p50_us <= 53_000);
+//! assert!(summary.p99_us >= 97_000 && summary.p99_us <= 100_000);
+//!
+//! let snapshot = recorder.snapshot();
+//! let json = serde_json::to_value(&snapshot).unwrap();
+//! assert_eq!(json["DeleteFoo"]["count"], 1);
+//! # }
+//! ```
+
+use std_prelude::*;
+
+/// Number of bits used for the linear sub-buckets of each power of two.
+const SUB_BITS: u32 = 4;
+const SUB_COUNT: u64 = 1 << SUB_BITS;
+
+/// A log-linear histogram of latencies, with microsecond resolution.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    min: u64,
+    max: u64,
+    sum: u64,
+}
+
+impl Histogram {
+    /// Create an empty histogram.
+    pub fn new() -> Self {
+        Histogram::default()
+    }
+
+    /// Record a single latency sample.
+    pub fn record(&mut self, latency: Duration) {
+        self.record_us(duration_us(latency));
+    }
+
+    /// Record a single latency sample, in microseconds.
+    pub fn record_us(&mut self, us: u64) {
+        let index = bucket_index(us);
+        if self.counts.len() <= index {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        if self.total == 0 || us < self.min {
+            self.min = us;
+        }
+        if us > self.max {
+            self.max = us;
+        }
+        self.total += 1;
+        self.sum = self.sum.saturating_add(us);
+    }
+
+    /// Merge all samples from `other` into this histogram.
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.total == 0 {
+            return;
+        }
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += *other;
+        }
+        if self.total == 0 || other.min < self.min {
+            self.min = other.min;
+        }
+        self.max = self.max.max(other.max);
+        self.total += other.total;
+        self.sum = self.sum.saturating_add(other.sum);
+    }
+
+    /// The number of recorded samples.
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// Return the latency (in microseconds) at or below which `percentile` percent of the samples
+    /// fall.
+    ///
+    /// `percentile` is clamped to `0.0..=100.0`. The result is the upper bound of the bucket
+    /// containing the sample, clamped to the largest recorded value. Returns `0` if the histogram
+    /// is empty.
+    pub fn percentile_us(&self, percentile: f64) -> u64 {
+        if self.total == 0 {
+            return 0;
+        }
+        let percentile = percentile.clamp(0.0, 100.0);
+        let rank = ((percentile / 100.0) * self.total as f64).ceil() as u64;
+        let rank = rank.max(1);
+
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += *count;
+            if seen >= rank {
+                return bucket_upper(index).min(self.max).max(self.min);
+            }
+        }
+        self.max
+    }
+
+    /// Return the latency at the given percentile. See
+    /// [`percentile_us`](#method.percentile_us).
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        Duration::from_micros(self.percentile_us(percentile))
+    }
+
+    /// Summarize the histogram.
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.total,
+            min_us: self.min,
+            max_us: self.max,
+            mean_us: self.sum.checked_div(self.total).unwrap_or(0),
+            p50_us: self.percentile_us(50.0),
+            p95_us: self.percentile_us(95.0),
+            p99_us: self.percentile_us(99.0),
+        }
+    }
+}
+
+/// A serializable summary of a [`Histogram`](struct.Histogram.html).
+///
+/// All values are in microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    /// The number of recorded samples.
+    pub count: u64,
+    /// The smallest recorded sample.
+    pub min_us: u64,
+    /// The largest recorded sample.
+    pub max_us: u64,
+    /// The (exact) arithmetic mean of all samples.
+    pub mean_us: u64,
+    /// The 50th percentile.
+    pub p50_us: u64,
+    /// The 95th percentile.
+    pub p95_us: u64,
+    /// The 99th percentile.
+    pub p99_us: u64,
+}
+
+/// Latency histograms, kept per method.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyRecorder {
+    methods: BTreeMap<String, Histogram>,
+}
+
+impl LatencyRecorder {
+    /// Create an empty recorder.
+    pub fn new() -> Self {
+        LatencyRecorder::default()
+    }
+
+    /// Record the latency of a single call to `method`.
+    pub fn record<S>(&mut self, method: S, latency: Duration)
+    where
+        S: AsRef<str>,
+    {
+        let method = method.as_ref();
+        if let Some(histogram) = self.methods.get_mut(method) {
+            histogram.record(latency);
+            return;
+        }
+        let mut histogram = Histogram::new();
+        histogram.record(latency);
+        self.methods.insert(method.to_string(), histogram);
+    }
+
+    /// Get the histogram for `method`, if any calls have been recorded.
+    pub fn histogram(&self, method: &str) -> Option<&Histogram> {
+        self.methods.get(method)
+    }
+
+    /// Summarize the latencies of `method`, if any calls have been recorded.
+    pub fn summary(&self, method: &str) -> Option<LatencySummary> {
+        self.methods.get(method).map(Histogram::summary)
+    }
+
+    /// Iterate over all methods and their histograms, ordered by method name.
+    pub fn iter(&self) -> ::std::collections::btree_map::Iter<'_, String, Histogram> {
+        self.methods.iter()
+    }
+
+    /// A histogram combining the samples of every method.
+    pub fn combined(&self) -> Histogram {
+        let mut out = Histogram::new();
+        for histogram in self.methods.values() {
+            out.merge(histogram);
+        }
+        out
+    }
+
+    /// Summarize every method, suitable for serializing.
+    pub fn snapshot(&self) -> BTreeMap<String, LatencySummary> {
+        self.methods
+            .iter()
+            .map(|(method, histogram)| (method.clone(), histogram.summary()))
+            .collect()
+    }
+
+    /// Remove all recorded samples.
+    pub fn clear(&mut self) {
+        self.methods.clear();
+    }
+}
+
+fn duration_us(duration: Duration) -> u64 {
+    let us = duration.as_secs().saturating_mul(1_000_000);
+    us.saturating_add(u64::from(duration.subsec_micros()))
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_COUNT {
+        return value as usize;
+    }
+    let msb = 63 - value.leading_zeros();
+    let group = u64::from(msb - SUB_BITS + 1);
+    let sub = (value >> (msb - SUB_BITS)) - SUB_COUNT;
+    (group * SUB_COUNT + sub) as usize
+}
+
+fn bucket_lower(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_COUNT {
+        return index;
+    }
+    let group = index / SUB_COUNT;
+    let sub = index % SUB_COUNT;
+    (SUB_COUNT + sub) << (group - 1)
+}
+
+fn bucket_upper(index: usize) -> u64 {
+    if index as u64 >= bucket_index(u64::MAX) as u64 {
+        return u64::MAX;
+    }
+    bucket_lower(index + 1) - 1
+}
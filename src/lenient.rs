@@ -0,0 +1,181 @@
+//! A lenient parse profile for hand-written json, intended for developer tooling.
+//!
+//! > Only available with the `lenient` feature. Do **not** use this on production endpoints:
+//! > the spec requires plain json and peers should not come to rely on these deviations.
+//!
+//! In addition to plain json, this accepts:
+//!
+//! - `// line comments` and `/* block comments */`
+//! - trailing commas in Arrays and Objects
+//!
+//! The input is first [normalized](fn.normalize.html) into strict json, which is then handed to
+//! the normal parse path. Normalization preserves byte offsets (comments and trailing commas are
+//! replaced by spaces), so line and column numbers in error messages still point into the
+//! original text.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{Id, IdReq};
+//!
+//! # fn main() {
+//! let json = r#"
+//! {
+//!     "jsonrpc": "2.0",
+//!     // TODO: try another method
+//!     "method": "CreateFoo",
+//!     "params": [1, 2, 3,],  /* a trailing comma */
+//!     "id": 4,
+//! }
+//! "#;
+//!
+//! assert!(jrpc::parse_request::<String>(json).is_err());
+//!
+//! let request = jrpc::lenient::parse_request::<String>(json).unwrap();
+//! assert_eq!(request.method, "CreateFoo");
+//! assert_eq!(request.id, IdReq::from(Id::from(4)));
+//! # }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {request_from_value, Error, ErrorCode, Id, Request, Value};
+
+/// Convert lenient json into strict json.
+///
+/// Comments and trailing commas are replaced with spaces (newlines inside block comments are
+/// kept), so the output has exactly the same length and line structure as the input. Returns
+/// the input unchanged if there was nothing to replace.
+///
+/// Invalid input is passed through as-is for the json parser to report. An unterminated block
+/// comment is left in place for the same reason.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// # fn main() {
+/// assert_eq!(jrpc::lenient::normalize("[1, 2, /* 3 */]"), "[1, 2         ]");
+/// assert_eq!(jrpc::lenient::normalize(r#""// not a comment""#), r#""// not a comment""#);
+/// # }
+/// ```
+pub fn normalize(json: &str) -> Cow<'_, str> {
+    let bytes = json.as_bytes();
+    let mut out: Option<Vec<u8>> = None;
+    let mut i = 0;
+
+    // Blank out `bytes[start..end]`, keeping newlines.
+    macro_rules! blank {
+        ($start:expr, $end:expr) => {{
+            let buf = out.get_or_insert_with(|| bytes.to_vec());
+            for b in &mut buf[$start..$end] {
+                if *b != b'\n' && *b != b'\r' {
+                    *b = b' ';
+                }
+            }
+        }};
+    }
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => i = skip_string(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = bytes[i..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map(|p| i + p)
+                    .unwrap_or(bytes.len());
+                blank!(i, end);
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                match find(&bytes[i + 2..], b"*/") {
+                    Some(p) => {
+                        let end = i + 2 + p + 2;
+                        blank!(i, end);
+                        i = end;
+                    }
+                    None => i = bytes.len(),
+                }
+            }
+            b',' => {
+                if let Some(next) = next_significant(bytes, i + 1) {
+                    if bytes[next] == b']' || bytes[next] == b'}' {
+                        blank!(i, i + 1);
+                    }
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+
+    match out {
+        // Only ASCII bytes outside of strings were replaced with ASCII spaces.
+        Some(buf) => Cow::Owned(String::from_utf8(buf).expect("normalize keeps utf-8 intact")),
+        None => Cow::Borrowed(json),
+    }
+}
+
+/// Deserialize any type from lenient json.
+pub fn from_str<T>(json: &str) -> serde_json::Result<T>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_str(&normalize(json))
+}
+
+/// The lenient equivalent of [`jrpc::parse_request`](../fn.parse_request.html).
+///
+/// Errors are mapped in exactly the same way.
+pub fn parse_request<M>(json: &str) -> Result<Request<M, Value>, Error<Value>>
+where
+    M: Serialize + DeserializeOwned,
+{
+    let value: Value = from_str(json)
+        .map_err(|err| Error::new(Id::Null, ErrorCode::ParseError, err.to_string(), None))?;
+    request_from_value(value)
+}
+
+/// Return the index just past the string starting at `bytes[start] == b'"'`.
+fn skip_string(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Find the next byte which is neither whitespace nor part of a comment.
+fn next_significant(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' => i += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = bytes[i..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map(|p| i + p)
+                    .unwrap_or(bytes.len());
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => match find(&bytes[i + 2..], b"*/") {
+                Some(p) => i = i + 2 + p + 2,
+                None => return None,
+            },
+            _ => return Some(i),
+        }
+    }
+    None
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
@@ -5,6 +5,18 @@
 //! This crate never touches the network, filesystem, etc. It simply uses serde
 //! to easily construct, serialize and deserialize Request and Response data types.
 //!
+//! # `no_std`
+//!
+//! Without the default `std` feature, the crate is `no_std` and only needs `alloc`, e.g. for
+//! embedded peers speaking jsonrpc over a serial link. The datatypes of the crate root and the
+//! [`erased`](erased/index.html), [`params`](params/index.html), [`scrub`](scrub/index.html) and
+//! [`strict`](strict/index.html) modules are available; the other modules need `std`.
+//!
+//! ```toml
+//! [dependencies]
+//! jrpc = { version = "0.4", default-features = false }
+//! ```
+//!
 //! # Specification
 //!
 //! The below is directly copy/pasted from: [http://www.jsonrpc.org/specification][spec]
@@ -63,7 +75,7 @@
 //!
 //! ## 4.1 Notification
 //!
-//! See [`IdReq`](enum.IdReq.html)
+//! See [`Notification`](struct.Notification.html) and [`IdReq`](enum.IdReq.html)
 //!
 //! ## 4.2 Parameter Structures
 //!
@@ -79,7 +91,8 @@
 //!
 //! ## 6 Batch
 //!
-//! > Note: simply use a `Vec<Request>` and `Vec<Response>`
+//! See [`BatchRequest`](batch/struct.BatchRequest.html) and
+//! [`BatchResponse`](batch/struct.BatchResponse.html)
 //!
 //! To send several Request objects at the same time, the Client MAY send an Array filled with
 //! Request objects.
@@ -107,25 +120,207 @@
 //!
 //! This library does not support checking for extensions. See
 //! [`Request.method`](struct.Request.html#structfield.method) for more details of the spec.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(unknown_lints)]
-#![allow(redundant_field_names)]
+#![allow(clippy::redundant_field_names)]
 #![warn(missing_docs)]
 
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "cbor")]
+extern crate ciborium;
+#[cfg(feature = "std")]
+extern crate core;
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(feature = "async")]
+extern crate futures_sink;
+#[cfg(feature = "derive")]
+extern crate jrpc_derive;
+#[cfg(feature = "msgpack")]
+extern crate rmp_serde;
+#[cfg(feature = "schemars")]
+extern crate schemars;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+#[cfg(feature = "std")]
 extern crate std_prelude;
 
 pub use serde_json::Value;
 
+#[cfg(feature = "derive")]
+pub use jrpc_derive::Params;
+
+#[cfg(feature = "std")]
+pub mod admission;
+#[cfg(feature = "std")]
+pub mod affinity;
+#[cfg(feature = "std")]
+pub mod allocator;
+#[cfg(feature = "async")]
+pub mod async_router;
+#[cfg(feature = "std")]
+pub mod backoff;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod borrowed;
+#[cfg(feature = "std")]
+pub mod call;
+#[cfg(feature = "std")]
+pub mod cancel;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "std")]
+pub mod canonical;
+#[cfg(feature = "std")]
+pub mod catalog;
+#[cfg(feature = "std")]
+pub mod coalesce;
+#[cfg(any(feature = "lsp-codes", feature = "eth-codes"))]
+pub mod codes;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "std")]
+pub mod compress;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod consistency;
+#[cfg(feature = "std")]
+pub mod contract;
+#[cfg(feature = "std")]
+pub mod correlation;
+#[cfg(feature = "std")]
+pub mod cost;
+#[cfg(feature = "std")]
+pub mod deadline;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod derive;
+#[cfg(feature = "differential")]
+pub mod differential;
+#[cfg(feature = "std")]
+pub mod encrypt;
+#[cfg(feature = "std")]
+pub mod endpoint;
+pub mod erased;
+#[cfg(feature = "std")]
+pub mod escalation;
+#[cfg(feature = "std")]
+pub mod examples;
+#[cfg(feature = "std")]
+pub mod examples_support;
+#[cfg(feature = "std")]
+pub mod fault;
+#[cfg(feature = "std")]
+pub mod framing;
+#[cfg(feature = "std")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod grpc;
+#[cfg(feature = "std")]
+pub mod hedge;
+#[cfg(feature = "std")]
+pub mod journal;
+#[cfg(feature = "std")]
+pub mod latency;
+#[cfg(feature = "lenient")]
+pub mod lenient;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
+pub mod loadgen;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod merkle;
+#[cfg(feature = "std")]
+pub mod method;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "std")]
+pub mod multi;
+#[cfg(feature = "json-patch")]
+pub mod patch;
+#[cfg(feature = "v1-compat")]
+pub mod negotiate;
+#[cfg(feature = "std")]
+pub mod null_id;
+#[cfg(feature = "openrpc")]
+pub mod openrpc;
+pub mod params;
+#[cfg(feature = "std")]
+pub mod poll;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod reader;
+#[cfg(feature = "std")]
+pub mod resource;
+#[cfg(feature = "std")]
+pub mod resume;
+#[cfg(feature = "std")]
+pub mod router;
+#[cfg(feature = "std")]
+pub mod schema;
+pub mod scrub;
+#[cfg(feature = "std")]
+pub mod shadow;
+#[cfg(feature = "std")]
+pub mod shard;
+#[cfg(feature = "std")]
+pub mod slow;
+#[cfg(feature = "std")]
+pub mod stats;
+pub mod strict;
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod surface;
+#[cfg(feature = "std")]
+pub mod table;
+#[cfg(feature = "std")]
+pub mod tenant;
+#[cfg(feature = "v1-compat")]
+pub mod v1;
+#[cfg(feature = "std")]
+pub mod vectors;
+#[cfg(feature = "std")]
+pub mod via;
+#[cfg(feature = "std")]
+pub mod webhook;
+#[cfg(feature = "std")]
+pub mod writer;
 mod serialize;
 
-use std_prelude::*;
+/// The items of `std_prelude` used by the modules available without `std`, from `alloc`.
+mod prelude {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::collections::BTreeMap;
+    #[cfg(not(feature = "std"))]
+    pub use alloc::string::{String, ToString};
+    #[cfg(not(feature = "std"))]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "std")]
+    pub use std_prelude::*;
+}
+
+use core::error;
+use core::fmt;
+use core::ops::Range;
+
+use prelude::*;
 use serde::ser::Serialize;
-use serde::de::DeserializeOwned;
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer};
+
+use erased::MessageKind;
 
 /// The `jsonrpc` version. Will serialize/deserialize to/from `"2.0"`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct V2_0;
 
 /// An identifier established by the Client that MUST contain a String, Number, or NULL value if
@@ -158,7 +353,7 @@ pub struct V2_0;
 /// );
 /// # }
 /// ```
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Id {
     /// An String id
@@ -236,7 +431,7 @@ impl From<i64> for Id {
 /// assert_eq!(request.id, IdReq::Notification);
 /// # }
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum IdReq {
     /// An String id
@@ -307,7 +502,7 @@ impl IdReq {
 /// assert_eq!(json, result);
 /// # }
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Request<M, T> {
     /// A String specifying the version of the JSON-RPC protocol. MUST be exactly "2.0".
     pub jsonrpc: V2_0,
@@ -324,7 +519,7 @@ pub struct Request<M, T> {
     /// used for anything else. Each system extension is defined in a related specification. All
     /// system extensions are OPTIONAL.
     ///
-    /// This library provides no way of checking for system extensions.
+    /// Use [`validate`](#method.validate) to check for system extensions.
     pub method: M,
 
     /// A Structured value that holds the parameter values to be used during the invocation of the
@@ -332,7 +527,8 @@ pub struct Request<M, T> {
     ///
     /// ## Spec Requiement
     ///
-    /// > Note: the following spec is **not** upheld by this library.
+    /// > Note: the following spec is **not** upheld by this library, use
+    /// > [`validate`](#method.validate) to check it.
     ///
     /// If present, parameters for the rpc call MUST be provided as a Structured value. Either
     /// by-position through an Array or by-name through an Object.
@@ -354,11 +550,39 @@ pub struct Request<M, T> {
 
 impl<M: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned> Request<M, T> {
     /// Helper to serialize the Request as json.
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 
+    /// Serialize the Request as indented json with a stable member order (`jsonrpc`, `id`,
+    /// `method`, `params`), for logs and debugging endpoints.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::{Id, Request};
+    ///
+    /// # fn main() {
+    /// let request = Request::with_params(Id::from(4), "CreateFoo".to_string(), vec![1, 2]);
+    /// assert_eq!(request.to_string_pretty_ordered(), r#"{
+    ///   "jsonrpc": "2.0",
+    ///   "id": 4,
+    ///   "method": "CreateFoo",
+    ///   "params": [
+    ///     1,
+    ///     2
+    ///   ]
+    /// }"#);
+    /// # }
+    /// ```
+    pub fn to_string_pretty_ordered(&self) -> String {
+        serialize::to_string_pretty_ordered(self)
+    }
+
     /// Helper to deserialize the Request from json.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> serde_json::Result<T> {
         serde_json::from_str(s)
     }
@@ -411,6 +635,425 @@ impl<M: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned> Request<M
             id: id.into(),
         }
     }
+
+    /// Create a new Request with the specified params, failing with
+    /// [`SpecViolation::Params`](strict/enum.SpecViolation.html#variant.Params) if they do not
+    /// serialize to an Array or an Object. See [`params::Params`](params/enum.Params.html).
+    pub fn try_with_params<I>(id: I, method: M, params: T) -> Result<Self, strict::SpecViolation>
+    where
+        I: Into<IdReq>,
+    {
+        params::Params::from_serialize(&params)?;
+        Ok(Request::with_params(id, method, params))
+    }
+}
+
+impl<M, T> Request<M, T> {
+    /// Convert into a [`Notification`](struct.Notification.html), dropping the `id`.
+    pub fn into_notification(self) -> Notification<M, T> {
+        Notification {
+            jsonrpc: V2_0,
+            method: self.method,
+            params: self.params,
+        }
+    }
+
+    /// Convert the params with `f`, keeping the `id` and `method`. Absent params stay absent.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::{Id, Request};
+    ///
+    /// # fn main() {
+    /// let request = Request::with_params(Id::from(1), "Sum".to_string(), vec![1, 2, 3]);
+    /// let request = request.map_params(|values| values.iter().sum::<u32>());
+    /// assert_eq!(request.params, Some(6));
+    /// assert_eq!(request.id, Id::from(1).into());
+    ///
+    /// let request = Request::with_params(Id::from(2), "Sum".to_string(), "x".to_string());
+    /// assert!(request.try_map_params(|s| s.parse::<u32>()).is_err());
+    /// # }
+    /// ```
+    pub fn map_params<U, F>(self, f: F) -> Request<M, U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Request {
+            jsonrpc: V2_0,
+            method: self.method,
+            params: self.params.map(f),
+            id: self.id,
+        }
+    }
+
+    /// Convert the params with `f`, failing with its error.
+    /// See [`map_params`](#method.map_params).
+    pub fn try_map_params<U, E, F>(self, f: F) -> Result<Request<M, U>, E>
+    where
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        let params = match self.params {
+            Some(params) => Some(f(params)?),
+            None => None,
+        };
+        Ok(Request {
+            jsonrpc: V2_0,
+            method: self.method,
+            params: params,
+            id: self.id,
+        })
+    }
+}
+
+/// A Request object without an `id`, to which the Server MUST NOT reply.
+///
+/// Unlike a [`Request`](struct.Request.html) with an [`IdReq::Notification`] id, it can not be
+/// mistaken for a call: it never serializes an `id` member and fails to deserialize from an Object
+/// which has one.
+///
+/// [`IdReq::Notification`]: enum.IdReq.html#variant.Notification
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// extern crate serde_json;
+/// use jrpc::{Id, Notification, Request, RequestOrNotification, Value};
+///
+/// # fn main() {
+/// let notification = Notification::with_params("Log".to_string(), vec![1, 2]);
+/// assert_eq!(
+///     notification.to_string(),
+///     r#"{"jsonrpc":"2.0","method":"Log","params":[1,2]}"#,
+/// );
+/// let request = Request::with_params(Id::from(1), "Log".to_string(), vec![1, 2]);
+/// assert_eq!(request.into_notification().to_string(), notification.to_string());
+///
+/// // Servers accepting both on the same endpoint.
+/// let json = r#"{"jsonrpc": "2.0", "method": "Log", "params": [3]}"#;
+/// let message: RequestOrNotification<String, Value> = serde_json::from_str(json).unwrap();
+/// assert!(message.id().is_none());
+/// let json = r#"{"jsonrpc": "2.0", "method": "Log", "params": [3], "id": null}"#;
+/// let message: RequestOrNotification<String, Value> = serde_json::from_str(json).unwrap();
+/// assert_eq!(message.id(), Some(Id::Null));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Notification<M, T> {
+    /// A String specifying the version of the JSON-RPC protocol. MUST be exactly "2.0".
+    pub jsonrpc: V2_0,
+
+    /// The method. See [`Request.method`](struct.Request.html#structfield.method).
+    pub method: M,
+
+    /// The params. See [`Request.params`](struct.Request.html#structfield.params).
+    #[serde(default = "default_t", skip_serializing_if = "Option::is_none")]
+    pub params: Option<T>,
+}
+
+impl<M: Serialize + DeserializeOwned> Notification<M, ()> {
+    /// Create a new Notification without params.
+    pub fn new(method: M) -> Self {
+        Notification {
+            jsonrpc: V2_0,
+            method: method,
+            params: None,
+        }
+    }
+}
+
+impl<M: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned> Notification<M, T> {
+    /// Create a new Notification with the specified params.
+    pub fn with_params(method: M, params: T) -> Self {
+        Notification {
+            jsonrpc: V2_0,
+            method: method,
+            params: Some(params),
+        }
+    }
+}
+
+/// Formats the Notification as compact json.
+impl<M: Serialize, T: Serialize> fmt::Display for Notification<M, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&serde_json::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+impl Notification<String, Value> {
+    /// Append a Notification as compact json to `out`, with params already serialized.
+    ///
+    /// This is for emitters sending many notifications, e.g. telemetry: it writes the bytes
+    /// directly, without building a Notification, and only allocates if `out` must grow. The
+    /// output is the same as formatting the Notification with `Display`. `params` are copied as is, so
+    /// MUST be a json Array or Object.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::Notification;
+    ///
+    /// # fn main() {
+    /// let mut out = Vec::with_capacity(1024);
+    /// Notification::emit_compact(&mut out, "cpu", Some(b"[0.5]"));
+    /// Notification::emit_compact(&mut out, "idle", None);
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     r#"{"jsonrpc":"2.0","method":"cpu","params":[0.5]}{"jsonrpc":"2.0","method":"idle"}"#,
+    /// );
+    /// let notification = Notification::with_params("cpu".to_string(), vec![0.5]);
+    /// assert_eq!(notification.to_string(), r#"{"jsonrpc":"2.0","method":"cpu","params":[0.5]}"#);
+    /// # }
+    /// ```
+    pub fn emit_compact(out: &mut Vec<u8>, method: &str, params: Option<&[u8]>) {
+        out.extend_from_slice(br#"{"jsonrpc":"2.0","method":"#);
+        serialize::write_str(out, method);
+        if let Some(params) = params {
+            out.extend_from_slice(br#","params":"#);
+            out.extend_from_slice(params);
+        }
+        out.push(b'}');
+    }
+}
+
+impl<M, T> From<Notification<M, T>> for Request<M, T> {
+    fn from(notification: Notification<M, T>) -> Self {
+        Request {
+            jsonrpc: V2_0,
+            method: notification.method,
+            params: notification.params,
+            id: IdReq::Notification,
+        }
+    }
+}
+
+/// Either a Request or a Notification, for servers accepting both on the same endpoint.
+///
+/// An Object with an `id` member, even `null`, is a Request.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum RequestOrNotification<M, T> {
+    /// A call, which the Server replies to.
+    Request(Request<M, T>),
+    /// A notification, which the Server MUST NOT reply to.
+    Notification(Notification<M, T>),
+}
+
+impl<M, T> RequestOrNotification<M, T> {
+    /// The method.
+    pub fn method(&self) -> &M {
+        match *self {
+            RequestOrNotification::Request(ref r) => &r.method,
+            RequestOrNotification::Notification(ref n) => &n.method,
+        }
+    }
+
+    /// The `id` to reply with, `None` for notifications.
+    pub fn id(&self) -> Option<Id> {
+        match *self {
+            RequestOrNotification::Request(ref r) => r.id.clone().to_id(),
+            RequestOrNotification::Notification(_) => None,
+        }
+    }
+}
+
+impl<M, T> From<Request<M, T>> for RequestOrNotification<M, T> {
+    /// Requests with an [`IdReq::Notification`](enum.IdReq.html#variant.Notification) id
+    /// become Notifications.
+    fn from(request: Request<M, T>) -> Self {
+        match request.id {
+            IdReq::Notification => RequestOrNotification::Notification(request.into_notification()),
+            _ => RequestOrNotification::Request(request),
+        }
+    }
+}
+
+impl<'de, M, T> Deserialize<'de> for RequestOrNotification<M, T>
+where
+    Request<M, T>: DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let has_id = value.get("id").is_some();
+        if has_id {
+            serde_json::from_value(value).map(RequestOrNotification::Request)
+        } else {
+            serde_json::from_value(value).map(|r: Request<M, T>| r.into())
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+/// Any JSON-RPC message, for transports and proxies dispatching on the kind of what they receive.
+///
+/// `T` is the type of the `params` of Requests and Notifications and of the `result` of
+/// successful Responses. Objects are told apart by their members:
+///
+/// - With a `method`, it is a Request if it has an `id`, even `null`, or else a Notification.
+/// - With a `result`, it is a Success, and with an `error`, an Error.
+///
+/// Objects with more than one of `method`, `result` and `error` are ambiguous and rejected.
+///
+/// Batches are Arrays of messages, see [`batch`](batch/index.html).
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// extern crate serde_json;
+/// use jrpc::{Id, Message};
+/// use jrpc::erased::MessageKind;
+///
+/// # fn main() {
+/// let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#;
+/// let message: Message = serde_json::from_str(json).unwrap();
+/// assert_eq!(message.kind(), MessageKind::Request);
+/// assert_eq!(message.method(), Some("subtract"));
+///
+/// let json = r#"{"jsonrpc": "2.0", "method": "update", "params": [1, 2]}"#;
+/// let message: Message = serde_json::from_str(json).unwrap();
+/// assert!(message.is_notification());
+/// assert_eq!(message.id(), None);
+///
+/// let json = r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": "1"}"#;
+/// let message: Message = serde_json::from_str(json).unwrap();
+/// assert!(message.is_response());
+/// assert_eq!(message.id(), Some(Id::from("1".to_string())));
+///
+/// // Neither a Request nor a Response.
+/// assert!(serde_json::from_str::<Message>(r#"{"jsonrpc": "2.0", "id": 1}"#).is_err());
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(untagged)]
+pub enum Message<T = Value> {
+    /// A call, with an `id`.
+    Request(Request<String, T>),
+    /// A call without an `id`, which MUST NOT be replied to.
+    Notification(Notification<String, T>),
+    /// A Response with a `result`.
+    Success(Success<T>),
+    /// A Response with an `error`.
+    Error(Error<Value>),
+}
+
+impl<T> Message<T> {
+    /// The kind of the message. Successes and Errors are both Responses.
+    pub fn kind(&self) -> MessageKind {
+        match *self {
+            Message::Request(_) => MessageKind::Request,
+            Message::Notification(_) => MessageKind::Notification,
+            Message::Success(_) | Message::Error(_) => MessageKind::Response,
+        }
+    }
+
+    /// The `id`, `None` for Notifications.
+    pub fn id(&self) -> Option<Id> {
+        match *self {
+            Message::Request(ref r) => r.id.clone().to_id(),
+            Message::Notification(_) => None,
+            Message::Success(ref s) => Some(s.id.clone()),
+            Message::Error(ref e) => Some(e.id.clone()),
+        }
+    }
+
+    /// The method, `None` for Responses.
+    pub fn method(&self) -> Option<&str> {
+        match *self {
+            Message::Request(ref r) => Some(&r.method),
+            Message::Notification(ref n) => Some(&n.method),
+            Message::Success(_) | Message::Error(_) => None,
+        }
+    }
+
+    /// Whether the message is a Request.
+    pub fn is_request(&self) -> bool {
+        self.kind() == MessageKind::Request
+    }
+
+    /// Whether the message is a Notification.
+    pub fn is_notification(&self) -> bool {
+        self.kind() == MessageKind::Notification
+    }
+
+    /// Whether the message is a Response, successful or not.
+    pub fn is_response(&self) -> bool {
+        self.kind() == MessageKind::Response
+    }
+}
+
+/// Formats the message as compact json.
+impl<T: Serialize> fmt::Display for Message<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&serde_json::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+impl<T> From<Request<String, T>> for Message<T> {
+    /// Requests with an [`IdReq::Notification`](enum.IdReq.html#variant.Notification) id
+    /// become Notifications.
+    fn from(request: Request<String, T>) -> Self {
+        match request.id {
+            IdReq::Notification => Message::Notification(request.into_notification()),
+            _ => Message::Request(request),
+        }
+    }
+}
+
+impl<T> From<Notification<String, T>> for Message<T> {
+    fn from(notification: Notification<String, T>) -> Self {
+        Message::Notification(notification)
+    }
+}
+
+impl<T> From<Response<T, Value>> for Message<T> {
+    fn from(response: Response<T, Value>) -> Self {
+        match response {
+            Response::Ok(success) => Message::Success(success),
+            Response::Err(error) => Message::Error(error),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Message<T>
+where
+    Request<String, T>: DeserializeOwned,
+    Notification<String, T>: DeserializeOwned,
+    Success<T>: DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let (has_method, has_id, has_result, has_error) = match value {
+            Value::Object(ref object) => (
+                object.contains_key("method"),
+                object.contains_key("id"),
+                object.contains_key("result"),
+                object.contains_key("error"),
+            ),
+            _ => return Err(de::Error::custom("a message must be an Object")),
+        };
+        match (has_method, has_result, has_error) {
+            (true, false, false) if has_id => serde_json::from_value(value).map(Message::Request),
+            (true, false, false) => serde_json::from_value(value).map(Message::Notification),
+            (false, true, false) => serde_json::from_value(value).map(Message::Success),
+            (false, false, true) => serde_json::from_value(value).map(Message::Error),
+            (false, false, false) => {
+                return Err(de::Error::custom(
+                    "a message must have a `method`, `result` or `error`",
+                ))
+            }
+            _ => {
+                return Err(de::Error::custom(
+                    "a message must have only one of `method`, `result` and `error`",
+                ))
+            }
+        }
+        .map_err(de::Error::custom)
+    }
 }
 
 /// Parse a json string, returning either:
@@ -420,9 +1063,12 @@ impl<M: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned> Request<M
 /// This parses the json in stages and will correctly return one of the following errors on
 /// failure:
 ///
-/// - `ParseError`
-/// - `InvalidRequest`
-/// - `MethodNotFound`
+/// - `ParseError`: the json is invalid.
+/// - `InvalidRequest`: the json is not a Request object, or its method is not a String.
+/// - `MethodNotFound`: the method does not deserialize to `M`.
+///
+/// As the spec requires, the `id` of the `ParseError` and `InvalidRequest` errors is Null, so they
+/// can be serialized and sent back as is.
 ///
 /// > Reminder: It is up to the user to return the `InvalidParams` error if the `request.params` is
 /// > invalid.
@@ -550,11 +1196,29 @@ where
     let value: serde_json::Value = serde_json::from_str(json)
         .map_err(|err| Error::new(Id::Null, ErrorCode::ParseError, err.to_string(), None))?;
 
+    request_from_value(value)
+}
+
+/// The stages of `parse_request` after the json has been parsed.
+pub(crate) fn request_from_value<M>(value: Value) -> Result<Request<M, Value>, Error<Value>>
+where
+    M: Serialize + DeserializeOwned,
+{
     let request: Request<Value, Value> = serde_json::from_value(value)
         .map_err(|err| Error::new(Id::Null, ErrorCode::InvalidRequest, err.to_string(), None))?;
 
     let (id, method, params) = (request.id, request.method, request.params);
 
+    // The spec requires a String. Any other method is a malformed Request, not an unknown method.
+    if !method.is_string() {
+        return Err(Error::new(
+            Id::Null,
+            ErrorCode::InvalidRequest,
+            format!("method must be a String, got {}", method),
+            None,
+        ));
+    }
+
     let method: M = serde_json::from_value(method).map_err(|err| {
         Error::new(
             id.clone().to_id().unwrap_or(Id::Null),
@@ -572,9 +1236,104 @@ where
     })
 }
 
-/// The Result is either:
-/// - a jsonrpc Response (with a result of a specific type)
-/// - a Error (with an error of type `serde_json::Value`).
+/// A single message parsed out of a buffer by [`parse_many`](fn.parse_many.html).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Parsed<T> {
+    /// The byte span of the message within the buffer, excluding surrounding whitespace.
+    pub span: Range<usize>,
+    /// The parsed message, or the error to reply with.
+    pub result: Result<T, Error<Value>>,
+}
+
+/// Parse a buffer containing any number of concatenated json values.
+///
+/// HTTP pipelining and buggy clients can produce buffers holding several messages back to back,
+/// optionally separated by whitespace. Each value is deserialized as `T` independently and
+/// reported together with its byte span:
+///
+/// - Values which are valid json but not a valid `T` produce an `InvalidRequest` error.
+/// - Invalid json produces a single `ParseError` spanning the rest of the buffer, since it is not
+///   possible to reliably find where the next message starts.
+///
+/// A buffer containing only whitespace produces no messages.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// use jrpc::{ErrorCode, Request, Value};
+///
+/// # fn main() {
+/// let buffer = concat!(
+///     r#"{"jsonrpc": "2.0", "method": "one", "id": 1}"#, "\n",
+///     r#"{"jsonrpc": "2.0", "method": "two"}"#,
+///     r#" {"not": "a request"} "#,
+///     r#"{"jsonrpc": "#,
+/// );
+///
+/// let parsed = jrpc::parse_many::<Request<String, Value>>(buffer);
+/// assert_eq!(parsed.len(), 4);
+/// assert_eq!(parsed[0].result.as_ref().unwrap().method, "one");
+/// assert_eq!(parsed[0].span, 0..44);
+/// assert_eq!(parsed[1].result.as_ref().unwrap().method, "two");
+/// assert_eq!(&buffer[parsed[2].span.clone()], r#"{"not": "a request"}"#);
+/// assert_eq!(
+///     parsed[2].result.as_ref().unwrap_err().error.code,
+///     ErrorCode::InvalidRequest,
+/// );
+/// assert_eq!(&buffer[parsed[3].span.clone()], r#"{"jsonrpc":"#);
+/// assert_eq!(
+///     parsed[3].result.as_ref().unwrap_err().error.code,
+///     ErrorCode::ParseError,
+/// );
+/// # }
+/// ```
+pub fn parse_many<T>(buffer: &str) -> Vec<Parsed<T>>
+where
+    T: DeserializeOwned,
+{
+    let mut out = Vec::new();
+    let mut stream = serde_json::Deserializer::from_str(buffer).into_iter::<Value>();
+    loop {
+        let start = stream.byte_offset();
+        let start = start + (buffer.len() - start - buffer[start..].trim_start().len());
+        match stream.next() {
+            None => break,
+            Some(Ok(value)) => {
+                let result = serde_json::from_value(value).map_err(|err| {
+                    Error::new(Id::Null, ErrorCode::InvalidRequest, err.to_string(), None)
+                });
+                out.push(Parsed {
+                    span: start..stream.byte_offset(),
+                    result: result,
+                });
+            }
+            Some(Err(err)) => {
+                let end = start + buffer[start..].trim_end().len();
+                out.push(Parsed {
+                    span: start..end,
+                    result: Err(Error::new(
+                        Id::Null,
+                        ErrorCode::ParseError,
+                        err.to_string(),
+                        None,
+                    )),
+                });
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// The Response is either:
+/// - a jsonrpc Success (with a result of a specific type)
+/// - a Error (with an error `data` of type `E`, `serde_json::Value` by default).
+///
+/// A single `serde_json::from_str` handles both success and error payloads. The spec forbids a
+/// Response with both a `result` and an `error` member, even if one of them is `null`, so it is
+/// rejected instead of picking one of them. For peers which send both anyway, use
+/// [`from_str_prefer_error`](#method.from_str_prefer_error).
 ///
 /// # Example
 ///
@@ -596,26 +1355,27 @@ where
 /// let json = json.replace("\n", "").replace(" ", "");
 /// let result = serde_json::to_string(&example).unwrap();
 /// assert_eq!(json, result);
+///
+/// // Errors with typed `data`.
+/// let json = r#"{"jsonrpc": "2.0", "error": {"code": -32000, "message": "BadIndexes", "data": [4]}, "id": 4}"#;
+/// let response: Response<Vec<u32>, Vec<u32>> = serde_json::from_str(json).unwrap();
+/// assert_eq!(response.id(), &Id::from(4));
+/// assert_eq!(response.into_result().unwrap_err().data, Some(vec![4]));
+///
+/// let json = r#"{"jsonrpc": "2.0", "result": null, "error": {"code": -32000, "message": "Oops"}, "id": 4}"#;
+/// assert!(serde_json::from_str::<Response<Vec<u32>>>(json).is_err());
 /// # }
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 #[serde(untagged)]
-pub enum Response<T> {
+pub enum Response<T, E = Value> {
     /// The Response has a `result` object and not an `error` object.
     Ok(Success<T>),
     /// The Response has a `error` object and not an `result` object.
-    Err(Error<Value>),
+    Err(Error<E>),
 }
 
-impl<T: Serialize + DeserializeOwned> Response<T> {
-    /// Retrieve the `id` regardless of whether there was an error or not.
-    pub fn id(&self) -> &Id {
-        match *self {
-            Response::Ok(ref r) => &r.id,
-            Response::Err(ref e) => &e.id,
-        }
-    }
-
+impl<T: Serialize + DeserializeOwned> Response<T, Value> {
     /// Construct a `Success`
     pub fn success(id: Id, result: T) -> Self {
         Response::Ok(Success::new(id, result))
@@ -629,16 +1389,167 @@ impl<T: Serialize + DeserializeOwned> Response<T> {
     {
         Response::Err(Error::new(id, code, message, data))
     }
+}
+
+impl<T: Serialize + DeserializeOwned, E: Serialize + DeserializeOwned> Response<T, E> {
+    /// Retrieve the `id` regardless of whether there was an error or not.
+    pub fn id(&self) -> &Id {
+        match *self {
+            Response::Ok(ref r) => &r.id,
+            Response::Err(ref e) => &e.id,
+        }
+    }
+
+    /// Whether the Response has a `result` object.
+    pub fn is_ok(&self) -> bool {
+        match *self {
+            Response::Ok(_) => true,
+            Response::Err(_) => false,
+        }
+    }
+
+    /// Whether the Response has an `error` object.
+    pub fn is_err(&self) -> bool {
+        !self.is_ok()
+    }
+
+    /// Convert into the `result`, or the `error` object.
+    pub fn into_result(self) -> Result<T, ErrorObject<E>> {
+        match self {
+            Response::Ok(success) => Ok(success.result),
+            Response::Err(error) => Err(error.error),
+        }
+    }
 
     /// Helper to serialize the Response as json.
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 
+    /// Serialize the Response as indented json with a stable member order (`jsonrpc`, `id`, then `result` or `error`).
+    ///
+    /// See [`Request::to_string_pretty_ordered`](struct.Request.html#method.to_string_pretty_ordered).
+    pub fn to_string_pretty_ordered(&self) -> String {
+        serialize::to_string_pretty_ordered(self)
+    }
+
     /// Helper to deserialize the Response from json.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> serde_json::Result<T> {
         serde_json::from_str(s)
     }
+
+    /// Deserialize a Response from a peer which sends both `result` and `error`, e.g. in the
+    /// style of JSON-RPC 1.0, keeping the `error` unless it is `null`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::{ErrorCode, Response};
+    ///
+    /// # fn main() {
+    /// let json = r#"{"jsonrpc": "2.0", "result": null, "error": {"code": -32000, "message": "Oops"}, "id": 4}"#;
+    /// let response = Response::<u32>::from_str_prefer_error(json).unwrap();
+    /// assert_eq!(response.into_result().unwrap_err().code, ErrorCode::ServerError(-32000));
+    ///
+    /// let json = r#"{"jsonrpc": "2.0", "result": 7, "error": null, "id": 4}"#;
+    /// let response = Response::<u32>::from_str_prefer_error(json).unwrap();
+    /// assert_eq!(response.into_result(), Ok(7));
+    /// # }
+    /// ```
+    pub fn from_str_prefer_error(s: &str) -> serde_json::Result<Self> {
+        let mut value: Value = serde_json::from_str(s)?;
+        if let Value::Object(ref mut object) = value {
+            if object.contains_key("result") {
+                match object.get("error") {
+                    Some(&Value::Null) => object.remove("error"),
+                    Some(_) => object.remove("result"),
+                    None => None,
+                };
+            }
+        }
+        serde_json::from_value(value)
+    }
+}
+
+impl<'de, T, E> Deserialize<'de> for Response<T, E>
+where
+    Success<T>: DeserializeOwned,
+    Error<E>: DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let (has_result, has_error) = match value {
+            Value::Object(ref object) => {
+                (object.contains_key("result"), object.contains_key("error"))
+            }
+            _ => return Err(de::Error::custom("a Response must be an Object")),
+        };
+        match (has_result, has_error) {
+            (true, false) => serde_json::from_value(value).map(Response::Ok),
+            (false, true) => serde_json::from_value(value).map(Response::Err),
+            (true, true) => {
+                return Err(de::Error::custom(
+                    "a Response must not have both `result` and `error`",
+                ))
+            }
+            (false, false) => return Err(de::Error::missing_field("result")),
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+impl<T, E> Response<T, E> {
+    /// Convert the `result` with `f`, keeping the `id`. Errors are returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::{Error, ErrorCode, Id, Response, Value};
+    ///
+    /// # fn main() {
+    /// let response: Response<u32> = Response::success(Id::from(1), 41);
+    /// let response = response.map_result(|n| n + 1);
+    /// assert_eq!(response.into_result().unwrap(), 42);
+    ///
+    /// let error = Error::new(Id::from(2), ErrorCode::InternalError, "Oops", Some(7));
+    /// let response: Response<Value, u32> = Response::Err(error);
+    /// let response = response.map_err_data(|code| format!("E{}", code));
+    /// assert_eq!(response.into_result().unwrap_err().data, Some("E7".to_string()));
+    /// # }
+    /// ```
+    pub fn map_result<U, F>(self, f: F) -> Response<U, E>
+    where
+        F: FnOnce(T) -> U,
+    {
+        match self {
+            Response::Ok(success) => Response::Ok(Success {
+                jsonrpc: V2_0,
+                result: f(success.result),
+                id: success.id,
+            }),
+            Response::Err(error) => Response::Err(error),
+        }
+    }
+
+    /// Convert the `data` of the `error` with `f`, keeping the `id`, `code` and `message`.
+    /// Successes are returned unchanged.
+    pub fn map_err_data<D, F>(self, f: F) -> Response<T, D>
+    where
+        F: FnOnce(E) -> D,
+    {
+        match self {
+            Response::Ok(success) => Response::Ok(success),
+            Response::Err(error) => Response::Err(Error {
+                jsonrpc: V2_0,
+                error: error.error.map_data(f),
+                id: error.id,
+            }),
+        }
+    }
 }
 
 /// The jsonrpc Success response, indicating a successful result.
@@ -667,7 +1578,7 @@ impl<T: Serialize + DeserializeOwned> Response<T> {
 /// assert_eq!(json, result);
 /// # }
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Success<T> {
     /// A String specifying the version of the JSON-RPC protocol. MUST be exactly "2.0".
@@ -696,11 +1607,20 @@ impl<T: Serialize + DeserializeOwned> Success<T> {
     }
 
     /// Helper to serialize the Success as json.
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 
+    /// Serialize the Success as indented json with a stable member order (`jsonrpc`, `id`, `result`).
+    ///
+    /// See [`Request::to_string_pretty_ordered`](struct.Request.html#method.to_string_pretty_ordered).
+    pub fn to_string_pretty_ordered(&self) -> String {
+        serialize::to_string_pretty_ordered(self)
+    }
+
     /// Helper to deserialize the Success from json.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> serde_json::Result<T> {
         serde_json::from_str(s)
     }
@@ -757,7 +1677,7 @@ impl<T: Serialize + DeserializeOwned> Success<T> {
 /// assert_eq!(data, result);
 /// # }
 /// ```
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Error<T> {
     /// Always "2.0"
@@ -786,22 +1706,110 @@ impl<T: Serialize + DeserializeOwned> Error<T> {
         }
     }
 
+    fn standard(id: Id, code: ErrorCode, data: Option<T>) -> Self {
+        Error::new(id, code, scrub::generic_message(code), data)
+    }
+
+    /// The `ParseError` error, with a Null `id`.
+    pub fn parse_error() -> Self {
+        Error::standard(Id::Null, ErrorCode::ParseError, None)
+    }
+
+    /// The `InvalidRequest` error, with a Null `id`.
+    pub fn invalid_request() -> Self {
+        Error::standard(Id::Null, ErrorCode::InvalidRequest, None)
+    }
+
+    /// The `MethodNotFound` error.
+    pub fn method_not_found(id: Id) -> Self {
+        Error::standard(id, ErrorCode::MethodNotFound, None)
+    }
+
+    /// The `InvalidParams` error with the schema violations as `data`.
+    pub fn invalid_params(id: Id, data: Option<T>) -> Self {
+        Error::standard(id, ErrorCode::InvalidParams, data)
+    }
+
+    /// The `InternalError` error.
+    pub fn internal_error(id: Id, data: Option<T>) -> Self {
+        Error::standard(id, ErrorCode::InternalError, data)
+    }
+
+    /// A server error.
+    ///
+    /// Fails with
+    /// [`SpecViolation::ServerErrorCode`](strict/enum.SpecViolation.html#variant.ServerErrorCode)
+    /// if `code` is not within -32099 and -32000, the range the spec reserves for server errors.
+    /// Use [`new`](#method.new) for application defined codes.
+    pub fn server_error<S: Into<String>>(
+        id: Id,
+        code: i64,
+        message: S,
+        data: Option<T>,
+    ) -> Result<Self, strict::SpecViolation> {
+        if !(-32099..=-32000).contains(&code) {
+            return Err(strict::SpecViolation::ServerErrorCode(code));
+        }
+        Ok(Error::new(id, ErrorCode::ServerError(code), message, data))
+    }
+
     /// Helper to serialize the Error as json.
-    pub fn to_string(&self) -> String {
+    ///
+    /// Unlike formatting it with `Display`, which describes the error to humans.
+    pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 
+    /// Helper to serialize the Error as json.
+    ///
+    /// This shadows `ToString`, so it still returns the json and not the `Display` text. It will
+    /// be removed in a future release, after which `to_string` describes the error like
+    /// `Display`: use [`to_json`](#method.to_json) for the json.
+    #[deprecated(note = "use `to_json` for the json, or `Display` to describe the error")]
+    #[allow(clippy::inherent_to_string, clippy::inherent_to_string_shadow_display)]
+    pub fn to_string(&self) -> String {
+        self.to_json()
+    }
+
+    /// Serialize the Error as indented json with a stable member order (`jsonrpc`, `id`, `error`).
+    ///
+    /// See [`Request::to_string_pretty_ordered`](struct.Request.html#method.to_string_pretty_ordered).
+    pub fn to_string_pretty_ordered(&self) -> String {
+        serialize::to_string_pretty_ordered(self)
+    }
+
     /// Helper to deserialize the Error from json.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> serde_json::Result<T> {
         serde_json::from_str(s)
     }
 }
 
+impl<T> From<(Id, ErrorObject<T>)> for Error<T> {
+    /// Create the Error answering the Request `id`.
+    fn from((id, error): (Id, ErrorObject<T>)) -> Self {
+        error.into_error(id)
+    }
+}
+
+/// Formats the error object, e.g. `Invalid params (-32602): "missing field `x`"`.
+///
+/// This is not the json of the Error, which [`to_json`](struct.Error.html#method.to_json)
+/// returns. The deprecated inherent `Error::to_string` also still returns the json, unlike
+/// `ToString` through this impl (e.g. `format!("{}", error)` or `&dyn std::error::Error`).
+impl<T: fmt::Display> fmt::Display for Error<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> error::Error for Error<T> {}
+
 /// The jsonrpc Error object, with details of the error.
 ///
 /// When a rpc call encounters an error, the Response Object MUST contain the error member with a
 /// value that is a Object. See the attributes for details.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ErrorObject<T> {
     /// The error code. See [`ErrorCode`](enum.ErrorCode.html)
     pub code: ErrorCode,
@@ -813,14 +1821,227 @@ pub struct ErrorObject<T> {
 
     /// A Primitive or Structured value that contains additional information about the error.
     ///
-    /// This may be omitted.
+    /// This may be omitted: a missing `data` deserializes to `None`, and `None` is not
+    /// serialized.
     ///
     /// The value of this member is defined by the Server (e.g. detailed error
     /// information, nested errors etc.).
-    #[serde(default = "default_t")]
+    #[serde(default = "default_t", skip_serializing_if = "Option::is_none")]
     pub data: Option<T>,
 }
 
+impl<T> ErrorObject<T> {
+    /// Create an error object, failing with
+    /// [`SpecViolation::ReservedCode`](strict/enum.SpecViolation.html#variant.ReservedCode) if
+    /// the code is reserved by the spec without being defined by it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::{ErrorCode, ErrorObject, Value};
+    ///
+    /// # fn main() {
+    /// let error = ErrorObject::<Value>::new(-32001, "Busy", None).unwrap();
+    /// assert_eq!(error.code, ErrorCode::ServerError(-32001));
+    /// // Application defined codes are outside of the reserved range.
+    /// assert!(ErrorObject::<Value>::new(42, "Expired", None).is_ok());
+    /// assert!(ErrorObject::<Value>::new(-32500, "Oops", None).is_err());
+    /// # }
+    /// ```
+    pub fn new<C, S>(code: C, message: S, data: Option<T>) -> Result<Self, strict::SpecViolation>
+    where
+        C: Into<ErrorCode>,
+        S: Into<String>,
+    {
+        let error = ErrorObject {
+            code: code.into(),
+            message: message.into(),
+            data: data,
+        };
+        error.validate()?;
+        Ok(error)
+    }
+
+    /// Convert the `data` with `f`, keeping the `code` and `message`.
+    pub fn map_data<D, F>(self, f: F) -> ErrorObject<D>
+    where
+        F: FnOnce(T) -> D,
+    {
+        ErrorObject {
+            code: self.code,
+            message: self.message,
+            data: self.data.map(f),
+        }
+    }
+
+    /// Convert into an [`Error`](struct.Error.html) answering the Request with `id`.
+    pub fn into_error(self, id: Id) -> Error<T> {
+        Error {
+            jsonrpc: V2_0,
+            error: self,
+            id: id,
+        }
+    }
+
+    /// Convert the `data` with the fallible `f`, keeping the `code` and `message`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::{ErrorCode, ErrorObject};
+    ///
+    /// # fn main() {
+    /// let error = ErrorObject::new(ErrorCode::InvalidParams, "Bad port", Some("80a")).unwrap();
+    /// let error = error.try_map_data(|port| port.parse::<u16>());
+    /// assert!(error.is_err());
+    /// # }
+    /// ```
+    pub fn try_map_data<D, X, F>(self, f: F) -> Result<ErrorObject<D>, X>
+    where
+        F: FnOnce(T) -> Result<D, X>,
+    {
+        let data = match self.data {
+            Some(data) => Some(f(data)?),
+            None => None,
+        };
+        Ok(ErrorObject {
+            code: self.code,
+            message: self.message,
+            data: data,
+        })
+    }
+}
+
+/// Formats the `message`, the `code` and the `data`, if any.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// use jrpc::{ErrorCode, ErrorObject, Value};
+///
+/// # fn main() {
+/// let error = ErrorObject::new(ErrorCode::InvalidParams, "Invalid params", None::<Value>).unwrap();
+/// assert_eq!(error.to_string(), "Invalid params (-32602)");
+/// let error = ErrorObject::new(ErrorCode::InvalidParams, "Invalid params", Some("missing field `x`")).unwrap();
+/// assert_eq!(error.to_string(), "Invalid params (-32602): missing field `x`");
+/// # }
+/// ```
+impl<T: fmt::Display> fmt::Display for ErrorObject<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code.code())?;
+        match self.data {
+            Some(ref data) => write!(f, ": {}", data),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> error::Error for ErrorObject<T> {}
+
+impl ErrorObject<Value> {
+    /// Deserialize the `data` as `D`, e.g. once the `code` told which type to expect.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// extern crate serde_json;
+    /// use jrpc::{ErrorCode, ErrorDetails, ErrorObject, Value};
+    ///
+    /// # fn main() {
+    /// let json = r#"{"code": -32602, "message": "Invalid params", "data": {"field": "port", "reason": "not a number"}}"#;
+    /// let error: ErrorObject<Value> = serde_json::from_str(json).unwrap();
+    /// if error.code == ErrorCode::InvalidParams {
+    ///     let error = error.decode_data::<ErrorDetails>().unwrap();
+    ///     let details = error.data.unwrap();
+    ///     assert_eq!(details.field.as_ref().map(String::as_str), Some("port"));
+    ///     assert!(!details.retryable);
+    /// }
+    /// # }
+    /// ```
+    pub fn decode_data<D: DeserializeOwned>(self) -> serde_json::Result<ErrorObject<D>> {
+        self.try_map_data(serde_json::from_value)
+    }
+}
+
+impl ErrorObject<ErrorDetails> {
+    /// Create an error object with structured details. Fails like
+    /// [`new`](struct.ErrorObject.html#method.new).
+    pub fn with_details<C, S>(
+        code: C,
+        message: S,
+        details: ErrorDetails,
+    ) -> Result<Self, strict::SpecViolation>
+    where
+        C: Into<ErrorCode>,
+        S: Into<String>,
+    {
+        ErrorObject::new(code, message, Some(details))
+    }
+}
+
+/// Structured `data` for the common errors: which field was wrong, why, and whether the call
+/// may succeed when retried.
+///
+/// A missing `field` or `retryable` deserializes to `None` and `false`, and neither is serialized
+/// then.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// extern crate serde_json;
+/// use jrpc::{ErrorCode, ErrorDetails, ErrorObject};
+///
+/// # fn main() {
+/// let details = ErrorDetails::new("not a number").field("port");
+/// let error = ErrorObject::with_details(ErrorCode::InvalidParams, "Invalid params", details).unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&error).unwrap(),
+///     r#"{"code":-32602,"message":"Invalid params","data":{"field":"port","reason":"not a number"}}"#,
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    /// The params field the error is about, e.g. a json pointer or a field name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+
+    /// Why the call failed.
+    pub reason: String,
+
+    /// Whether the same call may succeed when retried.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub retryable: bool,
+}
+
+impl ErrorDetails {
+    /// Create details with the reason.
+    pub fn new<S: Into<String>>(reason: S) -> Self {
+        ErrorDetails {
+            field: None,
+            reason: reason.into(),
+            retryable: false,
+        }
+    }
+
+    /// Set the field the error is about.
+    pub fn field<S: Into<String>>(mut self, field: S) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// Set whether the call may succeed when retried.
+    pub fn retryable(mut self, retryable: bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+}
+
 /// A Number that indicates the error type that occurred.
 /// This MUST be an integer.
 ///
@@ -844,17 +2065,55 @@ pub enum ErrorCode {
     /// - `-32603`: Internal error. Internal JSON-RPC error.
     InternalError,
     /// - `-32000 to -32099`: Server error. Reserved for implementation-defined server-errors.
+    ///
+    /// Codes outside of the reserved range, i.e. application defined codes, are also
+    /// represented by this variant.
     ServerError(i64),
+    /// - The other codes from `-32768` to `-32000`: reserved by the spec for future use.
+    ///
+    /// These are not [valid](enum.ErrorCode.html#method.is_valid). Peers which send them anyway,
+    /// e.g. `-32700` to `-32604` for their own parse errors, are classified here instead of
+    /// being mistaken for server errors.
+    Reserved(i64),
 }
 
 impl ErrorCode {
+    /// The numeric value of the code, the reverse of `From<i64>`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate jrpc;
+    /// use jrpc::ErrorCode;
+    ///
+    /// # fn main() {
+    /// assert_eq!(ErrorCode::InvalidParams.code(), -32602);
+    /// for &code in &[-32700, -32650, -32100, -32001, -32800, 409] {
+    ///     assert_eq!(ErrorCode::from(code).code(), code);
+    /// }
+    /// assert_eq!(ErrorCode::from(-32650), ErrorCode::Reserved(-32650));
+    /// assert_eq!(ErrorCode::from(-32001), ErrorCode::ServerError(-32001));
+    /// # }
+    /// ```
+    pub fn code(&self) -> i64 {
+        match *self {
+            ErrorCode::ParseError => -32700,
+            ErrorCode::InvalidRequest => -32600,
+            ErrorCode::MethodNotFound => -32601,
+            ErrorCode::InvalidParams => -32602,
+            ErrorCode::InternalError => -32603,
+            ErrorCode::ServerError(value) | ErrorCode::Reserved(value) => value,
+        }
+    }
+
     /// Return whether the ErrorCode is correct.
     ///
-    /// This will only return `false` if this is `ServerError` and is outside of the range of -32000
-    /// to -32099.
+    /// This will only return `false` if this is `Reserved`, or `ServerError` and is outside of the
+    /// range of -32000 to -32099.
     pub fn is_valid(&self) -> bool {
         match *self {
-            ErrorCode::ServerError(value) => (-32099 <= value) && (value <= -32000),
+            ErrorCode::ServerError(value) => (-32099..=-32000).contains(&value),
+            ErrorCode::Reserved(_) => false,
             _ => true,
         }
     }
@@ -868,6 +2127,8 @@ impl From<i64> for ErrorCode {
             -32601 => ErrorCode::MethodNotFound,
             -32602 => ErrorCode::InvalidParams,
             -32603 => ErrorCode::InternalError,
+            -32099..=-32000 => ErrorCode::ServerError(v),
+            -32768..=-32000 => ErrorCode::Reserved(v),
             _ => ErrorCode::ServerError(v),
         }
     }
@@ -880,3 +2141,7 @@ fn notification() -> IdReq {
 fn default_t<T>() -> Option<T> {
     None
 }
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
@@ -0,0 +1,274 @@
+//! Checking method names against naming rules.
+//!
+//! [`NamingRules`](struct.NamingRules.html) describes an API's naming standard: the namespace
+//! separator, the case of every segment between separators, reserved prefixes and a maximum
+//! length. Checking a whole [`Contract`](../contract/struct.Contract.html) when the router is
+//! built rejects non-conforming methods before they are ever called. Like
+//! [`Config`](../config/struct.Config.html), the rules are a serde struct with defaults.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::lint::{Case, LintError, NamingRules};
+//!
+//! # fn main() {
+//! let rules = NamingRules::default().case(Case::Camel).reserved_prefix("internal.");
+//!
+//! assert!(rules.check("accounts.getBalance").is_empty());
+//! assert_eq!(
+//!     rules.check("accounts.get_balance"),
+//!     vec![LintError::Case { segment: "get_balance".to_string(), expected: Case::Camel }],
+//! );
+//! assert_eq!(
+//!     rules.check("rpc.discover"),
+//!     vec![LintError::ReservedPrefix { prefix: "rpc.".to_string() }],
+//! );
+//!
+//! let findings = rules.check_all(vec!["users.list", "internal.gc", "users..get"]);
+//! assert_eq!(findings.len(), 2);
+//! assert_eq!(findings[0].method, "internal.gc");
+//! assert_eq!(findings[1].to_string(), "users..get: empty segment");
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use std_prelude::*;
+
+use contract::Contract;
+
+/// The case of the segments of a method name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Case {
+    /// Any case.
+    Any,
+    /// `camelCase`: ascii letters and digits, starting with a lowercase letter.
+    Camel,
+    /// `PascalCase`: ascii letters and digits, starting with an uppercase letter.
+    Pascal,
+    /// `snake_case`: lowercase ascii letters and digits, separated by single `_`.
+    Snake,
+    /// `kebab-case`: lowercase ascii letters and digits, separated by single `-`.
+    Kebab,
+}
+
+impl Case {
+    /// Whether `segment` is in this case.
+    pub fn matches(&self, segment: &str) -> bool {
+        let first = match segment.chars().next() {
+            Some(c) => c,
+            None => return false,
+        };
+        let alphanumeric = |s: &str| s.chars().all(|c| c.is_ascii_alphanumeric());
+        let words = |sep: char| {
+            first.is_ascii_lowercase()
+                && segment.split(sep).all(|word| {
+                    !word.is_empty()
+                        && word
+                            .chars()
+                            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+                })
+        };
+        match *self {
+            Case::Any => true,
+            Case::Camel => first.is_ascii_lowercase() && alphanumeric(segment),
+            Case::Pascal => first.is_ascii_uppercase() && alphanumeric(segment),
+            Case::Snake => words('_'),
+            Case::Kebab => words('-'),
+        }
+    }
+}
+
+impl fmt::Display for Case {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Case::Any => "any case",
+            Case::Camel => "camelCase",
+            Case::Pascal => "PascalCase",
+            Case::Snake => "snake_case",
+            Case::Kebab => "kebab-case",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A rule broken by a method name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum LintError {
+    /// The name is empty.
+    Empty,
+    /// The name is longer than `max`, in bytes.
+    TooLong {
+        /// The length of the name.
+        len: usize,
+        /// The maximum length.
+        max: usize,
+    },
+    /// The name starts with a reserved prefix.
+    ReservedPrefix {
+        /// The prefix.
+        prefix: String,
+    },
+    /// The name has an empty segment, i.e. two consecutive separators.
+    EmptySegment,
+    /// A segment is not in the expected case.
+    Case {
+        /// The segment.
+        segment: String,
+        /// The expected case.
+        expected: Case,
+    },
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LintError::Empty => write!(f, "empty name"),
+            LintError::TooLong { len, max } => {
+                write!(f, "{} bytes long, the maximum is {}", len, max)
+            }
+            LintError::ReservedPrefix { ref prefix } => {
+                write!(f, "the prefix `{}` is reserved", prefix)
+            }
+            LintError::EmptySegment => write!(f, "empty segment"),
+            LintError::Case {
+                ref segment,
+                expected,
+            } => write!(f, "`{}` is not {}", segment, expected),
+        }
+    }
+}
+
+impl error::Error for LintError {}
+
+/// A method name which breaks a rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Finding {
+    /// The method name.
+    pub method: String,
+
+    /// The broken rule.
+    pub error: LintError,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.method, self.error)
+    }
+}
+
+/// The naming rules of an API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamingRules {
+    /// The separator between the segments of a namespaced name. `None` checks the whole name as
+    /// a single segment. Default: `"."`.
+    pub separator: Option<String>,
+
+    /// The case of every segment. Default: any.
+    pub case: Case,
+
+    /// Prefixes which must not be used. Default: `rpc.`, which the spec reserves for system
+    /// extensions.
+    pub reserved_prefixes: Vec<String>,
+
+    /// The maximum length of a name, in bytes. Default: 128.
+    pub max_length: usize,
+}
+
+impl Default for NamingRules {
+    fn default() -> NamingRules {
+        NamingRules {
+            separator: Some(".".to_string()),
+            case: Case::Any,
+            reserved_prefixes: vec!["rpc.".to_string()],
+            max_length: 128,
+        }
+    }
+}
+
+impl NamingRules {
+    /// Set the separator.
+    pub fn separator<S: Into<String>>(mut self, separator: Option<S>) -> Self {
+        self.separator = separator.map(Into::into);
+        self
+    }
+
+    /// Set the case.
+    pub fn case(mut self, case: Case) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Add a reserved prefix.
+    pub fn reserved_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.reserved_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Set the maximum length.
+    pub fn max_length(mut self, max: usize) -> Self {
+        self.max_length = max;
+        self
+    }
+
+    /// Check a method name, returning every rule it breaks.
+    pub fn check(&self, name: &str) -> Vec<LintError> {
+        if name.is_empty() {
+            return vec![LintError::Empty];
+        }
+        let mut errors = Vec::new();
+        if name.len() > self.max_length {
+            errors.push(LintError::TooLong {
+                len: name.len(),
+                max: self.max_length,
+            });
+        }
+        if let Some(prefix) = self.reserved_prefixes.iter().find(|p| name.starts_with(&p[..])) {
+            errors.push(LintError::ReservedPrefix {
+                prefix: prefix.clone(),
+            });
+        }
+        let segments: Vec<&str> = match self.separator {
+            Some(ref sep) if !sep.is_empty() => name.split(&sep[..]).collect(),
+            _ => vec![name],
+        };
+        if segments.iter().any(|s| s.is_empty()) {
+            errors.push(LintError::EmptySegment);
+        }
+        for segment in segments {
+            if !segment.is_empty() && !self.case.matches(segment) {
+                errors.push(LintError::Case {
+                    segment: segment.to_string(),
+                    expected: self.case,
+                });
+            }
+        }
+        errors
+    }
+
+    /// Check method names, in order.
+    pub fn check_all<'a, I>(&self, names: I) -> Vec<Finding>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        names
+            .into_iter()
+            .flat_map(|name| {
+                self.check(name).into_iter().map(move |error| Finding {
+                    method: name.to_string(),
+                    error: error,
+                })
+            })
+            .collect()
+    }
+
+    /// Check the methods of a contract.
+    pub fn check_contract(&self, contract: &Contract) -> Vec<Finding> {
+        self.check_all(contract.methods.iter().map(|m| m.name.as_str()))
+    }
+}
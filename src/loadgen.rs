@@ -0,0 +1,222 @@
+//! Synthetic traffic generation for load and soak testing.
+//!
+//! This module produces serialized request frames which can be fed directly into a benchmark
+//! harness or the read side of a server under test. It never touches the network: the caller is
+//! responsible for sending the frames wherever they need to go.
+//!
+//! Generation is fully deterministic for a given seed, so a failing soak test can be replayed
+//! exactly.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::loadgen::LoadGen;
+//!
+//! # fn main() {
+//! let gen = || {
+//!     LoadGen::new(42)
+//!         .method("ReadFoo", 9, Some(json!([1, 2])))
+//!         .method("WriteFoo", 1, None)
+//!         .requests(100)
+//!         .batch_ramp(1, 10)
+//! };
+//!
+//! let frames: Vec<_> = gen().frames().collect();
+//! assert_eq!(frames.iter().map(|f| f.requests).sum::<usize>(), 100);
+//! assert_eq!(frames[0].requests, 1);
+//! assert!(frames[0].payload.starts_with('{'));
+//! assert!(frames.last().unwrap().payload.starts_with('['));
+//!
+//! // The same seed always produces the same traffic.
+//! let again: Vec<_> = gen().frames().collect();
+//! assert_eq!(frames, again);
+//! # }
+//! ```
+
+use serde_json;
+use std_prelude::*;
+
+use {Id, IdReq, Request, Value, V2_0};
+
+/// A small, seedable pseudo random number generator (splitmix64).
+///
+/// This is **not** cryptographically secure. It exists so that synthetic traffic is reproducible
+/// without pulling in a dependency.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a new generator from the given seed.
+    pub fn new(seed: u64) -> Self {
+        SeededRng { state: seed }
+    }
+
+    /// Return the next pseudo random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Return a pseudo random value in the range `0..bound`.
+    ///
+    /// Returns `0` if `bound == 0`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        self.next_u64() % bound
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Weighted {
+    method: String,
+    weight: u64,
+    params: Option<Value>,
+}
+
+/// Builder for a stream of synthetic request frames.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Debug, Clone)]
+pub struct LoadGen {
+    rng: SeededRng,
+    mix: Vec<Weighted>,
+    total_weight: u64,
+    requests: usize,
+    ramp: (usize, usize),
+}
+
+impl LoadGen {
+    /// Create a new generator with the given seed.
+    ///
+    /// By default it will generate 1000 requests in frames containing a single request each.
+    pub fn new(seed: u64) -> Self {
+        LoadGen {
+            rng: SeededRng::new(seed),
+            mix: Vec::new(),
+            total_weight: 0,
+            requests: 1000,
+            ramp: (1, 1),
+        }
+    }
+
+    /// Add a method to the mix.
+    ///
+    /// Each request picks a method with probability `weight / sum(weights)`. Methods with a
+    /// weight of `0` are never picked.
+    pub fn method<S>(mut self, method: S, weight: u32, params: Option<Value>) -> Self
+    where
+        S: Into<String>,
+    {
+        self.total_weight += u64::from(weight);
+        self.mix.push(Weighted {
+            method: method.into(),
+            weight: u64::from(weight),
+            params: params,
+        });
+        self
+    }
+
+    /// The total number of requests to generate.
+    pub fn requests(mut self, requests: usize) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Ramp the batch size linearly from `start` to `end` over the course of the run.
+    ///
+    /// A batch size of `1` produces a single Request object, larger sizes produce a batch (an
+    /// Array of Request objects). Sizes of `0` are treated as `1`.
+    pub fn batch_ramp(mut self, start: usize, end: usize) -> Self {
+        self.ramp = (start.max(1), end.max(1));
+        self
+    }
+
+    /// Consume the builder, returning an iterator over the generated frames.
+    ///
+    /// If no method has a non-zero weight no frames are generated.
+    pub fn frames(self) -> Frames {
+        Frames {
+            gen: self,
+            emitted: 0,
+        }
+    }
+
+    fn batch_size(&self, emitted: usize) -> usize {
+        let (start, end) = self.ramp;
+        let progress = emitted as f64 / self.requests as f64;
+        let size = start as f64 + (end as f64 - start as f64) * progress;
+        (size.round() as usize).max(1).min(self.requests - emitted)
+    }
+
+    fn pick(&mut self) -> &Weighted {
+        let mut roll = self.rng.below(self.total_weight);
+        for weighted in &self.mix {
+            if roll < weighted.weight {
+                return weighted;
+            }
+            roll -= weighted.weight;
+        }
+        unreachable!("roll is always below the total weight")
+    }
+}
+
+/// A single serialized frame, ready to be written to a transport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// The number of requests contained in the frame.
+    pub requests: usize,
+    /// The serialized json.
+    pub payload: String,
+}
+
+/// Iterator over generated [`Frame`](struct.Frame.html)s.
+///
+/// Created by [`LoadGen::frames`](struct.LoadGen.html#method.frames).
+#[derive(Debug, Clone)]
+pub struct Frames {
+    gen: LoadGen,
+    emitted: usize,
+}
+
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.emitted >= self.gen.requests || self.gen.total_weight == 0 {
+            return None;
+        }
+        let size = self.gen.batch_size(self.emitted);
+        let mut batch = Vec::with_capacity(size);
+        for _ in 0..size {
+            let id = Id::Int(self.emitted as i64);
+            let weighted = self.gen.pick().clone();
+            batch.push(Request {
+                jsonrpc: V2_0,
+                method: weighted.method,
+                params: weighted.params,
+                id: IdReq::from(id),
+            });
+            self.emitted += 1;
+        }
+
+        let payload = if batch.len() == 1 {
+            serde_json::to_string(&batch[0])
+        } else {
+            serde_json::to_string(&batch)
+        };
+        Some(Frame {
+            requests: size,
+            payload: payload.expect("requests are always serializable"),
+        })
+    }
+}
@@ -0,0 +1,118 @@
+//! Approximate memory accounting of the crate's buffers.
+//!
+//! The buffers retaining messages (the [`ResponseJournal`](../journal/struct.ResponseJournal.html),
+//! [`PollBuffer`](../poll/struct.PollBuffer.html), [`ResumeState`](../resume/struct.ResumeState.html)
+//! and [`Dedup`](../webhook/struct.Dedup.html)) and the
+//! [`TenantRouter`](../tenant/struct.TenantRouter.html) implement
+//! [`MemoryUsage`](trait.MemoryUsage.html). The estimates are the sizes of the retained data
+//! (strings and compact json), not of the allocator's bookkeeping, so leave some headroom.
+//!
+//! A [`MemoryBudget`](struct.MemoryBudget.html) sheds entries, oldest first, from the largest
+//! buffers until their total usage fits.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use std::time::Duration;
+//! use jrpc::{Id, Request, Response};
+//! use jrpc::journal::ResponseJournal;
+//! use jrpc::memory::{MemoryBudget, MemoryUsage};
+//! use jrpc::webhook::Dedup;
+//!
+//! # fn main() {
+//! let mut journal = ResponseJournal::new(1000, Duration::from_secs(600));
+//! let mut dedup = Dedup::new(1000);
+//! for i in 0..100 {
+//!     let request = Request::new(Id::from(format!("tx-{}", i)), "Transfer".to_string());
+//!     journal.record(&request, &Response::success(Id::from(1), "done".to_string()), i);
+//!     dedup.first(&format!("delivery-{}", i));
+//! }
+//!
+//! let budget = MemoryBudget::new(4096);
+//! assert!(journal.memory_usage() + dedup.memory_usage() > budget.limit());
+//! let shed = budget.enforce(&mut [&mut journal, &mut dedup]);
+//! assert!(shed.entries > 0);
+//! assert!(journal.memory_usage() + dedup.memory_usage() <= budget.limit());
+//! # }
+//! ```
+
+use std_prelude::*;
+
+/// A structure whose memory usage can be estimated, and which can shed its oldest entries.
+pub trait MemoryUsage {
+    /// The approximate number of bytes retained.
+    fn memory_usage(&self) -> usize;
+
+    /// Drop the oldest entry and return the approximate number of bytes freed, or `None` if
+    /// there is nothing which can be shed.
+    ///
+    /// The default never sheds, for structures whose entries are configuration.
+    fn shed_oldest(&mut self) -> Option<usize> {
+        None
+    }
+}
+
+/// What a [`MemoryBudget`](struct.MemoryBudget.html) shed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Shed {
+    /// The number of entries dropped.
+    pub entries: usize,
+
+    /// The approximate number of bytes freed.
+    pub bytes: usize,
+}
+
+/// A limit on the total memory usage of a set of buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    limit: usize,
+}
+
+impl MemoryBudget {
+    /// Create a budget of `limit` bytes.
+    pub fn new(limit: usize) -> Self {
+        MemoryBudget { limit: limit }
+    }
+
+    /// The limit, in bytes.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The total memory usage of `buffers`.
+    pub fn usage(&self, buffers: &[&mut dyn MemoryUsage]) -> usize {
+        buffers.iter().map(|b| b.memory_usage()).sum()
+    }
+
+    /// Whether `buffers` exceed the budget.
+    pub fn exceeded(&self, buffers: &[&mut dyn MemoryUsage]) -> bool {
+        self.usage(buffers) > self.limit
+    }
+
+    /// Shed the oldest entries of the largest buffers until the total usage of `buffers` fits
+    /// the budget, or nothing more can be shed.
+    pub fn enforce(&self, buffers: &mut [&mut dyn MemoryUsage]) -> Shed {
+        let mut shed = Shed::default();
+        let mut usage: Vec<usize> = buffers.iter().map(|b| b.memory_usage()).collect();
+        let mut sheddable = vec![true; buffers.len()];
+        while usage.iter().sum::<usize>() > self.limit {
+            let largest = (0..buffers.len())
+                .filter(|&i| sheddable[i])
+                .max_by_key(|&i| usage[i]);
+            let i = match largest {
+                Some(i) => i,
+                None => break,
+            };
+            match buffers[i].shed_oldest() {
+                Some(bytes) => {
+                    shed.entries += 1;
+                    shed.bytes += bytes;
+                    usage[i] = buffers[i].memory_usage();
+                }
+                None => sheddable[i] = false,
+            }
+        }
+        shed
+    }
+}
@@ -0,0 +1,228 @@
+//! Merkle commitments over batches, for audit logs.
+//!
+//! The tree follows [RFC 6962](https://tools.ietf.org/html/rfc6962#section-2.1) (Certificate
+//! Transparency): leaves are hashed as `H(0x00 || entry)` and interior nodes as
+//! `H(0x01 || left || right)`, and a tree of `n > 1` leaves is split after the largest power of
+//! two smaller than `n`. The leaf data is the compact json serialization of each entry, so the
+//! batch should be put in a deterministic order first, e.g. with
+//! [`canonical::sort`](../canonical/fn.sort.html).
+//!
+//! The hash function is supplied by the caller through [`Hasher`](trait.Hasher.html), which is
+//! implemented for any `Fn(&[u8]) -> Vec<u8>`. Use a cryptographic hash such as SHA-256.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use std::hash::Hasher as StdHasher;
+//! use std::collections::hash_map::DefaultHasher;
+//! use jrpc::{Id, Request};
+//! use jrpc::merkle;
+//!
+//! // NOT a cryptographic hash, only used to keep the example self contained.
+//! fn hash(data: &[u8]) -> Vec<u8> {
+//!     let mut hasher = DefaultHasher::new();
+//!     hasher.write(data);
+//!     hasher.finish().to_be_bytes().to_vec()
+//! }
+//!
+//! # fn main() {
+//! let batch: Vec<_> = (0..5)
+//!     .map(|i| Request::new(Id::from(i), "Log".to_string()))
+//!     .collect();
+//!
+//! let root = merkle::root(&hash, &batch);
+//!
+//! // Later, prove that the entry with id 3 was committed to.
+//! let proof = merkle::prove(&hash, &batch, 3).unwrap();
+//! assert!(proof.verify(&hash, &batch[3], &root));
+//! assert!(!proof.verify(&hash, &batch[2], &root));
+//! # }
+//! ```
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+/// A hash function.
+pub trait Hasher {
+    /// Hash `data`.
+    fn hash(&self, data: &[u8]) -> Vec<u8>;
+}
+
+impl<F: Fn(&[u8]) -> Vec<u8>> Hasher for F {
+    fn hash(&self, data: &[u8]) -> Vec<u8> {
+        self(data)
+    }
+}
+
+/// The data hashed for an entry: its compact json serialization.
+pub fn leaf_data<E: Serialize>(entry: &E) -> Vec<u8> {
+    serde_json::to_vec(entry).expect("batch entries are always serializable")
+}
+
+/// The leaf hash of an entry.
+pub fn leaf_hash<H: Hasher, E: Serialize>(hasher: &H, entry: &E) -> Vec<u8> {
+    let mut data = vec![0x00];
+    data.extend(leaf_data(entry));
+    hasher.hash(&data)
+}
+
+fn node_hash<H: Hasher>(hasher: &H, left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(1 + left.len() + right.len());
+    data.push(0x01);
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    hasher.hash(&data)
+}
+
+/// The largest power of two smaller than `n`, for `n > 1`.
+fn split(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+fn subtree_root<H: Hasher>(hasher: &H, leaves: &[Vec<u8>]) -> Vec<u8> {
+    match leaves.len() {
+        0 => hasher.hash(&[]),
+        1 => leaves[0].clone(),
+        n => {
+            let k = split(n);
+            let left = subtree_root(hasher, &leaves[..k]);
+            let right = subtree_root(hasher, &leaves[k..]);
+            node_hash(hasher, &left, &right)
+        }
+    }
+}
+
+fn path<H: Hasher>(hasher: &H, index: usize, leaves: &[Vec<u8>], out: &mut Vec<Vec<u8>>) {
+    let n = leaves.len();
+    if n <= 1 {
+        return;
+    }
+    let k = split(n);
+    if index < k {
+        path(hasher, index, &leaves[..k], out);
+        out.push(subtree_root(hasher, &leaves[k..]));
+    } else {
+        path(hasher, index - k, &leaves[k..], out);
+        out.push(subtree_root(hasher, &leaves[..k]));
+    }
+}
+
+/// Compute the Merkle root of a batch.
+pub fn root<H: Hasher, E: Serialize>(hasher: &H, batch: &[E]) -> Vec<u8> {
+    let leaves: Vec<_> = batch.iter().map(|e| leaf_hash(hasher, e)).collect();
+    subtree_root(hasher, &leaves)
+}
+
+/// Prove that the entry at `index` is part of the batch. Returns `None` if `index` is out of
+/// bounds.
+pub fn prove<H: Hasher, E: Serialize>(
+    hasher: &H,
+    batch: &[E],
+    index: usize,
+) -> Option<InclusionProof> {
+    if index >= batch.len() {
+        return None;
+    }
+    let leaves: Vec<_> = batch.iter().map(|e| leaf_hash(hasher, e)).collect();
+    let mut hashes = Vec::new();
+    path(hasher, index, &leaves, &mut hashes);
+    Some(InclusionProof {
+        index: index,
+        size: batch.len(),
+        path: hashes,
+    })
+}
+
+/// A proof that an entry is part of a batch with a known root.
+///
+/// Hashes are serialized as lowercase hex strings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    /// The index of the entry within the batch.
+    pub index: usize,
+
+    /// The number of entries in the batch.
+    pub size: usize,
+
+    /// The sibling hashes from the leaf up to the root.
+    #[serde(with = "hex")]
+    pub path: Vec<Vec<u8>>,
+}
+
+impl InclusionProof {
+    /// Verify that `entry` is part of the batch with the Merkle `root`.
+    pub fn verify<H: Hasher, E: Serialize>(&self, hasher: &H, entry: &E, root: &[u8]) -> bool {
+        self.verify_leaf(hasher, &leaf_hash(hasher, entry), root)
+    }
+
+    /// Like [`verify`](#method.verify), but from the entry's leaf hash.
+    pub fn verify_leaf<H: Hasher>(&self, hasher: &H, leaf_hash: &[u8], root: &[u8]) -> bool {
+        if self.index >= self.size {
+            return false;
+        }
+        let (mut f, mut s) = (self.index, self.size - 1);
+        let mut hash = leaf_hash.to_vec();
+        for sibling in &self.path {
+            if s == 0 {
+                return false;
+            }
+            if f & 1 == 1 || f == s {
+                hash = node_hash(hasher, sibling, &hash);
+                while f & 1 == 0 && f != 0 {
+                    f >>= 1;
+                    s >>= 1;
+                }
+            } else {
+                hash = node_hash(hasher, &hash, sibling);
+            }
+            f >>= 1;
+            s >>= 1;
+        }
+        s == 0 && hash == root
+    }
+}
+
+/// Lowercase hex strings, for `#[serde(with = "hex")]`.
+mod hex {
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::{SerializeSeq, Serializer};
+    use std_prelude::*;
+
+    fn encode(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn decode(text: &str) -> Option<Vec<u8>> {
+        if !text.len().is_multiple_of(2) || !text.is_ascii() {
+            return None;
+        }
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+            .collect()
+    }
+
+    pub fn serialize<S: Serializer>(hashes: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(hashes.len()))?;
+        for hash in hashes {
+            seq.serialize_element(&encode(hash))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|h| decode(h).ok_or_else(|| de::Error::custom("invalid hex")))
+            .collect()
+    }
+}
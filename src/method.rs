@@ -0,0 +1,303 @@
+//! Typed method descriptions.
+//!
+//! A [`Method`](trait.Method.html) ties a method name to its `params`, `result` and error `data`
+//! types, so that both clients and servers encode and decode them consistently.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{Error, Id, Response, Value};
+//! use jrpc::method::Method;
+//!
+//! struct Subtract;
+//!
+//! impl Method for Subtract {
+//!     type Params = (i64, i64);
+//!     type Result = i64;
+//!     /// The operand which overflowed.
+//!     type ErrorData = usize;
+//!
+//!     fn name() -> std::borrow::Cow<'static, str> {
+//!         "subtract".into()
+//!     }
+//! }
+//!
+//! # fn main() {
+//! // Client side.
+//! let request = Subtract::request(Id::from(1), (42, 23));
+//! let json = request.to_string();
+//!
+//! // Server side.
+//! let request = jrpc::parse_request::<String>(&json).unwrap();
+//! let request = Subtract::decode_params(request).unwrap();
+//! let (a, b) = request.params.unwrap();
+//! let response = Response::success(Id::from(1), Value::from(a - b));
+//!
+//! // Client side.
+//! assert_eq!(Subtract::decode_result(response).unwrap(), 19);
+//!
+//! let error = Error::new(Id::from(2), -32000, "Overflow", Some(Value::from(1)));
+//! let error = Subtract::decode_result(Response::Err(error)).unwrap_err();
+//! assert_eq!(error.data, Some(1));
+//! # }
+//! ```
+//!
+//! # Method names
+//!
+//! [`MethodName`](struct.MethodName.html) can be used as the method of a
+//! [`Request`](../struct.Request.html) instead of a `String`. Names known at compile time are
+//! stored without allocating, and so are names received from peers when they are
+//! [interned](struct.Interner.html):
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{Request, Value};
+//! use jrpc::method::{Interner, MethodName};
+//!
+//! # fn main() {
+//! assert!(MethodName::new("rpc.discover").is_err());
+//! let subtract = MethodName::new("subtract").unwrap();
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#;
+//! let request: Request<MethodName, Value> = serde_json::from_str(json).unwrap();
+//! assert_eq!(request.method, subtract);
+//!
+//! let interner = Interner::new(&["subtract", "add"]);
+//! let method = interner.intern(request.method);
+//! assert!(method.is_static());
+//! # }
+//! ```
+
+use std::borrow::Borrow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Deref;
+
+use serde::de::{Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde_json;
+use std_prelude::*;
+
+use strict::{self, SpecViolation};
+use {Error, ErrorCode, ErrorObject, IdReq, Request, Response, Value, V2_0};
+
+/// A method with typed `params`, `result` and error `data`.
+pub trait Method {
+    /// The type of the `params`.
+    type Params: Serialize + DeserializeOwned;
+
+    /// The type of the `result`.
+    type Result: Serialize + DeserializeOwned;
+
+    /// The type of the `data` of the method's errors. Use `Value` if it is not known.
+    type ErrorData: Serialize + DeserializeOwned;
+
+    /// The method name.
+    fn name() -> Cow<'static, str>;
+
+    /// Create a Request for this method.
+    fn request<I: Into<IdReq>>(id: I, params: Self::Params) -> Request<String, Self::Params> {
+        Request::with_params(id, Self::name().into_owned(), params)
+    }
+
+    /// Decode the `params` of a parsed Request.
+    ///
+    /// Missing `params` are decoded from Null, so they are only accepted if `Params` is e.g.
+    /// `()` or an `Option`. Fails with `InvalidParams`.
+    fn decode_params(
+        request: Request<String, Value>,
+    ) -> Result<Request<String, Self::Params>, Error<Value>> {
+        let id = request.id;
+        let params = request.params.unwrap_or(Value::Null);
+        match serde_json::from_value(params) {
+            Ok(params) => Ok(Request {
+                jsonrpc: V2_0,
+                method: request.method,
+                params: Some(params),
+                id: id,
+            }),
+            Err(err) => Err(Error::new(
+                id.to_id().unwrap_or(::Id::Null),
+                ErrorCode::InvalidParams,
+                err.to_string(),
+                None,
+            )),
+        }
+    }
+
+    /// Decode the `result` of a Response, or return its error.
+    ///
+    /// A `result` which does not decode as `Result` is reported as a `ParseError`. The error
+    /// `data` is decoded as `ErrorData`; if it does not decode, the error is returned without
+    /// it, so its `code` and `message` are never lost.
+    fn decode_result(
+        response: Response<Value>,
+    ) -> Result<Self::Result, ErrorObject<Self::ErrorData>> {
+        match response {
+            Response::Ok(success) => {
+                serde_json::from_value(success.result).map_err(|err| ErrorObject {
+                    code: ErrorCode::ParseError,
+                    message: format!("invalid result: {}", err),
+                    data: None,
+                })
+            }
+            Response::Err(error) => {
+                let error = error.error;
+                Err(ErrorObject {
+                    code: error.code,
+                    message: error.message,
+                    data: error.data.and_then(|data| serde_json::from_value(data).ok()),
+                })
+            }
+        }
+    }
+}
+
+/// The name of a method.
+///
+/// [`new`](#method.new) rejects names which the spec does not allow for application methods;
+/// the `From` conversions do not, so that existing `String` based code keeps compiling.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MethodName(Cow<'static, str>);
+
+impl MethodName {
+    /// Create a method name, failing if it is empty or starts with `rpc.`, which is reserved for
+    /// system extensions.
+    pub fn new<S: Into<Cow<'static, str>>>(name: S) -> Result<Self, SpecViolation> {
+        let name = name.into();
+        strict::check_method_name(&name)?;
+        Ok(MethodName(name))
+    }
+
+    /// Create the name of a system extension, e.g. `rpc.discover`.
+    ///
+    /// # Panics
+    ///
+    /// If `name` does not start with `rpc.`.
+    pub fn system(name: &'static str) -> Self {
+        assert!(name.starts_with("rpc."), "`{}` is not a system extension", name);
+        MethodName(Cow::Borrowed(name))
+    }
+
+    /// The name.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the name is stored without an allocation.
+    pub fn is_static(&self) -> bool {
+        match self.0 {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }
+    }
+
+    /// Convert into a `String`.
+    pub fn into_string(self) -> String {
+        self.0.into_owned()
+    }
+}
+
+impl From<&'static str> for MethodName {
+    fn from(name: &'static str) -> Self {
+        MethodName(Cow::Borrowed(name))
+    }
+}
+
+impl From<String> for MethodName {
+    fn from(name: String) -> Self {
+        MethodName(Cow::Owned(name))
+    }
+}
+
+impl From<MethodName> for String {
+    fn from(name: MethodName) -> Self {
+        name.into_string()
+    }
+}
+
+impl Deref for MethodName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for MethodName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for MethodName {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for MethodName {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for MethodName {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for MethodName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for MethodName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for MethodName {
+    /// Any String is accepted, see [`strict`](../strict/index.html) to reject reserved names.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(MethodName::from)
+    }
+}
+
+/// A set of known method names, to store the names received from peers without allocating.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    names: BTreeSet<&'static str>,
+}
+
+impl Interner {
+    /// Create an interner of `names`.
+    pub fn new(names: &[&'static str]) -> Self {
+        Interner {
+            names: names.iter().cloned().collect(),
+        }
+    }
+
+    /// Add a name.
+    pub fn insert(&mut self, name: &'static str) {
+        self.names.insert(name);
+    }
+
+    /// Replace a name by its static copy, if it is known. Unknown names are returned unchanged.
+    pub fn intern(&self, name: MethodName) -> MethodName {
+        match self.names.get(name.as_str()) {
+            Some(known) => MethodName(Cow::Borrowed(known)),
+            None => name,
+        }
+    }
+
+    /// Get the static copy of a name, if it is known.
+    pub fn get(&self, name: &str) -> Option<MethodName> {
+        self.names.get(name).map(|known| MethodName(Cow::Borrowed(known)))
+    }
+}
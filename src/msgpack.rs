@@ -0,0 +1,47 @@
+//! Encoding messages as MessagePack.
+//!
+//! > Only available with the `msgpack` feature.
+//!
+//! The messages are encoded with [`rmp-serde`](https://docs.rs/rmp-serde), with the members of
+//! Objects by name, so a MessagePack message has the same shape as its json. Any message type of
+//! this crate, or a batch of them, can be encoded and decoded.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! # #[cfg(feature = "msgpack")]
+//! # fn main() {
+//! use jrpc::{Id, Request, Response, Value};
+//! use jrpc::msgpack;
+//!
+//! let request = Request::with_params(Id::from(1), "subtract".to_string(), vec![42, 23]);
+//! let bytes = msgpack::to_vec(&request).unwrap();
+//! let decoded: Request<String, Vec<u32>> = msgpack::from_slice(&bytes).unwrap();
+//! assert_eq!(decoded, request);
+//!
+//! let response: Response<u32> = Response::success(Id::from(1), 19);
+//! let bytes = msgpack::to_vec(&response).unwrap();
+//! assert_eq!(msgpack::from_slice::<Response<u32>>(&bytes).unwrap(), response);
+//! # }
+//! # #[cfg(not(feature = "msgpack"))]
+//! # fn main() {}
+//! ```
+
+use rmp_serde;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std_prelude::*;
+
+pub use rmp_serde::decode::Error as DecodeError;
+pub use rmp_serde::encode::Error as EncodeError;
+
+/// Encode a message as MessagePack.
+pub fn to_vec<T: Serialize + ?Sized>(message: &T) -> Result<Vec<u8>, EncodeError> {
+    rmp_serde::to_vec_named(message)
+}
+
+/// Decode a message from MessagePack.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    rmp_serde::from_slice(bytes)
+}
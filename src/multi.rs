@@ -0,0 +1,201 @@
+//! Reporting the outcome of each item of a bulk operation.
+//!
+//! # Convention
+//!
+//! A method operating on many items, some of which may fail, succeeds as a whole and reports the
+//! outcome of each item in its `result`, as an Array of the items with their index in the
+//! params. An item has either a `result` or an `error`, which is an error object:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "result": [
+//!     {"index": 0, "result": "foo-1"},
+//!     {"index": 1, "error": {"code": 409, "message": "Already exists"}},
+//!     {"index": 2, "result": "foo-3"}
+//! ], "id": 1}
+//! ```
+//!
+//! The top level `error` remains for failures of the call itself, e.g. `InvalidParams`, so a
+//! client does not mistake a single failed item for the failure of every item.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{ErrorCode, ErrorObject, Value};
+//! use jrpc::multi::MultiResult;
+//!
+//! # fn main() {
+//! let names = vec!["foo", "", "bar"];
+//! let created: MultiResult<String> = names
+//!     .iter()
+//!     .map(|name| match *name {
+//!         "" => Err(ErrorObject::new(ErrorCode::InvalidParams, "Empty name", None).unwrap()),
+//!         name => Ok(name.to_uppercase()),
+//!     })
+//!     .collect();
+//! assert_eq!(
+//!     serde_json::to_string(&created).unwrap(),
+//!     r#"[{"index":0,"result":"FOO"},{"index":1,"error":{"code":-32602,"message":"Empty name"}},{"index":2,"result":"BAR"}]"#,
+//! );
+//!
+//! // The client.
+//! let json = serde_json::to_string(&created).unwrap();
+//! let created: MultiResult<String> = serde_json::from_str(&json).unwrap();
+//! assert!(!created.all_ok());
+//! assert_eq!(created.succeeded().collect::<Vec<_>>(), vec![(0, &"FOO".to_string()), (2, &"BAR".to_string())]);
+//! assert_eq!(created.failed().map(|(index, _)| index).collect::<Vec<_>>(), vec![1]);
+//! assert_eq!(created.get(2), Some(Ok(&"BAR".to_string())));
+//! # }
+//! ```
+
+use std::iter::FromIterator;
+
+use std_prelude::*;
+
+use {ErrorObject, Value};
+
+/// The outcome of one item of a bulk operation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum Item<T, E = Value> {
+    /// The operation on the item succeeded.
+    Ok {
+        /// The index of the item in the params.
+        index: usize,
+        /// The result of the item.
+        result: T,
+    },
+    /// The operation on the item failed.
+    Err {
+        /// The index of the item in the params.
+        index: usize,
+        /// Why the item failed.
+        error: ErrorObject<E>,
+    },
+}
+
+impl<T, E> Item<T, E> {
+    /// Create the outcome of the item at `index`.
+    pub fn new(index: usize, result: Result<T, ErrorObject<E>>) -> Self {
+        match result {
+            Ok(result) => Item::Ok {
+                index: index,
+                result: result,
+            },
+            Err(error) => Item::Err {
+                index: index,
+                error: error,
+            },
+        }
+    }
+
+    /// The index of the item in the params.
+    pub fn index(&self) -> usize {
+        match *self {
+            Item::Ok { index, .. } | Item::Err { index, .. } => index,
+        }
+    }
+
+    /// Whether the operation on the item succeeded.
+    pub fn is_ok(&self) -> bool {
+        match *self {
+            Item::Ok { .. } => true,
+            Item::Err { .. } => false,
+        }
+    }
+
+    /// The result, or the error object.
+    pub fn as_result(&self) -> Result<&T, &ErrorObject<E>> {
+        match *self {
+            Item::Ok { ref result, .. } => Ok(result),
+            Item::Err { ref error, .. } => Err(error),
+        }
+    }
+
+    /// Convert into the result, or the error object.
+    pub fn into_result(self) -> Result<T, ErrorObject<E>> {
+        match self {
+            Item::Ok { result, .. } => Ok(result),
+            Item::Err { error, .. } => Err(error),
+        }
+    }
+}
+
+/// The outcomes of the items of a bulk operation. Serializes as an Array of
+/// [`Item`](enum.Item.html)s.
+///
+/// Collecting an iterator of `Result`s numbers the items from `0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MultiResult<T, E = Value>(Vec<Item<T, E>>);
+
+impl<T, E> MultiResult<T, E> {
+    /// Create from the outcomes of items with their indexes, e.g. when only some items of the
+    /// params were processed.
+    pub fn from_indexed<I>(results: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, Result<T, ErrorObject<E>>)>,
+    {
+        MultiResult(
+            results
+                .into_iter()
+                .map(|(index, result)| Item::new(index, result))
+                .collect(),
+        )
+    }
+
+    /// The outcomes, in the order they were reported.
+    pub fn items(&self) -> &[Item<T, E>] {
+        &self.0
+    }
+
+    /// Convert into the outcomes.
+    pub fn into_items(self) -> Vec<Item<T, E>> {
+        self.0
+    }
+
+    /// The outcome of the item at `index`, if it was reported.
+    pub fn get(&self, index: usize) -> Option<Result<&T, &ErrorObject<E>>> {
+        self.0
+            .iter()
+            .find(|item| item.index() == index)
+            .map(Item::as_result)
+    }
+
+    /// The indexes and results of the items which succeeded.
+    pub fn succeeded(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.0.iter().filter_map(|item| match *item {
+            Item::Ok { index, ref result } => Some((index, result)),
+            Item::Err { .. } => None,
+        })
+    }
+
+    /// The indexes and error objects of the items which failed.
+    pub fn failed(&self) -> impl Iterator<Item = (usize, &ErrorObject<E>)> {
+        self.0.iter().filter_map(|item| match *item {
+            Item::Ok { .. } => None,
+            Item::Err { index, ref error } => Some((index, error)),
+        })
+    }
+
+    /// Whether every reported item succeeded.
+    pub fn all_ok(&self) -> bool {
+        self.0.iter().all(Item::is_ok)
+    }
+
+    /// The number of reported items.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether no item was reported.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T, E> FromIterator<Result<T, ErrorObject<E>>> for MultiResult<T, E> {
+    fn from_iter<I: IntoIterator<Item = Result<T, ErrorObject<E>>>>(results: I) -> Self {
+        MultiResult::from_indexed(results.into_iter().enumerate())
+    }
+}
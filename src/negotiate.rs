@@ -0,0 +1,368 @@
+//! Accepting JSON-RPC 1.0 and 2.0 on the same endpoint.
+//!
+//! > Only available with the `v1-compat` feature.
+//!
+//! [`VersionSniffer`](struct.VersionSniffer.html) classifies each inbound message by the
+//! presence of the `jsonrpc` member, parses it with the matching types and remembers the version
+//! so that the reply is constructed the way the caller expects.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::ErrorCode;
+//! use jrpc::negotiate::{Version, VersionSniffer};
+//!
+//! # fn main() {
+//! let sniffer = VersionSniffer::new();
+//!
+//! let legacy = sniffer
+//!     .parse::<String>(r#"{"method": "echo", "params": ["hi"], "id": 7}"#)
+//!     .unwrap();
+//! assert_eq!(legacy.version(), Version::V1_0);
+//! assert_eq!(legacy.method(), "echo");
+//! assert_eq!(
+//!     legacy.success("hi".to_string()).unwrap().to_string(),
+//!     r#"{"result":"hi","error":null,"id":7}"#,
+//! );
+//!
+//! let modern = sniffer
+//!     .parse::<String>(r#"{"jsonrpc": "2.0", "method": "echo", "params": ["hi"], "id": 8}"#)
+//!     .unwrap();
+//! assert_eq!(modern.version(), Version::V2_0);
+//! assert_eq!(
+//!     modern.error(ErrorCode::InvalidParams, "nope").unwrap().to_string(),
+//!     r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"nope"},"id":8}"#,
+//! );
+//!
+//! // Errors are also reported in the caller's version.
+//! let rejected = sniffer.parse::<String>(r#"{"method": 3, "params": [], "id": 9}"#).unwrap_err();
+//! assert_eq!(rejected.version, Version::V1_0);
+//! assert!(rejected.to_string().starts_with(r#"{"result":null,"error":{"code":-32600"#));
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use v1;
+use {request_from_value, Error, ErrorCode, ErrorObject, Id, IdReq, Request, Response, Value};
+
+/// The version of the JSON-RPC protocol a message was sent with.
+///
+/// Serializes to and from `"1.0"` and `"2.0"`, e.g. to record the version of a peer in a
+/// configuration file. 1.0 messages themselves have no `jsonrpc` member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Version {
+    /// JSON-RPC 1.0, which has no `jsonrpc` member.
+    #[serde(rename = "1.0")]
+    V1_0,
+    /// JSON-RPC 2.0.
+    #[serde(rename = "2.0")]
+    V2_0,
+}
+
+impl Version {
+    /// The version as a String: `"1.0"` or `"2.0"`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Version::V1_0 => "1.0",
+            Version::V2_0 => "2.0",
+        }
+    }
+}
+
+impl Default for Version {
+    /// The strict 2.0.
+    fn default() -> Self {
+        Version::V2_0
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<::V2_0> for Version {
+    fn from(_: ::V2_0) -> Self {
+        Version::V2_0
+    }
+}
+
+/// Classifies inbound messages by version and parses them accordingly.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionSniffer {
+    accept_v1: bool,
+}
+
+impl Default for VersionSniffer {
+    fn default() -> Self {
+        VersionSniffer { accept_v1: true }
+    }
+}
+
+impl VersionSniffer {
+    /// Create a sniffer accepting both versions.
+    pub fn new() -> Self {
+        VersionSniffer::default()
+    }
+
+    /// Whether 1.0 messages are accepted. If not, they are rejected with `InvalidRequest`.
+    pub fn accept_v1(mut self, accept: bool) -> Self {
+        self.accept_v1 = accept;
+        self
+    }
+
+    /// Classify a parsed message.
+    ///
+    /// Objects with a `jsonrpc` member and Arrays (batches, which 1.0 does not have) are 2.0.
+    /// Other Objects are 1.0. Anything else is not a message at all and returns `None`.
+    pub fn sniff(value: &Value) -> Option<Version> {
+        match *value {
+            Value::Object(ref map) if map.contains_key("jsonrpc") => Some(Version::V2_0),
+            Value::Object(_) => Some(Version::V1_0),
+            Value::Array(_) => Some(Version::V2_0),
+            _ => None,
+        }
+    }
+
+    /// Parse a single inbound Request of either version.
+    ///
+    /// This maps errors the same way as [`parse_request`](../fn.parse_request.html). Messages
+    /// whose version cannot be determined are answered as 2.0.
+    pub fn parse<M>(&self, json: &str) -> Result<Inbound<M>, Rejected>
+    where
+        M: Serialize + DeserializeOwned,
+    {
+        let value: Value = serde_json::from_str(json).map_err(|err| {
+            Rejected::new(Version::V2_0, Id::Null, ErrorCode::ParseError, err.to_string())
+        })?;
+
+        match VersionSniffer::sniff(&value) {
+            Some(Version::V1_0) if self.accept_v1 => parse_v1(value),
+            Some(Version::V1_0) => Err(Rejected::new(
+                Version::V1_0,
+                Id::Null,
+                ErrorCode::InvalidRequest,
+                "JSON-RPC 1.0 is not accepted",
+            )),
+            _ => request_from_value(value)
+                .map(Inbound::V2_0)
+                .map_err(|error| Rejected {
+                    version: Version::V2_0,
+                    error: error,
+                }),
+        }
+    }
+}
+
+fn parse_v1<M>(value: Value) -> Result<Inbound<M>, Rejected>
+where
+    M: Serialize + DeserializeOwned,
+{
+    let request: v1::Request<Value, Value> = serde_json::from_value(value).map_err(|err| {
+        Rejected::new(Version::V1_0, Id::Null, ErrorCode::InvalidRequest, err.to_string())
+    })?;
+
+    if !request.method.is_string() {
+        return Err(Rejected::new(
+            Version::V1_0,
+            request.id,
+            ErrorCode::InvalidRequest,
+            "method must be a String",
+        ));
+    }
+
+    let (id, method, params) = (request.id, request.method, request.params);
+
+    let method: M = serde_json::from_value(method).map_err(|err| {
+        Rejected::new(
+            Version::V1_0,
+            id.clone(),
+            ErrorCode::MethodNotFound,
+            err.to_string(),
+        )
+    })?;
+
+    Ok(Inbound::V1_0(v1::Request {
+        method: method,
+        params: params,
+        id: id,
+    }))
+}
+
+/// An inbound Request, tagged with the version it was sent with.
+#[derive(Debug)]
+pub enum Inbound<M> {
+    /// A JSON-RPC 1.0 Request.
+    V1_0(v1::Request<M, Value>),
+    /// A JSON-RPC 2.0 Request.
+    V2_0(Request<M, Value>),
+}
+
+impl<M> Inbound<M> {
+    /// The version of the Request.
+    pub fn version(&self) -> Version {
+        match *self {
+            Inbound::V1_0(_) => Version::V1_0,
+            Inbound::V2_0(_) => Version::V2_0,
+        }
+    }
+
+    /// The method of the Request.
+    pub fn method(&self) -> &M {
+        match *self {
+            Inbound::V1_0(ref r) => &r.method,
+            Inbound::V2_0(ref r) => &r.method,
+        }
+    }
+
+    /// The params of the Request, if any.
+    pub fn params(&self) -> Option<&Value> {
+        match *self {
+            Inbound::V1_0(ref r) => Some(&r.params),
+            Inbound::V2_0(ref r) => r.params.as_ref(),
+        }
+    }
+
+    /// The id to reply with, or `None` if this is a notification and MUST NOT be replied to.
+    pub fn id(&self) -> Option<Id> {
+        match *self {
+            Inbound::V1_0(ref r) if r.id == Id::Null => None,
+            Inbound::V1_0(ref r) => Some(r.id.clone()),
+            Inbound::V2_0(ref r) => r.id.clone().to_id(),
+        }
+    }
+
+    /// Whether this is a notification.
+    pub fn is_notification(&self) -> bool {
+        match *self {
+            Inbound::V1_0(ref r) => r.id == Id::Null,
+            Inbound::V2_0(ref r) => r.id == IdReq::Notification,
+        }
+    }
+
+    /// Construct a successful reply in the caller's version.
+    ///
+    /// Returns `None` for notifications.
+    pub fn success<T>(&self, result: T) -> Option<Outbound<T>>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let id = self.id()?;
+        Some(match self.version() {
+            Version::V1_0 => Outbound::V1_0(v1::Response::success(id, result)),
+            Version::V2_0 => Outbound::V2_0(Response::success(id, result)),
+        })
+    }
+
+    /// Construct an error reply in the caller's version.
+    ///
+    /// Returns `None` for notifications.
+    pub fn error<C, S>(&self, code: C, message: S) -> Option<Outbound<Value>>
+    where
+        C: Into<ErrorCode>,
+        S: Into<String>,
+    {
+        let id = self.id()?;
+        Some(Outbound::error(self.version(), id, code, message, None))
+    }
+}
+
+/// A reply, in the version of the Request it answers.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Outbound<T> {
+    /// A JSON-RPC 1.0 Response.
+    V1_0(v1::Response<T, Value>),
+    /// A JSON-RPC 2.0 Response.
+    V2_0(Response<T>),
+}
+
+impl<T: Serialize + DeserializeOwned> Outbound<T> {
+    /// Construct an error reply for the given version.
+    pub fn error<C, S>(version: Version, id: Id, code: C, message: S, data: Option<Value>) -> Self
+    where
+        C: Into<ErrorCode>,
+        S: Into<String>,
+    {
+        match version {
+            Version::V1_0 => {
+                let error = ErrorObject {
+                    code: code.into(),
+                    message: message.into(),
+                    data: data,
+                };
+                Outbound::V1_0(v1::Response::from_error_object(id, &error))
+            }
+            Version::V2_0 => Outbound::V2_0(Response::error(id, code, message, data)),
+        }
+    }
+
+    /// The version of the reply.
+    pub fn version(&self) -> Version {
+        match *self {
+            Outbound::V1_0(_) => Version::V1_0,
+            Outbound::V2_0(_) => Version::V2_0,
+        }
+    }
+}
+
+/// Formats the reply as compact json.
+impl<T: Serialize + DeserializeOwned> fmt::Display for Outbound<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&serde_json::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// A message which could not be parsed, with the error to reply with.
+#[derive(Debug)]
+pub struct Rejected {
+    /// The version to reply in.
+    pub version: Version,
+    /// The error describing why the message was rejected.
+    pub error: Error<Value>,
+}
+
+impl Rejected {
+    fn new<S: Into<String>>(version: Version, id: Id, code: ErrorCode, message: S) -> Self {
+        Rejected {
+            version: version,
+            error: Error::new(id, code, message, None),
+        }
+    }
+
+    /// Convert into the reply to send, in the caller's version.
+    pub fn into_response(self) -> Outbound<Value> {
+        let error = self.error.error;
+        Outbound::error(
+            self.version,
+            self.error.id,
+            error.code,
+            error.message,
+            error.data,
+        )
+    }
+}
+
+/// Formats the reply as compact json, in the caller's version.
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.version {
+            Version::V1_0 => {
+                let response: v1::Response<Value> =
+                    v1::Response::from_error_object(self.error.id.clone(), &self.error.error);
+                f.write_str(&serde_json::to_string(&response).map_err(|_| fmt::Error)?)
+            }
+            Version::V2_0 => f.write_str(&self.error.to_json()),
+        }
+    }
+}
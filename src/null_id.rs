@@ -0,0 +1,168 @@
+//! Keeping clients which send Requests with a Null `id` working while they are migrated.
+//!
+//! The spec discourages `"id": null`, and a server can not tell apart the Responses of
+//! concurrent calls which all use it. Many clients send it anyway and expect a Response.
+//! [`NullIdCompat`](struct.NullIdCompat.html) keeps them working:
+//!
+//! 1. [`admit`](struct.NullIdCompat.html#method.admit) gives every inbound Request with a Null
+//!    `id` a unique synthetic `id`, so the server correlates it like any other call, and returns
+//!    a [`Deviation`](struct.Deviation.html) to log.
+//! 2. [`restore`](struct.NullIdCompat.html#method.restore) puts the Null `id` back into the
+//!    Response before it is sent, so the client sees what it expects.
+//! 3. The [`NullIdReport`](struct.NullIdReport.html) counts the offending calls per peer and per
+//!    method, to flag the clients to fix.
+//!
+//! Synthetic ids are Strings starting with [`SYNTHETIC_PREFIX`](constant.SYNTHETIC_PREFIX.html),
+//! which clients must not use themselves.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{Id, Request, Response, Value};
+//! use jrpc::null_id::NullIdCompat;
+//!
+//! # fn main() {
+//! let mut compat = NullIdCompat::new();
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "Ping", "id": null}"#;
+//! let request: Request<String, Value> = serde_json::from_str(json).unwrap();
+//! let (request, deviation) = compat.admit("10.0.0.7", request);
+//! let deviation = deviation.unwrap();
+//! assert_eq!(deviation.method, "Ping");
+//! assert_eq!(request.id, deviation.synthetic.clone().into());
+//!
+//! // The server answers with the synthetic id, the client gets `null` back.
+//! let response = Response::success(deviation.synthetic, Value::from("pong"));
+//! let response = compat.restore(response);
+//! assert_eq!(response.id(), &Id::Null);
+//!
+//! let report = compat.report();
+//! assert_eq!(report.requests, 1);
+//! assert_eq!(report.peers["10.0.0.7"], 1);
+//! assert_eq!(report.pending, 0);
+//! # }
+//! ```
+
+use std::collections::BTreeSet;
+
+use std_prelude::*;
+
+use {Id, IdReq, Request, Response};
+
+/// The prefix of the synthetic ids.
+pub const SYNTHETIC_PREFIX: &str = "jrpc-null-id-";
+
+/// An inbound Request with a Null `id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deviation {
+    /// The peer which sent the Request.
+    pub peer: String,
+
+    /// The method of the Request.
+    pub method: String,
+
+    /// The id the Request was given.
+    pub synthetic: Id,
+}
+
+/// The Requests with a Null `id` seen so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullIdReport {
+    /// The number of Requests with a Null `id`.
+    pub requests: u64,
+
+    /// The number of Requests, per peer.
+    #[serde(default)]
+    pub peers: BTreeMap<String, u64>,
+
+    /// The number of Requests, per method.
+    #[serde(default)]
+    pub methods: BTreeMap<String, u64>,
+
+    /// The number of Requests which were not answered yet.
+    pub pending: usize,
+}
+
+/// Assigns synthetic ids to Requests with a Null `id`, and restores them in the Responses.
+#[derive(Debug, Clone, Default)]
+pub struct NullIdCompat {
+    next: u64,
+    pending: BTreeSet<String>,
+    report: NullIdReport,
+}
+
+impl NullIdCompat {
+    /// Create the compatibility layer of an endpoint.
+    pub fn new() -> Self {
+        NullIdCompat::default()
+    }
+
+    /// Give the Request a synthetic id if its `id` is Null. Other Requests, including
+    /// notifications, are returned unchanged and without a `Deviation`.
+    pub fn admit<M, T>(
+        &mut self,
+        peer: &str,
+        request: Request<M, T>,
+    ) -> (Request<M, T>, Option<Deviation>)
+    where
+        M: AsRef<str>,
+    {
+        if request.id != IdReq::Null {
+            return (request, None);
+        }
+        let synthetic = format!("{}{}", SYNTHETIC_PREFIX, self.next);
+        self.next += 1;
+        self.pending.insert(synthetic.clone());
+
+        let method = request.method.as_ref().to_string();
+        self.report.requests += 1;
+        *self.report.peers.entry(peer.to_string()).or_insert(0) += 1;
+        *self.report.methods.entry(method.clone()).or_insert(0) += 1;
+
+        let deviation = Deviation {
+            peer: peer.to_string(),
+            method: method,
+            synthetic: Id::String(synthetic.clone()),
+        };
+        let request = Request {
+            id: IdReq::String(synthetic),
+            ..request
+        };
+        (request, Some(deviation))
+    }
+
+    /// Put the Null `id` back into the Response of an admitted Request. Other Responses are
+    /// returned unchanged.
+    pub fn restore<T, E>(&mut self, mut response: Response<T, E>) -> Response<T, E> {
+        let id = match response {
+            Response::Ok(ref mut success) => &mut success.id,
+            Response::Err(ref mut error) => &mut error.id,
+        };
+        let synthetic = match *id {
+            Id::String(ref s) => self.pending.remove(s),
+            _ => false,
+        };
+        if synthetic {
+            *id = Id::Null;
+        }
+        response
+    }
+
+    /// Forget an admitted Request which will not be answered, e.g. because it timed out.
+    pub fn forget(&mut self, synthetic: &Id) -> bool {
+        match *synthetic {
+            Id::String(ref s) => self.pending.remove(s),
+            _ => false,
+        }
+    }
+
+    /// The Requests with a Null `id` seen so far.
+    pub fn report(&self) -> NullIdReport {
+        NullIdReport {
+            pending: self.pending.len(),
+            ..self.report.clone()
+        }
+    }
+}
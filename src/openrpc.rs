@@ -0,0 +1,340 @@
+//! OpenRPC discovery documents.
+//!
+//! > Only available with the `openrpc` feature.
+//!
+//! A [`ServiceDescription`](struct.ServiceDescription.html) collects the methods of a server with
+//! the JSON Schemas of their `params` and `result`, and the errors they may return, like an
+//! [`ErrorCatalog`](../catalog/struct.ErrorCatalog.html), which it can be created from. Its
+//! [`document`](struct.ServiceDescription.html#method.document) is an
+//! [OpenRPC](https://spec.open-rpc.org) document, which the server answers the conventional
+//! [`rpc.discover`](constant.DISCOVER_METHOD.html) method with once it is
+//! [registered](struct.ServiceDescription.html#method.register) with its router.
+//!
+//! Schemas are plain json values, so they can be written by hand or generated. With the
+//! `schemars` feature, [`MethodDoc::params_schema_for`] and [`MethodDoc::result_schema_for`]
+//! generate them from any type implementing `schemars::JsonSchema`, with its subschemas inlined
+//! so the document is self-contained.
+//!
+//! [`MethodDoc::params_schema_for`]: ../catalog/struct.MethodDoc.html#method.params_schema_for
+//! [`MethodDoc::result_schema_for`]: ../catalog/struct.MethodDoc.html#method.result_schema_for
+//!
+//! # Params
+//!
+//! OpenRPC describes each param separately. The params schema of a method is split into:
+//!
+//! - the properties of an `object` schema, passed by-name, in alphabetical order and required
+//!   if listed in `required`,
+//! - the `prefixItems`, or `items` if it is an Array, of an `array` schema, passed by-position
+//!   and all required,
+//! - otherwise, a single required param named `params`.
+//!
+//! A method without a params schema has no params.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::catalog::{ErrorDoc, MethodDoc};
+//! use jrpc::openrpc::{ParamStructure, ServiceDescription};
+//! use jrpc::router::Router;
+//!
+//! # fn main() {
+//! let service = ServiceDescription::new("Bank", "1.0.0")
+//!     .error(ErrorDoc::new(-32001, "InsufficientFunds", "The account balance is too low."))
+//!     .method(
+//!         MethodDoc::new("transfer")
+//!             .params_schema(json!({
+//!                 "type": "object",
+//!                 "properties": {"to": {"type": "string"}, "amount": {"type": "integer"}},
+//!                 "required": ["to", "amount"],
+//!             }))
+//!             .result_schema(json!({"type": "string"}))
+//!             .errors(&[-32001]),
+//!     );
+//!
+//! let document = service.document();
+//! let transfer = &document.methods[0];
+//! assert_eq!(transfer.param_structure, ParamStructure::ByName);
+//! assert_eq!(transfer.params[1].name, "to");
+//! assert_eq!(transfer.errors[0].message, "The account balance is too low.");
+//!
+//! let mut router = Router::new();
+//! service.register(&mut router);
+//! let json = r#"{"jsonrpc": "2.0", "method": "rpc.discover", "id": 1}"#;
+//! let response: serde_json::Value = serde_json::from_str(&router.handle(json).unwrap()).unwrap();
+//! assert_eq!(response["result"]["info"], json!({"title": "Bank", "version": "1.0.0"}));
+//! # }
+//! ```
+
+#[cfg(feature = "schemars")]
+use schemars::generate::SchemaSettings;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde_json;
+use std_prelude::*;
+
+use catalog::{ErrorCatalog, ErrorDoc, MethodDoc};
+use router::Router;
+use {ErrorCode, ErrorObject, Value};
+
+/// The conventional method answering with the OpenRPC document.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// The version of the OpenRPC specification of the documents.
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// An OpenRPC document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    /// The version of the OpenRPC specification.
+    pub openrpc: String,
+
+    /// The metadata of the service.
+    pub info: Info,
+
+    /// The methods of the service.
+    pub methods: Vec<MethodObject>,
+}
+
+/// The metadata of a service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Info {
+    /// The name of the service.
+    pub title: String,
+
+    /// The version of the service, not of OpenRPC.
+    pub version: String,
+
+    /// A longer description of the service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// How a method accepts its params.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamStructure {
+    /// As an Object.
+    ByName,
+    /// As an Array.
+    ByPosition,
+    /// As either.
+    #[default]
+    Either,
+}
+
+/// The description of a method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodObject {
+    /// The method name.
+    pub name: String,
+
+    /// The params, in the order they are passed by-position.
+    pub params: Vec<ContentDescriptor>,
+
+    /// The result.
+    pub result: ContentDescriptor,
+
+    /// How the method accepts its params.
+    #[serde(default)]
+    pub param_structure: ParamStructure,
+
+    /// The errors the method may return.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ErrorObject<Value>>,
+}
+
+/// A named value and its JSON Schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentDescriptor {
+    /// The name.
+    pub name: String,
+
+    /// The JSON Schema of the value.
+    pub schema: Value,
+
+    /// Whether the value must be present.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub required: bool,
+}
+
+impl ContentDescriptor {
+    /// Describe a value.
+    pub fn new<N: Into<String>>(name: N, schema: Value, required: bool) -> Self {
+        ContentDescriptor {
+            name: name.into(),
+            schema: schema,
+            required: required,
+        }
+    }
+}
+
+/// Collects the methods of a service, to describe them in an OpenRPC document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceDescription {
+    info: Info,
+    catalog: ErrorCatalog,
+}
+
+impl ServiceDescription {
+    /// Describe the service `title` at `version`, without any methods.
+    pub fn new<T: Into<String>, V: Into<String>>(title: T, version: V) -> Self {
+        ServiceDescription::from_catalog(title, version, ErrorCatalog::new())
+    }
+
+    /// Describe the service `title` at `version`, with the methods and errors of `catalog`.
+    pub fn from_catalog<T: Into<String>, V: Into<String>>(
+        title: T,
+        version: V,
+        catalog: ErrorCatalog,
+    ) -> Self {
+        ServiceDescription {
+            info: Info {
+                title: title.into(),
+                version: version.into(),
+                description: None,
+            },
+            catalog: catalog,
+        }
+    }
+
+    /// Set the description of the service.
+    pub fn description<D: Into<String>>(mut self, description: D) -> Self {
+        self.info.description = Some(description.into());
+        self
+    }
+
+    /// Add a method, replacing an existing one with the same name.
+    pub fn method(mut self, doc: MethodDoc) -> Self {
+        self.catalog = self.catalog.method(doc);
+        self
+    }
+
+    /// Add an error code, replacing an existing one with the same code.
+    pub fn error(mut self, doc: ErrorDoc) -> Self {
+        self.catalog = self.catalog.error(doc);
+        self
+    }
+
+    /// The methods and errors.
+    pub fn catalog(&self) -> &ErrorCatalog {
+        &self.catalog
+    }
+
+    /// The OpenRPC document, with the methods in the order they were added.
+    ///
+    /// Error codes the catalog does not document are left out.
+    pub fn document(&self) -> Document {
+        let methods = self
+            .catalog
+            .methods
+            .iter()
+            .map(|method| {
+                let (param_structure, params) = split_params(method.params_schema.as_ref());
+                let errors = self
+                    .catalog
+                    .errors_of(&method.name)
+                    .into_iter()
+                    .map(|doc| ErrorObject {
+                        code: ErrorCode::from(doc.code),
+                        message: doc.description.clone(),
+                        data: None,
+                    })
+                    .collect();
+                MethodObject {
+                    name: method.name.clone(),
+                    params: params,
+                    result: ContentDescriptor::new(
+                        "result",
+                        method.result_schema.clone().unwrap_or(Value::Bool(true)),
+                        false,
+                    ),
+                    param_structure: param_structure,
+                    errors: errors,
+                }
+            })
+            .collect();
+        Document {
+            openrpc: OPENRPC_VERSION.to_string(),
+            info: self.info.clone(),
+            methods: methods,
+        }
+    }
+
+    /// Answer [`rpc.discover`](constant.DISCOVER_METHOD.html) with the document, as it is now.
+    pub fn register(&self, router: &mut Router) {
+        let document = serde_json::to_value(self.document()).expect("documents are serializable");
+        router.add::<(), Value, _>(DISCOVER_METHOD, move |()| Ok(document.clone()));
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl MethodDoc {
+    /// Set the JSON Schema of the `params` to the schema of `T`.
+    ///
+    /// > Only available with the `schemars` feature.
+    pub fn params_schema_for<T: JsonSchema>(self) -> Self {
+        self.params_schema(schema_for::<T>())
+    }
+
+    /// Set the JSON Schema of the `result` to the schema of `R`.
+    ///
+    /// > Only available with the `schemars` feature.
+    pub fn result_schema_for<R: JsonSchema>(self) -> Self {
+        self.result_schema(schema_for::<R>())
+    }
+}
+
+/// The schema of `T`, with its subschemas inlined and without the `$schema` keyword.
+#[cfg(feature = "schemars")]
+fn schema_for<T: JsonSchema>() -> Value {
+    let mut schema = SchemaSettings::draft2020_12()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>();
+    schema.remove("$schema");
+    schema.to_value()
+}
+
+/// Split a params schema into the descriptors of the params.
+fn split_params(schema: Option<&Value>) -> (ParamStructure, Vec<ContentDescriptor>) {
+    let schema = match schema {
+        Some(schema) => schema,
+        None => return (ParamStructure::Either, Vec::new()),
+    };
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let params = properties
+            .iter()
+            .map(|(name, schema)| {
+                ContentDescriptor::new(name.clone(), schema.clone(), required.contains(&&**name))
+            })
+            .collect();
+        return (ParamStructure::ByName, params);
+    }
+    let items = schema
+        .get("prefixItems")
+        .or_else(|| schema.get("items"))
+        .and_then(Value::as_array);
+    if let Some(items) = items {
+        let params = items
+            .iter()
+            .enumerate()
+            .map(|(i, schema)| ContentDescriptor::new(format!("arg{}", i), schema.clone(), true))
+            .collect();
+        return (ParamStructure::ByPosition, params);
+    }
+    let params = vec![ContentDescriptor::new("params", schema.clone(), true)];
+    (ParamStructure::Either, params)
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
@@ -0,0 +1,202 @@
+//! Structured `params`.
+//!
+//! The spec requires `params`, if present, to be an Array (by-position) or an Object (by-name),
+//! never a scalar. [`Request`](../struct.Request.html) accepts any type as its params;
+//! [`Params`](enum.Params.html) is the type which only accepts the two allowed shapes, so a
+//! `Request<String, Params>` rejects `"params": 3` when it is deserialized. The user's own type
+//! is decoded later with [`parse`](enum.Params.html#method.parse).
+//!
+//! Ad-hoc by-position params are built with the [`params!`](../macro.params.html) macro. A
+//! struct deriving `Params`, with the `derive` feature, implements
+//! [`DualParams`](trait.DualParams.html) to be passed in either style.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use std::convert::TryFrom;
+//! use jrpc::{Id, Request};
+//! use jrpc::params::Params;
+//! use jrpc::strict::SpecViolation;
+//!
+//! # fn main() {
+//! let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#;
+//! let request: Request<String, Params> = serde_json::from_str(json).unwrap();
+//! let (a, b): (i64, i64) = request.params.unwrap().parse().unwrap();
+//! assert_eq!(a - b, 19);
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": 42, "id": 1}"#;
+//! assert!(serde_json::from_str::<Request<String, Params>>(json).is_err());
+//! assert_eq!(Params::try_from(json!(42)), Err(SpecViolation::Params(json!(42))));
+//!
+//! // Checking the shape of typed params when building a Request.
+//! assert!(Request::try_with_params(Id::from(1), "subtract".to_string(), (42, 23)).is_ok());
+//! assert!(Request::try_with_params(Id::from(1), "subtract".to_string(), 42).is_err());
+//! # }
+//! ```
+
+use core::convert::TryFrom;
+
+use serde::de::{self, Deserialize, DeserializeOwned, Deserializer};
+use serde::ser::Serialize;
+use serde_json;
+use prelude::*;
+
+use strict::SpecViolation;
+use Value;
+
+/// The `params` of a Request: by-position or by-name.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Params {
+    /// By-position: the values in the order the Server expects them.
+    Array(Vec<Value>),
+    /// By-name: the values by the parameter names the Server expects.
+    Map(serde_json::Map<String, Value>),
+}
+
+impl Default for Params {
+    /// No params, by-position. Required to deserialize a `Request<M, Params>`.
+    fn default() -> Self {
+        Params::Array(Vec::new())
+    }
+}
+
+impl Params {
+    /// Serialize `params`, failing if they are neither an Array nor an Object.
+    pub fn from_serialize<T: Serialize>(params: &T) -> Result<Params, SpecViolation> {
+        let value =
+            serde_json::to_value(params).map_err(|e| SpecViolation::Deserialize(e.to_string()))?;
+        Params::try_from(value)
+    }
+
+    /// Decode the params into the user's type.
+    pub fn parse<T: DeserializeOwned>(self) -> serde_json::Result<T> {
+        serde_json::from_value(self.into())
+    }
+
+    /// The number of params.
+    pub fn len(&self) -> usize {
+        match *self {
+            Params::Array(ref values) => values.len(),
+            Params::Map(ref map) => map.len(),
+        }
+    }
+
+    /// Whether there are no params.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Params which can be passed both by-name and by-position.
+///
+/// Implemented by `#[derive(Params)]`, with the `derive` feature, so one struct works with servers
+/// expecting either style.
+pub trait DualParams {
+    /// The params by-name, as an Object.
+    fn to_named(&self) -> serde_json::Result<Params>;
+
+    /// The params by-position, as an Array.
+    fn to_positional(&self) -> serde_json::Result<Params>;
+}
+
+/// Build by-position [`Params`](params/enum.Params.html) from a list of values.
+///
+/// Each value is serialized with `serde_json::to_value`.
+///
+/// # Panics
+///
+/// If a value can not be serialized, e.g. a map with non-String keys.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate jrpc;
+/// use jrpc::{Id, Request};
+/// use jrpc::params::Params;
+///
+/// # fn main() {
+/// let params = params![42, "foo", vec![1, 2]];
+/// assert_eq!(params.len(), 3);
+///
+/// let request = Request::with_params(Id::from(1), "GetFoo".to_string(), params);
+/// assert_eq!(
+///     request.to_string(),
+///     r#"{"jsonrpc":"2.0","method":"GetFoo","params":[42,"foo",[1,2]],"id":1}"#,
+/// );
+/// assert_eq!(params![], Params::Array(Vec::new()));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! params {
+    ($($value:expr),* $(,)*) => {{
+        #[allow(unused_mut)]
+        let mut values = $crate::params::Positional::new();
+        $(values.push(&$value);)*
+        values.into_params()
+    }};
+}
+
+/// Support for [`params!`](../macro.params.html). Not public API.
+#[doc(hidden)]
+#[derive(Default)]
+pub struct Positional(Vec<Value>);
+
+#[doc(hidden)]
+impl Positional {
+    pub fn new() -> Self {
+        Positional::default()
+    }
+
+    pub fn push<T: Serialize + ?Sized>(&mut self, value: &T) {
+        let value = serde_json::to_value(value).expect("params! values must be serializable");
+        self.0.push(value);
+    }
+
+    pub fn into_params(self) -> Params {
+        Params::Array(self.0)
+    }
+}
+
+impl TryFrom<Value> for Params {
+    type Error = SpecViolation;
+
+    fn try_from(value: Value) -> Result<Params, SpecViolation> {
+        match value {
+            Value::Array(values) => Ok(Params::Array(values)),
+            Value::Object(map) => Ok(Params::Map(map)),
+            other => Err(SpecViolation::Params(other)),
+        }
+    }
+}
+
+impl From<Params> for Value {
+    fn from(params: Params) -> Value {
+        match params {
+            Params::Array(values) => Value::Array(values),
+            Params::Map(map) => Value::Object(map),
+        }
+    }
+}
+
+impl From<Vec<Value>> for Params {
+    fn from(values: Vec<Value>) -> Params {
+        Params::Array(values)
+    }
+}
+
+impl From<serde_json::Map<String, Value>> for Params {
+    fn from(map: serde_json::Map<String, Value>) -> Params {
+        Params::Map(map)
+    }
+}
+
+impl<'de> Deserialize<'de> for Params {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Params::try_from(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,568 @@
+//! Results expressed as [RFC 6902](https://tools.ietf.org/html/rfc6902) JSON Patch deltas, and
+//! partial updates expressed as [RFC 7386](https://tools.ietf.org/html/rfc7386) merge patches.
+//!
+//! > Only available with the `json-patch` feature.
+//!
+//! # Deltas
+//!
+//! Subscription feeds can save bandwidth by sending only what changed since the previous result.
+//! A [`PatchResult<T>`](enum.PatchResult.html) is either a full snapshot of `T` or a patch
+//! against an earlier revision:
+//!
+//! ```json
+//! {"snapshot": {"revision": 1, "value": {"price": 10}}}
+//! {"patch": {"base": 1, "revision": 2, "ops": [{"op": "replace", "path": "/price", "value": 11}]}}
+//! ```
+//!
+//! The receiver keeps the latest [`Revision`](struct.Revision.html) and
+//! [applies](enum.PatchResult.html#method.apply) each result to it. A patch against any other
+//! revision than the current one is rejected, in which case the receiver should request a new
+//! snapshot.
+//!
+//! # Partial updates
+//!
+//! `update` style methods conventionally take [`UpdateParams`](struct.UpdateParams.html): the
+//! id of the resource and a [`MergePatch`](struct.MergePatch.html) of the members to change.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::patch::{PatchResult, Revision};
+//!
+//! # fn main() {
+//! let mut current: Option<Revision> = None;
+//!
+//! let first: PatchResult<serde_json::Value> = serde_json::from_value(json!({
+//!     "snapshot": {"revision": 1, "value": {"price": 10, "tags": ["new"]}}
+//! })).unwrap();
+//! first.apply(&mut current).unwrap();
+//!
+//! let second: PatchResult<serde_json::Value> = serde_json::from_value(json!({
+//!     "patch": {"base": 1, "revision": 2, "ops": [
+//!         {"op": "replace", "path": "/price", "value": 11},
+//!         {"op": "remove", "path": "/tags/0"}
+//!     ]}
+//! })).unwrap();
+//! let value = second.apply(&mut current).unwrap();
+//!
+//! assert_eq!(value, json!({"price": 11, "tags": []}));
+//! assert_eq!(current.unwrap().revision, 2);
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use Value;
+
+/// A single JSON Patch operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    /// Add a value to an Object or insert it into an Array.
+    Add {
+        /// A JSON Pointer to the target location.
+        path: String,
+        /// The value to add.
+        value: Value,
+    },
+    /// Remove the value at the target location.
+    Remove {
+        /// A JSON Pointer to the target location.
+        path: String,
+    },
+    /// Replace the value at the target location.
+    Replace {
+        /// A JSON Pointer to the target location.
+        path: String,
+        /// The new value.
+        value: Value,
+    },
+    /// Remove the value at `from` and add it at `path`.
+    Move {
+        /// A JSON Pointer to the source location.
+        from: String,
+        /// A JSON Pointer to the target location.
+        path: String,
+    },
+    /// Copy the value at `from` to `path`.
+    Copy {
+        /// A JSON Pointer to the source location.
+        from: String,
+        /// A JSON Pointer to the target location.
+        path: String,
+    },
+    /// Test that the value at the target location equals `value`.
+    Test {
+        /// A JSON Pointer to the target location.
+        path: String,
+        /// The expected value.
+        value: Value,
+    },
+}
+
+/// A result sent either in full or as a patch against an earlier revision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchResult<T> {
+    /// The complete result.
+    Snapshot {
+        /// The revision of the result.
+        revision: u64,
+        /// The result.
+        value: T,
+    },
+    /// The changes since the revision `base`.
+    Patch {
+        /// The revision the patch applies to.
+        base: u64,
+        /// The revision of the result after applying the patch.
+        revision: u64,
+        /// The operations, applied in order.
+        ops: Vec<PatchOp>,
+    },
+}
+
+/// The latest result known to the receiver of a feed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    /// The revision number.
+    pub revision: u64,
+    /// The result as json.
+    pub value: Value,
+}
+
+impl<T: Serialize + DeserializeOwned> PatchResult<T> {
+    /// The revision of the result.
+    pub fn revision(&self) -> u64 {
+        match *self {
+            PatchResult::Snapshot { revision, .. } | PatchResult::Patch { revision, .. } => {
+                revision
+            }
+        }
+    }
+
+    /// Apply the result to the `current` revision and return the new result.
+    ///
+    /// `current` is only updated on success.
+    pub fn apply(self, current: &mut Option<Revision>) -> Result<T, PatchError> {
+        let (revision, value) = match self {
+            PatchResult::Snapshot { revision, value } => {
+                let json =
+                    serde_json::to_value(&value).map_err(|e| PatchError::Json(e.to_string()))?;
+                *current = Some(Revision {
+                    revision: revision,
+                    value: json,
+                });
+                return Ok(value);
+            }
+            PatchResult::Patch {
+                base,
+                revision,
+                ops,
+            } => {
+                let prior = match *current {
+                    Some(ref prior) if prior.revision == base => prior,
+                    ref other => {
+                        return Err(PatchError::BaseMismatch {
+                            base: base,
+                            current: other.as_ref().map(|r| r.revision),
+                        })
+                    }
+                };
+                let mut value = prior.value.clone();
+                apply(&mut value, &ops)?;
+                (revision, value)
+            }
+        };
+        let result =
+            serde_json::from_value(value.clone()).map_err(|e| PatchError::Json(e.to_string()))?;
+        *current = Some(Revision {
+            revision: revision,
+            value: value,
+        });
+        Ok(result)
+    }
+}
+
+/// Apply a patch to `doc`.
+///
+/// The patch is applied atomically: on error `doc` is left unchanged.
+pub fn apply(doc: &mut Value, ops: &[PatchOp]) -> Result<(), PatchError> {
+    validate(ops)?;
+    let mut patched = doc.clone();
+    for op in ops {
+        apply_op(&mut patched, op)?;
+    }
+    *doc = patched;
+    Ok(())
+}
+
+/// Check that a patch is well formed, without applying it.
+///
+/// All pointers must be valid and no value may be moved into one of its own children.
+pub fn validate(ops: &[PatchOp]) -> Result<(), PatchError> {
+    for op in ops {
+        match *op {
+            PatchOp::Add { ref path, .. }
+            | PatchOp::Remove { ref path }
+            | PatchOp::Replace { ref path, .. }
+            | PatchOp::Test { ref path, .. } => {
+                parse_pointer(path)?;
+            }
+            PatchOp::Copy { ref from, ref path } => {
+                parse_pointer(from)?;
+                parse_pointer(path)?;
+            }
+            PatchOp::Move { ref from, ref path } => {
+                let from_tokens = parse_pointer(from)?;
+                let path_tokens = parse_pointer(path)?;
+                if path_tokens.len() > from_tokens.len() && path_tokens.starts_with(&from_tokens) {
+                    return Err(PatchError::MoveIntoChild(path.clone()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn apply_op(doc: &mut Value, op: &PatchOp) -> Result<(), PatchError> {
+    match *op {
+        PatchOp::Add {
+            ref path,
+            ref value,
+        } => add(doc, path, value.clone()),
+        PatchOp::Remove { ref path } => remove(doc, path).map(|_| ()),
+        PatchOp::Replace {
+            ref path,
+            ref value,
+        } => {
+            let target = doc
+                .pointer_mut(path)
+                .ok_or_else(|| PatchError::NotFound(path.clone()))?;
+            *target = value.clone();
+            Ok(())
+        }
+        PatchOp::Move { ref from, ref path } => {
+            if from == path {
+                return Ok(());
+            }
+            let value = remove(doc, from)?;
+            add(doc, path, value)
+        }
+        PatchOp::Copy { ref from, ref path } => {
+            let value = doc
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| PatchError::NotFound(from.clone()))?;
+            add(doc, path, value)
+        }
+        PatchOp::Test {
+            ref path,
+            ref value,
+        } => match doc.pointer(path) {
+            Some(actual) if actual == value => Ok(()),
+            Some(_) => Err(PatchError::TestFailed(path.clone())),
+            None => Err(PatchError::NotFound(path.clone())),
+        },
+    }
+}
+
+/// Split a JSON Pointer into its unescaped reference tokens.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, PatchError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PatchError::InvalidPointer(pointer.to_string()));
+    }
+    pointer[1..]
+        .split('/')
+        .map(|token| {
+            let mut out = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '~' => match chars.next() {
+                        Some('0') => out.push('~'),
+                        Some('1') => out.push('/'),
+                        _ => return Err(PatchError::InvalidPointer(pointer.to_string())),
+                    },
+                    c => out.push(c),
+                }
+            }
+            Ok(out)
+        })
+        .collect()
+}
+
+/// Split a pointer into the pointer of its parent and the last (unescaped) token.
+fn split_last(pointer: &str) -> Result<(&str, String), PatchError> {
+    let index = pointer
+        .rfind('/')
+        .ok_or_else(|| PatchError::InvalidPointer(pointer.to_string()))?;
+    let mut last = parse_pointer(&pointer[index..])?;
+    Ok((&pointer[..index], last.pop().unwrap_or_default()))
+}
+
+fn array_index(token: &str, len: usize, path: &str) -> Result<usize, PatchError> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(index) if valid && index <= len => Ok(index),
+        _ => Err(PatchError::InvalidIndex(path.to_string())),
+    }
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+    let (parent, token) = split_last(path)?;
+    match doc.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(token, value);
+            Ok(())
+        }
+        Some(Value::Array(array)) => {
+            let index = if token == "-" {
+                array.len()
+            } else {
+                array_index(&token, array.len(), path)?
+            };
+            array.insert(index, value);
+            Ok(())
+        }
+        Some(_) => Err(PatchError::InvalidIndex(path.to_string())),
+        None => Err(PatchError::NotFound(parent.to_string())),
+    }
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value, PatchError> {
+    if path.is_empty() {
+        return Ok(::std::mem::replace(doc, Value::Null));
+    }
+    let (parent, token) = split_last(path)?;
+    let removed = match doc.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&token),
+        Some(Value::Array(array)) => {
+            let index = array_index(&token, array.len(), path)?;
+            if index < array.len() {
+                Some(array.remove(index))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    };
+    removed.ok_or_else(|| PatchError::NotFound(path.to_string()))
+}
+
+/// An [RFC 7386](https://tools.ietf.org/html/rfc7386) JSON Merge Patch.
+///
+/// Members of the patch replace the members of the target, recursively for Objects; a Null
+/// member removes the target member. Serialized as the patch document itself.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// #[macro_use]
+/// extern crate serde_json;
+/// use jrpc::patch::{MergePatch, UpdateParams};
+///
+/// # fn main() {
+/// let params: UpdateParams<u64> = serde_json::from_value(json!({
+///     "id": 7,
+///     "patch": {"title": "Hello!", "author": {"phone": null}},
+/// })).unwrap();
+/// params.patch.validate(&["title", "author"]).unwrap();
+/// assert!(params.patch.validate(&["title"]).is_err());
+///
+/// let mut doc = json!({"title": "Goodbye!", "author": {"name": "John", "phone": "555"}});
+/// params.patch.apply(&mut doc);
+/// assert_eq!(doc, json!({"title": "Hello!", "author": {"name": "John"}}));
+///
+/// let old = json!({"a": 1, "b": {"c": 2}});
+/// let new = json!({"b": {"c": 3}});
+/// let mut patched = old.clone();
+/// MergePatch::diff(&old, &new).apply(&mut patched);
+/// assert_eq!(patched, new);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergePatch(pub Value);
+
+impl MergePatch {
+    /// Compute the merge patch turning `old` into `new`.
+    ///
+    /// Null members within `new` Objects can not be expressed by a merge patch (they mean
+    /// removal) and are dropped.
+    pub fn diff(old: &Value, new: &Value) -> MergePatch {
+        MergePatch(merge_diff(old, new))
+    }
+
+    /// Whether the patch changes nothing, i.e. is an empty Object.
+    pub fn is_empty(&self) -> bool {
+        self.0.as_object().is_some_and(|o| o.is_empty())
+    }
+
+    /// Apply the patch to `target`.
+    pub fn apply(&self, target: &mut Value) {
+        merge(target, &self.0);
+    }
+
+    /// Apply the patch to a typed value, returning the patched value.
+    pub fn apply_to<T>(&self, target: &T) -> Result<T, PatchError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut value =
+            serde_json::to_value(target).map_err(|e| PatchError::Json(e.to_string()))?;
+        self.apply(&mut value);
+        serde_json::from_value(value).map_err(|e| PatchError::Json(e.to_string()))
+    }
+
+    /// Check that the patch is suitable for an update method: it must be an Object and may only
+    /// change the top level members listed in `mutable`.
+    pub fn validate(&self, mutable: &[&str]) -> Result<(), PatchError> {
+        let object = self.0.as_object().ok_or(PatchError::NotAnObject)?;
+        match object.keys().find(|k| !mutable.contains(&k.as_str())) {
+            Some(key) => Err(PatchError::Immutable(key.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The conventional `params` of an `update` style method: the id of the resource and a merge
+/// patch to apply to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateParams<K> {
+    /// The id of the resource to update.
+    pub id: K,
+    /// The changes.
+    pub patch: MergePatch,
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    let patch = match *patch {
+        Value::Object(ref patch) => patch,
+        ref other => {
+            *target = other.clone();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let target = target.as_object_mut().expect("target is an Object");
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn merge_diff(old: &Value, new: &Value) -> Value {
+    let (old, new) = match (old, new) {
+        (Value::Object(old), Value::Object(new)) => (old, new),
+        (_, new) => return strip_nulls(new),
+    };
+    let mut patch = serde_json::Map::new();
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            patch.insert(key.clone(), Value::Null);
+        }
+    }
+    for (key, value) in new {
+        match old.get(key) {
+            Some(previous) if previous == value => {}
+            Some(previous) => {
+                patch.insert(key.clone(), merge_diff(previous, value));
+            }
+            None if value.is_null() => {}
+            None => {
+                patch.insert(key.clone(), strip_nulls(value));
+            }
+        }
+    }
+    Value::Object(patch)
+}
+
+fn strip_nulls(value: &Value) -> Value {
+    match *value {
+        Value::Object(ref object) => Value::Object(
+            object
+                .iter()
+                .filter(|&(_, v)| !v.is_null())
+                .map(|(k, v)| (k.clone(), strip_nulls(v)))
+                .collect(),
+        ),
+        ref other => other.clone(),
+    }
+}
+
+/// The error returned when a patch can not be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// A JSON Pointer is malformed.
+    InvalidPointer(String),
+    /// The target location does not exist.
+    NotFound(String),
+    /// An Array index is malformed or out of bounds, or the parent is not a container.
+    InvalidIndex(String),
+    /// A `test` operation failed.
+    TestFailed(String),
+    /// A `move` operation would move a value into one of its own children.
+    MoveIntoChild(String),
+    /// The patch applies to a different revision than the current one.
+    BaseMismatch {
+        /// The revision the patch applies to.
+        base: u64,
+        /// The current revision, if any.
+        current: Option<u64>,
+    },
+    /// The result could not be (de)serialized.
+    Json(String),
+    /// A merge patch for an update method is not an Object.
+    NotAnObject,
+    /// A merge patch changes a member which may not be changed.
+    Immutable(String),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PatchError::InvalidPointer(ref p) => write!(f, "invalid JSON Pointer {:?}", p),
+            PatchError::NotFound(ref p) => write!(f, "no value at {:?}", p),
+            PatchError::InvalidIndex(ref p) => write!(f, "invalid array index at {:?}", p),
+            PatchError::TestFailed(ref p) => write!(f, "test failed at {:?}", p),
+            PatchError::MoveIntoChild(ref p) => write!(f, "cannot move a value into {:?}", p),
+            PatchError::BaseMismatch { base, current } => match current {
+                Some(current) => write!(
+                    f,
+                    "patch applies to revision {} but the current revision is {}",
+                    base, current
+                ),
+                None => write!(f, "patch applies to revision {} but there is none", base),
+            },
+            PatchError::Json(ref err) => write!(f, "invalid result: {}", err),
+            PatchError::NotAnObject => write!(f, "merge patch must be an Object"),
+            PatchError::Immutable(ref key) => write!(f, "member `{}` may not be changed", key),
+        }
+    }
+}
+
+impl error::Error for PatchError {}
@@ -0,0 +1,199 @@
+//! Long polling, for clients which can not hold a connection open.
+//!
+//! Such clients receive their notifications by repeatedly calling
+//! [`rpc.poll`](constant.POLL_METHOD.html) with the cursor returned by the previous call. The
+//! server answers as soon as there are events after the cursor, or with an empty result after
+//! `max_wait_ms`. Events up to the cursor of a call are acknowledged and discarded.
+//!
+//! The server queues the notifications of each session in a [`PollBuffer`](struct.PollBuffer.html).
+//! The buffer does not wait itself: if [`poll`](struct.PollBuffer.html#method.poll) returns
+//! `None`, the caller should park the Request for at most
+//! [`wait`](struct.PollBuffer.html#method.wait) and poll again once events are pushed.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{IdReq, Request, Value};
+//! use jrpc::poll::{PollBuffer, PollParams};
+//!
+//! # fn main() {
+//! let mut buffer = PollBuffer::new(100);
+//!
+//! let params = PollParams { session: "s1".into(), cursor: 0, max_wait_ms: 30_000, max_events: None };
+//! assert!(buffer.poll(&params).is_none());
+//!
+//! buffer.push("s1", &Request::with_params(IdReq::Notification, "progress".to_string(), 50));
+//! buffer.push("s1", &Request::with_params(IdReq::Notification, "progress".to_string(), 100));
+//!
+//! let result = buffer.poll(&params).unwrap();
+//! assert_eq!(result.events.len(), 2);
+//! assert_eq!(result.events[1]["params"], Value::from(100));
+//!
+//! // The next call acknowledges both events.
+//! let params = PollParams { cursor: result.cursor, ..params };
+//! assert!(buffer.poll(&params).is_none());
+//! assert_eq!(buffer.pending("s1"), 0);
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use memory::MemoryUsage;
+use Value;
+
+/// The method name of the long poll.
+pub const POLL_METHOD: &str = "rpc.poll";
+
+/// The `params` of an [`rpc.poll`](constant.POLL_METHOD.html) Request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollParams {
+    /// The session token.
+    pub session: String,
+
+    /// The cursor returned by the previous poll, 0 for the first one.
+    #[serde(default)]
+    pub cursor: u64,
+
+    /// How long the server may hold the Request if there are no events.
+    pub max_wait_ms: u64,
+
+    /// The maximum number of events to return.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_events: Option<usize>,
+}
+
+/// The `result` of an [`rpc.poll`](constant.POLL_METHOD.html) Request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollResult {
+    /// The cursor to pass to the next poll.
+    pub cursor: u64,
+
+    /// The events after the previous cursor, oldest first. Usually notifications.
+    pub events: Vec<Value>,
+
+    /// The number of events which were dropped because the session's queue overflowed.
+    #[serde(default)]
+    pub dropped: u64,
+
+    /// Whether more events are immediately available.
+    #[serde(default)]
+    pub more: bool,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    /// The sequence number of the last pushed event.
+    last: u64,
+    events: VecDeque<(u64, Value)>,
+    dropped: u64,
+}
+
+/// Queues events per session until they are polled.
+#[derive(Debug)]
+pub struct PollBuffer {
+    max_events: usize,
+    max_wait: Duration,
+    sessions: BTreeMap<String, Queue>,
+}
+
+impl PollBuffer {
+    /// Create a buffer queueing at most `max_events` per session. Older events are dropped
+    /// when a queue is full.
+    pub fn new(max_events: usize) -> Self {
+        PollBuffer {
+            max_events: max_events,
+            max_wait: Duration::from_secs(60),
+            sessions: BTreeMap::new(),
+        }
+    }
+
+    /// Set the longest time the server is willing to hold a poll. Defaults to 60 seconds.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Queue an event for a session.
+    ///
+    /// # Panics
+    ///
+    /// If the event fails to serialize.
+    pub fn push<T: Serialize>(&mut self, session: &str, event: &T) {
+        let event = serde_json::to_value(event).expect("event must be serializable");
+        let max_events = self.max_events;
+        let queue = self.sessions.entry(session.to_string()).or_default();
+        queue.last += 1;
+        queue.events.push_back((queue.last, event));
+        while queue.events.len() > max_events {
+            queue.events.pop_front();
+            queue.dropped += 1;
+        }
+    }
+
+    /// Answer a poll, or return `None` if there are no events after the cursor yet.
+    ///
+    /// The events up to `params.cursor` are discarded in either case.
+    pub fn poll(&mut self, params: &PollParams) -> Option<PollResult> {
+        let queue = self.sessions.get_mut(&params.session)?;
+        while queue.events.front().is_some_and(|e| e.0 <= params.cursor) {
+            queue.events.pop_front();
+        }
+        if queue.events.is_empty() {
+            return None;
+        }
+        let take = params.max_events.unwrap_or(usize::MAX).max(1);
+        let events: Vec<_> = queue.events.iter().take(take).collect();
+        let cursor = events.last().map(|e| e.0).unwrap_or(params.cursor);
+        Some(PollResult {
+            cursor: cursor,
+            events: events.into_iter().map(|e| e.1.clone()).collect(),
+            dropped: ::std::mem::replace(&mut queue.dropped, 0),
+            more: queue.events.len() > take,
+        })
+    }
+
+    /// How long to hold a poll which returned `None`: the requested `max_wait_ms`, capped by
+    /// the server's [`max_wait`](#method.max_wait).
+    pub fn wait(&self, params: &PollParams) -> Duration {
+        Duration::from_millis(params.max_wait_ms).min(self.max_wait)
+    }
+
+    /// The number of events queued for a session.
+    pub fn pending(&self, session: &str) -> usize {
+        self.sessions.get(session).map_or(0, |q| q.events.len())
+    }
+
+    /// Drop a session and its queued events.
+    pub fn remove_session(&mut self, session: &str) -> bool {
+        self.sessions.remove(session).is_some()
+    }
+}
+
+impl MemoryUsage for PollBuffer {
+    fn memory_usage(&self) -> usize {
+        self.sessions
+            .iter()
+            .map(|(session, queue)| {
+                let events: usize = queue.events.iter().map(|e| event_size(&e.1)).sum();
+                session.len() + events
+            })
+            .sum()
+    }
+
+    /// Drop the oldest event of the longest queue. It is counted as `dropped`.
+    fn shed_oldest(&mut self) -> Option<usize> {
+        let queue = self.sessions.values_mut().max_by_key(|q| q.events.len())?;
+        let (_, event) = queue.events.pop_front()?;
+        queue.dropped += 1;
+        Some(event_size(&event))
+    }
+}
+
+fn event_size(event: &Value) -> usize {
+    8 + serde_json::to_vec(event).map_or(0, |data| data.len())
+}
@@ -0,0 +1,131 @@
+//! Progress and partial result Notifications of long running Requests.
+//!
+//! # Protocol
+//!
+//! The client picks a [`ProgressToken`](enum.ProgressToken.html), an integer or a String, and
+//! passes it to the server with the Request, e.g. in its params. The server reports progress, or
+//! partial results, with `$/progress` Notifications carrying the token and a value whose type is
+//! agreed upon by the method, as in the Language Server Protocol:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "method": "$/progress", "params": {"token": "index-1", "value": 40}}
+//! ```
+//!
+//! Servers create the Notification with
+//! [`Notification::progress`](../struct.Notification.html#method.progress) and clients recognize
+//! it in the incoming [`Message`](../enum.Message.html)s with
+//! [`progress_of`](fn.progress_of.html), then dispatch on the token.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{Message, Notification};
+//! use jrpc::progress::{self, ProgressToken};
+//!
+//! # fn main() {
+//! // Server side.
+//! let token = ProgressToken::from("index-1");
+//! let json = Notification::progress(token.clone(), 40).to_string();
+//! assert_eq!(
+//!     json,
+//!     r#"{"jsonrpc":"2.0","method":"$/progress","params":{"token":"index-1","value":40}}"#,
+//! );
+//!
+//! // Client side.
+//! let message: Message = serde_json::from_str(&json).unwrap();
+//! let params = progress::progress_of::<u8>(&message).unwrap();
+//! assert_eq!(params.token, token);
+//! assert_eq!(params.value, 40);
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {Message, Notification, Value};
+
+/// The method of progress Notifications.
+pub const PROGRESS_METHOD: &str = "$/progress";
+
+/// Identifies the progress of a Request, chosen by the client.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ProgressToken {
+    /// An integer token.
+    Int(i64),
+    /// A String token.
+    String(String),
+}
+
+impl From<i64> for ProgressToken {
+    fn from(token: i64) -> Self {
+        ProgressToken::Int(token)
+    }
+}
+
+impl From<String> for ProgressToken {
+    fn from(token: String) -> Self {
+        ProgressToken::String(token)
+    }
+}
+
+impl<'a> From<&'a str> for ProgressToken {
+    fn from(token: &'a str) -> Self {
+        ProgressToken::String(token.to_string())
+    }
+}
+
+impl fmt::Display for ProgressToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProgressToken::Int(token) => write!(f, "{}", token),
+            ProgressToken::String(ref token) => f.write_str(token),
+        }
+    }
+}
+
+/// The params of the [`PROGRESS_METHOD`](constant.PROGRESS_METHOD.html) Notification.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProgressParams<T = Value> {
+    /// The token of the Request making progress.
+    pub token: ProgressToken,
+
+    /// The progress, or partial result.
+    pub value: T,
+}
+
+impl<T> ProgressParams<T> {
+    /// Create the params reporting `value` for `token`.
+    pub fn new<P: Into<ProgressToken>>(token: P, value: T) -> Self {
+        ProgressParams {
+            token: token.into(),
+            value: value,
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Notification<String, ProgressParams<T>> {
+    /// Create the Notification reporting `value` for `token`.
+    pub fn progress<P: Into<ProgressToken>>(token: P, value: T) -> Self {
+        Notification::with_params(
+            PROGRESS_METHOD.to_string(),
+            ProgressParams::new(token, value),
+        )
+    }
+}
+
+/// The params of `message`, if it is a progress Notification whose value deserializes as `T`.
+pub fn progress_of<T: DeserializeOwned>(message: &Message) -> Option<ProgressParams<T>> {
+    match *message {
+        Message::Notification(ref notification) if notification.method == PROGRESS_METHOD => {
+            serde_json::from_value(notification.params.clone()?).ok()
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,280 @@
+//! Streaming the `params` of a Request.
+//!
+//! The mirror of the [`writer`](../writer/index.html) module: a
+//! [`ParamsReader`](struct.ParamsReader.html) deserializes a Request straight from an
+//! `io::Read` and hands the elements of its by-position `params` to a callback one at a time, so
+//! huge Arrays are processed without ever being held in memory, neither as json text nor as a
+//! `Value`.
+//!
+//! The members of the Request may come in any order, so elements can be handed over before the
+//! `method` and `id` are known. They are returned in the [`Head`](struct.Head.html) once the
+//! whole Request was read. By-name params (an Object) are rejected, as they can not be streamed
+//! element by element.
+//!
+//! Errors are reported like [`parse_request`](../fn.parse_request.html) does: invalid json is a
+//! `ParseError` and a malformed Request an `InvalidRequest`. An element which is not a `T` is an
+//! `InvalidParams`. Unless the json is invalid, the Error answers the `id` if it was read before
+//! the error. An error returned by the callback stops the reading and is returned as is.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{ErrorCode, Id, IdReq};
+//! use jrpc::reader::{Head, ParamsReader, ReadError};
+//!
+//! # fn main() {
+//! let json = r#"{"jsonrpc": "2.0", "params": [1, 2, 3, 4], "method": "sum", "id": 7}"#;
+//! let mut sum = 0;
+//! let head: Head<String> = ParamsReader::new(json.as_bytes())
+//!     .for_each(|n: u64| -> Result<(), String> {
+//!         sum += n;
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! assert_eq!(sum, 10);
+//! assert_eq!(head.method, "sum");
+//! assert_eq!(head.id, IdReq::Int(7));
+//! assert_eq!(head.elements, 4);
+//!
+//! // The callback can stop the reading.
+//! let result = ParamsReader::new(json.as_bytes()).for_each::<String, u64, _, _>(|n| {
+//!     if n < 3 { Ok(()) } else { Err("too big") }
+//! });
+//! assert_eq!(result.unwrap_err(), ReadError::Callback("too big"));
+//!
+//! let json = r#"{"jsonrpc": "2.0", "params": {"a": 1}, "method": "sum", "id": 7}"#;
+//! match ParamsReader::new(json.as_bytes()).for_each::<String, u64, String, _>(|_| Ok(())) {
+//!     Err(ReadError::Request(error)) => assert_eq!(error.error.code, ErrorCode::InvalidRequest),
+//!     other => panic!("{:?}", other),
+//! }
+//!
+//! let json = r#"{"jsonrpc": "2.0", "id": 7, "method": "sum", "params": [1, "two"]}"#;
+//! match ParamsReader::new(json.as_bytes()).for_each::<String, u64, String, _>(|_| Ok(())) {
+//!     Err(ReadError::Request(error)) => {
+//!         assert_eq!(error.error.code, ErrorCode::InvalidParams);
+//!         assert_eq!(error.id, Id::from(7));
+//!     }
+//!     other => panic!("{:?}", other),
+//! }
+//! # }
+//! ```
+
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny, MapAccess};
+use serde::de::{SeqAccess, Visitor};
+use serde_json;
+use std_prelude::*;
+
+use {Error, ErrorCode, Id, IdReq, Value, V2_0};
+
+/// The members of a streamed Request, other than the `params`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Head<M> {
+    /// The method.
+    pub method: M,
+
+    /// The `id`, `Notification` if absent.
+    pub id: IdReq,
+
+    /// The number of elements of the `params` handed to the callback.
+    pub elements: usize,
+}
+
+/// Why reading a Request failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadError<E> {
+    /// The Request is not valid, and should be answered with this Error.
+    Request(Error<Value>),
+    /// The callback failed.
+    Callback(E),
+}
+
+/// Reads a Request, streaming the elements of its `params`.
+#[derive(Debug)]
+pub struct ParamsReader<R> {
+    reader: R,
+}
+
+impl<R: io::Read> ParamsReader<R> {
+    /// Read a Request from `reader`.
+    pub fn new(reader: R) -> Self {
+        ParamsReader { reader: reader }
+    }
+
+    /// Read the Request, calling `f` with every element of the `params` in order. Missing or
+    /// Null `params` have no elements.
+    pub fn for_each<M, T, E, F>(self, f: F) -> Result<Head<M>, ReadError<E>>
+    where
+        M: DeserializeOwned,
+        T: DeserializeOwned,
+        F: FnMut(T) -> Result<(), E>,
+    {
+        let mut state = State {
+            failed: None,
+            id: None,
+            in_element: false,
+        };
+        let mut deserializer = serde_json::Deserializer::from_reader(self.reader);
+        let result = RequestSeed {
+            f: f,
+            state: &mut state,
+            _types: PhantomData,
+        }
+        .deserialize(&mut deserializer)
+        .and_then(|head| deserializer.end().map(|_| head));
+        match (result, state.failed) {
+            (_, Some(error)) => Err(ReadError::Callback(error)),
+            (Ok(head), None) => Ok(head),
+            (Err(error), None) => {
+                if error.is_syntax() || error.is_eof() || error.is_io() {
+                    let error =
+                        Error::new(Id::Null, ErrorCode::ParseError, error.to_string(), None);
+                    return Err(ReadError::Request(error));
+                }
+                let code = if state.in_element {
+                    ErrorCode::InvalidParams
+                } else {
+                    ErrorCode::InvalidRequest
+                };
+                let id = state.id.and_then(IdReq::to_id).unwrap_or(Id::Null);
+                Err(ReadError::Request(Error::new(
+                    id,
+                    code,
+                    error.to_string(),
+                    None,
+                )))
+            }
+        }
+    }
+}
+
+/// What is known about the Request when reading it fails.
+struct State<E> {
+    /// The error of the callback.
+    failed: Option<E>,
+    /// The `id`, if it was read.
+    id: Option<IdReq>,
+    /// Whether an element was being read.
+    in_element: bool,
+}
+
+struct RequestSeed<'a, M, T, E: 'a, F> {
+    f: F,
+    state: &'a mut State<E>,
+    _types: PhantomData<(M, T)>,
+}
+
+impl<'de, 'a, M, T, E, F> DeserializeSeed<'de> for RequestSeed<'a, M, T, E, F>
+where
+    M: DeserializeOwned,
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<(), E>,
+{
+    type Value = Head<M>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Head<M>, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a, M, T, E, F> Visitor<'de> for RequestSeed<'a, M, T, E, F>
+where
+    M: DeserializeOwned,
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<(), E>,
+{
+    type Value = Head<M>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a Request object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<Head<M>, A::Error> {
+        let (mut jsonrpc, mut method, mut elements) = (false, None, 0);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "jsonrpc" => {
+                    map.next_value::<V2_0>()?;
+                    jsonrpc = true;
+                }
+                "method" => method = Some(map.next_value::<M>()?),
+                "id" => self.state.id = Some(map.next_value::<IdReq>()?),
+                "params" => {
+                    elements = map.next_value_seed(ElementsSeed {
+                        f: &mut self.f,
+                        state: &mut *self.state,
+                        _types: PhantomData,
+                    })?
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !jsonrpc {
+            return Err(de::Error::missing_field("jsonrpc"));
+        }
+        Ok(Head {
+            method: method.ok_or_else(|| de::Error::missing_field("method"))?,
+            id: self.state.id.take().unwrap_or(IdReq::Notification),
+            elements: elements,
+        })
+    }
+}
+
+struct ElementsSeed<'a, T, E: 'a, F: 'a> {
+    f: &'a mut F,
+    state: &'a mut State<E>,
+    _types: PhantomData<T>,
+}
+
+impl<'de, 'a, T, E, F> DeserializeSeed<'de> for ElementsSeed<'a, T, E, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<(), E>,
+{
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a, T, E, F> Visitor<'de> for ElementsSeed<'a, T, E, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<(), E>,
+{
+    type Value = usize;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("by-position params")
+    }
+
+    fn visit_unit<Err: de::Error>(self) -> Result<usize, Err> {
+        Ok(0)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let mut n = 0;
+        loop {
+            self.state.in_element = true;
+            let element = match seq.next_element::<T>()? {
+                Some(element) => element,
+                None => break,
+            };
+            self.state.in_element = false;
+            if let Err(error) = (self.f)(element) {
+                self.state.failed = Some(error);
+                return Err(de::Error::custom("stopped by the callback"));
+            }
+            n += 1;
+        }
+        self.state.in_element = false;
+        Ok(n)
+    }
+}
@@ -0,0 +1,278 @@
+//! Conventional CRUD methods for a resource.
+//!
+//! Implementing [`Resource`](trait.Resource.html) for a type describes a resource by name and its
+//! serde types, from which the conventional method set is derived:
+//!
+//! | Method       | `params`                  | `result`              |
+//! |--------------|---------------------------|-----------------------|
+//! | `foo.create` | `R::Create`               | `R::Item`             |
+//! | `foo.get`    | [`IdParams<R::Id>`](struct.IdParams.html) | `R::Item` |
+//! | `foo.list`   | `R::Filter`               | `Vec<R::Item>`        |
+//! | `foo.update` | `R::Update`               | `R::Item`             |
+//! | `foo.delete` | [`IdParams<R::Id>`](struct.IdParams.html) | Null      |
+//!
+//! Each method is a typed [`Method`](../method/trait.Method.html), e.g. [`Get<R>`](struct.Get.html).
+//! On the server, implement [`ResourceHandler`](trait.ResourceHandler.html) and route the
+//! resource's Requests to it with [`handle`](fn.handle.html).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_json;
+//! use std::collections::BTreeMap;
+//! use jrpc::{ErrorCode, ErrorObject, Id, Value};
+//! use jrpc::method::Method;
+//! use jrpc::resource::{self, Get, IdParams, Resource, ResourceHandler};
+//!
+//! #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+//! struct Book {
+//!     id: u64,
+//!     title: String,
+//! }
+//!
+//! impl Resource for Book {
+//!     const NAME: &'static str = "book";
+//!     type Id = u64;
+//!     type Item = Book;
+//!     type Create = String;
+//!     type Filter = ();
+//!     type Update = Book;
+//! }
+//!
+//! #[derive(Default)]
+//! struct Library(BTreeMap<u64, Book>);
+//!
+//! fn not_found() -> ErrorObject<Value> {
+//!     ErrorObject { code: ErrorCode::from(-32001), message: "no such book".into(), data: None }
+//! }
+//!
+//! impl ResourceHandler<Book> for Library {
+//!     fn create(&mut self, title: String) -> Result<Book, ErrorObject<Value>> {
+//!         let book = Book { id: self.0.len() as u64 + 1, title: title };
+//!         self.0.insert(book.id, book.clone());
+//!         Ok(book)
+//!     }
+//!     fn get(&mut self, id: u64) -> Result<Book, ErrorObject<Value>> {
+//!         self.0.get(&id).cloned().ok_or_else(not_found)
+//!     }
+//!     fn list(&mut self, _: ()) -> Result<Vec<Book>, ErrorObject<Value>> {
+//!         Ok(self.0.values().cloned().collect())
+//!     }
+//!     fn update(&mut self, book: Book) -> Result<Book, ErrorObject<Value>> {
+//!         self.0.insert(book.id, book.clone());
+//!         Ok(book)
+//!     }
+//!     fn delete(&mut self, id: u64) -> Result<(), ErrorObject<Value>> {
+//!         self.0.remove(&id).map(|_| ()).ok_or_else(not_found)
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let mut library = Library::default();
+//! let json = r#"{"jsonrpc": "2.0", "method": "book.create", "params": "Dune", "id": 1}"#;
+//! let request = jrpc::parse_request::<String>(json).unwrap();
+//! assert!(resource::handles::<Book>(&request.method));
+//! resource::handle::<Book, _>(&mut library, request).unwrap();
+//!
+//! // A typed client call.
+//! let request = Get::<Book>::request(Id::from(2), IdParams { id: 1 });
+//! assert_eq!(request.method, "book.get");
+//! let request = jrpc::parse_request::<String>(&request.to_string()).unwrap();
+//! let response = resource::handle::<Book, _>(&mut library, request).unwrap();
+//! let book = Get::<Book>::decode_result(response).unwrap();
+//! assert_eq!(book.title, "Dune");
+//! # }
+//! ```
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use method::Method;
+use {Error, ErrorCode, ErrorObject, Id, Request, Response, Value};
+
+/// The suffixes of the conventional methods, in the order create, get, list, update, delete.
+pub const SUFFIXES: [&str; 5] = ["create", "get", "list", "update", "delete"];
+
+/// A resource exposed through the conventional CRUD methods.
+pub trait Resource {
+    /// The resource name, the prefix of its method names.
+    const NAME: &'static str;
+
+    /// The type identifying a single item.
+    type Id: Serialize + DeserializeOwned;
+
+    /// The representation of an item.
+    type Item: Serialize + DeserializeOwned;
+
+    /// The `params` of `create`.
+    type Create: Serialize + DeserializeOwned;
+
+    /// The `params` of `list`. Use `()` for no filter.
+    type Filter: Serialize + DeserializeOwned;
+
+    /// The `params` of `update`, e.g. the item itself or a
+    /// [`patch::UpdateParams`](../patch/struct.UpdateParams.html).
+    type Update: Serialize + DeserializeOwned;
+}
+
+/// The `params` of the `get` and `delete` methods.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdParams<K> {
+    /// The id of the item.
+    pub id: K,
+}
+
+fn method_name<R: Resource>(suffix: &str) -> Cow<'static, str> {
+    Cow::Owned(format!("{}.{}", R::NAME, suffix))
+}
+
+macro_rules! resource_method {
+    ($name:ident, $suffix:expr, $params:ty, $result:ty, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Debug)]
+        pub struct $name<R>(PhantomData<R>);
+
+        impl<R: Resource> Method for $name<R> {
+            type Params = $params;
+            type Result = $result;
+            type ErrorData = Value;
+
+            fn name() -> Cow<'static, str> {
+                method_name::<R>($suffix)
+            }
+        }
+    };
+}
+
+resource_method!(
+    Create,
+    "create",
+    R::Create,
+    R::Item,
+    "The `create` method of the resource `R`."
+);
+resource_method!(
+    Get,
+    "get",
+    IdParams<R::Id>,
+    R::Item,
+    "The `get` method of the resource `R`."
+);
+resource_method!(
+    List,
+    "list",
+    R::Filter,
+    Vec<R::Item>,
+    "The `list` method of the resource `R`."
+);
+resource_method!(
+    Update,
+    "update",
+    R::Update,
+    R::Item,
+    "The `update` method of the resource `R`."
+);
+resource_method!(
+    Delete,
+    "delete",
+    IdParams<R::Id>,
+    (),
+    "The `delete` method of the resource `R`."
+);
+
+/// The names of the methods of the resource `R`, in the order of [`SUFFIXES`](constant.SUFFIXES.html).
+pub fn method_names<R: Resource>() -> Vec<String> {
+    SUFFIXES
+        .iter()
+        .map(|s| method_name::<R>(s).into_owned())
+        .collect()
+}
+
+/// Whether `method` is one of the methods of the resource `R`.
+pub fn handles<R: Resource>(method: &str) -> bool {
+    match split_method(method) {
+        Some((name, suffix)) => name == R::NAME && SUFFIXES.contains(&suffix),
+        None => false,
+    }
+}
+
+/// Split `foo.get` into the resource name and the suffix. The resource name may contain dots.
+fn split_method(method: &str) -> Option<(&str, &str)> {
+    method.rfind('.').map(|i| (&method[..i], &method[i + 1..]))
+}
+
+/// The server side implementation of the resource `R`.
+pub trait ResourceHandler<R: Resource> {
+    /// Create a new item.
+    fn create(&mut self, params: R::Create) -> Result<R::Item, ErrorObject<Value>>;
+    /// Get an item.
+    fn get(&mut self, id: R::Id) -> Result<R::Item, ErrorObject<Value>>;
+    /// List the items matching `filter`.
+    fn list(&mut self, filter: R::Filter) -> Result<Vec<R::Item>, ErrorObject<Value>>;
+    /// Update an item.
+    fn update(&mut self, params: R::Update) -> Result<R::Item, ErrorObject<Value>>;
+    /// Delete an item.
+    fn delete(&mut self, id: R::Id) -> Result<(), ErrorObject<Value>>;
+}
+
+/// Dispatch a Request for one of the methods of `R` to `handler`.
+///
+/// Returns `None` for notifications. Methods not belonging to `R` (see
+/// [`handles`](fn.handles.html)) get a `MethodNotFound` error.
+pub fn handle<R, H>(handler: &mut H, request: Request<String, Value>) -> Option<Response<Value>>
+where
+    R: Resource,
+    H: ResourceHandler<R>,
+{
+    let id = request.id.clone().to_id();
+    let suffix = if handles::<R>(&request.method) {
+        split_method(&request.method).map(|(_, s)| s.to_string())
+    } else {
+        None
+    };
+    let result = match suffix.as_deref() {
+        Some("create") => call::<Create<R>, _>(request, |p| handler.create(p)),
+        Some("get") => call::<Get<R>, _>(request, |p| handler.get(p.id)),
+        Some("list") => call::<List<R>, _>(request, |p| handler.list(p)),
+        Some("update") => call::<Update<R>, _>(request, |p| handler.update(p)),
+        Some("delete") => call::<Delete<R>, _>(request, |p| handler.delete(p.id)),
+        _ => Err(Error::new(
+            id.clone().unwrap_or(Id::Null),
+            ErrorCode::MethodNotFound,
+            format!("method `{}` not found", request.method),
+            None,
+        )),
+    };
+    let id = id?;
+    Some(match result {
+        Ok(value) => Response::success(id, value),
+        Err(mut error) => {
+            error.id = id;
+            Response::Err(error)
+        }
+    })
+}
+
+fn call<M, F>(request: Request<String, Value>, f: F) -> Result<Value, Error<Value>>
+where
+    M: Method,
+    F: FnOnce(M::Params) -> Result<M::Result, ErrorObject<Value>>,
+{
+    let request = M::decode_params(request)?;
+    let params = request.params.expect("decode_params always sets params");
+    match f(params) {
+        Ok(result) => Ok(serde_json::to_value(result).expect("results are always serializable")),
+        Err(error) => Err(Error {
+            jsonrpc: ::V2_0,
+            error: error,
+            id: Id::Null,
+        }),
+    }
+}
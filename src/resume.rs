@@ -0,0 +1,290 @@
+//! Sequence numbered connection resumption.
+//!
+//! Transports which want to offer a transparent reconnect number every message they send on a
+//! session (starting at 1) and keep the unacknowledged ones buffered. After reconnecting, the
+//! client calls [`rpc.resume`](constant.RESUME_METHOD.html) with the session token and the last
+//! sequence number it received; the server answers with the last sequence number *it* received.
+//! Each side then retransmits everything the other has not seen, as returned by
+//! [`ResumeState::retransmit`](struct.ResumeState.html#method.retransmit).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::resume::ResumeState;
+//!
+//! # fn main() {
+//! let mut client = ResumeState::new("session-1", 100);
+//! let mut server = ResumeState::new("session-1", 100);
+//!
+//! let seq = client.send("request 1".to_string());
+//! assert!(server.receive(seq).unwrap());
+//! client.send("request 2".to_string()); // lost in the disconnect
+//!
+//! server.send("response 1".to_string()); // lost as well
+//!
+//! // Reconnect.
+//! let params = client.params();
+//! let result = server.accept(&params).unwrap();
+//! assert_eq!(server.retransmit(), vec![(1, "response 1")]);
+//! client.resume(result.last_received).unwrap();
+//! assert_eq!(client.retransmit(), vec![(2, "request 2")]);
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+
+use std_prelude::*;
+
+use memory::MemoryUsage;
+use {Error, ErrorCode, Id, Value};
+
+/// The method name of the resumption handshake.
+pub const RESUME_METHOD: &str = "rpc.resume";
+
+/// The server error code returned when a session can not be resumed.
+pub const RESUME_FAILED: i64 = -32043;
+
+/// The `params` of an [`rpc.resume`](constant.RESUME_METHOD.html) Request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeParams {
+    /// The session token.
+    pub session: String,
+
+    /// The last sequence number the client received from the server (0 if none).
+    pub last_received: u64,
+
+    /// The last sequence number the client sent.
+    pub last_sent: u64,
+}
+
+/// The `result` of an [`rpc.resume`](constant.RESUME_METHOD.html) Request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeResult {
+    /// The last sequence number the server received from the client (0 if none).
+    pub last_received: u64,
+
+    /// The last sequence number the server sent.
+    pub last_sent: u64,
+}
+
+/// One side's state of a resumable session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResumeState {
+    session: String,
+    max_buffered: usize,
+    last_sent: u64,
+    last_received: u64,
+    /// Unacknowledged outbound messages, oldest first.
+    buffer: VecDeque<(u64, String)>,
+    /// The sequence number of the peer's last acknowledgement.
+    acked: u64,
+}
+
+impl ResumeState {
+    /// Create the state of a new session, buffering at most `max_buffered` unacknowledged
+    /// messages.
+    pub fn new<S: Into<String>>(session: S, max_buffered: usize) -> Self {
+        ResumeState {
+            session: session.into(),
+            max_buffered: max_buffered,
+            last_sent: 0,
+            last_received: 0,
+            buffer: VecDeque::new(),
+            acked: 0,
+        }
+    }
+
+    /// The session token.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// The last sequence number sent.
+    pub fn last_sent(&self) -> u64 {
+        self.last_sent
+    }
+
+    /// The last sequence number received.
+    pub fn last_received(&self) -> u64 {
+        self.last_received
+    }
+
+    /// Buffer an outbound message and return its sequence number.
+    ///
+    /// If the buffer is full, the oldest message is dropped; resuming a session which still
+    /// needed it will fail.
+    pub fn send(&mut self, message: String) -> u64 {
+        self.last_sent += 1;
+        self.buffer.push_back((self.last_sent, message));
+        while self.buffer.len() > self.max_buffered {
+            self.buffer.pop_front();
+        }
+        self.last_sent
+    }
+
+    /// Record an inbound sequence number. Returns `false` for a duplicate (which should be
+    /// discarded) and fails if messages were skipped.
+    pub fn receive(&mut self, seq: u64) -> Result<bool, ResumeError> {
+        if seq <= self.last_received {
+            return Ok(false);
+        }
+        if seq != self.last_received + 1 {
+            return Err(ResumeError::Gap {
+                expected: self.last_received + 1,
+                received: seq,
+            });
+        }
+        self.last_received = seq;
+        Ok(true)
+    }
+
+    /// Drop the buffered messages the peer acknowledged having received.
+    pub fn ack(&mut self, last_received: u64) -> Result<(), ResumeError> {
+        if last_received > self.last_sent {
+            return Err(ResumeError::AheadOfSent {
+                acked: last_received,
+                sent: self.last_sent,
+            });
+        }
+        self.acked = self.acked.max(last_received);
+        while self
+            .buffer
+            .front()
+            .is_some_and(|&(seq, _)| seq <= self.acked)
+        {
+            self.buffer.pop_front();
+        }
+        Ok(())
+    }
+
+    /// The `params` for resuming this session as the client.
+    pub fn params(&self) -> ResumeParams {
+        ResumeParams {
+            session: self.session.clone(),
+            last_received: self.last_received,
+            last_sent: self.last_sent,
+        }
+    }
+
+    /// Accept a resumption request as the server.
+    ///
+    /// On success, [`retransmit`](#method.retransmit) returns the messages to send to the client.
+    pub fn accept(&mut self, params: &ResumeParams) -> Result<ResumeResult, ResumeError> {
+        if params.session != self.session {
+            return Err(ResumeError::UnknownSession(params.session.clone()));
+        }
+        self.resume(params.last_received)?;
+        Ok(ResumeResult {
+            last_received: self.last_received,
+            last_sent: self.last_sent,
+        })
+    }
+
+    /// Process the peer's `last_received` after reconnecting.
+    ///
+    /// Fails if the peer claims to have received messages which were never sent, or if messages
+    /// the peer is missing were already dropped from the buffer.
+    pub fn resume(&mut self, last_received: u64) -> Result<(), ResumeError> {
+        self.ack(last_received)?;
+        let first_needed = last_received + 1;
+        let first_buffered = self
+            .buffer
+            .front()
+            .map(|&(seq, _)| seq)
+            .unwrap_or(self.last_sent + 1);
+        if first_buffered > first_needed {
+            return Err(ResumeError::Lost {
+                from: first_needed,
+                to: first_buffered - 1,
+            });
+        }
+        Ok(())
+    }
+
+    /// The messages not yet acknowledged by the peer, oldest first.
+    pub fn retransmit(&self) -> Vec<(u64, &str)> {
+        self.buffer
+            .iter()
+            .map(|&(seq, ref message)| (seq, message.as_str()))
+            .collect()
+    }
+}
+
+impl MemoryUsage for ResumeState {
+    fn memory_usage(&self) -> usize {
+        let buffer: usize = self.buffer.iter().map(|m| 8 + m.1.len()).sum();
+        self.session.len() + buffer
+    }
+
+    /// Drop the oldest unacknowledged message; resuming a session which still needed it will
+    /// fail.
+    fn shed_oldest(&mut self) -> Option<usize> {
+        self.buffer.pop_front().map(|m| 8 + m.1.len())
+    }
+}
+
+/// The error returned when a session can not be resumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResumeError {
+    /// The session token is not known.
+    UnknownSession(String),
+    /// An inbound message was skipped.
+    Gap {
+        /// The next expected sequence number.
+        expected: u64,
+        /// The received sequence number.
+        received: u64,
+    },
+    /// The peer acknowledged messages which were never sent.
+    AheadOfSent {
+        /// The acknowledged sequence number.
+        acked: u64,
+        /// The last sent sequence number.
+        sent: u64,
+    },
+    /// Messages the peer is missing were dropped from the buffer.
+    Lost {
+        /// The first lost sequence number.
+        from: u64,
+        /// The last lost sequence number.
+        to: u64,
+    },
+}
+
+impl ResumeError {
+    /// Convert into an error response for the `rpc.resume` request `id`.
+    pub fn into_error(self, id: Id) -> Error<Value> {
+        Error::new(
+            id,
+            ErrorCode::ServerError(RESUME_FAILED),
+            self.to_string(),
+            None,
+        )
+    }
+}
+
+impl fmt::Display for ResumeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResumeError::UnknownSession(ref s) => write!(f, "unknown session `{}`", s),
+            ResumeError::Gap { expected, received } => write!(
+                f,
+                "expected sequence number {} but received {}",
+                expected, received
+            ),
+            ResumeError::AheadOfSent { acked, sent } => write!(
+                f,
+                "sequence number {} acknowledged but only {} sent",
+                acked, sent
+            ),
+            ResumeError::Lost { from, to } => {
+                write!(f, "messages {} to {} are no longer buffered", from, to)
+            }
+        }
+    }
+}
+
+impl error::Error for ResumeError {}
@@ -0,0 +1,192 @@
+//! Dispatching Requests to typed handlers.
+//!
+//! A [`Router`](struct.Router.html) maps method names to handlers taking the deserialized
+//! params and returning a result to serialize, and takes care of the rest of the spec: it
+//! answers invalid json with `ParseError`, malformed Requests with `InvalidRequest`, unknown
+//! methods with `MethodNotFound` and params which do not deserialize with `InvalidParams`. It
+//! answers batches with a batch, and does not answer Notifications.
+//!
+//! Like the rest of this crate, it does no I/O: [`handle`](struct.Router.html#method.handle)
+//! takes the json received by the transport and returns the json to send back, if any.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::router::Router;
+//! use jrpc::{ErrorCode, ErrorObject, Response, Value};
+//!
+//! # fn main() {
+//! let mut router = Router::new();
+//! router.add::<(i64, i64), i64, _>("subtract", |(a, b)| Ok(a - b));
+//! router.add::<Vec<f64>, f64, _>("mean", |values| {
+//!     if values.is_empty() {
+//!         return Err(ErrorObject::<Value>::new(1, "No values", None).unwrap());
+//!     }
+//!     Ok(values.iter().sum::<f64>() / values.len() as f64)
+//! });
+//! router.add::<(), (), _>("ping", |()| Ok(()));
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#;
+//! assert_eq!(router.handle(json).unwrap(), r#"{"jsonrpc":"2.0","result":19,"id":1}"#);
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "mean", "params": [], "id": 2}"#;
+//! assert_eq!(
+//!     router.handle(json).unwrap(),
+//!     r#"{"jsonrpc":"2.0","error":{"code":1,"message":"No values"},"id":2}"#,
+//! );
+//!
+//! // Notifications are not answered.
+//! assert_eq!(router.handle(r#"{"jsonrpc": "2.0", "method": "ping"}"#), None);
+//!
+//! let json = r#"[
+//!     {"jsonrpc": "2.0", "method": "subtract", "params": ["a", "b"], "id": 3},
+//!     {"jsonrpc": "2.0", "method": "ping"},
+//!     {"jsonrpc": "2.0", "method": "divide", "params": [42, 23], "id": 4}
+//! ]"#;
+//! let responses: Vec<Response<i64>> = serde_json::from_str(&router.handle(json).unwrap()).unwrap();
+//! let codes: Vec<_> = responses.into_iter().map(|r| r.into_result().unwrap_err().code).collect();
+//! assert_eq!(codes, vec![ErrorCode::InvalidParams, ErrorCode::MethodNotFound]);
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {request_from_value, Error, ErrorCode, ErrorObject, Id, IdReq, Request, Response, Success};
+use {Value, V2_0};
+
+type Handler = Box<dyn Fn(Option<Value>) -> Result<Value, Error<Value>> + Send + Sync>;
+
+/// Maps method names to handlers, answering Requests with their Responses.
+#[derive(Default)]
+pub struct Router {
+    methods: BTreeMap<String, Handler>,
+}
+
+impl fmt::Debug for Router {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Router")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Router {
+    /// Create a router without any methods.
+    pub fn new() -> Self {
+        Router::default()
+    }
+
+    /// Register the handler of a method, replacing any previous one.
+    ///
+    /// The handler is called with the params deserialized as `P`. A Request without params
+    /// has Null params, so `P` can be `()` or an `Option`. The error object the handler returns
+    /// is sent as is.
+    pub fn add<P, R, F>(&mut self, method: &str, handler: F) -> &mut Self
+    where
+        P: DeserializeOwned,
+        R: Serialize,
+        F: Fn(P) -> Result<R, ErrorObject<Value>> + Send + Sync + 'static,
+    {
+        let handler = move |params: Option<Value>| encode_result(handler(decode_params(params)?));
+        self.methods.insert(method.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Whether a handler is registered for `method`.
+    pub fn contains(&self, method: &str) -> bool {
+        self.methods.contains_key(method)
+    }
+
+    /// Handle the json of a Request, a Notification or a batch, returning the json of the
+    /// Response or batch of Responses, or `None` if there is nothing to send back.
+    pub fn handle(&self, json: &str) -> Option<String> {
+        let (batch, values) = match split(json) {
+            Ok(input) => input,
+            Err(error) => return Some(error),
+        };
+        let responses = values
+            .into_iter()
+            .filter_map(|value| match request_from_value(value) {
+                Ok(request) => self.handle_request(request),
+                Err(error) => Some(Response::Err(error)),
+            })
+            .collect();
+        join(batch, responses)
+    }
+
+    /// Handle a parsed Request, returning its Response, or `None` for Notifications.
+    pub fn handle_request(&self, request: Request<String, Value>) -> Option<Response<Value>> {
+        let result = match self.methods.get(&request.method) {
+            Some(handler) => handler(request.params),
+            None => Err(Error::method_not_found(Id::Null)),
+        };
+        respond(request.id, result)
+    }
+}
+
+/// Split json into the messages to handle and whether they are a batch, or fail with the json of
+/// the error answering it.
+pub(crate) fn split(json: &str) -> Result<(bool, Vec<Value>), String> {
+    let value: Value = serde_json::from_str(json).map_err(|err| {
+        Error::<Value>::new(Id::Null, ErrorCode::ParseError, err.to_string(), None).to_json()
+    })?;
+    match value {
+        Value::Array(ref values) if values.is_empty() => {
+            let message = "batch must not be empty";
+            Err(Error::<Value>::new(Id::Null, ErrorCode::InvalidRequest, message, None).to_json())
+        }
+        Value::Array(values) => Ok((true, values)),
+        value => Ok((false, vec![value])),
+    }
+}
+
+/// Deserialize the params for a handler, failing with `InvalidParams`.
+pub(crate) fn decode_params<P: DeserializeOwned>(params: Option<Value>) -> Result<P, Error<Value>> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|err| Error::invalid_params(Id::Null, Some(Value::String(err.to_string()))))
+}
+
+/// Serialize the result of a handler, failing with `InternalError`.
+pub(crate) fn encode_result<R: Serialize>(
+    result: Result<R, ErrorObject<Value>>,
+) -> Result<Value, Error<Value>> {
+    let result = result.map_err(|error| Error {
+        jsonrpc: V2_0,
+        error: error,
+        id: Id::Null,
+    })?;
+    serde_json::to_value(result)
+        .map_err(|err| Error::internal_error(Id::Null, Some(Value::String(err.to_string()))))
+}
+
+/// The Response to a Request with `id`, `None` for Notifications.
+pub(crate) fn respond(id: IdReq, result: Result<Value, Error<Value>>) -> Option<Response<Value>> {
+    let id = match id {
+        IdReq::Notification => return None,
+        id => id.to_id().unwrap_or(Id::Null),
+    };
+    Some(match result {
+        Ok(result) => Response::Ok(Success {
+            jsonrpc: V2_0,
+            result: result,
+            id: id,
+        }),
+        Err(error) => Response::Err(Error { id: id, ..error }),
+    })
+}
+
+/// The json answering the messages split by [`split`](fn.split.html).
+pub(crate) fn join(batch: bool, mut responses: Vec<Response<Value>>) -> Option<String> {
+    if batch && !responses.is_empty() {
+        Some(serde_json::to_string(&responses).unwrap())
+    } else {
+        responses.pop().map(|response| response.to_string())
+    }
+}
@@ -0,0 +1,242 @@
+//! Validation of values against a subset of JSON Schema.
+//!
+//! The schemas documented in a [`catalog`](../catalog/index.html) are checked with
+//! [`validate`](fn.validate.html), which returns every [`Violation`](struct.Violation.html) with
+//! the JSON pointer of the offending value. Only the keywords needed to describe params and
+//! results are supported; all others are ignored:
+//!
+//! - `type` (a name or an array of names), `enum` and `const`
+//! - `properties`, `required` and `additionalProperties`
+//! - `items`, `minItems` and `maxItems`
+//! - `minimum`, `maximum`, `minLength` and `maxLength`
+//! - `anyOf`
+//!
+//! The schemas `true` and `false` accept and reject everything.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::schema;
+//!
+//! # fn main() {
+//! let schema = json!({
+//!     "type": "object",
+//!     "properties": {"name": {"type": "string"}, "age": {"type": "integer", "minimum": 0}},
+//!     "required": ["name"],
+//! });
+//! assert!(schema::validate(&schema, &json!({"name": "a", "age": 3})).is_empty());
+//!
+//! let violations = schema::validate(&schema, &json!({"age": -1}));
+//! assert_eq!(violations.len(), 2);
+//! assert_eq!(violations[0].pointer, "");
+//! assert_eq!(violations[1].pointer, "/age");
+//! # }
+//! ```
+
+use std::fmt;
+
+use std_prelude::*;
+
+use Value;
+
+/// A value which does not match its schema.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Violation {
+    /// The JSON pointer of the value, relative to the validated one.
+    pub pointer: String,
+
+    /// What is wrong with it.
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.pointer, self.message)
+        }
+    }
+}
+
+/// Validate `value` against `schema`, returning all violations.
+pub fn validate(schema: &Value, value: &Value) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check(schema, value, "", &mut violations);
+    violations
+}
+
+/// Whether `value` matches `schema`.
+pub fn is_valid(schema: &Value, value: &Value) -> bool {
+    validate(schema, value).is_empty()
+}
+
+/// The JSON Schema type names of a value; integers are also numbers.
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn escape(member: &str) -> String {
+    member.replace('~', "~0").replace('/', "~1")
+}
+
+fn push(violations: &mut Vec<Violation>, pointer: &str, message: String) {
+    violations.push(Violation {
+        pointer: pointer.to_string(),
+        message: message,
+    })
+}
+
+fn check(schema: &Value, value: &Value, pointer: &str, violations: &mut Vec<Violation>) {
+    let schema = match *schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => return push(violations, pointer, "no value is allowed".to_string()),
+        Value::Object(ref schema) => schema,
+        _ => return,
+    };
+
+    if let Some(types) = schema.get("type") {
+        let names: Vec<&str> = match *types {
+            Value::String(ref name) => vec![name.as_str()],
+            Value::Array(ref names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !names.is_empty() && !names.iter().any(|name| is_type(value, name)) {
+            return push(
+                violations,
+                pointer,
+                format!("expected {}", names.join(" or ")),
+            );
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            push(
+                violations,
+                pointer,
+                format!("expected one of {}", Value::Array(allowed.clone())),
+            );
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            push(violations, pointer, format!("expected {}", expected));
+        }
+    }
+    if let Some(n) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < minimum {
+                push(
+                    violations,
+                    pointer,
+                    format!("expected at least {}", minimum),
+                );
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > maximum {
+                push(violations, pointer, format!("expected at most {}", maximum));
+            }
+        }
+    }
+    if let Some(s) = value.as_str() {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if len < min {
+                push(
+                    violations,
+                    pointer,
+                    format!("expected at least {} characters", min),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if len > max {
+                push(
+                    violations,
+                    pointer,
+                    format!("expected at most {} characters", max),
+                );
+            }
+        }
+    }
+    if let Some(schemas) = schema.get("anyOf").and_then(Value::as_array) {
+        if !schemas.iter().any(|schema| is_valid(schema, value)) {
+            push(
+                violations,
+                pointer,
+                "expected a value matching any of the schemas".to_string(),
+            );
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                push(
+                    violations,
+                    pointer,
+                    format!("expected at least {} items", min),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if items.len() as u64 > max {
+                push(
+                    violations,
+                    pointer,
+                    format!("expected at most {} items", max),
+                );
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                check(
+                    item_schema,
+                    item,
+                    &format!("{}/{}", pointer, index),
+                    violations,
+                );
+            }
+        }
+    }
+
+    if let Some(members) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !members.contains_key(name) {
+                    push(violations, pointer, format!("missing member `{}`", name));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let additional = schema.get("additionalProperties");
+        for (name, member) in members {
+            let member_pointer = format!("{}/{}", pointer, escape(name));
+            match properties.and_then(|p| p.get(name)) {
+                Some(member_schema) => check(member_schema, member, &member_pointer, violations),
+                None => match additional {
+                    Some(&Value::Bool(false)) => {
+                        push(violations, &member_pointer, "unexpected member".to_string())
+                    }
+                    Some(additional) => check(additional, member, &member_pointer, violations),
+                    None => {}
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Sanitization of the errors leaving a server.
+//!
+//! Errors built from internal failures tend to carry stack traces, file paths or queries in
+//! their `message` and `data`. An [`ErrorScrubber`](struct.ErrorScrubber.html) rewrites each
+//! outgoing [`ErrorObject`](../struct.ErrorObject.html) according to the [`Scrub`](enum.Scrub.html)
+//! rule of its code and returns what it removed, for the server's own logs.
+//!
+//! By default `InternalError`s are redacted and every other code is kept: the errors caused by
+//! the client must explain what was wrong, and server error codes are deliberately public
+//! extensions (e.g. the data of [`backoff::OVERLOADED`](../backoff/constant.OVERLOADED.html)).
+//!
+//! # Verbosity
+//!
+//! Errors may carry extended detail in members of an Object `data`: the chain of
+//! [`causes`](constant.CAUSES_MEMBER.html), [`suggestions`](constant.SUGGESTIONS_MEMBER.html)
+//! for the caller and [`debug`](constant.DEBUG_MEMBER.html) information. A
+//! [`Verbosity`](enum.Verbosity.html) profile selects how much of it leaves the server, and a
+//! [`VerbositySwitch`](struct.VerbositySwitch.html) lets the same binary change profile at
+//! runtime:
+//!
+//! | Profile      | `InternalError` | `debug` | `causes` | `suggestions` |
+//! |--------------|-----------------|---------|----------|---------------|
+//! | `Production` | redacted        | removed | removed  | kept          |
+//! | `Staging`    | kept            | removed | kept     | kept          |
+//! | `Debug`      | kept            | kept    | kept     | kept          |
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{ErrorCode, ErrorObject, Value};
+//! use jrpc::scrub::{ErrorScrubber, Scrub};
+//!
+//! # fn main() {
+//! let scrubber = ErrorScrubber::new().rule(-32001, Scrub::StripData);
+//!
+//! let mut error: ErrorObject<Value> = ErrorObject {
+//!     code: ErrorCode::InternalError,
+//!     message: "connection refused: /var/run/db.sock".into(),
+//!     data: Some(Value::from("at db::connect (db.rs:42)")),
+//! };
+//! let removed = scrubber.scrub(&mut error).unwrap();
+//! assert_eq!(error.message, "Internal error");
+//! assert_eq!(error.data, None);
+//! assert_eq!(removed.message.unwrap(), "connection refused: /var/run/db.sock");
+//!
+//! let mut error: ErrorObject<Value> = ErrorObject {
+//!     code: ErrorCode::InvalidParams,
+//!     message: "missing field `name`".into(),
+//!     data: None,
+//! };
+//! assert!(scrubber.scrub(&mut error).is_none());
+//! # }
+//! ```
+//!
+//! Switching profiles:
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::{ErrorCode, ErrorObject};
+//! use jrpc::scrub::{Verbosity, VerbositySwitch};
+//!
+//! # fn main() {
+//! let switch = VerbositySwitch::new("production".parse().unwrap());
+//! let error = || ErrorObject {
+//!     code: ErrorCode::InvalidParams,
+//!     message: "invalid date".to_string(),
+//!     data: Some(json!({"causes": ["month 13"], "suggestions": ["use YYYY-MM-DD"]})),
+//! };
+//!
+//! let mut production = error();
+//! switch.scrub(&mut production);
+//! assert_eq!(production.data, Some(json!({"suggestions": ["use YYYY-MM-DD"]})));
+//!
+//! switch.set(Verbosity::Debug);
+//! let mut debug = error();
+//! assert!(switch.scrub(&mut debug).is_none());
+//! assert_eq!(debug.data, error().data);
+//! # }
+//! ```
+
+use core::error;
+use core::fmt;
+use core::mem;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use prelude::*;
+
+use serde_json;
+
+use {ErrorCode, ErrorObject, Response, Value};
+
+/// The member of an error's Object `data` with the chain of causes.
+pub const CAUSES_MEMBER: &str = "causes";
+
+/// The member of an error's Object `data` with suggestions for the caller.
+pub const SUGGESTIONS_MEMBER: &str = "suggestions";
+
+/// The member of an error's Object `data` with debugging information.
+pub const DEBUG_MEMBER: &str = "debug";
+
+/// What to remove from an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scrub {
+    /// Keep the error as is.
+    Keep,
+    /// Remove the `data`.
+    StripData,
+    /// Replace the `message` with a generic one and remove the `data`.
+    Redact,
+}
+
+/// What was removed from an error, to be logged locally.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scrubbed {
+    /// The error code.
+    pub code: ErrorCode,
+    /// The original message, if it was replaced.
+    pub message: Option<String>,
+    /// The removed data, or an Object of the removed members.
+    pub data: Option<Value>,
+}
+
+/// The generic message of a code, used by [`Scrub::Redact`](enum.Scrub.html).
+pub fn generic_message(code: ErrorCode) -> &'static str {
+    match code {
+        ErrorCode::ParseError => "Parse error",
+        ErrorCode::InvalidRequest => "Invalid Request",
+        ErrorCode::MethodNotFound => "Method not found",
+        ErrorCode::InvalidParams => "Invalid params",
+        ErrorCode::InternalError => "Internal error",
+        ErrorCode::ServerError(_) | ErrorCode::Reserved(_) => "Server error",
+    }
+}
+
+/// Rewrites outgoing errors by code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorScrubber {
+    rules: BTreeMap<i64, Scrub>,
+    default: Scrub,
+    members: Vec<String>,
+}
+
+impl Default for ErrorScrubber {
+    fn default() -> Self {
+        ErrorScrubber::new()
+    }
+}
+
+impl ErrorScrubber {
+    /// Create a scrubber redacting `InternalError`s and keeping all other codes.
+    pub fn new() -> Self {
+        ErrorScrubber {
+            rules: BTreeMap::new(),
+            default: Scrub::Keep,
+            members: Vec::new(),
+        }
+        .rule(ErrorCode::InternalError.code(), Scrub::Redact)
+    }
+
+    /// Set the rule of a code.
+    pub fn rule(mut self, code: i64, scrub: Scrub) -> Self {
+        self.rules.insert(code, scrub);
+        self
+    }
+
+    /// Set the rule of the codes without their own rule.
+    pub fn default_rule(mut self, scrub: Scrub) -> Self {
+        self.default = scrub;
+        self
+    }
+
+    /// Remove a member from the Object `data` of the errors which are kept.
+    pub fn strip_member<S: Into<String>>(mut self, member: S) -> Self {
+        self.members.push(member.into());
+        self
+    }
+
+    /// The rule applied to a code.
+    pub fn rule_of(&self, code: ErrorCode) -> Scrub {
+        self.rules
+            .get(&code.code())
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Scrub an error in place. Returns what was removed, if anything.
+    pub fn scrub(&self, error: &mut ErrorObject<Value>) -> Option<Scrubbed> {
+        let (message, data) = match self.rule_of(error.code) {
+            Scrub::Keep => (None, self.strip_members(error)),
+            Scrub::StripData => (None, error.data.take()),
+            Scrub::Redact => {
+                let generic = generic_message(error.code).to_string();
+                let message = mem::replace(&mut error.message, generic);
+                (Some(message), error.data.take())
+            }
+        };
+        if message.is_none() && data.is_none() {
+            return None;
+        }
+        Some(Scrubbed {
+            code: error.code,
+            message: message,
+            data: data,
+        })
+    }
+
+    fn strip_members(&self, error: &mut ErrorObject<Value>) -> Option<Value> {
+        let data = error.data.as_mut().and_then(Value::as_object_mut)?;
+        let mut removed = serde_json::Map::new();
+        for member in &self.members {
+            if let Some(value) = data.remove(member) {
+                removed.insert(member.clone(), value);
+            }
+        }
+        if removed.is_empty() {
+            None
+        } else {
+            Some(Value::Object(removed))
+        }
+    }
+
+    /// Scrub the error of a Response in place.
+    pub fn scrub_response(&self, response: &mut Response<Value>) -> Option<Scrubbed> {
+        match *response {
+            Response::Ok(_) => None,
+            Response::Err(ref mut error) => self.scrub(&mut error.error),
+        }
+    }
+}
+
+/// How much detail errors carry, by environment.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// Redact internal errors and remove debugging information and causes. The default.
+    #[default]
+    Production,
+    /// Remove debugging information.
+    Staging,
+    /// Keep everything.
+    Debug,
+}
+
+impl Verbosity {
+    const ALL: [Verbosity; 3] = [Verbosity::Production, Verbosity::Staging, Verbosity::Debug];
+
+    /// The name of the profile, as parsed by `from_str`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verbosity::Production => "production",
+            Verbosity::Staging => "staging",
+            Verbosity::Debug => "debug",
+        }
+    }
+
+    /// The scrubber of the profile.
+    pub fn scrubber(self) -> ErrorScrubber {
+        match self {
+            Verbosity::Production => ErrorScrubber::new()
+                .strip_member(DEBUG_MEMBER)
+                .strip_member(CAUSES_MEMBER),
+            Verbosity::Staging => ErrorScrubber::new()
+                .rule(ErrorCode::InternalError.code(), Scrub::Keep)
+                .strip_member(DEBUG_MEMBER),
+            Verbosity::Debug => {
+                ErrorScrubber::new().rule(ErrorCode::InternalError.code(), Scrub::Keep)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Verbosity {
+    type Err = UnknownVerbosity;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Verbosity::ALL
+            .iter()
+            .find(|v| v.as_str() == s)
+            .cloned()
+            .ok_or_else(|| UnknownVerbosity(s.to_string()))
+    }
+}
+
+/// The error returned when parsing an unknown verbosity profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownVerbosity(pub String);
+
+impl fmt::Display for UnknownVerbosity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "unknown verbosity `{}`, expected production, staging or debug",
+            self.0
+        )
+    }
+}
+
+impl error::Error for UnknownVerbosity {}
+
+/// The verbosity profile of a server, which may be changed at runtime from any thread.
+#[derive(Debug, Default)]
+pub struct VerbositySwitch {
+    level: AtomicUsize,
+}
+
+impl VerbositySwitch {
+    /// Create a switch set to `verbosity`.
+    pub fn new(verbosity: Verbosity) -> Self {
+        VerbositySwitch {
+            level: AtomicUsize::new(verbosity as usize),
+        }
+    }
+
+    /// The current profile.
+    pub fn get(&self) -> Verbosity {
+        Verbosity::ALL[self.level.load(Ordering::Relaxed)]
+    }
+
+    /// Change the profile, returning the previous one.
+    pub fn set(&self, verbosity: Verbosity) -> Verbosity {
+        Verbosity::ALL[self.level.swap(verbosity as usize, Ordering::Relaxed)]
+    }
+
+    /// Scrub an error according to the current profile.
+    pub fn scrub(&self, error: &mut ErrorObject<Value>) -> Option<Scrubbed> {
+        self.get().scrubber().scrub(error)
+    }
+}
@@ -1,7 +1,10 @@
-use std::result;
-use std::fmt;
+use core::convert::TryFrom;
+use core::result;
+use core::fmt;
 use serde::{de, ser};
-use std_prelude::*;
+use prelude::*;
+
+use serde_json;
 
 use super::*;
 
@@ -48,6 +51,18 @@ impl<'de> de::Visitor<'de> for V2_0Visitor {
             Err(de::Error::invalid_value(de::Unexpected::Str(value), &V2_0))
         }
     }
+
+    // Binary formats, e.g. MessagePack, may carry strings as bytes.
+    fn visit_bytes<E>(self, value: &[u8]) -> result::Result<V2_0, E>
+    where
+        E: de::Error,
+    {
+        if value == b"2.0" {
+            Ok(V2_0)
+        } else {
+            Err(de::Error::invalid_value(de::Unexpected::Bytes(value), &V2_0))
+        }
+    }
 }
 
 impl<'de> de::Deserialize<'de> for V2_0 {
@@ -67,15 +82,7 @@ impl ser::Serialize for ErrorCode {
     where
         S: ser::Serializer,
     {
-        let value = match *self {
-            ErrorCode::ParseError => -32700,
-            ErrorCode::InvalidRequest => -32600,
-            ErrorCode::MethodNotFound => -32601,
-            ErrorCode::InvalidParams => -32602,
-            ErrorCode::InternalError => -32603,
-            ErrorCode::ServerError(value) => value,
-        };
-        serializer.serialize_i64(value)
+        serializer.serialize_i64(self.code())
     }
 }
 
@@ -100,6 +107,38 @@ impl<'de> de::Visitor<'de> for ErrorCodeVisitor {
     {
         Ok(ErrorCode::from(value))
     }
+
+    fn visit_u64<E>(self, value: u64) -> result::Result<ErrorCode, E>
+    where
+        E: de::Error,
+    {
+        // Application defined codes may be positive.
+        match i64::try_from(value) {
+            Ok(value) => Ok(ErrorCode::from(value)),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Unsigned(value), &self)),
+        }
+    }
+
+    // Binary formats, e.g. CBOR, may hand out integers as 128 bits.
+    fn visit_i128<E>(self, value: i128) -> result::Result<ErrorCode, E>
+    where
+        E: de::Error,
+    {
+        match i64::try_from(value) {
+            Ok(value) => Ok(ErrorCode::from(value)),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Other("128 bit integer"), &self)),
+        }
+    }
+
+    fn visit_u128<E>(self, value: u128) -> result::Result<ErrorCode, E>
+    where
+        E: de::Error,
+    {
+        match i64::try_from(value) {
+            Ok(value) => Ok(ErrorCode::from(value)),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Other("128 bit integer"), &self)),
+        }
+    }
 }
 
 impl<'de> de::Deserialize<'de> for ErrorCode {
@@ -110,3 +149,100 @@ impl<'de> de::Deserialize<'de> for ErrorCode {
         deserializer.deserialize_i64(ErrorCodeVisitor)
     }
 }
+
+/// Append `s` as a json String, escaped like `serde_json` does, without allocating.
+pub(crate) fn write_str(out: &mut Vec<u8>, s: &str) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let bytes = s.as_bytes();
+    let mut unicode = *b"\\u0000";
+    let mut start = 0;
+    out.push(b'"');
+    for (i, &b) in bytes.iter().enumerate() {
+        let escaped: &[u8] = match b {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08 => b"\\b",
+            0x0c => b"\\f",
+            0x00..=0x1f => {
+                unicode[4] = HEX[usize::from(b >> 4)];
+                unicode[5] = HEX[usize::from(b & 0xf)];
+                &unicode
+            }
+            _ => continue,
+        };
+        out.extend_from_slice(&bytes[start..i]);
+        out.extend_from_slice(escaped);
+        start = i + 1;
+    }
+    out.extend_from_slice(&bytes[start..]);
+    out.push(b'"');
+}
+
+// ##################################################
+// # PRETTY ORDERED
+
+/// The order of well known members. Any other members follow, sorted by name.
+const MEMBER_ORDER: &[&str] = &[
+    "jsonrpc", "id", "method", "params", "result", "error", "code", "message", "data",
+];
+
+/// Serialize as indented json with the members of every Object ordered per the spec.
+pub(crate) fn to_string_pretty_ordered<T: ser::Serialize>(value: &T) -> String {
+    let value = serde_json::to_value(value).expect("message types are always serializable");
+    let mut out = String::new();
+    write_pretty(&mut out, &value, 0);
+    out
+}
+
+fn member_rank(key: &str) -> usize {
+    MEMBER_ORDER
+        .iter()
+        .position(|k| *k == key)
+        .unwrap_or(MEMBER_ORDER.len())
+}
+
+fn write_pretty(out: &mut String, value: &Value, indent: usize) {
+    match *value {
+        Value::Array(ref items) if !items.is_empty() => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, indent + 1);
+                write_pretty(out, item, indent + 1);
+            }
+            newline(out, indent);
+            out.push(']');
+        }
+        Value::Object(ref map) if !map.is_empty() => {
+            let mut members: Vec<_> = map.iter().collect();
+            members.sort_by(|a, b| {
+                (member_rank(a.0), a.0.as_str()).cmp(&(member_rank(b.0), b.0.as_str()))
+            });
+            out.push('{');
+            for (i, (key, item)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                newline(out, indent + 1);
+                out.push_str(&serde_json::to_string(key).expect("strings are serializable"));
+                out.push_str(": ");
+                write_pretty(out, item, indent + 1);
+            }
+            newline(out, indent);
+            out.push('}');
+        }
+        _ => out.push_str(&serde_json::to_string(value).expect("json is serializable")),
+    }
+}
+
+fn newline(out: &mut String, indent: usize) {
+    out.push('\n');
+    for _ in 0..indent {
+        out.push_str("  ");
+    }
+}
@@ -0,0 +1,264 @@
+//! Measuring the params rejected by a schema before enforcing it.
+//!
+//! A [`ShadowValidator`](struct.ShadowValidator.html) checks the params of every call against a
+//! [`Contract`](../contract/struct.Contract.html). In
+//! [`Enforcement::Shadow`](enum.Enforcement.html#variant.Shadow) mode it never rejects a call:
+//! violations are only counted per method and per JSON pointer, and a bounded, uniform sample
+//! of them is kept. The [`ShadowReport`](struct.ShadowReport.html) tells operators how many
+//! calls enforcing the schema would reject before they switch to
+//! [`Enforcement::Enforce`](enum.Enforcement.html#variant.Enforce).
+//!
+//! Calls to undeclared methods are rejected in both modes, they are not a schema violation.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::catalog::MethodDoc;
+//! use jrpc::contract::Contract;
+//! use jrpc::shadow::{Enforcement, ShadowValidator};
+//!
+//! # fn main() {
+//! let contract = Contract::new("accounts").method(
+//!     MethodDoc::new("transfer").params_schema(json!({"type": "object", "required": ["amount"]})),
+//! );
+//! let mut validator = ShadowValidator::new(contract).max_samples(2);
+//!
+//! assert!(validator.check_params("transfer", &json!({"amount": 5})).is_ok());
+//! // Shadow mode accepts the call, but records the violation.
+//! assert!(validator.check_params("transfer", &json!({"amuont": 5})).is_ok());
+//!
+//! let report = validator.report();
+//! let transfer = &report.methods["transfer"];
+//! assert_eq!(transfer.checked, 2);
+//! assert_eq!(transfer.violating, 1);
+//! assert_eq!(transfer.pointers[""], 1);
+//! assert_eq!(transfer.samples[0][0].message, "missing member `amount`");
+//!
+//! validator.set_mode(Enforcement::Enforce);
+//! assert!(validator.check_params("transfer", &json!({"amuont": 5})).is_err());
+//! # }
+//! ```
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use contract::{Contract, ContractError};
+use loadgen::SeededRng;
+use schema::{self, Violation};
+use {Request, Value};
+
+/// Whether violations of the schema are rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Enforcement {
+    /// Violations are recorded, and the call is accepted.
+    Shadow,
+    /// Violations are recorded, and the call is rejected.
+    Enforce,
+}
+
+/// The violations of a single method.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodReport {
+    /// The number of calls checked.
+    pub checked: u64,
+
+    /// The number of calls with at least one violation.
+    pub violating: u64,
+
+    /// The number of violations, per JSON pointer relative to the params.
+    #[serde(default)]
+    pub pointers: BTreeMap<String, u64>,
+
+    /// The number of violations at pointers which did not fit in `pointers`.
+    #[serde(default)]
+    pub other_pointers: u64,
+
+    /// A uniform sample of the violations of the violating calls.
+    #[serde(default)]
+    pub samples: Vec<Vec<Violation>>,
+}
+
+impl MethodReport {
+    /// The fraction of checked calls which violate the schema.
+    pub fn violation_rate(&self) -> f64 {
+        if self.checked == 0 {
+            0.0
+        } else {
+            self.violating as f64 / self.checked as f64
+        }
+    }
+}
+
+/// The violations recorded by a [`ShadowValidator`](struct.ShadowValidator.html).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShadowReport {
+    /// The mode of the validator.
+    pub mode: Enforcement,
+
+    /// The violations, per method. Only methods which were called are listed.
+    #[serde(default)]
+    pub methods: BTreeMap<String, MethodReport>,
+}
+
+impl ShadowReport {
+    /// The number of calls checked, over all methods.
+    pub fn checked(&self) -> u64 {
+        self.methods.values().map(|m| m.checked).sum()
+    }
+
+    /// The number of calls with at least one violation, over all methods.
+    pub fn violating(&self) -> u64 {
+        self.methods.values().map(|m| m.violating).sum()
+    }
+}
+
+/// Checks params against a contract, recording the violations.
+#[derive(Debug, Clone)]
+pub struct ShadowValidator {
+    contract: Contract,
+    mode: Enforcement,
+    max_samples: usize,
+    max_pointers: usize,
+    rng: SeededRng,
+    methods: BTreeMap<String, MethodReport>,
+}
+
+impl ShadowValidator {
+    /// Create a validator in shadow mode, keeping 16 samples and 64 pointers per method.
+    pub fn new(contract: Contract) -> Self {
+        ShadowValidator {
+            contract: contract,
+            mode: Enforcement::Shadow,
+            max_samples: 16,
+            max_pointers: 64,
+            rng: SeededRng::new(0),
+            methods: BTreeMap::new(),
+        }
+    }
+
+    /// Set the mode.
+    pub fn mode(mut self, mode: Enforcement) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set the number of samples kept per method.
+    pub fn max_samples(mut self, max: usize) -> Self {
+        self.max_samples = max;
+        self
+    }
+
+    /// Set the number of distinct pointers counted per method. Pointers include array indexes,
+    /// so they are not bounded by the schema.
+    pub fn max_pointers(mut self, max: usize) -> Self {
+        self.max_pointers = max;
+        self
+    }
+
+    /// Seed the sampling, for reproducible reports.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = SeededRng::new(seed);
+        self
+    }
+
+    /// Change the mode, e.g. to start enforcing once the report looks right. The recorded
+    /// violations are kept.
+    pub fn set_mode(&mut self, mode: Enforcement) {
+        self.mode = mode;
+    }
+
+    /// The contract.
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Check the params of a Request. See [`check_params`](#method.check_params).
+    pub fn check_request<M, T>(&mut self, request: &Request<M, T>) -> Result<(), ContractError>
+    where
+        M: AsRef<str>,
+        T: Serialize,
+    {
+        let params = match request.params {
+            Some(ref params) => {
+                serde_json::to_value(params).map_err(|e| ContractError::Serialize(e.to_string()))?
+            }
+            None => Value::Null,
+        };
+        self.check_params(request.method.as_ref(), &params)
+    }
+
+    /// Check that `method` is declared and record the violations of `params`.
+    ///
+    /// The violations are only returned in [`Enforcement::Enforce`] mode.
+    ///
+    /// [`Enforcement::Enforce`]: enum.Enforcement.html#variant.Enforce
+    pub fn check_params(&mut self, method: &str, params: &Value) -> Result<(), ContractError> {
+        let violations = {
+            let doc = self
+                .contract
+                .get(method)
+                .ok_or_else(|| ContractError::UnknownMethod(method.to_string()))?;
+            match doc.params_schema {
+                Some(ref schema) => schema::validate(schema, params),
+                None => Vec::new(),
+            }
+        };
+        self.record(method, &violations);
+        if violations.is_empty() || self.mode == Enforcement::Shadow {
+            Ok(())
+        } else {
+            Err(ContractError::Params {
+                method: method.to_string(),
+                violations: violations,
+            })
+        }
+    }
+
+    fn record(&mut self, method: &str, violations: &[Violation]) {
+        let (max_samples, max_pointers) = (self.max_samples, self.max_pointers);
+        let report = self.methods.entry(method.to_string()).or_default();
+        report.checked += 1;
+        if violations.is_empty() {
+            return;
+        }
+        report.violating += 1;
+        for violation in violations {
+            if let Some(count) = report.pointers.get_mut(&violation.pointer) {
+                *count += 1;
+                continue;
+            }
+            if report.pointers.len() < max_pointers {
+                report.pointers.insert(violation.pointer.clone(), 1);
+            } else {
+                report.other_pointers += 1;
+            }
+        }
+        // Reservoir sampling over the violating calls.
+        if report.samples.len() < max_samples {
+            report.samples.push(violations.to_vec());
+        } else {
+            let index = self.rng.below(report.violating) as usize;
+            if index < max_samples {
+                report.samples[index] = violations.to_vec();
+            }
+        }
+    }
+
+    /// The violations recorded so far.
+    pub fn report(&self) -> ShadowReport {
+        ShadowReport {
+            mode: self.mode,
+            methods: self.methods.clone(),
+        }
+    }
+
+    /// Forget the recorded violations.
+    pub fn reset(&mut self) {
+        self.methods.clear();
+    }
+}
@@ -0,0 +1,216 @@
+//! Routing of Requests to shards by a key in their params.
+//!
+//! A [`ShardRouter`](struct.ShardRouter.html) extracts the shard key of a Request, either with a
+//! JSON pointer into its `params` or with a closure, and maps it to a shard by consistent
+//! hashing: adding or removing a shard only moves the keys of that shard.
+//!
+//! Routed Requests are tagged with their shard in the `shard` member of the Request Object, an
+//! extension member ignored by peers which do not know it (like `tenant`).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::{Id, Request};
+//! use jrpc::shard::{self, ShardRouter};
+//!
+//! # fn main() {
+//! let mut router = ShardRouter::pointer("/user");
+//! router.add_shard("db-1");
+//! router.add_shard("db-2");
+//!
+//! let request = Request::with_params(Id::from(1), "get".to_string(), json!({"user": "alice"}));
+//! let tagged = router.tag(&request).unwrap();
+//! let shard = shard::shard_of(&tagged).unwrap();
+//!
+//! // The same key always goes to the same shard.
+//! let again = Request::with_params(Id::from(2), "put".to_string(), json!({"user": "alice", "x": 1}));
+//! assert_eq!(router.route(&again), Ok(shard));
+//!
+//! // Requests without the key can not be routed.
+//! let request = Request::with_params(Id::from(3), "get".to_string(), json!({}));
+//! assert!(router.route(&request).is_err());
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use journal::fnv1a;
+use {Request, Value};
+
+/// The Request member carrying the shard.
+pub const SHARD_MEMBER: &str = "shard";
+
+/// The default number of points of each shard on the hash ring.
+pub const DEFAULT_REPLICAS: usize = 64;
+
+/// Get the shard of a tagged Request, if any.
+pub fn shard_of(request: &Value) -> Option<&str> {
+    request.get(SHARD_MEMBER).and_then(Value::as_str)
+}
+
+type KeyFn = dyn Fn(&Value) -> Option<String>;
+
+enum KeySource {
+    Pointer(String),
+    Closure(Box<KeyFn>),
+}
+
+/// The position of `data` on the ring: FNV-1a, finalized as in MurmurHash3 so that similar
+/// keys spread over the whole ring.
+fn ring_hash(data: &[u8]) -> u64 {
+    let mut hash = fnv1a(data);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+/// Maps Requests to shards by consistent hashing of their shard key.
+pub struct ShardRouter {
+    key: KeySource,
+    replicas: usize,
+    /// The hash ring: the points of all shards, sorted.
+    ring: Vec<(u64, String)>,
+}
+
+impl fmt::Debug for ShardRouter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let key = match self.key {
+            KeySource::Pointer(ref pointer) => pointer.as_str(),
+            KeySource::Closure(_) => "<closure>",
+        };
+        f.debug_struct("ShardRouter")
+            .field("key", &key)
+            .field("shards", &self.shards())
+            .finish()
+    }
+}
+
+impl ShardRouter {
+    /// Create a router taking the shard key from the `params` at the JSON pointer `pointer`,
+    /// e.g. `/user` or `/0` for positional params.
+    pub fn pointer<S: Into<String>>(pointer: S) -> Self {
+        ShardRouter::with_key(KeySource::Pointer(pointer.into()))
+    }
+
+    /// Create a router taking the shard key from the `params` with a closure.
+    pub fn closure<F>(key: F) -> Self
+    where
+        F: Fn(&Value) -> Option<String> + 'static,
+    {
+        ShardRouter::with_key(KeySource::Closure(Box::new(key)))
+    }
+
+    fn with_key(key: KeySource) -> Self {
+        ShardRouter {
+            key: key,
+            replicas: DEFAULT_REPLICAS,
+            ring: Vec::new(),
+        }
+    }
+
+    /// Set the number of points of each shard on the ring; more points spread the keys more
+    /// evenly. Must be set before adding shards.
+    pub fn replicas(mut self, replicas: usize) -> Self {
+        self.replicas = replicas.max(1);
+        self
+    }
+
+    /// Add a shard.
+    pub fn add_shard(&mut self, shard: &str) {
+        if self.ring.iter().any(|p| p.1 == shard) {
+            return;
+        }
+        for replica in 0..self.replicas {
+            let point = ring_hash(format!("{}#{}", shard, replica).as_bytes());
+            self.ring.push((point, shard.to_string()));
+        }
+        self.ring.sort();
+    }
+
+    /// Remove a shard. Its keys move to the other shards.
+    pub fn remove_shard(&mut self, shard: &str) -> bool {
+        let before = self.ring.len();
+        self.ring.retain(|p| p.1 != shard);
+        self.ring.len() != before
+    }
+
+    /// The shards, sorted.
+    pub fn shards(&self) -> Vec<&str> {
+        let mut shards: Vec<&str> = self.ring.iter().map(|p| p.1.as_str()).collect();
+        shards.sort();
+        shards.dedup();
+        shards
+    }
+
+    /// The shard key of `params`. Strings are used as is, other values as their json.
+    pub fn key(&self, params: &Value) -> Option<String> {
+        match self.key {
+            KeySource::Pointer(ref pointer) => params.pointer(pointer).map(|key| match *key {
+                Value::String(ref s) => s.clone(),
+                ref other => other.to_string(),
+            }),
+            KeySource::Closure(ref key) => key(params),
+        }
+    }
+
+    /// The shard of a key.
+    pub fn shard_for(&self, key: &str) -> Option<&str> {
+        if self.ring.is_empty() {
+            return None;
+        }
+        let hash = ring_hash(key.as_bytes());
+        let index = match self.ring.binary_search_by(|p| p.0.cmp(&hash)) {
+            Ok(index) => index,
+            Err(index) => index % self.ring.len(),
+        };
+        Some(&self.ring[index].1)
+    }
+
+    /// The shard of a Request.
+    pub fn route<M>(&self, request: &Request<M, Value>) -> Result<&str, ShardError> {
+        let params = request.params.as_ref().unwrap_or(&Value::Null);
+        let key = self.key(params).ok_or(ShardError::NoKey)?;
+        self.shard_for(&key).ok_or(ShardError::NoShards)
+    }
+
+    /// Serialize a Request with its shard added in the `shard` member.
+    pub fn tag<M: Serialize>(&self, request: &Request<M, Value>) -> Result<Value, ShardError> {
+        let shard = self.route(request)?;
+        let mut value = serde_json::to_value(request).expect("request is serializable");
+        value
+            .as_object_mut()
+            .expect("request is an Object")
+            .insert(SHARD_MEMBER.to_string(), Value::from(shard));
+        Ok(value)
+    }
+}
+
+/// The error returned when a Request can not be routed to a shard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardError {
+    /// The params have no shard key.
+    NoKey,
+    /// The router has no shards.
+    NoShards,
+}
+
+impl fmt::Display for ShardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShardError::NoKey => write!(f, "params have no shard key"),
+            ShardError::NoShards => write!(f, "no shards configured"),
+        }
+    }
+}
+
+impl error::Error for ShardError {}
@@ -0,0 +1,184 @@
+//! Detecting messages which are slow to serialize or deserialize.
+//!
+//! Pathological payloads (deep nesting, megabyte strings, huge arrays) can stall an event loop in
+//! serde alone. [`SlowSerde`](struct.SlowSerde.html) times the serialization and deserialization
+//! of individual messages and calls back with a [`SlowMessage`](struct.SlowMessage.html) for
+//! every one which takes longer than a threshold, so the offending payloads can be logged.
+//!
+//! Like the rest of this crate it never reads the clock itself: `clock` returns the time elapsed
+//! since any fixed point, e.g. `Instant::elapsed` of an `Instant` taken at startup. Only use it
+//! where the overhead of reading the clock twice per message is acceptable.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use std::cell::Cell;
+//! use std::time::Duration;
+//! use jrpc::{Id, Request, Value};
+//! use jrpc::slow::{Operation, SlowSerde};
+//!
+//! # fn main() {
+//! // A fake clock advancing 1ms per byte of json.
+//! let now = Cell::new(Duration::from_millis(0));
+//! let mut slow = Vec::new();
+//! let mut timer = SlowSerde::new(Duration::from_millis(100), || now.get(), |m| {
+//!     slow.push(m.clone())
+//! });
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "Log", "id": 1}"#;
+//! let request: Request<String, Value> = timer
+//!     .from_json_with(json, |json| {
+//!         now.set(now.get() + Duration::from_millis(json.len() as u64));
+//!         serde_json::from_str(json)
+//!     })
+//!     .unwrap();
+//! assert_eq!(request.method, "Log");
+//!
+//! let json = format!(
+//!     r#"{{"jsonrpc": "2.0", "method": "Log", "params": ["{}"], "id": 1}}"#,
+//!     "x".repeat(100)
+//! );
+//! let _: Request<String, Value> = timer
+//!     .from_json_with(&json, |json| {
+//!         now.set(now.get() + Duration::from_millis(json.len() as u64));
+//!         serde_json::from_str(json)
+//!     })
+//!     .unwrap();
+//!
+//! assert_eq!(timer.stats().checked, 2);
+//! assert_eq!(timer.stats().slow, 1);
+//! drop(timer);
+//! assert_eq!(slow[0].operation, Operation::Deserialize);
+//! assert_eq!(slow[0].size, json.len());
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+/// What was timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    /// Serializing a message to json.
+    Serialize,
+    /// Deserializing a message from json.
+    Deserialize,
+}
+
+/// A message which took longer than the threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlowMessage {
+    /// What was slow.
+    pub operation: Operation,
+
+    /// The Rust type of the message.
+    pub kind: String,
+
+    /// The size of the json, in bytes.
+    pub size: usize,
+
+    /// How long it took.
+    pub elapsed: Duration,
+}
+
+/// The number of messages timed by a [`SlowSerde`](struct.SlowSerde.html).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlowStats {
+    /// The number of messages timed.
+    pub checked: u64,
+
+    /// The number of messages which took longer than the threshold.
+    pub slow: u64,
+}
+
+/// Times the serialization and deserialization of messages.
+pub struct SlowSerde<C, F> {
+    threshold: Duration,
+    clock: C,
+    on_slow: F,
+    stats: SlowStats,
+}
+
+impl<C, F> fmt::Debug for SlowSerde<C, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SlowSerde")
+            .field("threshold", &self.threshold)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl<C, F> SlowSerde<C, F>
+where
+    C: FnMut() -> Duration,
+    F: FnMut(&SlowMessage),
+{
+    /// Call `on_slow` for every message taking longer than `threshold`, as measured by `clock`.
+    pub fn new(threshold: Duration, clock: C, on_slow: F) -> Self {
+        SlowSerde {
+            threshold: threshold,
+            clock: clock,
+            on_slow: on_slow,
+            stats: SlowStats::default(),
+        }
+    }
+
+    /// The number of messages timed so far.
+    pub fn stats(&self) -> SlowStats {
+        self.stats
+    }
+
+    /// Serialize a message to json.
+    pub fn to_json<T: Serialize>(&mut self, message: &T) -> serde_json::Result<String> {
+        self.to_json_with(message, serde_json::to_string)
+    }
+
+    /// Serialize a message with `serialize`, e.g. `serde_json::to_string_pretty`.
+    pub fn to_json_with<T, S>(&mut self, message: &T, serialize: S) -> serde_json::Result<String>
+    where
+        S: FnOnce(&T) -> serde_json::Result<String>,
+    {
+        let start = (self.clock)();
+        let result = serialize(message);
+        let size = result.as_ref().map(|json| json.len()).unwrap_or(0);
+        self.observe::<T>(Operation::Serialize, size, start);
+        result
+    }
+
+    /// Deserialize a message from json.
+    pub fn from_json<T: DeserializeOwned>(&mut self, json: &str) -> serde_json::Result<T> {
+        self.from_json_with(json, |json| serde_json::from_str(json))
+    }
+
+    /// Deserialize a message with `deserialize`, e.g. [`parse_request`](../fn.parse_request.html).
+    pub fn from_json_with<T, E, D>(&mut self, json: &str, deserialize: D) -> Result<T, E>
+    where
+        D: FnOnce(&str) -> Result<T, E>,
+    {
+        let start = (self.clock)();
+        let result = deserialize(json);
+        self.observe::<T>(Operation::Deserialize, json.len(), start);
+        result
+    }
+
+    fn observe<T>(&mut self, operation: Operation, size: usize, start: Duration) {
+        let elapsed = (self.clock)().checked_sub(start).unwrap_or_default();
+        self.stats.checked += 1;
+        if elapsed > self.threshold {
+            self.stats.slow += 1;
+            (self.on_slow)(&SlowMessage {
+                operation: operation,
+                kind: ::std::any::type_name::<T>().to_string(),
+                size: size,
+                elapsed: elapsed,
+            });
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Aggregated runtime statistics and the optional `rpc.stats` method.
+//!
+//! A server feeds a [`StatsCollector`](struct.StatsCollector.html) as it handles calls and can
+//! answer the reserved [`METHOD`](constant.METHOD.html) with a typed [`Stats`](struct.Stats.html)
+//! snapshot, so dashboards can poll any server built on this crate in the same way.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use std::time::Duration;
+//! use jrpc::{ErrorCode, Id, Request, Response};
+//! use jrpc::stats::{self, Stats, StatsCollector};
+//!
+//! # fn main() {
+//! let mut collector = StatsCollector::new();
+//! collector.record_request("CreateFoo");
+//! collector.record_latency("CreateFoo", Duration::from_millis(4));
+//! collector.record_request("Missing");
+//! collector.record_error(ErrorCode::MethodNotFound);
+//! collector.set_queue_depth("pending", 3);
+//!
+//! let request = Request::new(Id::from(1), stats::METHOD.to_string());
+//! let response = collector.respond(&request).unwrap();
+//!
+//! // A client decodes the response like any other.
+//! let response: Response<Stats> = serde_json::from_str(&response.to_string()).unwrap();
+//! match response {
+//!     Response::Ok(success) => {
+//!         assert_eq!(success.result.requests["CreateFoo"], 1);
+//!         assert_eq!(success.result.error_count(ErrorCode::MethodNotFound), 1);
+//!         assert_eq!(success.result.latency["CreateFoo"].count, 1);
+//!         assert_eq!(success.result.queues["pending"], 3);
+//!     }
+//!     Response::Err(_) => panic!("expected stats"),
+//! }
+//! # }
+//! ```
+
+use std_prelude::*;
+
+use latency::{LatencyRecorder, LatencySummary};
+use {ErrorCode, Request, Response};
+
+/// The reserved method name used to request a [`Stats`](struct.Stats.html) snapshot.
+pub const METHOD: &str = "rpc.stats";
+
+/// A snapshot of a server's aggregated statistics.
+///
+/// This is the `result` of the [`METHOD`](constant.METHOD.html) call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    /// The number of calls received, per method.
+    #[serde(default)]
+    pub requests: BTreeMap<String, u64>,
+
+    /// The number of error responses sent, per error code, ordered by the numeric code.
+    #[serde(default)]
+    pub errors: Vec<ErrorCount>,
+
+    /// Latency percentiles, per method.
+    #[serde(default)]
+    pub latency: BTreeMap<String, LatencySummary>,
+
+    /// The current depth of any queues the server chooses to report, by name.
+    #[serde(default)]
+    pub queues: BTreeMap<String, u64>,
+}
+
+impl Stats {
+    /// Return the number of error responses sent with `code`.
+    pub fn error_count<C>(&self, code: C) -> u64
+    where
+        C: Into<ErrorCode>,
+    {
+        let code = code.into();
+        self.errors
+            .iter()
+            .find(|e| e.code == code)
+            .map(|e| e.count)
+            .unwrap_or(0)
+    }
+}
+
+/// The number of error responses sent with a single error code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCount {
+    /// The error code.
+    pub code: ErrorCode,
+    /// The number of responses sent with `code`.
+    pub count: u64,
+}
+
+/// Collects the statistics reported by [`METHOD`](constant.METHOD.html).
+#[derive(Debug, Clone, Default)]
+pub struct StatsCollector {
+    requests: BTreeMap<String, u64>,
+    /// By the numeric code, which `ErrorCode`'s variants are not ordered by.
+    errors: BTreeMap<i64, u64>,
+    latency: LatencyRecorder,
+    queues: BTreeMap<String, u64>,
+}
+
+impl StatsCollector {
+    /// Create an empty collector.
+    pub fn new() -> Self {
+        StatsCollector::default()
+    }
+
+    /// Record that a call to `method` was received.
+    pub fn record_request<S>(&mut self, method: S)
+    where
+        S: Into<String>,
+    {
+        *self.requests.entry(method.into()).or_insert(0) += 1;
+    }
+
+    /// Record that an error response with `code` was sent.
+    pub fn record_error<C>(&mut self, code: C)
+    where
+        C: Into<ErrorCode>,
+    {
+        *self.errors.entry(code.into().code()).or_insert(0) += 1;
+    }
+
+    /// Record how long a call to `method` took.
+    pub fn record_latency<S>(&mut self, method: S, latency: Duration)
+    where
+        S: AsRef<str>,
+    {
+        self.latency.record(method, latency);
+    }
+
+    /// Set the current depth of the queue called `name`.
+    pub fn set_queue_depth<S>(&mut self, name: S, depth: u64)
+    where
+        S: Into<String>,
+    {
+        self.queues.insert(name.into(), depth);
+    }
+
+    /// Access the underlying latency recorder.
+    pub fn latency(&self) -> &LatencyRecorder {
+        &self.latency
+    }
+
+    /// Take a snapshot of the current statistics.
+    pub fn snapshot(&self) -> Stats {
+        Stats {
+            requests: self.requests.clone(),
+            errors: self
+                .errors
+                .iter()
+                .map(|(code, count)| ErrorCount {
+                    code: ErrorCode::from(*code),
+                    count: *count,
+                })
+                .collect(),
+            latency: self.latency.snapshot(),
+            queues: self.queues.clone(),
+        }
+    }
+
+    /// Answer a [`METHOD`](constant.METHOD.html) call.
+    ///
+    /// Returns `None` if the request is for a different method or is a notification, in which
+    /// case the caller should dispatch it as usual.
+    pub fn respond<T>(&self, request: &Request<String, T>) -> Option<Response<Stats>> {
+        if request.method != METHOD {
+            return None;
+        }
+        let id = request.id.clone().to_id()?;
+        Some(Response::success(id, self.snapshot()))
+    }
+
+    /// Reset every counter and histogram. Queue depths are kept.
+    pub fn reset(&mut self) {
+        self.requests.clear();
+        self.errors.clear();
+        self.latency.clear();
+    }
+}
@@ -0,0 +1,520 @@
+//! Async adapters for the notifications of subscriptions: streams in, sinks out.
+//!
+//! > Only available with the `async` feature.
+//!
+//! # Streams
+//!
+//! [`subscription`](fn.subscription.html) creates a connected pair: the [`Publisher`](struct.Publisher.html)
+//! is fed the notifications of a subscription by the connection's reader, and the
+//! [`NotificationStream`](struct.NotificationStream.html) yields their params deserialized as `T`.
+//! It implements `futures::Stream` (through `futures-core`), and has the same `poll_next` as an
+//! inherent method.
+//!
+//! Notifications whose params do not deserialize are yielded as an `InvalidParams`
+//! [`ErrorObject`](../struct.ErrorObject.html). When the consumer falls behind by more than the
+//! capacity, the [`Overflow`](enum.Overflow.html) policy decides what is dropped, and the number
+//! of dropped notifications is yielded as a [`LAGGED`](constant.LAGGED.html) error.
+//!
+//! Dropping the stream unsubscribes: [`Publisher::push`](struct.Publisher.html#method.push)
+//! returns `false` from then on and the `on_drop` callback, if any, is called.
+//!
+//! # Sinks
+//!
+//! [`sink`](fn.sink.html) is the converse: the [`NotificationSink`](struct.NotificationSink.html)
+//! implements `futures::Sink` for typed params, and the connection's writer takes
+//! serialized frames from the [`Frames`](struct.Frames.html). Up to `max_batch` notifications are
+//! sent as one batch; a flush sends a partial batch. The sink is not ready while `max_frames`
+//! frames are waiting for the writer.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use std::pin::Pin;
+//! use std::task::{Context, Poll, Waker};
+//!
+//! use jrpc::Value;
+//! use jrpc::stream::{self, Overflow};
+//!
+//! # fn main() {
+//! let (publisher, mut prices) = stream::subscription::<u32>(2, Overflow::DropOldest);
+//! let mut cx = Context::from_waker(Waker::noop());
+//!
+//! assert!(Pin::new(&mut prices).poll_next(&mut cx).is_pending());
+//! publisher.push(Value::from(10));
+//! publisher.push(Value::from(11));
+//! publisher.push(Value::from(12)); // the consumer lags, 10 is dropped
+//!
+//! match Pin::new(&mut prices).poll_next(&mut cx) {
+//!     Poll::Ready(Some(Err(error))) => assert_eq!(error.data, Some(Value::from(1))),
+//!     _ => unreachable!(),
+//! }
+//! for expected in 11..13 {
+//!     match Pin::new(&mut prices).poll_next(&mut cx) {
+//!         Poll::Ready(Some(Ok(price))) => assert_eq!(price, expected),
+//!         _ => unreachable!(),
+//!     }
+//! }
+//!
+//! // Dropping the stream unsubscribes.
+//! drop(prices);
+//! assert!(!publisher.push(Value::from(13)));
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use {ErrorCode, ErrorObject, IdReq, Request, Value};
+
+/// The server error code of the item yielded when notifications were dropped. Its `data` is the
+/// number of dropped notifications.
+pub const LAGGED: i64 = -32044;
+
+/// What to do when a notification arrives and the stream is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Drop the oldest queued notification.
+    DropOldest,
+    /// Drop the new notification.
+    DropNewest,
+    /// Yield the lag error and end the stream, unsubscribing.
+    Close,
+}
+
+type OnDrop = Box<dyn FnOnce() + Send>;
+
+struct Shared {
+    queue: VecDeque<Value>,
+    capacity: usize,
+    overflow: Overflow,
+    /// Notifications dropped since the last lag error.
+    missed: u64,
+    /// The publisher is gone or the stream closed on overflow.
+    closed: bool,
+    /// The stream is gone.
+    unsubscribed: bool,
+    waker: Option<Waker>,
+}
+
+fn lock<S>(shared: &Mutex<S>) -> MutexGuard<'_, S> {
+    shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Create a subscription buffering at most `capacity` notifications.
+pub fn subscription<T: DeserializeOwned>(
+    capacity: usize,
+    overflow: Overflow,
+) -> (Publisher, NotificationStream<T>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        queue: VecDeque::new(),
+        capacity: capacity.max(1),
+        overflow: overflow,
+        missed: 0,
+        closed: false,
+        unsubscribed: false,
+        waker: None,
+    }));
+    let publisher = Publisher {
+        shared: shared.clone(),
+    };
+    let stream = NotificationStream {
+        shared: shared,
+        on_drop: None,
+        item: PhantomData,
+    };
+    (publisher, stream)
+}
+
+/// The feeding end of a subscription. Dropping it ends the stream.
+pub struct Publisher {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl fmt::Debug for Publisher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shared = lock(&self.shared);
+        f.debug_struct("Publisher")
+            .field("queued", &shared.queue.len())
+            .field("subscribed", &!shared.unsubscribed)
+            .finish()
+    }
+}
+
+impl Publisher {
+    /// Push the params of a notification. Returns `false` if the stream was dropped or closed,
+    /// in which case the notification is discarded.
+    pub fn push(&self, params: Value) -> bool {
+        let mut shared = lock(&self.shared);
+        if shared.unsubscribed || shared.closed {
+            return false;
+        }
+        if shared.queue.len() >= shared.capacity {
+            shared.missed += 1;
+            match shared.overflow {
+                Overflow::DropOldest => {
+                    shared.queue.pop_front();
+                    shared.queue.push_back(params);
+                }
+                Overflow::DropNewest => {}
+                Overflow::Close => shared.closed = true,
+            }
+        } else {
+            shared.queue.push_back(params);
+        }
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+
+    /// Whether the stream still exists.
+    pub fn is_subscribed(&self) -> bool {
+        !lock(&self.shared).unsubscribed
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        let mut shared = lock(&self.shared);
+        shared.closed = true;
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The consuming end of a subscription, yielding the params of its notifications as `T`.
+pub struct NotificationStream<T> {
+    shared: Arc<Mutex<Shared>>,
+    on_drop: Option<OnDrop>,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for NotificationStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shared = lock(&self.shared);
+        f.debug_struct("NotificationStream")
+            .field("queued", &shared.queue.len())
+            .field("closed", &shared.closed)
+            .finish()
+    }
+}
+
+impl<T: DeserializeOwned> NotificationStream<T> {
+    /// Call `f` when the stream is dropped, e.g. to send the unsubscribe Request.
+    pub fn on_drop<F: FnOnce() + Send + 'static>(mut self, f: F) -> Self {
+        self.on_drop = Some(Box::new(f));
+        self
+    }
+
+    /// The number of queued notifications.
+    pub fn queued(&self) -> usize {
+        lock(&self.shared).queue.len()
+    }
+
+    /// Poll for the next notification, as `futures::Stream::poll_next`.
+    ///
+    /// Yields `None` once the publisher is dropped and all queued notifications were yielded.
+    pub fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<T, ErrorObject<Value>>>> {
+        let mut shared = lock(&self.shared);
+        if shared.missed > 0 {
+            let missed = shared.missed;
+            shared.missed = 0;
+            if shared.overflow == Overflow::Close {
+                shared.queue.clear();
+            }
+            return Poll::Ready(Some(Err(ErrorObject {
+                code: ErrorCode::ServerError(LAGGED),
+                message: format!("subscription lagged by {} notifications", missed),
+                data: Some(Value::from(missed)),
+            })));
+        }
+        match shared.queue.pop_front() {
+            Some(params) => Poll::Ready(Some(serde_json::from_value(params).map_err(|e| {
+                ErrorObject {
+                    code: ErrorCode::InvalidParams,
+                    message: e.to_string(),
+                    data: None,
+                }
+            }))),
+            None if shared.closed => Poll::Ready(None),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Stream for NotificationStream<T> {
+    type Item = Result<T, ErrorObject<Value>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        NotificationStream::poll_next(self, cx)
+    }
+}
+
+impl<T> Drop for NotificationStream<T> {
+    fn drop(&mut self) {
+        {
+            let mut shared = lock(&self.shared);
+            shared.unsubscribed = true;
+            shared.queue.clear();
+        }
+        if let Some(on_drop) = self.on_drop.take() {
+            on_drop();
+        }
+    }
+}
+
+struct Outbound {
+    method: String,
+    max_batch: usize,
+    max_frames: usize,
+    /// The serialized notifications of the next frame.
+    batch: Vec<String>,
+    frames: VecDeque<String>,
+    /// The sink is closed.
+    closed: bool,
+    /// The writer is gone.
+    disconnected: bool,
+    waker: Option<Waker>,
+}
+
+impl Outbound {
+    fn seal(&mut self) {
+        let frame = match self.batch.len() {
+            0 => return,
+            1 => self.batch.pop().expect("one notification"),
+            _ => format!("[{}]", self.batch.join(",")),
+        };
+        self.batch.clear();
+        self.frames.push_back(frame);
+    }
+
+    fn poll_drained(&mut self, cx: &mut Context) -> Poll<Result<(), SinkError>> {
+        if self.disconnected {
+            Poll::Ready(Err(SinkError::Disconnected))
+        } else if self.frames.is_empty() {
+            Poll::Ready(Ok(()))
+        } else {
+            self.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// Create a sink of notifications of `method`, batching up to `max_batch` of them per frame and
+/// buffering at most `max_frames` frames.
+pub fn sink<T: Serialize>(
+    method: &str,
+    max_batch: usize,
+    max_frames: usize,
+) -> (NotificationSink<T>, Frames) {
+    let shared = Arc::new(Mutex::new(Outbound {
+        method: method.to_string(),
+        max_batch: max_batch.max(1),
+        max_frames: max_frames.max(1),
+        batch: Vec::new(),
+        frames: VecDeque::new(),
+        closed: false,
+        disconnected: false,
+        waker: None,
+    }));
+    let frames = Frames {
+        shared: shared.clone(),
+    };
+    let sink = NotificationSink {
+        shared: shared,
+        item: PhantomData,
+    };
+    (sink, frames)
+}
+
+/// The producing end of notifications, accepting their params as `T`.
+pub struct NotificationSink<T> {
+    shared: Arc<Mutex<Outbound>>,
+    item: PhantomData<fn(T)>,
+}
+
+impl<T> fmt::Debug for NotificationSink<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shared = lock(&self.shared);
+        f.debug_struct("NotificationSink")
+            .field("method", &shared.method)
+            .field("batched", &shared.batch.len())
+            .field("frames", &shared.frames.len())
+            .finish()
+    }
+}
+
+impl<T: Serialize> NotificationSink<T> {
+    /// Whether the sink can accept a notification, as `futures::Sink::poll_ready`.
+    pub fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SinkError>> {
+        let mut shared = lock(&self.shared);
+        if shared.disconnected {
+            Poll::Ready(Err(SinkError::Disconnected))
+        } else if shared.closed {
+            Poll::Ready(Err(SinkError::Closed))
+        } else if shared.frames.len() >= shared.max_frames {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Add a notification with `params` to the current batch, as `futures::Sink::start_send`.
+    /// A full batch becomes a frame.
+    pub fn start_send(self: Pin<&mut Self>, params: T) -> Result<(), SinkError> {
+        let mut shared = lock(&self.shared);
+        if shared.disconnected {
+            return Err(SinkError::Disconnected);
+        }
+        if shared.closed {
+            return Err(SinkError::Closed);
+        }
+        let notification = serde_json::to_string(&Request {
+            jsonrpc: ::V2_0,
+            method: shared.method.as_str(),
+            params: Some(&params),
+            id: IdReq::Notification,
+        })
+        .map_err(|e| SinkError::Serialize(e.to_string()))?;
+        shared.batch.push(notification);
+        if shared.batch.len() >= shared.max_batch {
+            shared.seal();
+        }
+        Ok(())
+    }
+
+    /// Turn the current batch into a frame and wait for the writer to take all frames, as
+    /// `futures::Sink::poll_flush`.
+    pub fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SinkError>> {
+        let mut shared = lock(&self.shared);
+        shared.seal();
+        shared.poll_drained(cx)
+    }
+
+    /// Flush and close the sink, as `futures::Sink::poll_close`.
+    pub fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SinkError>> {
+        let mut shared = lock(&self.shared);
+        shared.seal();
+        shared.closed = true;
+        shared.poll_drained(cx)
+    }
+}
+
+impl<T: Serialize> Sink<T> for NotificationSink<T> {
+    type Error = SinkError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SinkError>> {
+        NotificationSink::poll_ready(self, cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, params: T) -> Result<(), SinkError> {
+        NotificationSink::start_send(self, params)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SinkError>> {
+        NotificationSink::poll_flush(self, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SinkError>> {
+        NotificationSink::poll_close(self, cx)
+    }
+}
+
+impl<T> Drop for NotificationSink<T> {
+    fn drop(&mut self) {
+        let mut shared = lock(&self.shared);
+        shared.seal();
+        shared.closed = true;
+    }
+}
+
+/// The writing end of a sink, yielding serialized frames.
+pub struct Frames {
+    shared: Arc<Mutex<Outbound>>,
+}
+
+impl fmt::Debug for Frames {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shared = lock(&self.shared);
+        f.debug_struct("Frames")
+            .field("frames", &shared.frames.len())
+            .field("closed", &shared.closed)
+            .finish()
+    }
+}
+
+impl Frames {
+    /// Take the next frame, a single notification or a batch of them.
+    pub fn next_frame(&self) -> Option<String> {
+        let mut shared = lock(&self.shared);
+        let frame = shared.frames.pop_front();
+        if frame.is_some() {
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+        frame
+    }
+
+    /// Whether the sink is closed and all its frames were taken.
+    pub fn is_finished(&self) -> bool {
+        let shared = lock(&self.shared);
+        shared.closed && shared.frames.is_empty()
+    }
+}
+
+impl Drop for Frames {
+    fn drop(&mut self) {
+        let mut shared = lock(&self.shared);
+        shared.disconnected = true;
+        shared.frames.clear();
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The error returned by a [`NotificationSink`](struct.NotificationSink.html).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkError {
+    /// The params could not be serialized.
+    Serialize(String),
+    /// The sink was closed.
+    Closed,
+    /// The writer dropped its `Frames`.
+    Disconnected,
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SinkError::Serialize(ref e) => write!(f, "could not serialize params: {}", e),
+            SinkError::Closed => write!(f, "sink is closed"),
+            SinkError::Disconnected => write!(f, "writer is gone"),
+        }
+    }
+}
+
+impl error::Error for SinkError {}
@@ -0,0 +1,330 @@
+//! Strict checking of spec conformance.
+//!
+//! The types of this crate accept some messages the spec forbids (e.g. any params, methods
+//! starting with `rpc.`), and report the others with serde's error messages. The `validate`
+//! methods of [`Request`](../struct.Request.html), [`Success`](../struct.Success.html),
+//! [`Error`](../struct.Error.html) and [`ErrorObject`](../struct.ErrorObject.html), and the
+//! `from_str` functions of this module, return a [`SpecViolation`](enum.SpecViolation.html)
+//! describing the first rule a message breaks instead.
+//!
+//! The `from_str` functions check the raw json, so they also catch what the types cannot hold:
+//! unknown members, a wrong `jsonrpc` version, fractional ids and `"params": null`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{ErrorCode, Id, Request, Value};
+//! use jrpc::strict::{self, SpecViolation};
+//!
+//! # fn main() {
+//! let request = Request::new(Id::from(1), "rpc.discover".to_string());
+//! assert_eq!(
+//!     request.validate(),
+//!     Err(SpecViolation::ReservedMethod("rpc.discover".to_string())),
+//! );
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "foo", "id": 1.5}"#;
+//! let violation = strict::request_from_str::<String, Value>(json).unwrap_err();
+//! assert_eq!(violation, SpecViolation::InvalidId(Value::from(1.5)));
+//! assert_eq!(violation.error_code(), ErrorCode::InvalidRequest);
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "foo", "params": [1], "id": 1}"#;
+//! assert!(strict::request_from_str::<String, Vec<u32>>(json).is_ok());
+//! # }
+//! ```
+
+use core::error;
+use core::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use prelude::*;
+
+use {Error, ErrorCode, ErrorObject, Request, Response, Success, Value};
+
+/// A violation of the spec.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecViolation {
+    /// The message is not valid json.
+    Json(String),
+    /// The message is not an Object.
+    NotAnObject,
+    /// A required member is missing.
+    MissingMember(&'static str),
+    /// A member which the spec does not define.
+    UnknownMember(String),
+    /// The `jsonrpc` member is not exactly `"2.0"`.
+    Version(Value),
+    /// The method is not a String.
+    MethodNotAString(Value),
+    /// The method is the empty String.
+    EmptyMethod,
+    /// The method starts with `rpc.`, which is reserved for system extensions.
+    ReservedMethod(String),
+    /// The params are neither an Array nor an Object.
+    Params(Value),
+    /// The `id` is neither a String, an integer Number nor Null.
+    InvalidId(Value),
+    /// A Response has both `result` and `error`.
+    ResultAndError,
+    /// The `error` member is not an Object.
+    ErrorNotAnObject(Value),
+    /// The error code is not an integer.
+    CodeNotAnInteger(Value),
+    /// The error code is reserved by the spec, but not defined by it.
+    ReservedCode(i64),
+    /// The code of a server error is not within -32099 and -32000.
+    ServerErrorCode(i64),
+    /// The error message is not a String.
+    MessageNotAString(Value),
+    /// The message is valid, but could not be deserialized to the requested type.
+    Deserialize(String),
+}
+
+impl SpecViolation {
+    /// The code of the error to answer a Request with this violation.
+    pub fn error_code(&self) -> ErrorCode {
+        match *self {
+            SpecViolation::Json(_) => ErrorCode::ParseError,
+            _ => ErrorCode::InvalidRequest,
+        }
+    }
+}
+
+impl fmt::Display for SpecViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SpecViolation::Json(ref e) => write!(f, "invalid json: {}", e),
+            SpecViolation::NotAnObject => write!(f, "the message is not an Object"),
+            SpecViolation::MissingMember(m) => write!(f, "missing member `{}`", m),
+            SpecViolation::UnknownMember(ref m) => write!(f, "unknown member `{}`", m),
+            SpecViolation::Version(ref v) => write!(f, "`jsonrpc` must be \"2.0\", got {}", v),
+            SpecViolation::MethodNotAString(ref v) => write!(f, "method must be a String, got {}", v),
+            SpecViolation::EmptyMethod => write!(f, "method must not be empty"),
+            SpecViolation::ReservedMethod(ref m) => {
+                write!(f, "method `{}` is reserved for system extensions", m)
+            }
+            SpecViolation::Params(ref v) => {
+                write!(f, "params must be an Array or an Object, got {}", v)
+            }
+            SpecViolation::InvalidId(ref v) => {
+                write!(f, "id must be a String, an integer or Null, got {}", v)
+            }
+            SpecViolation::ResultAndError => {
+                write!(f, "a Response must not have both `result` and `error`")
+            }
+            SpecViolation::ErrorNotAnObject(ref v) => write!(f, "error must be an Object, got {}", v),
+            SpecViolation::CodeNotAnInteger(ref v) => {
+                write!(f, "error code must be an integer, got {}", v)
+            }
+            SpecViolation::ReservedCode(c) => write!(f, "error code {} is reserved", c),
+            SpecViolation::ServerErrorCode(c) => {
+                write!(f, "server error code {} is not within -32099 and -32000", c)
+            }
+            SpecViolation::MessageNotAString(ref v) => {
+                write!(f, "error message must be a String, got {}", v)
+            }
+            SpecViolation::Deserialize(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for SpecViolation {}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, SpecViolation> {
+    serde_json::to_value(value).map_err(|e| SpecViolation::Deserialize(e.to_string()))
+}
+
+/// Check that a method name is not empty and does not start with `rpc.`.
+pub(crate) fn check_method_name(method: &str) -> Result<(), SpecViolation> {
+    if method.is_empty() {
+        Err(SpecViolation::EmptyMethod)
+    } else if method.starts_with("rpc.") {
+        Err(SpecViolation::ReservedMethod(method.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_method(method: &Value) -> Result<(), SpecViolation> {
+    match *method {
+        Value::String(ref m) => check_method_name(m),
+        ref other => Err(SpecViolation::MethodNotAString(other.clone())),
+    }
+}
+
+fn check_params(params: &Value) -> Result<(), SpecViolation> {
+    match *params {
+        Value::Array(_) | Value::Object(_) => Ok(()),
+        ref other => Err(SpecViolation::Params(other.clone())),
+    }
+}
+
+fn check_id(id: &Value) -> Result<(), SpecViolation> {
+    match *id {
+        Value::String(_) | Value::Null => Ok(()),
+        Value::Number(ref n) if n.is_i64() => Ok(()),
+        ref other => Err(SpecViolation::InvalidId(other.clone())),
+    }
+}
+
+fn check_code(code: &ErrorCode) -> Result<(), SpecViolation> {
+    let value = code.code();
+    if !code.is_valid() && (-32768..=-32000).contains(&value) {
+        Err(SpecViolation::ReservedCode(value))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_members(
+    object: &serde_json::Map<String, Value>,
+    allowed: &[&str],
+) -> Result<(), SpecViolation> {
+    match object.keys().find(|k| !allowed.contains(&k.as_str())) {
+        Some(key) => Err(SpecViolation::UnknownMember(key.clone())),
+        None => Ok(()),
+    }
+}
+
+fn check_version(object: &serde_json::Map<String, Value>) -> Result<(), SpecViolation> {
+    match object.get("jsonrpc") {
+        None => Err(SpecViolation::MissingMember("jsonrpc")),
+        Some(Value::String(v)) if v == "2.0" => Ok(()),
+        Some(other) => Err(SpecViolation::Version(other.clone())),
+    }
+}
+
+/// Check a raw Request or notification.
+pub fn check_request(value: &Value) -> Result<(), SpecViolation> {
+    let object = value.as_object().ok_or(SpecViolation::NotAnObject)?;
+    check_version(object)?;
+    check_members(object, &["jsonrpc", "method", "params", "id"])?;
+    check_method(object.get("method").ok_or(SpecViolation::MissingMember("method"))?)?;
+    if let Some(params) = object.get("params") {
+        check_params(params)?;
+    }
+    if let Some(id) = object.get("id") {
+        check_id(id)?;
+    }
+    Ok(())
+}
+
+/// Check a raw Error object.
+pub fn check_error_object(value: &Value) -> Result<(), SpecViolation> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| SpecViolation::ErrorNotAnObject(value.clone()))?;
+    check_members(object, &["code", "message", "data"])?;
+    match object.get("code") {
+        None => return Err(SpecViolation::MissingMember("code")),
+        Some(code) => match code.as_i64() {
+            Some(code) => check_code(&ErrorCode::from(code))?,
+            None => return Err(SpecViolation::CodeNotAnInteger(code.clone())),
+        },
+    }
+    match object.get("message") {
+        None => Err(SpecViolation::MissingMember("message")),
+        Some(Value::String(_)) => Ok(()),
+        Some(other) => Err(SpecViolation::MessageNotAString(other.clone())),
+    }
+}
+
+/// Check a raw Response.
+pub fn check_response(value: &Value) -> Result<(), SpecViolation> {
+    let object = value.as_object().ok_or(SpecViolation::NotAnObject)?;
+    check_version(object)?;
+    check_members(object, &["jsonrpc", "result", "error", "id"])?;
+    match (object.get("result"), object.get("error")) {
+        (Some(_), Some(_)) => return Err(SpecViolation::ResultAndError),
+        (None, None) => return Err(SpecViolation::MissingMember("result")),
+        (None, Some(error)) => check_error_object(error)?,
+        (Some(_), None) => {}
+    }
+    check_id(object.get("id").ok_or(SpecViolation::MissingMember("id"))?)
+}
+
+fn parse(json: &str) -> Result<Value, SpecViolation> {
+    serde_json::from_str(json).map_err(|e| SpecViolation::Json(e.to_string()))
+}
+
+fn deserialize<T: DeserializeOwned>(value: Value) -> Result<T, SpecViolation> {
+    serde_json::from_value(value).map_err(|e| SpecViolation::Deserialize(e.to_string()))
+}
+
+/// Parse a Request or notification, rejecting any violation of the spec.
+pub fn request_from_str<M, T>(json: &str) -> Result<Request<M, T>, SpecViolation>
+where
+    M: Serialize + DeserializeOwned,
+    T: Serialize + DeserializeOwned,
+    Request<M, T>: DeserializeOwned,
+{
+    let value = parse(json)?;
+    check_request(&value)?;
+    let request: Request<M, T> = deserialize(value)?;
+    request.validate()?;
+    Ok(request)
+}
+
+/// Parse a Response, rejecting any violation of the spec.
+pub fn response_from_str<T>(json: &str) -> Result<Response<T>, SpecViolation>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let value = parse(json)?;
+    check_response(&value)?;
+    deserialize(value)
+}
+
+impl<M: Serialize, T: Serialize> Request<M, T> {
+    /// Check that the Request conforms to the spec: the method is a non-empty String which does
+    /// not start with `rpc.`, and the params, if any, are an Array or an Object.
+    pub fn validate(&self) -> Result<(), SpecViolation> {
+        check_method(&to_value(&self.method)?)?;
+        if let Some(ref params) = self.params {
+            check_params(&to_value(params)?)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the Request conforms to the spec. See [`validate`](#method.validate).
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+}
+
+impl<T> ErrorObject<T> {
+    /// Check that the error object conforms to the spec: its code is not reserved by the spec
+    /// without being defined by it.
+    pub fn validate(&self) -> Result<(), SpecViolation> {
+        check_code(&self.code)
+    }
+}
+
+impl<T> Error<T> {
+    /// Check that the Error conforms to the spec. See
+    /// [`ErrorObject::validate`](struct.ErrorObject.html#method.validate).
+    pub fn validate(&self) -> Result<(), SpecViolation> {
+        self.error.validate()
+    }
+}
+
+impl<T> Success<T> {
+    /// Check that the Success conforms to the spec. Every Success does, this is for symmetry
+    /// with [`Error::validate`](struct.Error.html#method.validate).
+    pub fn validate(&self) -> Result<(), SpecViolation> {
+        Ok(())
+    }
+}
+
+impl<T, E> Response<T, E> {
+    /// Check that the Response conforms to the spec.
+    pub fn validate(&self) -> Result<(), SpecViolation> {
+        match *self {
+            Response::Ok(ref success) => success.validate(),
+            Response::Err(ref error) => error.validate(),
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Snapshots of the RPC surface of a service, and the compatibility between releases.
+//!
+//! An [`ApiSurface`](struct.ApiSurface.html) lists the methods of a service, with the schemas of
+//! their params and results, and the error codes it returns. Teams store the snapshot of every
+//! release and gate the next one on
+//! [`compatible_with`](struct.ApiSurface.html#method.compatible_with), which lists the
+//! [`Break`](enum.Break.html)s for existing clients:
+//!
+//! - a method or an error code was removed,
+//! - the params of a method were narrowed: a call accepted by the older release may now be
+//!   rejected (a type removed, a member newly required, a bound tightened, ...),
+//! - the result of a method changed shape: it may now contain values the clients of the older
+//!   release do not expect (a type added, a member no longer guaranteed, ...).
+//!
+//! Schemas are compared keyword by keyword, for the keywords supported by the
+//! [`schema`](../schema/index.html) module.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::catalog::MethodDoc;
+//! use jrpc::surface::{ApiSurface, Break, Bump};
+//!
+//! # fn main() {
+//! let v1 = ApiSurface::new("1.0.0")
+//!     .method(MethodDoc::new("get").params_schema(json!({"type": "object", "required": ["id"]})))
+//!     .method(MethodDoc::new("legacy"));
+//!
+//! let v2 = ApiSurface::new("2.0.0")
+//!     .method(MethodDoc::new("get").params_schema(json!({"type": "object", "required": ["id", "tenant"]})))
+//!     .method(MethodDoc::new("put"));
+//!
+//! let breaks = v2.compatible_with(&v1);
+//! assert_eq!(breaks.len(), 2);
+//! assert_eq!(breaks[0].to_string(), "params of `get` narrowed: member `tenant` is now required");
+//! assert_eq!(breaks[1], Break::MethodRemoved { method: "legacy".to_string() });
+//! assert_eq!(v2.required_bump(&v1), Bump::Major);
+//! # }
+//! ```
+
+use std::fmt;
+
+use std_prelude::*;
+
+use catalog::{ErrorCatalog, MethodDoc};
+use Value;
+
+/// The methods and error codes of a release of a service.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiSurface {
+    /// The version of the release.
+    pub version: String,
+
+    /// The methods, in the order they were added.
+    #[serde(default)]
+    pub methods: Vec<MethodDoc>,
+
+    /// The error codes, sorted.
+    #[serde(default)]
+    pub errors: Vec<i64>,
+}
+
+impl ApiSurface {
+    /// Create a surface without methods.
+    pub fn new<S: Into<String>>(version: S) -> Self {
+        ApiSurface {
+            version: version.into(),
+            methods: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Snapshot the methods and error codes of a catalog.
+    pub fn from_catalog<S: Into<String>>(version: S, catalog: &ErrorCatalog) -> Self {
+        let mut surface = ApiSurface::new(version);
+        surface.methods = catalog.methods.clone();
+        for doc in &catalog.errors {
+            surface = surface.error(doc.code);
+        }
+        surface
+    }
+
+    /// Add a method, replacing an existing one with the same name.
+    pub fn method(mut self, doc: MethodDoc) -> Self {
+        match self.methods.iter().position(|m| m.name == doc.name) {
+            Some(index) => self.methods[index] = doc,
+            None => self.methods.push(doc),
+        }
+        self
+    }
+
+    /// Add an error code.
+    pub fn error(mut self, code: i64) -> Self {
+        if let Err(index) = self.errors.binary_search(&code) {
+            self.errors.insert(index, code);
+        }
+        self
+    }
+
+    /// Get a method.
+    pub fn get(&self, name: &str) -> Option<&MethodDoc> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    /// The changes of this surface which break the clients of `older`.
+    pub fn compatible_with(&self, older: &ApiSurface) -> Vec<Break> {
+        let mut breaks = Vec::new();
+        for old in &older.methods {
+            let new = match self.get(&old.name) {
+                Some(new) => new,
+                None => {
+                    breaks.push(Break::MethodRemoved {
+                        method: old.name.clone(),
+                    });
+                    continue;
+                }
+            };
+            let mut narrowed = Vec::new();
+            narrowing(
+                schema_or_any(&old.params_schema),
+                schema_or_any(&new.params_schema),
+                "",
+                &mut narrowed,
+            );
+            for (pointer, reason) in narrowed {
+                breaks.push(Break::ParamsNarrowed {
+                    method: old.name.clone(),
+                    pointer: pointer,
+                    reason: reason,
+                });
+            }
+            // Clients must accept every result of the new release.
+            let mut widened = Vec::new();
+            narrowing(
+                schema_or_any(&new.result_schema),
+                schema_or_any(&old.result_schema),
+                "",
+                &mut widened,
+            );
+            for (pointer, reason) in widened {
+                breaks.push(Break::ResultChanged {
+                    method: old.name.clone(),
+                    pointer: pointer,
+                    reason: reason,
+                });
+            }
+        }
+        for code in &older.errors {
+            if self.errors.binary_search(code).is_err() {
+                breaks.push(Break::ErrorRemoved { code: *code });
+            }
+        }
+        breaks
+    }
+
+    /// The semantic version bump from `older` to this surface.
+    pub fn required_bump(&self, older: &ApiSurface) -> Bump {
+        if !self.compatible_with(older).is_empty() {
+            return Bump::Major;
+        }
+        let added_method = self.methods.iter().any(|m| older.get(&m.name).is_none());
+        let added_error = self
+            .errors
+            .iter()
+            .any(|c| older.errors.binary_search(c).is_err());
+        if added_method || added_error || self != older {
+            Bump::Minor
+        } else {
+            Bump::Patch
+        }
+    }
+}
+
+/// The semantic version bump of a release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    /// Nothing changed.
+    Patch,
+    /// The surface grew compatibly.
+    Minor,
+    /// The surface broke its clients.
+    Major,
+}
+
+/// A change which breaks the clients of an older release.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Break {
+    /// The method was removed.
+    MethodRemoved {
+        /// The method.
+        method: String,
+    },
+    /// The error code was removed.
+    ErrorRemoved {
+        /// The error code.
+        code: i64,
+    },
+    /// Params accepted by the older release may be rejected.
+    ParamsNarrowed {
+        /// The method.
+        method: String,
+        /// The JSON pointer of the narrowed schema, relative to the params.
+        pointer: String,
+        /// How it was narrowed.
+        reason: String,
+    },
+    /// Results may contain values the clients of the older release do not expect.
+    ResultChanged {
+        /// The method.
+        method: String,
+        /// The JSON pointer of the changed schema, relative to the result.
+        pointer: String,
+        /// How it changed.
+        reason: String,
+    },
+}
+
+impl fmt::Display for Break {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (what, method, pointer, reason) = match *self {
+            Break::MethodRemoved { ref method } => return write!(f, "method `{}` removed", method),
+            Break::ErrorRemoved { code } => return write!(f, "error code {} removed", code),
+            Break::ParamsNarrowed {
+                ref method,
+                ref pointer,
+                ref reason,
+            } => ("params of", method, pointer, reason),
+            Break::ResultChanged {
+                ref method,
+                ref pointer,
+                ref reason,
+            } => ("result of", method, pointer, reason),
+        };
+        let change = if what == "params of" {
+            "narrowed"
+        } else {
+            "changed"
+        };
+        if pointer.is_empty() {
+            write!(f, "{} `{}` {}: {}", what, method, change, reason)
+        } else {
+            write!(
+                f,
+                "{} `{}` {} at {}: {}",
+                what, method, change, pointer, reason
+            )
+        }
+    }
+}
+
+static ANY: Value = Value::Bool(true);
+
+fn schema_or_any(schema: &Option<Value>) -> &Value {
+    schema.as_ref().unwrap_or(&ANY)
+}
+
+fn type_names(schema: &Value) -> Option<Vec<&str>> {
+    match *schema.get("type")? {
+        Value::String(ref name) => Some(vec![name.as_str()]),
+        Value::Array(ref names) => Some(names.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+fn escape(member: &str) -> String {
+    member.replace('~', "~0").replace('/', "~1")
+}
+
+fn push(out: &mut Vec<(String, String)>, pointer: &str, reason: String) {
+    out.push((pointer.to_string(), reason));
+}
+
+/// Find where `narrow` rejects values `wide` accepts, pushing `(pointer, reason)`.
+fn narrowing(wide: &Value, narrow: &Value, pointer: &str, out: &mut Vec<(String, String)>) {
+    match (wide, narrow) {
+        (_, &Value::Bool(true)) => return,
+        (&Value::Bool(false), _) => return,
+        (_, &Value::Bool(false)) => return push(out, pointer, "no value is accepted".to_string()),
+        _ => {}
+    }
+
+    match (type_names(wide), type_names(narrow)) {
+        (None, Some(narrow)) => push(
+            out,
+            pointer,
+            format!("type restricted to {}", narrow.join(" or ")),
+        ),
+        (Some(wide), Some(narrow)) => {
+            let accepts = |name: &str| {
+                narrow.contains(&name) || (name == "integer" && narrow.contains(&"number"))
+            };
+            for name in wide.into_iter().filter(|name| !accepts(name)) {
+                push(out, pointer, format!("type {} no longer accepted", name));
+            }
+        }
+        _ => {}
+    }
+
+    match (
+        wide.get("enum").and_then(Value::as_array),
+        narrow.get("enum").and_then(Value::as_array),
+    ) {
+        (None, Some(_)) => push(out, pointer, "values restricted to an enum".to_string()),
+        (Some(wide), Some(narrow)) => {
+            for value in wide.iter().filter(|v| !narrow.contains(v)) {
+                push(out, pointer, format!("value {} no longer accepted", value));
+            }
+        }
+        _ => {}
+    }
+    if let Some(expected) = narrow.get("const") {
+        if wide.get("const") != Some(expected) {
+            push(out, pointer, format!("value restricted to {}", expected));
+        }
+    }
+
+    for &(keyword, lower) in &[
+        ("minimum", true),
+        ("maximum", false),
+        ("minLength", true),
+        ("maxLength", false),
+        ("minItems", true),
+        ("maxItems", false),
+    ] {
+        let bound = match narrow.get(keyword).and_then(Value::as_f64) {
+            Some(bound) => bound,
+            None => continue,
+        };
+        let tightened = match wide.get(keyword).and_then(Value::as_f64) {
+            None => true,
+            Some(old) if lower => bound > old,
+            Some(old) => bound < old,
+        };
+        if tightened {
+            push(out, pointer, format!("{} tightened to {}", keyword, bound));
+        }
+    }
+
+    let required = |schema: &Value| -> Vec<String> {
+        schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| {
+                r.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let wide_required = required(wide);
+    for name in required(narrow) {
+        if !wide_required.contains(&name) {
+            push(out, pointer, format!("member `{}` is now required", name));
+        }
+    }
+
+    if let Some(&Value::Bool(false)) = narrow.get("additionalProperties") {
+        if wide.get("additionalProperties") != Some(&Value::Bool(false)) {
+            push(
+                out,
+                pointer,
+                "additional members are no longer accepted".to_string(),
+            );
+        }
+    }
+    let empty = Default::default();
+    let wide_properties = wide
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let narrow_properties = narrow
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let narrow_additional = narrow.get("additionalProperties").unwrap_or(&ANY);
+    for (name, wide_member) in wide_properties {
+        let narrow_member = narrow_properties.get(name).unwrap_or(narrow_additional);
+        let member_pointer = format!("{}/{}", pointer, escape(name));
+        narrowing(wide_member, narrow_member, &member_pointer, out);
+    }
+    for (name, narrow_member) in narrow_properties {
+        if !wide_properties.contains_key(name) {
+            let wide_member = wide.get("additionalProperties").unwrap_or(&ANY);
+            let member_pointer = format!("{}/{}", pointer, escape(name));
+            narrowing(wide_member, narrow_member, &member_pointer, out);
+        }
+    }
+
+    if let Some(narrow_items) = narrow.get("items") {
+        let wide_items = wide.get("items").unwrap_or(&ANY);
+        narrowing(wide_items, narrow_items, &format!("{}/items", pointer), out);
+    }
+}
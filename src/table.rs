@@ -0,0 +1,137 @@
+//! Method tables fixed at compile time.
+//!
+//! A server with a fixed set of methods does not need to register handlers in a map at startup
+//! and hash every method name it receives. [`method_table!`](../macro.method_table.html) declares
+//! the methods as an enum implementing [`MethodTable`](trait.MethodTable.html): looking up a
+//! name is a `match` on string literals, which the compiler turns into comparisons of lengths
+//! and bytes, without hashing or allocating. Dispatching is then a `match` on the enum, which the
+//! compiler checks covers every method.
+//!
+//! The enum implements `AsRef<str>`, so it can be the method of a
+//! [`Request`](../struct.Request.html); [`route`](fn.route.html) converts a parsed Request,
+//! answering unknown methods with `MethodNotFound`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::{ErrorCode, Request, Value};
+//! use jrpc::table::{self, MethodTable};
+//!
+//! method_table! {
+//!     /// The methods of the calculator.
+//!     pub enum Calculator {
+//!         /// Add the params.
+//!         Add = "add",
+//!         /// Subtract the second param from the first.
+//!         Subtract = "subtract",
+//!     }
+//! }
+//!
+//! fn call(method: Calculator, params: Vec<i64>) -> i64 {
+//!     match method {
+//!         Calculator::Add => params.iter().sum(),
+//!         Calculator::Subtract => params[0] - params[1],
+//!     }
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(Calculator::from_name("subtract"), Some(Calculator::Subtract));
+//! assert_eq!(Calculator::Add.name(), "add");
+//! assert_eq!(Calculator::ALL.len(), 2);
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#;
+//! let request = jrpc::parse_request::<String>(json).unwrap();
+//! let request: Request<Calculator, Value> = table::route(request).unwrap();
+//! let params: Vec<i64> = serde_json::from_value(request.params.unwrap()).unwrap();
+//! assert_eq!(call(request.method, params), 19);
+//!
+//! let json = r#"{"jsonrpc": "2.0", "method": "divide", "params": [42, 23], "id": 2}"#;
+//! let request = jrpc::parse_request::<String>(json).unwrap();
+//! let error = table::route::<Calculator, _>(request).unwrap_err();
+//! assert_eq!(error.error.code, ErrorCode::MethodNotFound);
+//! # }
+//! ```
+
+use {Error, Id, Request, Value, V2_0};
+
+/// A fixed set of methods, declared with [`method_table!`](../macro.method_table.html).
+pub trait MethodTable: Copy + 'static {
+    /// All methods, in the order they were declared.
+    const ALL: &'static [Self];
+
+    /// The method with a name, if there is one.
+    fn from_name(name: &str) -> Option<Self>;
+
+    /// The name of the method.
+    fn name(self) -> &'static str;
+}
+
+/// Convert the method of a Request into the method of a table, failing with `MethodNotFound`.
+///
+/// The error has the `id` of the Request, or Null for notifications, which must not be answered.
+pub fn route<T: MethodTable, P>(
+    request: Request<String, P>,
+) -> Result<Request<T, P>, Error<Value>> {
+    match T::from_name(&request.method) {
+        Some(method) => Ok(Request {
+            jsonrpc: V2_0,
+            method: method,
+            params: request.params,
+            id: request.id,
+        }),
+        None => Err(Error::method_not_found(
+            request.id.to_id().unwrap_or(Id::Null),
+        )),
+    }
+}
+
+/// Declare an enum of methods implementing [`MethodTable`](table/trait.MethodTable.html).
+///
+/// See the [`table`](table/index.html) module for an example.
+#[macro_export]
+macro_rules! method_table {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[doc = $doc:expr])* $variant:ident = $method:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum $name {
+            $($(#[doc = $doc])* $variant,)*
+        }
+
+        impl $crate::table::MethodTable for $name {
+            const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            fn from_name(name: &str) -> ::std::option::Option<$name> {
+                match name {
+                    $($method => ::std::option::Option::Some($name::$variant),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => $method,)*
+                }
+            }
+        }
+
+        impl ::std::convert::AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                $crate::table::MethodTable::name(*self)
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str($crate::table::MethodTable::name(*self))
+            }
+        }
+    };
+}
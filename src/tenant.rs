@@ -0,0 +1,342 @@
+//! Serving many tenants from one endpoint.
+//!
+//! # Convention
+//!
+//! The tenant is identified by the top level extension member
+//! [`TENANT_MEMBER`](constant.TENANT_MEMBER.html) of the Request Object, next to `jsonrpc` and
+//! `method`:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "tenant": "acme", "method": "CreateFoo", "params": [1], "id": 1}
+//! ```
+//!
+//! Keeping it outside of `params` means method handlers never have to know about tenancy, and
+//! peers which do not implement the extension simply ignore the member.
+//!
+//! # Isolation
+//!
+//! A [`TenantRouter`](struct.TenantRouter.html) holds a separate method table and separate
+//! [`TenantLimits`](struct.TenantLimits.html) for each tenant. A tenant can only reach the
+//! methods registered for it (plus any shared ones) and exhausting one tenant's limits does not
+//! affect the others. The router is generic over the handler type and does not call handlers
+//! itself.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::ErrorCode;
+//! use jrpc::tenant::{self, TenantLimits, TenantRouter};
+//!
+//! # fn main() {
+//! let mut router = TenantRouter::new();
+//! router.shared("rpc.stats", "stats");
+//! router.add_tenant("acme", TenantLimits::new().max_in_flight(1));
+//! router.method("acme", "CreateFoo", "create_foo");
+//! router.add_tenant("globex", TenantLimits::new());
+//!
+//! let json = r#"{"jsonrpc": "2.0", "tenant": "acme", "method": "CreateFoo", "id": 1}"#;
+//! let parsed = tenant::parse_request::<String>(json).unwrap();
+//! let (tenant, request) = (parsed.tenant.unwrap(), parsed.request);
+//!
+//! assert_eq!(router.route(&tenant, &request.method), Ok(&"create_foo"));
+//! // acme already has a request in flight.
+//! let err = router.route(&tenant, &request.method).unwrap_err();
+//! assert_eq!(err.code(), ErrorCode::ServerError(tenant::LIMIT_EXCEEDED));
+//! router.finish(&tenant);
+//!
+//! // Other tenants can't see acme's methods.
+//! let err = router.route("globex", "CreateFoo").unwrap_err();
+//! assert_eq!(err.code(), ErrorCode::MethodNotFound);
+//! assert_eq!(router.route("globex", "rpc.stats"), Ok(&"stats"));
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+use std::mem;
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use memory::MemoryUsage;
+use {request_from_value, Error, ErrorCode, Id, Request, Value};
+
+/// The Request member carrying the tenant id.
+pub const TENANT_MEMBER: &str = "tenant";
+
+/// The server error code returned for unknown tenants.
+pub const UNKNOWN_TENANT: i64 = -32040;
+
+/// The server error code returned when a tenant exceeds its limits or quota.
+pub const LIMIT_EXCEEDED: i64 = -32041;
+
+/// Get the tenant id of a Request, if any.
+pub fn tenant_of(request: &Value) -> Option<&str> {
+    request.get(TENANT_MEMBER).and_then(Value::as_str)
+}
+
+/// Serialize a Request (or any Object) with the tenant id added.
+///
+/// # Panics
+///
+/// If `request` does not serialize to an Object.
+pub fn with_tenant<T: Serialize>(request: &T, tenant: &str) -> Value {
+    let mut value = serde_json::to_value(request).expect("request is serializable");
+    value
+        .as_object_mut()
+        .expect("request must be an Object")
+        .insert(TENANT_MEMBER.to_string(), Value::from(tenant));
+    value
+}
+
+/// A Request together with its tenant id.
+#[derive(Debug)]
+pub struct TenantRequest<M> {
+    /// The tenant id, if the Request had one.
+    pub tenant: Option<String>,
+    /// The Request.
+    pub request: Request<M, Value>,
+}
+
+/// Like [`jrpc::parse_request`](../fn.parse_request.html), but also return the tenant id.
+pub fn parse_request<M>(json: &str) -> Result<TenantRequest<M>, Error<Value>>
+where
+    M: Serialize + DeserializeOwned,
+{
+    let value: Value = serde_json::from_str(json)
+        .map_err(|err| Error::new(Id::Null, ErrorCode::ParseError, err.to_string(), None))?;
+    let tenant = tenant_of(&value).map(String::from);
+    Ok(TenantRequest {
+        tenant: tenant,
+        request: request_from_value(value)?,
+    })
+}
+
+/// The limits of a single tenant.
+///
+/// Unset limits are unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantLimits {
+    /// The maximum number of requests in flight at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<u64>,
+
+    /// The maximum number of requests per quota window. The caller ends a window with
+    /// [`TenantRouter::reset_quotas`](struct.TenantRouter.html#method.reset_quotas).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota: Option<u64>,
+}
+
+impl TenantLimits {
+    /// Create unlimited limits.
+    pub fn new() -> Self {
+        TenantLimits::default()
+    }
+
+    /// Set the maximum number of requests in flight.
+    pub fn max_in_flight(mut self, max: u64) -> Self {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Set the maximum number of requests per quota window.
+    pub fn quota(mut self, quota: u64) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+}
+
+/// The usage of a single tenant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantUsage {
+    /// The number of requests currently in flight.
+    pub in_flight: u64,
+    /// The number of requests admitted in the current quota window.
+    pub used: u64,
+}
+
+#[derive(Debug)]
+struct Tenant<H> {
+    methods: BTreeMap<String, H>,
+    limits: TenantLimits,
+    usage: TenantUsage,
+}
+
+/// Routes methods within per-tenant method tables, enforcing per-tenant limits.
+///
+/// Every successful [`route`](#method.route) counts as a request in flight until the caller
+/// calls [`finish`](#method.finish) for the tenant.
+#[derive(Debug)]
+pub struct TenantRouter<H> {
+    tenants: BTreeMap<String, Tenant<H>>,
+    shared: BTreeMap<String, H>,
+}
+
+impl<H> Default for TenantRouter<H> {
+    fn default() -> Self {
+        TenantRouter {
+            tenants: BTreeMap::new(),
+            shared: BTreeMap::new(),
+        }
+    }
+}
+
+impl<H> TenantRouter<H> {
+    /// Create a router without any tenants.
+    pub fn new() -> Self {
+        TenantRouter::default()
+    }
+
+    /// Add a tenant, or replace the limits of an existing one.
+    pub fn add_tenant(&mut self, tenant: &str, limits: TenantLimits) {
+        self.tenants
+            .entry(tenant.to_string())
+            .or_insert_with(|| Tenant {
+                methods: BTreeMap::new(),
+                limits: limits,
+                usage: TenantUsage::default(),
+            })
+            .limits = limits;
+    }
+
+    /// Remove a tenant and all of its methods.
+    pub fn remove_tenant(&mut self, tenant: &str) -> bool {
+        self.tenants.remove(tenant).is_some()
+    }
+
+    /// Register a method for a single tenant. Returns the handler it replaced, if any.
+    ///
+    /// # Panics
+    ///
+    /// If the tenant was not [added](#method.add_tenant).
+    pub fn method(&mut self, tenant: &str, method: &str, handler: H) -> Option<H> {
+        self.tenants
+            .get_mut(tenant)
+            .expect("unknown tenant")
+            .methods
+            .insert(method.to_string(), handler)
+    }
+
+    /// Register a method available to every tenant. Tenant methods of the same name take
+    /// precedence.
+    pub fn shared(&mut self, method: &str, handler: H) -> Option<H> {
+        self.shared.insert(method.to_string(), handler)
+    }
+
+    /// Look up the handler for `method` and admit the request against the tenant's limits.
+    pub fn route(&mut self, tenant: &str, method: &str) -> Result<&H, TenantError> {
+        let TenantRouter {
+            ref mut tenants,
+            ref shared,
+        } = *self;
+        let entry = tenants
+            .get_mut(tenant)
+            .ok_or_else(|| TenantError::UnknownTenant(tenant.to_string()))?;
+        let handler = match entry.methods.get(method) {
+            Some(handler) => handler,
+            None => shared
+                .get(method)
+                .ok_or_else(|| TenantError::MethodNotFound(method.to_string()))?,
+        };
+        let limits = entry.limits;
+        let usage = &mut entry.usage;
+        if limits
+            .max_in_flight
+            .is_some_and(|max| usage.in_flight >= max)
+        {
+            return Err(TenantError::InFlight(tenant.to_string()));
+        }
+        if limits.quota.is_some_and(|quota| usage.used >= quota) {
+            return Err(TenantError::Quota(tenant.to_string()));
+        }
+        usage.in_flight += 1;
+        usage.used += 1;
+        Ok(handler)
+    }
+
+    /// Mark one request of the tenant as finished.
+    pub fn finish(&mut self, tenant: &str) {
+        if let Some(entry) = self.tenants.get_mut(tenant) {
+            entry.usage.in_flight = entry.usage.in_flight.saturating_sub(1);
+        }
+    }
+
+    /// Start a new quota window for all tenants.
+    pub fn reset_quotas(&mut self) {
+        for entry in self.tenants.values_mut() {
+            entry.usage.used = 0;
+        }
+    }
+
+    /// Get the usage of a tenant.
+    pub fn usage(&self, tenant: &str) -> Option<TenantUsage> {
+        self.tenants.get(tenant).map(|t| t.usage)
+    }
+
+    /// Iterate over the tenant ids.
+    pub fn tenants(&self) -> impl Iterator<Item = &str> {
+        self.tenants.keys().map(String::as_str)
+    }
+}
+
+/// Routers hold configuration only, so they never shed.
+impl<H> MemoryUsage for TenantRouter<H> {
+    fn memory_usage(&self) -> usize {
+        let handlers = |methods: &BTreeMap<String, H>| -> usize {
+            methods.keys().map(|m| m.len() + mem::size_of::<H>()).sum()
+        };
+        let tenants: usize = self
+            .tenants
+            .iter()
+            .map(|(name, t)| name.len() + mem::size_of::<Tenant<H>>() + handlers(&t.methods))
+            .sum();
+        tenants + handlers(&self.shared)
+    }
+}
+
+/// The error returned when a request can not be routed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TenantError {
+    /// The tenant is not known to the router.
+    UnknownTenant(String),
+    /// The method is not available to the tenant.
+    MethodNotFound(String),
+    /// The tenant has too many requests in flight.
+    InFlight(String),
+    /// The tenant has exhausted its quota for the current window.
+    Quota(String),
+}
+
+impl TenantError {
+    /// The error code to respond with.
+    pub fn code(&self) -> ErrorCode {
+        match *self {
+            TenantError::UnknownTenant(_) => ErrorCode::ServerError(UNKNOWN_TENANT),
+            TenantError::MethodNotFound(_) => ErrorCode::MethodNotFound,
+            TenantError::InFlight(_) | TenantError::Quota(_) => {
+                ErrorCode::ServerError(LIMIT_EXCEEDED)
+            }
+        }
+    }
+
+    /// Convert into an error response for the request `id`.
+    pub fn into_error(self, id: Id) -> Error<Value> {
+        Error::new(id, self.code(), self.to_string(), None)
+    }
+}
+
+impl fmt::Display for TenantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TenantError::UnknownTenant(ref t) => write!(f, "unknown tenant `{}`", t),
+            TenantError::MethodNotFound(ref m) => write!(f, "method `{}` not found", m),
+            TenantError::InFlight(ref t) => write!(f, "tenant `{}` has too many requests", t),
+            TenantError::Quota(ref t) => write!(f, "tenant `{}` exceeded its quota", t),
+        }
+    }
+}
+
+impl error::Error for TenantError {}
@@ -0,0 +1,131 @@
+//! Types for legacy JSON-RPC 1.0 peers.
+//!
+//! > Only available with the `v1-compat` feature.
+//!
+//! JSON-RPC 1.0 messages have no `jsonrpc` member. A Request always carries `params` (an Array)
+//! and an `id`, where an `id` of Null marks a notification. A Response always carries **both**
+//! `result` and `error`, one of which MUST be Null.
+//!
+//! The strict 2.0 types in the crate root are unaffected by these.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! extern crate serde_json;
+//! use jrpc::Id;
+//! use jrpc::v1;
+//!
+//! # fn main() {
+//! let request = v1::Request::new(Id::from(1), "echo".to_string(), vec!["Hello".to_string()]);
+//! assert_eq!(
+//!     serde_json::to_string(&request).unwrap(),
+//!     r#"{"method":"echo","params":["Hello"],"id":1}"#,
+//! );
+//!
+//! let response: v1::Response<String> =
+//!     serde_json::from_str(r#"{"result": "Hello", "error": null, "id": 1}"#).unwrap();
+//! assert_eq!(response.into_result(), Ok("Hello".to_string()));
+//! # }
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::ser::Serialize;
+use std_prelude::*;
+
+use {ErrorObject, Id, Value};
+
+/// A JSON-RPC 1.0 Request.
+///
+/// A Request with an `id` of Null is a notification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request<M, T> {
+    /// The method to be invoked.
+    pub method: M,
+
+    /// The parameters. The 1.0 spec requires this to be an Array.
+    pub params: T,
+
+    /// The request id. Null for notifications.
+    pub id: Id,
+}
+
+impl<M: Serialize + DeserializeOwned, T: Serialize + DeserializeOwned> Request<M, T> {
+    /// Create a new Request.
+    pub fn new(id: Id, method: M, params: T) -> Self {
+        Request {
+            method: method,
+            params: params,
+            id: id,
+        }
+    }
+
+    /// Create a new notification, i.e. a Request with a Null id.
+    pub fn notification(method: M, params: T) -> Self {
+        Request::new(Id::Null, method, params)
+    }
+
+    /// Whether this is a notification.
+    pub fn is_notification(&self) -> bool {
+        self.id == Id::Null
+    }
+}
+
+/// A JSON-RPC 1.0 Response.
+///
+/// Exactly one of `result` and `error` should be non-null; both are always serialized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response<T, E = Value> {
+    /// The result. Null if there was an error.
+    pub result: Option<T>,
+
+    /// The error. Null if there was no error.
+    pub error: Option<E>,
+
+    /// The id of the Request this is responding to.
+    pub id: Id,
+}
+
+impl<T: Serialize + DeserializeOwned, E: Serialize + DeserializeOwned> Response<T, E> {
+    /// Construct a successful Response.
+    pub fn success(id: Id, result: T) -> Self {
+        Response {
+            result: Some(result),
+            error: None,
+            id: id,
+        }
+    }
+
+    /// Construct an error Response.
+    pub fn error(id: Id, error: E) -> Self {
+        Response {
+            result: None,
+            error: Some(error),
+            id: id,
+        }
+    }
+
+    /// Interpret the Response as a `Result`.
+    ///
+    /// A non-null `error` always wins. If both members are null, the `Ok` value results from
+    /// deserializing `T` from Null: this succeeds for `Option<_>`, `()` and `Value`, which
+    /// allows methods whose result is legitimately null.
+    pub fn into_result(self) -> Result<T, Option<E>> {
+        if let Some(error) = self.error {
+            return Err(Some(error));
+        }
+        match self.result {
+            Some(result) => Ok(result),
+            None => ::serde_json::from_value(Value::Null).map_err(|_| None),
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Response<T, Value> {
+    /// Construct an error Response from a 2.0 style `ErrorObject`, the most common error shape
+    /// used by 1.0 peers.
+    pub fn from_error_object<D: Serialize>(id: Id, error: &ErrorObject<D>) -> Self {
+        let error = ::serde_json::to_value(error).expect("ErrorObject is always serializable");
+        Response::error(id, error)
+    }
+}
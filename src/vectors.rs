@@ -0,0 +1,304 @@
+//! Test vectors for other implementations.
+//!
+//! [`corpus`](fn.corpus.html) generates the messages this crate is tested against, together with
+//! the outcome of parsing them with this crate, so that implementations in other languages can
+//! be validated against exactly the same corpus. Each [`Vector`](struct.Vector.html) holds:
+//!
+//! - the raw `message`, which is not necessarily valid json,
+//! - the `kind` of message it is parsed as,
+//! - the `expected` outcome: either the message is accepted, with its canonical form (the
+//!   compact json serialization, with sorted Object members) and the
+//!   [content hash](../journal/fn.content_hash.html) of the canonical form (64 bit FNV-1a, as 16
+//!   lowercase hex digits), or it is rejected, with the error code of the Response a server
+//!   sends back.
+//!
+//! The corpus is keyed by file path, and [`Corpus::tree`](struct.Corpus.html#method.tree) nests
+//! it into directories, e.g. to be written to disk by a build script:
+//!
+//! ```text
+//! request/valid/positional_params.json
+//! request/invalid/parse_error.json
+//! response/valid/success.json
+//! ...
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::vectors::{self, Expected};
+//!
+//! # fn main() {
+//! let corpus = vectors::corpus();
+//! assert!(corpus.check().is_empty());
+//!
+//! let vector = &corpus.files["request/invalid/parse_error.json"];
+//! assert_eq!(vector.expected, Expected::Rejected { code: Some(-32700) });
+//!
+//! let tree = corpus.tree();
+//! let vector = &tree["request"]["valid"]["positional_params.json"];
+//! assert_eq!(vector["expected"]["outcome"], "accepted");
+//! assert_eq!(vector["expected"]["hash"].as_str().unwrap().len(), 16);
+//! # }
+//! ```
+
+use serde_json;
+use std_prelude::*;
+
+use journal;
+use {parse_request, Response, Value};
+
+/// The kind of message a vector is parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// A Request or notification, as received by a server.
+    Request,
+    /// A Response, as received by a client.
+    Response,
+}
+
+/// The outcome of parsing a message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "lowercase")]
+pub enum Expected {
+    /// The message is valid.
+    Accepted {
+        /// The message as parsed, reserialized.
+        canonical: Value,
+        /// The content hash of `canonical`.
+        hash: String,
+    },
+    /// The message is invalid.
+    Rejected {
+        /// The error code a server answers an invalid Request with. Invalid Responses are not
+        /// answered, so they have no code.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<i64>,
+    },
+}
+
+/// A single test vector.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vector {
+    /// The kind of message.
+    pub kind: Kind,
+
+    /// The raw message.
+    pub message: String,
+
+    /// The outcome of parsing `message` with this crate.
+    pub expected: Expected,
+}
+
+impl Vector {
+    /// Create a vector by parsing `message` with this crate.
+    pub fn new<S: Into<String>>(kind: Kind, message: S) -> Self {
+        let message = message.into();
+        let expected = parse(kind, &message);
+        Vector {
+            kind: kind,
+            message: message,
+            expected: expected,
+        }
+    }
+
+    /// Whether this crate still parses `message` to the `expected` outcome.
+    pub fn check(&self) -> bool {
+        parse(self.kind, &self.message) == self.expected
+    }
+}
+
+fn accepted(canonical: Value) -> Expected {
+    Expected::Accepted {
+        hash: journal::content_hash(&canonical),
+        canonical: canonical,
+    }
+}
+
+fn parse(kind: Kind, message: &str) -> Expected {
+    match kind {
+        Kind::Request => match parse_request::<String>(message) {
+            Ok(request) => accepted(serde_json::to_value(&request).expect("requests serialize")),
+            Err(error) => Expected::Rejected {
+                code: Some(error.error.code.code()),
+            },
+        },
+        Kind::Response => match serde_json::from_str::<Response<Value>>(message) {
+            Ok(response) => {
+                accepted(serde_json::to_value(&response).expect("responses serialize"))
+            }
+            Err(_) => Expected::Rejected { code: None },
+        },
+    }
+}
+
+/// A set of test vectors, keyed by file path.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Corpus {
+    /// The vectors, keyed by `/` separated path.
+    pub files: BTreeMap<String, Vector>,
+}
+
+impl Corpus {
+    /// Create an empty corpus.
+    pub fn new() -> Self {
+        Corpus::default()
+    }
+
+    /// Add a vector at `path`, replacing any vector already there.
+    pub fn insert<S: Into<String>>(&mut self, path: S, vector: Vector) {
+        self.files.insert(path.into(), vector);
+    }
+
+    /// The paths of the vectors which this crate no longer parses to their expected outcome.
+    pub fn check(&self) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|&(_, vector)| !vector.check())
+            .map(|(path, _)| path.as_str())
+            .collect()
+    }
+
+    /// The corpus as nested Objects, one per directory, with the vectors as leaves.
+    ///
+    /// # Panics
+    ///
+    /// If a path is both a vector and a directory.
+    pub fn tree(&self) -> Value {
+        let mut root = serde_json::Map::new();
+        for (path, vector) in &self.files {
+            let mut parts: Vec<&str> = path.split('/').collect();
+            let file = parts.pop().expect("split returns at least one part");
+            let mut dir = &mut root;
+            for part in parts {
+                dir = dir
+                    .entry(part.to_string())
+                    .or_insert_with(|| Value::Object(serde_json::Map::new()))
+                    .as_object_mut()
+                    .unwrap_or_else(|| panic!("{} is both a vector and a directory", part));
+            }
+            let vector = serde_json::to_value(vector).expect("vectors serialize");
+            if dir.insert(file.to_string(), vector).is_some() {
+                panic!("{} is both a vector and a directory", path);
+            }
+        }
+        Value::Object(root)
+    }
+}
+
+const REQUESTS: &[(&str, &str)] = &[
+    (
+        "valid/positional_params",
+        r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#,
+    ),
+    (
+        "valid/named_params",
+        r#"{"jsonrpc": "2.0", "method": "subtract", "params": {"subtrahend": 23, "minuend": 42}, "id": 3}"#,
+    ),
+    (
+        "valid/no_params",
+        r#"{"jsonrpc": "2.0", "method": "foobar", "id": "1"}"#,
+    ),
+    (
+        "valid/notification",
+        r#"{"jsonrpc": "2.0", "method": "update", "params": [1, 2, 3, 4, 5]}"#,
+    ),
+    (
+        "valid/null_id",
+        r#"{"jsonrpc": "2.0", "method": "foobar", "id": null}"#,
+    ),
+    (
+        "valid/unicode_method",
+        r#"{"jsonrpc": "2.0", "method": "zürich.😀", "id": 7}"#,
+    ),
+    (
+        "valid/negative_id",
+        r#"{"id": -9007199254740993, "method": "foobar", "jsonrpc": "2.0"}"#,
+    ),
+    (
+        "invalid/parse_error",
+        r#"{"jsonrpc": "2.0", "method": "foobar, "params": "bar", "baz]"#,
+    ),
+    ("invalid/empty", ""),
+    ("invalid/not_an_object", "1"),
+    ("invalid/empty_object", "{}"),
+    (
+        "invalid/wrong_version",
+        r#"{"jsonrpc": "1.0", "method": "foobar", "id": 1}"#,
+    ),
+    (
+        "invalid/missing_version",
+        r#"{"method": "foobar", "id": 1}"#,
+    ),
+    (
+        "invalid/method_not_a_string",
+        r#"{"jsonrpc": "2.0", "method": 1, "params": "bar", "id": 1}"#,
+    ),
+    (
+        "invalid/fractional_id",
+        r#"{"jsonrpc": "2.0", "method": "foobar", "id": 1.5}"#,
+    ),
+    (
+        "invalid/object_id",
+        r#"{"jsonrpc": "2.0", "method": "foobar", "id": {}}"#,
+    ),
+];
+
+const RESPONSES: &[(&str, &str)] = &[
+    (
+        "valid/success",
+        r#"{"jsonrpc": "2.0", "result": 19, "id": 1}"#,
+    ),
+    (
+        "valid/null_result",
+        r#"{"jsonrpc": "2.0", "result": null, "id": "a"}"#,
+    ),
+    (
+        "valid/error",
+        r#"{"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": "1"}"#,
+    ),
+    (
+        "valid/error_with_data",
+        r#"{"jsonrpc": "2.0", "error": {"code": -32000, "message": "Server error", "data": [1, "a"]}, "id": 2}"#,
+    ),
+    (
+        "valid/null_id_error",
+        r#"{"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}"#,
+    ),
+    ("invalid/parse_error", r#"{"jsonrpc": "2.0", "result": "#),
+    (
+        "invalid/result_and_error",
+        r#"{"jsonrpc": "2.0", "result": 1, "error": {"code": -32603, "message": "Internal error"}, "id": 1}"#,
+    ),
+    (
+        "invalid/missing_id",
+        r#"{"jsonrpc": "2.0", "result": 19}"#,
+    ),
+    (
+        "invalid/wrong_version",
+        r#"{"jsonrpc": "1.0", "result": 19, "id": 1}"#,
+    ),
+    (
+        "invalid/error_not_an_object",
+        r#"{"jsonrpc": "2.0", "error": "Method not found", "id": 1}"#,
+    ),
+];
+
+/// Generate the corpus of this crate.
+pub fn corpus() -> Corpus {
+    let mut corpus = Corpus::new();
+    for &(kind, messages) in &[(Kind::Request, REQUESTS), (Kind::Response, RESPONSES)] {
+        let dir = match kind {
+            Kind::Request => "request",
+            Kind::Response => "response",
+        };
+        for &(path, message) in messages {
+            corpus.insert(
+                format!("{}/{}.json", dir, path),
+                Vector::new(kind, message),
+            );
+        }
+    }
+    corpus
+}
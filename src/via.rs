@@ -0,0 +1,203 @@
+//! Request provenance for multi-hop gateways.
+//!
+//! # Convention
+//!
+//! Each proxy forwarding a Request appends a [`Hop`](struct.Hop.html) to the top level extension
+//! member [`VIA_MEMBER`](constant.VIA_MEMBER.html), recording its gateway id, the time it
+//! forwarded the Request and the Request `id` as it was received (proxies commonly rewrite ids).
+//! The first hop therefore carries the id used by the original client:
+//!
+//! ```json
+//! {
+//!     "jsonrpc": "2.0",
+//!     "method": "CreateFoo",
+//!     "id": 7,
+//!     "via": [
+//!         {"gateway": "edge-1", "timestamp_ms": 1526000000000, "id": "client-42"},
+//!         {"gateway": "core-3", "timestamp_ms": 1526000000012, "id": 3}
+//!     ]
+//! }
+//! ```
+//!
+//! A gateway which finds its own id already in the chain has received the Request in a loop and
+//! must reject it with [`LOOP_DETECTED`](constant.LOOP_DETECTED.html).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! #[macro_use]
+//! extern crate serde_json;
+//! use jrpc::{ErrorCode, Id};
+//! use jrpc::via::{self, Hop, Via};
+//!
+//! # fn main() {
+//! let mut request = json!({"jsonrpc": "2.0", "method": "CreateFoo", "id": "client-42"});
+//!
+//! via::forward(&mut request, Hop::new("edge-1", 1000, Id::from("client-42"))).unwrap();
+//! request["id"] = json!(3);
+//! via::forward(&mut request, Hop::new("core-3", 1012, Id::from(3))).unwrap();
+//!
+//! // The origin server sees the whole chain.
+//! let chain = Via::of(&request).unwrap();
+//! assert_eq!(chain.len(), 2);
+//! assert_eq!(chain.origin_id(), Some(&Id::from("client-42")));
+//!
+//! // Forwarding back through edge-1 is a loop.
+//! let err = via::forward(&mut request, Hop::new("edge-1", 1020, Id::from(3))).unwrap_err();
+//! assert_eq!(err.code(), ErrorCode::ServerError(via::LOOP_DETECTED));
+//! # }
+//! ```
+
+use std::error;
+use std::fmt;
+
+use serde_json;
+use std_prelude::*;
+
+use {Error, ErrorCode, Id, Value};
+
+/// The Request member carrying the provenance chain.
+pub const VIA_MEMBER: &str = "via";
+
+/// The server error code returned when a gateway finds itself in the chain.
+pub const LOOP_DETECTED: i64 = -32042;
+
+/// A single forwarding step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hop {
+    /// The id of the gateway which forwarded the Request.
+    pub gateway: String,
+
+    /// When the Request was forwarded, in milliseconds since the unix epoch.
+    pub timestamp_ms: u64,
+
+    /// The Request `id` as received by the gateway.
+    pub id: Id,
+}
+
+impl Hop {
+    /// Create a new hop.
+    pub fn new<G: Into<String>>(gateway: G, timestamp_ms: u64, id: Id) -> Self {
+        Hop {
+            gateway: gateway.into(),
+            timestamp_ms: timestamp_ms,
+            id: id,
+        }
+    }
+}
+
+/// The provenance chain of a Request, oldest hop first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Via(pub Vec<Hop>);
+
+impl Via {
+    /// Read the chain from a Request. A Request without the member has an empty chain.
+    pub fn of(request: &Value) -> Result<Via, ViaError> {
+        match request.get(VIA_MEMBER) {
+            None | Some(&Value::Null) => Ok(Via::default()),
+            Some(via) => Via::deserialize_value(via.clone()),
+        }
+    }
+
+    fn deserialize_value(value: Value) -> Result<Via, ViaError> {
+        serde_json::from_value(value).map_err(|err| ViaError::Malformed(err.to_string()))
+    }
+
+    /// The hops, oldest first.
+    pub fn hops(&self) -> &[Hop] {
+        &self.0
+    }
+
+    /// The number of hops.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the Request was not forwarded.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The `id` used by the original client, if the Request was forwarded.
+    pub fn origin_id(&self) -> Option<&Id> {
+        self.0.first().map(|hop| &hop.id)
+    }
+
+    /// Whether `gateway` already forwarded the Request.
+    pub fn contains(&self, gateway: &str) -> bool {
+        self.0.iter().any(|hop| hop.gateway == gateway)
+    }
+
+    /// Fail if `gateway` already forwarded the Request.
+    pub fn check(&self, gateway: &str) -> Result<(), ViaError> {
+        if self.contains(gateway) {
+            Err(ViaError::Loop(gateway.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Append `hop` to the chain of `request`, failing if the hop's gateway is already in it.
+///
+/// Returns the updated chain. `request` is left unchanged on failure.
+///
+/// # Panics
+///
+/// If `request` is not an Object.
+pub fn forward(request: &mut Value, hop: Hop) -> Result<Via, ViaError> {
+    let mut via = Via::of(request)?;
+    via.check(&hop.gateway)?;
+    via.0.push(hop);
+    let value = serde_json::to_value(&via).expect("Via is always serializable");
+    request
+        .as_object_mut()
+        .expect("request must be an Object")
+        .insert(VIA_MEMBER.to_string(), value);
+    Ok(via)
+}
+
+/// Remove the chain from `request` and return it.
+pub fn strip(request: &mut Value) -> Result<Via, ViaError> {
+    let via = match request.as_object_mut().and_then(|o| o.remove(VIA_MEMBER)) {
+        None | Some(Value::Null) => return Ok(Via::default()),
+        Some(via) => via,
+    };
+    Via::deserialize_value(via)
+}
+
+/// The error returned for an invalid provenance chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViaError {
+    /// The member is not a valid chain.
+    Malformed(String),
+    /// The gateway is already in the chain.
+    Loop(String),
+}
+
+impl ViaError {
+    /// The error code to respond with.
+    pub fn code(&self) -> ErrorCode {
+        match *self {
+            ViaError::Malformed(_) => ErrorCode::InvalidRequest,
+            ViaError::Loop(_) => ErrorCode::ServerError(LOOP_DETECTED),
+        }
+    }
+
+    /// Convert into an error response for the request `id`.
+    pub fn into_error(self, id: Id) -> Error<Value> {
+        Error::new(id, self.code(), self.to_string(), None)
+    }
+}
+
+impl fmt::Display for ViaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ViaError::Malformed(ref err) => write!(f, "invalid `{}` member: {}", VIA_MEMBER, err),
+            ViaError::Loop(ref gateway) => write!(f, "request loop through gateway `{}`", gateway),
+        }
+    }
+}
+
+impl error::Error for ViaError {}
@@ -0,0 +1,331 @@
+//! A delivery envelope for server-to-server notifications (webhooks).
+//!
+//! The sender POSTs a [`WebhookEnvelope`](struct.WebhookEnvelope.html) carrying a batch of
+//! notifications to the receiver. Every delivery has a unique id, which is kept across retries
+//! together with an increasing attempt count, so receivers can drop duplicates with a
+//! [`Dedup`](struct.Dedup.html):
+//!
+//! ```json
+//! {
+//!     "delivery_id": "d-1a2b",
+//!     "attempt": 2,
+//!     "timestamp_ms": 1526000000000,
+//!     "policy": "at_least_once",
+//!     "notifications": [{"jsonrpc": "2.0", "method": "order.shipped", "params": {"order": 7}}],
+//!     "signature": "..."
+//! }
+//! ```
+//!
+//! # Delivery policy
+//!
+//! Each envelope states its [`DeliveryPolicy`](enum.DeliveryPolicy.html). Under
+//! `at_least_once` (the default) the sender retries until the delivery is acknowledged; under
+//! `at_most_once` the sender never retries. Under both the receiver must drop duplicates, which
+//! the network or an attacker replaying an envelope may still produce. Peers advertise
+//! the policies they support in their capabilities under the key
+//! [`CAPABILITY`](constant.CAPABILITY.html) and agree on one with
+//! [`negotiate`](fn.negotiate.html).
+//!
+//! # Signatures
+//!
+//! The `signature` is computed by the caller (e.g. an HMAC with a shared secret) over the
+//! [`signing_payload`](struct.WebhookEnvelope.html#method.signing_payload).
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::webhook::{Dedup, WebhookEnvelope};
+//!
+//! // NOT a real signature, only used to keep the example self contained.
+//! fn sign(payload: &[u8]) -> String {
+//!     format!("len-{}", payload.len())
+//! }
+//!
+//! # fn main() {
+//! let mut envelope = WebhookEnvelope::new("d-1a2b", 1000);
+//! envelope.notify("order.shipped", &vec![7]);
+//! envelope.sign(sign);
+//! let json = envelope.to_string();
+//!
+//! // The receiver.
+//! let mut dedup = Dedup::new(1000);
+//! let envelope: WebhookEnvelope = json.parse().unwrap();
+//! envelope.validate().unwrap();
+//! assert!(envelope.verify(|payload, signature| sign(payload) == signature));
+//! assert!(dedup.first_delivery(&envelope));
+//!
+//! // A retry of the same delivery is a duplicate.
+//! let retry = envelope.retry(2000).unwrap();
+//! assert_eq!(retry.attempt, 2);
+//! assert!(!dedup.first_delivery(&retry));
+//! # }
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+use std::error;
+use std::fmt;
+use std::str;
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use memory::MemoryUsage;
+use {Value, V2_0};
+
+/// The capability key under which peers advertise the delivery policies they support.
+pub const CAPABILITY: &str = "delivery";
+
+/// The delivery semantics of webhook notifications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryPolicy {
+    /// Every delivery is attempted once and may be lost; receivers still drop duplicates by
+    /// delivery id.
+    AtMostOnce,
+    /// Deliveries are retried until acknowledged and may arrive more than once; receivers drop
+    /// duplicates by delivery id.
+    #[default]
+    AtLeastOnce,
+}
+
+/// Agree on a delivery policy from the policies supported by the sender and the receiver.
+///
+/// `AtLeastOnce` is preferred. Returns `None` if there is no common policy.
+///
+/// # Examples
+///
+/// ```rust
+/// # extern crate jrpc;
+/// use jrpc::webhook::{negotiate, DeliveryPolicy::*};
+///
+/// # fn main() {
+/// assert_eq!(negotiate(&[AtMostOnce, AtLeastOnce], &[AtLeastOnce]), Some(AtLeastOnce));
+/// assert_eq!(negotiate(&[AtMostOnce, AtLeastOnce], &[AtMostOnce]), Some(AtMostOnce));
+/// assert_eq!(negotiate(&[AtMostOnce], &[AtLeastOnce]), None);
+/// # }
+/// ```
+pub fn negotiate(sender: &[DeliveryPolicy], receiver: &[DeliveryPolicy]) -> Option<DeliveryPolicy> {
+    [DeliveryPolicy::AtLeastOnce, DeliveryPolicy::AtMostOnce]
+        .iter()
+        .find(|p| sender.contains(p) && receiver.contains(p))
+        .cloned()
+}
+
+/// A batch of notifications delivered to a webhook.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookEnvelope {
+    /// The unique id of the delivery, the same for all attempts.
+    pub delivery_id: String,
+
+    /// The delivery attempt, starting at 1.
+    pub attempt: u32,
+
+    /// When this attempt was sent, in milliseconds since the unix epoch.
+    pub timestamp_ms: u64,
+
+    /// The delivery semantics.
+    #[serde(default)]
+    pub policy: DeliveryPolicy,
+
+    /// The notifications, i.e. Requests without an `id`.
+    pub notifications: Vec<Value>,
+
+    /// The signature over the [`signing_payload`](#method.signing_payload), if signed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl WebhookEnvelope {
+    /// Create the first attempt of an empty delivery.
+    pub fn new<S: Into<String>>(delivery_id: S, timestamp_ms: u64) -> Self {
+        WebhookEnvelope {
+            delivery_id: delivery_id.into(),
+            attempt: 1,
+            timestamp_ms: timestamp_ms,
+            policy: DeliveryPolicy::default(),
+            notifications: Vec::new(),
+            signature: None,
+        }
+    }
+
+    /// Set the delivery policy.
+    pub fn policy(mut self, policy: DeliveryPolicy) -> Self {
+        self.policy = policy;
+        self.signature = None;
+        self
+    }
+
+    /// Add a notification.
+    ///
+    /// # Panics
+    ///
+    /// If `params` fails to serialize.
+    pub fn notify<T: Serialize>(&mut self, method: &str, params: &T) {
+        let mut notification = serde_json::Map::new();
+        notification.insert("jsonrpc".into(), serde_json::to_value(V2_0).expect("V2_0"));
+        notification.insert("method".into(), Value::from(method));
+        notification.insert(
+            "params".into(),
+            serde_json::to_value(params).expect("params must be serializable"),
+        );
+        self.notifications.push(Value::Object(notification));
+        self.signature = None;
+    }
+
+    /// The next attempt of this delivery, or `None` if the policy forbids retries. The signature
+    /// is cleared, since it covers the attempt and timestamp.
+    pub fn retry(&self, timestamp_ms: u64) -> Option<Self> {
+        if self.policy == DeliveryPolicy::AtMostOnce {
+            return None;
+        }
+        Some(WebhookEnvelope {
+            attempt: self.attempt + 1,
+            timestamp_ms: timestamp_ms,
+            signature: None,
+            ..self.clone()
+        })
+    }
+
+    /// The bytes covered by the signature: the compact json serialization of the envelope
+    /// without the `signature` member, with Object members sorted.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut value = serde_json::to_value(self).expect("envelope is always serializable");
+        if let Some(object) = value.as_object_mut() {
+            object.remove("signature");
+        }
+        serde_json::to_vec(&value).expect("envelope is always serializable")
+    }
+
+    /// Sign the envelope with `sign`, which maps the signing payload to a signature.
+    pub fn sign<F: FnOnce(&[u8]) -> String>(&mut self, sign: F) {
+        self.signature = Some(sign(&self.signing_payload()));
+    }
+
+    /// Verify the signature with `verify`, which is given the signing payload and the
+    /// signature. Unsigned envelopes fail verification.
+    pub fn verify<F: FnOnce(&[u8], &str) -> bool>(&self, verify: F) -> bool {
+        match self.signature {
+            Some(ref signature) => verify(&self.signing_payload(), signature),
+            None => false,
+        }
+    }
+
+    /// Check the envelope is well formed: it has an attempt of at least 1 (exactly 1 if
+    /// `at_most_once`) and at least one notification, and every notification has a String
+    /// `method` and no `id`.
+    pub fn validate(&self) -> Result<(), WebhookError> {
+        if self.attempt == 0 || (self.policy == DeliveryPolicy::AtMostOnce && self.attempt > 1) {
+            return Err(WebhookError::InvalidAttempt);
+        }
+        if self.notifications.is_empty() {
+            return Err(WebhookError::Empty);
+        }
+        for (index, notification) in self.notifications.iter().enumerate() {
+            let valid = notification.get("jsonrpc") == Some(&Value::from("2.0"))
+                && notification.get("method").is_some_and(Value::is_string)
+                && notification.get("id").is_none();
+            if !valid {
+                return Err(WebhookError::NotANotification(index));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats the envelope as compact json.
+impl fmt::Display for WebhookEnvelope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&serde_json::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Parses an envelope from json.
+impl str::FromStr for WebhookEnvelope {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Remembers the most recent delivery ids, to drop duplicate deliveries.
+#[derive(Debug, Clone)]
+pub struct Dedup {
+    capacity: usize,
+    order: VecDeque<String>,
+    seen: HashSet<String>,
+}
+
+impl Dedup {
+    /// Create a dedup remembering the last `capacity` delivery ids.
+    pub fn new(capacity: usize) -> Self {
+        Dedup {
+            capacity: capacity,
+            order: VecDeque::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Record the delivery and return whether it is the first time it was seen.
+    ///
+    /// Deliveries are recorded under both policies: `at_most_once` deliveries are never retried
+    /// by the sender, but may still be duplicated or replayed on their way.
+    pub fn first_delivery(&mut self, envelope: &WebhookEnvelope) -> bool {
+        self.first(&envelope.delivery_id)
+    }
+
+    /// Record a delivery id and return whether it is the first time it was seen.
+    pub fn first(&mut self, delivery_id: &str) -> bool {
+        if self.seen.contains(delivery_id) {
+            return false;
+        }
+        self.seen.insert(delivery_id.to_string());
+        self.order.push_back(delivery_id.to_string());
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+impl MemoryUsage for Dedup {
+    /// Each id is retained twice, in insertion order and in the set.
+    fn memory_usage(&self) -> usize {
+        self.order.iter().map(|id| 2 * id.len()).sum()
+    }
+
+    fn shed_oldest(&mut self) -> Option<usize> {
+        let oldest = self.order.pop_front()?;
+        self.seen.remove(&oldest);
+        Some(2 * oldest.len())
+    }
+}
+
+/// The error returned for a malformed [`WebhookEnvelope`](struct.WebhookEnvelope.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookError {
+    /// The attempt is 0, or an `at_most_once` delivery was retried.
+    InvalidAttempt,
+    /// The envelope has no notifications.
+    Empty,
+    /// The entry at this index is not a notification.
+    NotANotification(usize),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WebhookError::InvalidAttempt => write!(f, "invalid delivery attempt"),
+            WebhookError::Empty => write!(f, "delivery has no notifications"),
+            WebhookError::NotANotification(index) => {
+                write!(f, "entry {} is not a notification", index)
+            }
+        }
+    }
+}
+
+impl error::Error for WebhookError {}
@@ -0,0 +1,155 @@
+//! Streaming the `result` of a Response.
+//!
+//! A [`ResultWriter`](struct.ResultWriter.html) writes the envelope of a successful Response to
+//! an `io::Write`, and lets the caller stream the `result` in between, so that a huge result
+//! never has to be held in memory:
+//!
+//! - as Array elements, one at a time, with [`element`](struct.ResultWriter.html#method.element),
+//! - through a `serde_json::Serializer`, with
+//!   [`serializer`](struct.ResultWriter.html#method.serializer),
+//! - or as raw json chunks, with [`write_raw`](struct.ResultWriter.html#method.write_raw).
+//!
+//! The output is byte-for-byte what [`Response::to_string`](../enum.Response.html) produces for
+//! the same result.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate jrpc;
+//! use jrpc::{Id, Response, Value};
+//! use jrpc::writer::ResultWriter;
+//!
+//! # fn main() {
+//! let mut writer = ResultWriter::new(Vec::new(), Id::from(1)).unwrap();
+//! writer.begin_array().unwrap();
+//! for i in 0..3 {
+//!     writer.element(&i).unwrap();
+//! }
+//! let json = writer.finish().unwrap();
+//!
+//! let expected = Response::success(Id::from(1), Value::from(vec![0, 1, 2])).to_string();
+//! assert_eq!(String::from_utf8(json).unwrap(), expected);
+//! # }
+//! ```
+
+use std::io::{self, Write};
+
+use serde::ser::Serialize;
+use serde_json;
+use std_prelude::*;
+
+use Id;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Nothing of the result was written.
+    Empty,
+    /// An Array was begun, and `n` elements written.
+    Array(usize),
+    /// The result was written.
+    Written,
+}
+
+/// Writes a successful Response, streaming its `result`.
+#[derive(Debug)]
+pub struct ResultWriter<W: Write> {
+    writer: W,
+    id: Id,
+    state: State,
+}
+
+impl<W: Write> ResultWriter<W> {
+    /// Start the Response to the Request with `id`.
+    pub fn new(mut writer: W, id: Id) -> io::Result<Self> {
+        writer.write_all(br#"{"jsonrpc":"2.0","result":"#)?;
+        Ok(ResultWriter {
+            writer: writer,
+            id: id,
+            state: State::Empty,
+        })
+    }
+
+    fn expect(&self, state: State, action: &str) -> io::Result<()> {
+        if self.state == state {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("can not {} in state {:?}", action, self.state),
+            ))
+        }
+    }
+
+    /// Write the whole result at once.
+    pub fn value<T: Serialize>(&mut self, result: &T) -> io::Result<()> {
+        self.expect(State::Empty, "write a value")?;
+        serde_json::to_writer(&mut self.writer, result)?;
+        self.state = State::Written;
+        Ok(())
+    }
+
+    /// Serialize the result with a `serde_json::Serializer`. Exactly one value must be
+    /// serialized with it.
+    pub fn serializer(&mut self) -> io::Result<serde_json::Serializer<&mut W>> {
+        self.expect(State::Empty, "serialize a value")?;
+        self.state = State::Written;
+        Ok(serde_json::Serializer::new(&mut self.writer))
+    }
+
+    /// Write a chunk of raw json. The chunks must add up to exactly one json value; they are not
+    /// checked.
+    pub fn write_raw(&mut self, chunk: &[u8]) -> io::Result<()> {
+        match self.state {
+            State::Empty | State::Written => {
+                self.state = State::Written;
+                self.writer.write_all(chunk)
+            }
+            State::Array(_) => self.expect(State::Empty, "write raw json"),
+        }
+    }
+
+    /// Begin an Array result, whose elements are written with [`element`](#method.element).
+    pub fn begin_array(&mut self) -> io::Result<()> {
+        self.expect(State::Empty, "begin an array")?;
+        self.writer.write_all(b"[")?;
+        self.state = State::Array(0);
+        Ok(())
+    }
+
+    /// Write the next element of the Array result.
+    pub fn element<T: Serialize>(&mut self, element: &T) -> io::Result<()> {
+        let n = match self.state {
+            State::Array(n) => n,
+            _ => return self.expect(State::Array(0), "write an element"),
+        };
+        if n > 0 {
+            self.writer.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut self.writer, element)?;
+        self.state = State::Array(n + 1);
+        Ok(())
+    }
+
+    /// The number of elements written to the Array result.
+    pub fn elements(&self) -> usize {
+        match self.state {
+            State::Array(n) => n,
+            _ => 0,
+        }
+    }
+
+    /// End the Array result, if any, and the Response, returning the writer. A result which was
+    /// not written is Null.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.state {
+            State::Empty => self.writer.write_all(b"null")?,
+            State::Array(_) => self.writer.write_all(b"]")?,
+            State::Written => {}
+        }
+        self.writer.write_all(br#","id":"#)?;
+        serde_json::to_writer(&mut self.writer, &self.id)?;
+        self.writer.write_all(b"}")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+
+use jrpc::allocator::{AllocError, Allocation, ErrorCodeAllocator, Mismatch};
+
+fn allocator() -> ErrorCodeAllocator {
+    let mut allocator = ErrorCodeAllocator::new();
+    allocator.reserve("billing", -32010..-32008).unwrap();
+    allocator.reserve("search", 1000..1010).unwrap();
+    allocator
+}
+
+#[test]
+fn test_allocator_reserve() {
+    let mut allocator = allocator();
+    assert_eq!(
+        allocator.reserve("legacy", -32200..-32100),
+        Err(AllocError::Reserved("legacy".to_string()))
+    );
+    assert_eq!(
+        allocator.reserve("empty", 5..5),
+        Err(AllocError::EmptyRange("empty".to_string()))
+    );
+    assert_eq!(
+        allocator.reserve("search", 0..10),
+        Err(AllocError::DuplicateSubsystem("search".to_string()))
+    );
+    assert_eq!(
+        allocator.reserve("auth", 1009..1020),
+        Err(AllocError::Overlap {
+            subsystem: "auth".to_string(),
+            other: "search".to_string(),
+        })
+    );
+    allocator.reserve("auth", 1010..1020).unwrap();
+}
+
+#[test]
+fn test_allocator_allocate() {
+    let mut allocator = allocator();
+    assert_eq!(allocator.allocate("billing", "a"), Ok(-32010));
+    assert_eq!(allocator.allocate("billing", "b"), Ok(-32009));
+    assert_eq!(
+        allocator.allocate("billing", "c"),
+        Err(AllocError::Exhausted("billing".to_string()))
+    );
+    assert_eq!(allocator.allocate("billing", "a"), Ok(-32010));
+    assert_eq!(
+        allocator.allocate("auth", "a"),
+        Err(AllocError::UnknownSubsystem("auth".to_string()))
+    );
+    assert_eq!(allocator.code_of("billing", "b"), Some(-32009));
+    assert_eq!(allocator.get(-32009).unwrap().name, "b");
+}
+
+#[test]
+fn test_allocator_verify() {
+    let mut allocator = allocator();
+    allocator.allocate("billing", "a").unwrap();
+    allocator.allocate("search", "q").unwrap();
+
+    let live = [
+        Allocation::new(-32010, "billing", "a"),
+        Allocation::new(1000, "search", "q"),
+        // Retired codes are still allocated.
+        Allocation::new(1000, "search", "r"),
+        Allocation::new(-32009, "billing", "new"),
+        Allocation::new(2000, "search", "far"),
+        Allocation::new(1001, "search", "dup"),
+        Allocation::new(1001, "billing", "dup"),
+        Allocation::new(7, "auth", "x"),
+    ];
+    let mismatches = allocator.verify(&live);
+    assert_eq!(
+        mismatches,
+        vec![
+            Mismatch::Collision {
+                code: 1000,
+                first: live[1].clone(),
+                second: live[2].clone(),
+            },
+            Mismatch::Unallocated(live[3].clone()),
+            Mismatch::OutOfRange(live[4].clone()),
+            Mismatch::Unallocated(live[5].clone()),
+            Mismatch::Collision {
+                code: 1001,
+                first: live[5].clone(),
+                second: live[6].clone(),
+            },
+            Mismatch::UnknownSubsystem(live[7].clone()),
+        ]
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        "search.q and search.r both use 1000"
+    );
+    assert!(allocator.verify(&live[..2]).is_empty());
+}
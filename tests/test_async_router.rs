@@ -0,0 +1,127 @@
+#![cfg(feature = "async")]
+
+extern crate jrpc;
+extern crate serde_json;
+
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use jrpc::async_router::AsyncRouter;
+use jrpc::{ErrorCode, ErrorObject, Id, Response, Value};
+
+/// Resolves to `value` after being polled `n` times, counting its polls in `polls`.
+struct Countdown {
+    n: u32,
+    value: u32,
+    polls: Arc<AtomicUsize>,
+}
+
+impl Future for Countdown {
+    type Output = Result<u32, ErrorObject<Value>>;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        self.polls.fetch_add(1, Ordering::SeqCst);
+        if self.n == 0 {
+            return Poll::Ready(Ok(self.value));
+        }
+        self.n -= 1;
+        Poll::Pending
+    }
+}
+
+fn poll(handling: &mut jrpc::async_router::Handling) -> Poll<Option<String>> {
+    Pin::new(handling).poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn test_async_router_batch() {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut router = AsyncRouter::new();
+    let (p, c) = (polls.clone(), calls.clone());
+    router.add::<(u32, u32), u32, _, _>("wait", move |(n, value)| {
+        c.fetch_add(1, Ordering::SeqCst);
+        Countdown {
+            n,
+            value,
+            polls: p.clone(),
+        }
+    });
+    assert!(router.contains("wait"));
+
+    let json = r#"[
+        {"jsonrpc": "2.0", "method": "wait", "params": [2, 10], "id": 1},
+        {"jsonrpc": "2.0", "method": "wait", "params": [1, 20]},
+        {"jsonrpc": "2.0", "method": "wait", "params": [0, 30], "id": 2},
+        {"jsonrpc": "2.0", "method": "wait", "params": ["a"], "id": 3},
+        {"jsonrpc": "2.0", "method": "sleep", "id": 4}
+    ]"#;
+    let mut handling = router.handle(json);
+    // The handlers are called once each, but not for invalid params.
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // Every pending handler is polled on each poll.
+    assert!(poll(&mut handling).is_pending());
+    assert_eq!(polls.load(Ordering::SeqCst), 3);
+    assert!(poll(&mut handling).is_pending());
+    assert_eq!(polls.load(Ordering::SeqCst), 5);
+    let json = match poll(&mut handling) {
+        Poll::Ready(json) => json.unwrap(),
+        Poll::Pending => panic!("pending after the last handler completed"),
+    };
+    assert_eq!(polls.load(Ordering::SeqCst), 6);
+
+    let responses: Vec<Response<Value>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(responses.len(), 4);
+    assert_eq!(
+        responses[0],
+        Response::success(Id::from(1), Value::from(10))
+    );
+    assert_eq!(
+        responses[1],
+        Response::success(Id::from(2), Value::from(30))
+    );
+    let codes: Vec<_> = responses[2..]
+        .iter()
+        .map(|r| r.clone().into_result().unwrap_err().code)
+        .collect();
+    assert_eq!(
+        codes,
+        vec![ErrorCode::InvalidParams, ErrorCode::MethodNotFound]
+    );
+}
+
+#[test]
+fn test_async_router_answers() {
+    let mut router = AsyncRouter::new();
+    router.add::<(), (), _, _>("fail", |()| {
+        future::ready(Err(ErrorObject::new(-32000, "Failed", None).unwrap()))
+    });
+
+    let json = r#"{"jsonrpc": "2.0", "method": "fail", "id": "a"}"#;
+    match poll(&mut router.handle(json)) {
+        Poll::Ready(json) => assert_eq!(
+            json.unwrap(),
+            r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Failed"},"id":"a"}"#
+        ),
+        Poll::Pending => unreachable!(),
+    }
+
+    // Errors answering the whole input.
+    for json in ["[", "[]"].iter() {
+        match poll(&mut router.handle(json)) {
+            Poll::Ready(json) => {
+                let response: Response<Value> = serde_json::from_str(&json.unwrap()).unwrap();
+                assert_eq!(response.id(), &Id::Null);
+            }
+            Poll::Pending => unreachable!(),
+        }
+    }
+
+    // Notifications are not answered.
+    let json = r#"[{"jsonrpc": "2.0", "method": "fail"}, {"jsonrpc": "2.0", "method": "sleep"}]"#;
+    assert_eq!(poll(&mut router.handle(json)), Poll::Ready(None));
+}
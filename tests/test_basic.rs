@@ -1,4 +1,5 @@
 extern crate jrpc;
+extern crate serde;
 extern crate serde_json;
 
 use jrpc::*;
@@ -14,3 +15,1182 @@ fn test_id() {
     let id: Id = serde_json::from_str("null").unwrap();
     assert_eq!(id, Id::Null);
 }
+
+#[test]
+fn test_response() {
+    let json = r#"{"jsonrpc": "2.0", "result": [1, 2], "id": 1}"#;
+    let response: Response<Vec<u32>> = serde_json::from_str(json).unwrap();
+    assert!(response.is_ok());
+    assert_eq!(response.into_result().unwrap(), vec![1, 2]);
+
+    let json = r#"{"jsonrpc": "2.0", "error": {"code": -32602, "message": "bad"}, "id": 2}"#;
+    let response: Response<Vec<u32>, String> = serde_json::from_str(json).unwrap();
+    assert!(response.is_err());
+    assert_eq!(response.id(), &Id::Int(2));
+    let error = response.into_result().unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidParams);
+    assert_eq!(error.data, None);
+    // A missing `data` is not serialized either.
+    assert_eq!(
+        serde_json::to_string(&error).unwrap(),
+        r#"{"code":-32602,"message":"bad"}"#
+    );
+
+    // Neither a valid result nor error.
+    let json = r#"{"jsonrpc": "2.0", "result": "x", "id": 3}"#;
+    assert!(serde_json::from_str::<Response<Vec<u32>>>(json).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_batch() {
+    use jrpc::batch::{BatchRequest, BatchResponse};
+
+    let json = r#"[{"jsonrpc": "2.0", "method": "a", "id": 1}, {"jsonrpc": "2.0", "method": "b"}]"#;
+    let batch: BatchRequest<String, Value> = serde_json::from_str(json).unwrap();
+    assert_eq!(batch.len(), 2);
+    assert!(batch.expects_response());
+    let value = serde_json::to_value(&batch).unwrap();
+    assert_eq!(value[0]["method"], "a");
+    assert_eq!(value[1]["method"], "b");
+    assert!(serde_json::from_str::<BatchRequest<String, Value>>("[]").is_err());
+
+    let notifications: BatchRequest<String, Value> =
+        serde_json::from_str(r#"[{"jsonrpc": "2.0", "method": "b"}]"#).unwrap();
+    assert!(!notifications.expects_response());
+
+    let responses: BatchResponse<Value> = BatchResponse::new(vec![Response::error(
+        Id::Null,
+        ErrorCode::InvalidRequest,
+        "bad",
+        None,
+    )])
+    .unwrap();
+    let pairs = responses.pair(&batch);
+    assert_eq!(pairs.len(), 1);
+    assert!(pairs[0].response.is_none());
+    assert_eq!(responses.unmatched(&batch).len(), 1);
+    assert!(BatchResponse::<Value>::new(vec![]).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_lenient_batch() {
+    use jrpc::batch::{BatchRequest, BatchResponse, LenientBatch, Outcome, INDEX_MEMBER};
+
+    for &(json, code) in &[
+        ("[", ErrorCode::ParseError),
+        ("[]", ErrorCode::InvalidRequest),
+        (
+            r#"{"jsonrpc": "2.0", "method": "a", "id": 1}"#,
+            ErrorCode::InvalidRequest,
+        ),
+    ] {
+        let error = LenientBatch::<String>::parse(json).unwrap_err();
+        assert_eq!(error.error.code, code);
+    }
+
+    let json = r#"[1, {"jsonrpc": "2.0", "method": "a", "id": 1}, {"jsonrpc": "2.0", "method": 2, "id": 2}]"#;
+    let batch = LenientBatch::<String>::parse(json).unwrap();
+    assert_eq!(batch.len(), 3);
+    let entries = batch.into_entries();
+    let error = entries[0].as_ref().unwrap_err();
+    assert_eq!(error.error.data.as_ref().unwrap()[INDEX_MEMBER], 0);
+    assert!(entries[1].is_ok());
+    // A method which is not a String is an invalid Request, so its id is not trusted.
+    let error = entries[2].as_ref().unwrap_err();
+    assert_eq!(error.id, Id::Null);
+    assert_eq!(error.error.data.as_ref().unwrap()[INDEX_MEMBER], 2);
+
+    let sent: BatchRequest<String, Value> = BatchRequest::new(vec![
+        Request::with_params(Id::from(1), "a".to_string(), Value::Null),
+        Request::with_params(IdReq::Notification, "b".to_string(), Value::Null),
+        Request::with_params(Id::from(3), "c".to_string(), Value::Null),
+    ])
+    .unwrap();
+    let responses: BatchResponse<Value> =
+        BatchResponse::new(vec![Response::success(Id::from(1), Value::Null)]).unwrap();
+    let outcomes = responses.by_position(&sent);
+    assert_eq!(outcomes.len(), 3);
+    match (&outcomes[0], &outcomes[1], &outcomes[2]) {
+        (&Outcome::Answered(_), &Outcome::Notification, &Outcome::Missing) => {}
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn test_notification() {
+    let json = r#"{"jsonrpc": "2.0", "method": "Log"}"#;
+    let notification: Notification<String, Value> = serde_json::from_str(json).unwrap();
+    assert_eq!(notification.params, None);
+    assert_eq!(
+        Notification::new("Log".to_string()).to_string(),
+        r#"{"jsonrpc":"2.0","method":"Log"}"#
+    );
+
+    // Notifications never have an id.
+    let json = r#"{"jsonrpc": "2.0", "method": "Log", "id": 1}"#;
+    assert!(serde_json::from_str::<Notification<String, Value>>(json).is_err());
+    let message: RequestOrNotification<String, Value> = serde_json::from_str(json).unwrap();
+    assert_eq!(message.method(), "Log");
+    assert_eq!(message.id(), Some(Id::Int(1)));
+
+    let request: Request<String, Value> =
+        Notification::with_params("Log".to_string(), Value::Null).into();
+    assert_eq!(request.id, IdReq::Notification);
+    match RequestOrNotification::from(request) {
+        RequestOrNotification::Notification(n) => assert_eq!(n.method, "Log"),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn test_strict() {
+    use jrpc::strict::{self, SpecViolation};
+
+    let request = Request::with_params(Id::from(1), "".to_string(), 5);
+    assert_eq!(request.validate(), Err(SpecViolation::EmptyMethod));
+    let request = Request::with_params(Id::from(1), "add".to_string(), 5);
+    assert_eq!(request.validate(), Err(SpecViolation::Params(Value::from(5))));
+    assert!(Request::with_params(Id::from(1), "add".to_string(), vec![5]).is_valid());
+
+    let json = r#"{"jsonrpc": "1.0", "method": "add", "id": 1}"#;
+    assert_eq!(
+        strict::request_from_str::<String, Value>(json).unwrap_err(),
+        SpecViolation::Version(Value::from("1.0"))
+    );
+    let json = r#"{"jsonrpc": "2.0", "method": "add", "params": null, "id": 1}"#;
+    assert_eq!(
+        strict::request_from_str::<String, Value>(json).unwrap_err(),
+        SpecViolation::Params(Value::Null)
+    );
+    let json = r#"{"jsonrpc": "2.0", "method": "add", "id": 1, "extra": 1}"#;
+    assert_eq!(
+        strict::request_from_str::<String, Value>(json).unwrap_err(),
+        SpecViolation::UnknownMember("extra".to_string())
+    );
+    let violation = strict::request_from_str::<String, Value>("{").unwrap_err();
+    assert_eq!(violation.error_code(), ErrorCode::ParseError);
+
+    // Application codes are outside of the reserved range.
+    let error = Error::new(Id::from(1), ErrorCode::from(-32100), "oops", None::<Value>);
+    assert_eq!(error.validate(), Err(SpecViolation::ReservedCode(-32100)));
+    let response: Response<Value> = Response::error(Id::from(1), ErrorCode::from(7), "oops", None);
+    assert_eq!(response.validate(), Ok(()));
+
+    let json = r#"{"jsonrpc": "2.0", "result": 1, "error": {"code": 1, "message": "a"}, "id": 1}"#;
+    assert_eq!(
+        strict::response_from_str::<Value>(json).unwrap_err(),
+        SpecViolation::ResultAndError
+    );
+    let json = r#"{"jsonrpc": "2.0", "error": {"code": 1.5, "message": "a"}, "id": 1}"#;
+    assert_eq!(
+        strict::response_from_str::<Value>(json).unwrap_err(),
+        SpecViolation::CodeNotAnInteger(Value::from(1.5))
+    );
+    let json = r#"{"jsonrpc": "2.0", "result": [1], "id": "a"}"#;
+    assert!(strict::response_from_str::<Vec<u32>>(json).is_ok());
+}
+
+#[test]
+fn test_parse_request_errors() {
+    let error = parse_request::<String>(r#"{"jsonrpc": "2.0", "method": "foobar, "params": "bar", "baz]"#)
+        .unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::ParseError);
+    assert_eq!(error.id, Id::Null);
+
+    // The examples of the spec.
+    let error = parse_request::<String>(r#"{"jsonrpc": "2.0", "method": 1, "params": "bar"}"#)
+        .unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::InvalidRequest);
+    assert_eq!(error.id, Id::Null);
+    let error = parse_request::<String>("1").unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::InvalidRequest);
+    assert_eq!(
+        serde_json::to_value(&error).unwrap()["error"]["code"],
+        Value::from(-32600)
+    );
+}
+
+#[test]
+fn test_error_constructors() {
+    let error = Error::<Value>::method_not_found(Id::from(1));
+    assert_eq!(
+        error.to_json(),
+        r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#
+    );
+    let error = Error::invalid_params(Id::from("a"), Some(vec!["/amount".to_string()]));
+    assert_eq!(error.error.code, ErrorCode::InvalidParams);
+    assert_eq!(error.error.data, Some(vec!["/amount".to_string()]));
+    assert_eq!(Error::<Value>::parse_error().id, Id::Null);
+    assert_eq!(Error::<Value>::invalid_request().error.message, "Invalid Request");
+
+    let error = Error::<Value>::server_error(Id::from(2), -32001, "Busy", None).unwrap();
+    assert_eq!(error.error.code, ErrorCode::ServerError(-32001));
+    assert!(error.validate().is_ok());
+}
+
+#[test]
+fn test_server_error_out_of_range() {
+    for &code in &[-32100, -31999, 0, i64::MIN] {
+        let error = Error::<Value>::server_error(Id::from(2), code, "Busy", None);
+        assert_eq!(error, Err(strict::SpecViolation::ServerErrorCode(code)));
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_result_writer() {
+    use jrpc::writer::ResultWriter;
+    use serde::Serialize;
+
+    let mut writer = ResultWriter::new(Vec::new(), Id::from("a")).unwrap();
+    {
+        let mut serializer = writer.serializer().unwrap();
+        vec!["x", "y"].serialize(&mut serializer).unwrap();
+    }
+    assert!(writer.element(&1).is_err());
+    let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(json, r#"{"jsonrpc":"2.0","result":["x","y"],"id":"a"}"#);
+
+    let mut writer = ResultWriter::new(Vec::new(), Id::Null).unwrap();
+    writer.write_raw(br#"{"big":"#).unwrap();
+    writer.write_raw(b"true}").unwrap();
+    let json = String::from_utf8(writer.finish().unwrap()).unwrap();
+    let response: Response<Value> = serde_json::from_str(&json).unwrap();
+    assert_eq!(response.into_result().unwrap()["big"], Value::Bool(true));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_params_reader() {
+    use jrpc::reader::{Head, ParamsReader, ReadError};
+
+    let json = r#"{"params": [["a", 1], ["b", 2]], "method": "Put", "jsonrpc": "2.0", "x": {}}"#;
+    let mut seen = Vec::new();
+    let head: Head<String> = ParamsReader::new(json.as_bytes())
+        .for_each(|pair: (String, u32)| -> Result<(), ()> {
+            seen.push(pair);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(seen, vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+    assert_eq!(head.id, IdReq::Notification);
+    assert_eq!(head.elements, 2);
+
+    let json = r#"{"jsonrpc": "2.0", "method": "Put", "params": null, "id": "1"}"#;
+    let head: Head<String> = ParamsReader::new(json.as_bytes())
+        .for_each(|_: Value| -> Result<(), ()> { unreachable!() })
+        .unwrap();
+    assert_eq!(head.elements, 0);
+    assert_eq!(head.id, IdReq::String("1".into()));
+
+    let code = |json: &str| match ParamsReader::new(json.as_bytes())
+        .for_each::<String, Value, (), _>(|_| Ok(()))
+    {
+        Err(ReadError::Request(error)) => error.error.code,
+        other => panic!("{:?}", other),
+    };
+    let cases = [
+        (r#"{"jsonrpc": "2.0", "method": "Put", "params": [1"#, ErrorCode::ParseError),
+        (r#"{"jsonrpc": "2.0", "method": "Put"} {}"#, ErrorCode::ParseError),
+        (r#"{"jsonrpc": "2.0", "params": [1]}"#, ErrorCode::InvalidRequest),
+        (r#"{"method": "Put", "params": [1]}"#, ErrorCode::InvalidRequest),
+        (r#"{"jsonrpc": "1.0", "method": "Put"}"#, ErrorCode::InvalidRequest),
+        (r#"{"jsonrpc": "2.0", "method": "Put", "params": 3}"#, ErrorCode::InvalidRequest),
+    ];
+    for &(json, ref expected) in cases.iter() {
+        assert_eq!(&code(json), expected, "{}", json);
+    }
+
+    // Elements of the wrong type, answering the id read so far.
+    let error = |json: &str| match ParamsReader::new(json.as_bytes())
+        .for_each::<String, u32, (), _>(|_| Ok(()))
+    {
+        Err(ReadError::Request(error)) => (error.error.code, error.id),
+        other => panic!("{:?}", other),
+    };
+    let json = r#"{"jsonrpc": "2.0", "id": 3, "method": "Put", "params": [1, "x"]}"#;
+    assert_eq!(error(json), (ErrorCode::InvalidParams, Id::from(3)));
+    let json = r#"{"jsonrpc": "2.0", "method": "Put", "params": [-1], "id": 3}"#;
+    assert_eq!(error(json), (ErrorCode::InvalidParams, Id::Null));
+    let json = r#"{"jsonrpc": "2.0", "id": "a", "params": [1]}"#;
+    assert_eq!(error(json), (ErrorCode::InvalidRequest, Id::from("a")));
+    let json = r#"{"jsonrpc": "2.0", "id": "a", "params": [1"#;
+    assert_eq!(error(json), (ErrorCode::ParseError, Id::Null));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_borrowed() {
+    use jrpc::borrowed::{ErrorObjectRef, IdRef, IdReqRef, RequestRef};
+    use std::borrow::Cow;
+
+    let json = r#"{"jsonrpc": "2.0", "method": "Esc\"aped"}"#;
+    let request: RequestRef<Value> = serde_json::from_str(json).unwrap();
+    match request.method {
+        Cow::Owned(ref method) => assert_eq!(method, "Esc\"aped"),
+        Cow::Borrowed(_) => panic!("escapes can not be borrowed"),
+    }
+    assert_eq!(request.id, IdReqRef::Notification);
+    assert!(request.params.is_none());
+    let owned = request.into_owned();
+    assert_eq!(owned.id, IdReq::Notification);
+    assert_eq!(
+        serde_json::to_string(&RequestRef::from(&owned)).unwrap(),
+        owned.to_string()
+    );
+
+    let json = r#"{"code": -32601, "message": "Method not found"}"#;
+    let error: ErrorObjectRef<Value> = serde_json::from_str(json).unwrap();
+    assert_eq!(error.message, Cow::Borrowed("Method not found"));
+    let owned = error.to_owned();
+    assert_eq!(owned.code, ErrorCode::MethodNotFound);
+    assert_eq!(
+        serde_json::to_string(&owned).unwrap(),
+        serde_json::to_string(&error).unwrap()
+    );
+
+    let id: IdRef = serde_json::from_str("7").unwrap();
+    assert_eq!(id.to_owned(), Id::Int(7));
+    assert_eq!(IdRef::from(&Id::from("x")).into_owned(), Id::from("x"));
+    assert_eq!(IdReqRef::from(IdRef::Null).to_id(), Some(IdRef::Null));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_correlation() {
+    use jrpc::correlation::{Correlation, CorrelationError, IdGenerator, Sequential, Uuids};
+
+    let mut uuids = Uuids::new(42);
+    let id = match uuids.next_id() {
+        Id::String(id) => id,
+        other => panic!("{:?}", other),
+    };
+    let groups: Vec<usize> = id.split('-').map(str::len).collect();
+    assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+    assert_eq!(&id[14..15], "4");
+    assert!("89ab".contains(&id[19..20]));
+    assert_ne!(uuids.next_id(), Id::String(id.clone()));
+    assert_eq!(Uuids::new(42).next_id(), Id::String(id));
+
+    // Ids in flight are skipped, a generator stuck on them gives up.
+    let mut calls = Correlation::new();
+    calls.insert(Id::Int(1), ()).unwrap();
+    assert_eq!(calls.start(&mut Sequential::new(), ()), Ok(Id::Int(2)));
+    let mut stuck = Sequential::starting_at(1);
+    struct Constant;
+    impl IdGenerator for Constant {
+        fn next_id(&mut self) -> Id {
+            Id::Int(1)
+        }
+    }
+    assert_eq!(calls.start(&mut Constant, ()), Err(CorrelationError::Exhausted));
+    assert_eq!(calls.start(&mut stuck, ()), Ok(Id::Int(3)));
+
+    assert_eq!(calls.ids().collect::<Vec<_>>(), vec![&Id::Int(1), &Id::Int(2), &Id::Int(3)]);
+    assert_eq!(calls.drain().len(), 3);
+    assert!(calls.is_empty());
+}
+
+#[test]
+fn test_map_payloads() {
+    let request: Request<String, Value> = Request::new(Id::from("a"), "Ping".to_string())
+        .map_params(|()| Value::Null);
+    assert!(request.params.is_none());
+    let request = Request::with_params(Id::from("a"), "Ping".to_string(), "7".to_string())
+        .try_map_params(|s| s.parse::<u8>())
+        .unwrap();
+    assert_eq!(request.params, Some(7));
+    assert_eq!(request.method, "Ping");
+
+    let response: Response<u8, Value> = Response::success(Id::from(3), 7);
+    let response: Response<u8, String> = response
+        .map_err_data(|data| data.to_string())
+        .map_result(|n| n * 6);
+    assert_eq!(response.id(), &Id::from(3));
+    assert_eq!(response.into_result().unwrap(), 42);
+}
+
+#[test]
+fn test_std_traits() {
+    use std::collections::{BTreeSet, HashMap};
+
+    let request = Request::with_params(Id::from(1), "Add".to_string(), vec![1, 2]);
+    let mut retries = HashMap::new();
+    retries.insert(request.clone(), 0);
+    assert_eq!(retries[&request], 0);
+    assert_eq!(request.clone().map_params(|p| p.len()).params, Some(2));
+
+    let error: Error<Value> = Error::new(Id::Null, ErrorCode::ParseError, "Parse error", None);
+    let response: Response<Value> = Response::Err(error.clone());
+    assert_eq!(response.clone(), Response::Err(error));
+    assert_ne!(response, Response::success(Id::Null, Value::Null));
+
+    let ids: BTreeSet<Id> = vec![Id::Null, Id::from(2), Id::from("a"), Id::from(1)]
+        .into_iter()
+        .collect();
+    assert_eq!(ids.iter().next(), Some(&Id::from("a")));
+    assert!(IdReq::Int(1) < IdReq::Notification);
+    assert_eq!(Some(V2_0).max(None), Some(V2_0));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_views() {
+    use jrpc::method::MethodName;
+
+    let request = Request::with_params(Id::from(1), MethodName::from("Add"), vec![1, 2]);
+    let view = request.as_view();
+    let copy = view;
+    assert_eq!(copy.method, "Add");
+    assert_eq!(view.id, &IdReq::Int(1));
+    assert_eq!(view.to_owned().params, Some(vec![1, 2]));
+
+    let response: Response<Value, String> =
+        Response::Err(Error::new(Id::from("a"), ErrorCode::InternalError, "Oops", None));
+    let view = response.as_view();
+    assert_eq!(view.id, &Id::from("a"));
+    assert_eq!(view.result.unwrap_err().code, ErrorCode::InternalError);
+    assert_eq!(view.to_owned(), response);
+    let response: Response<Value> = Response::success(Id::from(2), Value::Bool(true));
+    assert_eq!(response.as_view().result, Ok(&Value::Bool(true)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_method_error_data() {
+    use jrpc::method::Method;
+    use std::borrow::Cow;
+
+    struct Withdraw;
+
+    impl Method for Withdraw {
+        type Params = (u64,);
+        type Result = u64;
+        type ErrorData = Vec<String>;
+
+        fn name() -> Cow<'static, str> {
+            "withdraw".into()
+        }
+    }
+
+    let error = Error::new(Id::from(1), 42, "Insufficient funds", Some(Value::from("x")));
+    let error = Withdraw::decode_result(Response::Err(error)).unwrap_err();
+    assert_eq!(error.code, ErrorCode::ServerError(42));
+    assert_eq!(error.message, "Insufficient funds");
+    assert_eq!(error.data, None);
+
+    let data = Value::from(vec!["balance"]);
+    let error = Error::new(Id::from(1), 42, "Insufficient funds", Some(data));
+    let error = Withdraw::decode_result(Response::Err(error)).unwrap_err();
+    assert_eq!(error.data, Some(vec!["balance".to_string()]));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_erased() {
+    use jrpc::erased::{AnyMessage, ErasedRequest, ErasedResponse, MessageKind};
+
+    let notification = Request::with_params(IdReq::Notification, "log".to_string(), "hi".to_string());
+    let erased = ErasedRequest::new(&notification).unwrap();
+    assert_eq!(erased.kind(), MessageKind::Notification);
+    assert_eq!(erased.id(), None);
+    assert_eq!(erased.to_value().unwrap()["params"], Value::from("hi"));
+
+    let error = Error::new(Id::from("a"), ErrorCode::InvalidParams, "Bad", Some(vec![1u8]));
+    let response: Response<Value, Vec<u8>> = Response::Err(error);
+    let erased: Box<dyn AnyMessage> = Box::new(ErasedResponse::new(&response).unwrap());
+    assert_eq!(erased.method(), None);
+    assert_eq!(erased.to_json().unwrap(), response.to_string());
+
+    let erased = ErasedResponse::new(&response).unwrap();
+    let decoded: Response<Value, Vec<u8>> = erased.decode().unwrap();
+    assert_eq!(decoded.into_result().unwrap_err().data, Some(vec![1]));
+    assert!(ErasedResponse::new(&response).unwrap().decode::<Value, String>().is_err());
+}
+
+#[cfg(feature = "std")]
+method_table! {
+    /// The methods of a key-value store.
+    pub enum Store {
+        Get = "kv.get",
+        Put = "kv.put",
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_method_table() {
+    use jrpc::table::{self, MethodTable};
+
+    assert_eq!(Store::ALL, &[Store::Get, Store::Put]);
+    assert_eq!(Store::from_name("kv.Get"), None);
+    assert_eq!(Store::Put.to_string(), "kv.put");
+
+    let json = r#"{"jsonrpc": "2.0", "method": "kv.get", "params": ["k"], "id": 1}"#;
+    let request: Request<Store, Value> = table::route(parse_request(json).unwrap()).unwrap();
+    assert_eq!(request.as_view().method, "kv.get");
+    assert_eq!(request.method, Store::Get);
+
+    let notification = Request::new(IdReq::Notification, "kv.delete".to_string());
+    let error = table::route::<Store, ()>(notification).unwrap_err();
+    assert_eq!(error.id, Id::Null);
+}
+
+#[test]
+fn test_emit_compact() {
+    let method = "log \"quoted\"\n\u{1}\\".to_string();
+    let params = serde_json::to_vec(&vec![1, 2]).unwrap();
+    let mut out = Vec::new();
+    Notification::emit_compact(&mut out, &method, Some(&params));
+    let expected = Notification::with_params(method.clone(), vec![1, 2]).to_string();
+    assert_eq!(String::from_utf8(out.clone()).unwrap(), expected);
+
+    // Appends without clearing.
+    Notification::emit_compact(&mut out, &method, None);
+    let appended = Notification::new(method).to_string();
+    assert_eq!(String::from_utf8(out).unwrap(), expected + &appended);
+}
+
+#[test]
+fn test_message() {
+    use jrpc::erased::MessageKind;
+
+    let messages: Vec<Message<Vec<u64>>> = vec![
+        Request::with_params(Id::from(1), "sum".to_string(), vec![1, 2]).into(),
+        Request::with_params(IdReq::Null, "sum".to_string(), vec![3]).into(),
+        Request::with_params(IdReq::Notification, "log".to_string(), vec![4]).into(),
+        Response::Ok(Success::new(Id::from(1), vec![3])).into(),
+        Message::Error(Error::method_not_found(Id::from(2))),
+    ];
+    let kinds: Vec<_> = messages.iter().map(|m| m.kind()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            MessageKind::Request,
+            MessageKind::Request,
+            MessageKind::Notification,
+            MessageKind::Response,
+            MessageKind::Response,
+        ]
+    );
+    let ids: Vec<_> = messages.iter().map(|m| m.id()).collect();
+    assert_eq!(
+        ids,
+        vec![Some(Id::from(1)), Some(Id::Null), None, Some(Id::from(1)), Some(Id::from(2))]
+    );
+    for message in &messages {
+        let parsed: Message<Vec<u64>> = serde_json::from_str(&message.to_string()).unwrap();
+        assert_eq!(&parsed, message);
+    }
+
+    let invalid = [
+        r#"[]"#,
+        r#"{"jsonrpc": "2.0"}"#,
+        r#"{"jsonrpc": "2.0", "result": 1, "error": {"code": 1, "message": ""}, "id": 1}"#,
+        r#"{"jsonrpc": "2.0", "method": "sum", "result": 1, "id": 1}"#,
+        r#"{"jsonrpc": "2.0", "result": [1], "id": 1, "method": "sum"}"#,
+        r#"{"jsonrpc": "2.0", "result": "one", "id": 1}"#,
+    ];
+    for json in invalid.iter() {
+        assert!(serde_json::from_str::<Message<Vec<u64>>>(json).is_err(), "{}", json);
+    }
+}
+
+#[test]
+fn test_response_result_and_error() {
+    let both = [
+        r#"{"jsonrpc": "2.0", "result": 1, "error": {"code": -32000, "message": "a"}, "id": 1}"#,
+        r#"{"jsonrpc": "2.0", "result": null, "error": {"code": -32000, "message": "a"}, "id": 1}"#,
+        r#"{"jsonrpc": "2.0", "result": 1, "error": null, "id": 1}"#,
+    ];
+    for json in both.iter() {
+        let err = serde_json::from_str::<Response<Value>>(json).unwrap_err();
+        assert!(err.to_string().contains("both `result` and `error`"), "{}", err);
+    }
+    assert!(serde_json::from_str::<Response<Value>>(r#"{"jsonrpc": "2.0", "id": 1}"#).is_err());
+    assert!(serde_json::from_str::<Response<Value>>("[]").is_err());
+
+    let responses: Vec<_> = both
+        .iter()
+        .map(|json| Response::<Value>::from_str_prefer_error(json).unwrap())
+        .collect();
+    assert!(responses[0].is_err());
+    assert!(responses[1].is_err());
+    assert_eq!(responses[2], Response::success(Id::from(1), Value::from(1)));
+
+    // Without both, it is the same as the strict deserializer.
+    let json = r#"{"jsonrpc": "2.0", "result": null, "id": 1}"#;
+    assert_eq!(
+        Response::<Value>::from_str_prefer_error(json).unwrap(),
+        serde_json::from_str(json).unwrap()
+    );
+    let json = r#"{"jsonrpc": "2.0", "error": null, "id": 1}"#;
+    assert!(Response::<Value>::from_str_prefer_error(json).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_multi_result() {
+    use jrpc::multi::{Item, MultiResult};
+
+    let error = ErrorObject {
+        code: ErrorCode::from(409),
+        message: "Already exists".to_string(),
+        data: Some(vec![7]),
+    };
+    let results: MultiResult<Value, Vec<u32>> =
+        MultiResult::from_indexed(vec![(3, Ok(Value::Null)), (5, Err(error.clone()))]);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results.get(3), Some(Ok(&Value::Null)));
+    assert_eq!(results.get(5), Some(Err(&error)));
+    assert_eq!(results.get(0), None);
+
+    let json = serde_json::to_string(&results).unwrap();
+    let parsed: MultiResult<Value, Vec<u32>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, results);
+    let items: Vec<_> = parsed.into_items().into_iter().map(Item::into_result).collect();
+    assert_eq!(items, vec![Ok(Value::Null), Err(error)]);
+
+    // An item has either a result or an error.
+    let invalid = [
+        r#"[{"index": 0}]"#,
+        r#"[{"index": 0, "result": 1, "error": {"code": -32000, "message": "a"}}]"#,
+        r#"[{"result": 1}]"#,
+    ];
+    for json in invalid.iter() {
+        assert!(serde_json::from_str::<MultiResult<Value>>(json).is_err(), "{}", json);
+    }
+
+    let empty: MultiResult<u32> = Vec::new().into_iter().collect();
+    assert!(empty.is_empty() && empty.all_ok());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_admission() {
+    use jrpc::admission::{Admission, AdmissionLimits, Admit, Overflow};
+
+    // Nothing queued: refused as soon as the calls in flight are full.
+    let mut calls = Admission::new(AdmissionLimits::new().max_in_flight(2));
+    assert_eq!(calls.submit(1), Admit::Send(1));
+    assert!(!calls.would_block());
+    assert_eq!(calls.submit(2), Admit::Send(2));
+    assert!(calls.would_block());
+    assert_eq!(calls.submit(3), Admit::WouldBlock(3));
+    assert_eq!(calls.complete(), None);
+    assert_eq!(calls.in_flight(), 1);
+    assert_eq!(calls.submit(3), Admit::Send(3));
+
+    // Evicting needs a queue.
+    let limits = AdmissionLimits::new()
+        .max_in_flight(0)
+        .overflow(Overflow::DropOldest);
+    let mut calls = Admission::new(limits);
+    assert_eq!(calls.submit(1), Admit::Send(1));
+    assert!(calls.would_block());
+    assert_eq!(calls.submit(2), Admit::WouldBlock(2));
+
+    let mut calls = Admission::new(limits.max_queued(2));
+    assert_eq!(calls.submit(1), Admit::Send(1));
+    assert_eq!(calls.submit(2), Admit::Queued);
+    assert_eq!(calls.submit(3), Admit::Queued);
+    assert!(!calls.would_block());
+    assert_eq!(calls.submit(4), Admit::Evicted(2));
+    assert_eq!((calls.in_flight(), calls.queued()), (1, 2));
+    assert_eq!(calls.drain(), vec![3, 4]);
+    assert_eq!((calls.in_flight(), calls.queued()), (0, 0));
+
+    let json = r#"{"max_in_flight": 8, "overflow": "drop_oldest"}"#;
+    let limits: AdmissionLimits = serde_json::from_str(json).unwrap();
+    assert_eq!(limits, AdmissionLimits::new().max_in_flight(8).overflow(Overflow::DropOldest));
+}
+
+#[test]
+fn test_binary_format_inputs() {
+    use serde::de::value::{BytesDeserializer, Error as ValueError};
+    use serde::de::IntoDeserializer;
+    use serde::Deserialize;
+
+    // Strings as bytes, as MessagePack may carry them.
+    let version = V2_0::deserialize(BytesDeserializer::<ValueError>::new(b"2.0"));
+    assert_eq!(version.unwrap(), V2_0);
+    assert!(V2_0::deserialize(BytesDeserializer::<ValueError>::new(b"1.0")).is_err());
+
+    // Integers of any width and sign.
+    fn code<T: IntoDeserializer<'static, ValueError>>(code: T) -> Result<ErrorCode, ValueError> {
+        ErrorCode::deserialize(code.into_deserializer())
+    }
+    assert_eq!(code(-32601i16).unwrap(), ErrorCode::MethodNotFound);
+    assert_eq!(code(409u16).unwrap(), ErrorCode::from(409));
+    assert_eq!(code(-32000i128).unwrap(), ErrorCode::ServerError(-32000));
+    assert_eq!(code(7u128).unwrap(), ErrorCode::from(7));
+    assert!(code(u64::MAX).is_err());
+    assert!(code(i128::MIN).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_affinity() {
+    use jrpc::affinity::{self, AffinityError, Issuer, Sticky, AFFINITY_REJECTED};
+    use serde_json::Value;
+
+    fn sign(data: &[u8]) -> Vec<u8> {
+        data.iter().rev().cloned().collect()
+    }
+    let app1 = Issuer::new("app-1", sign).max_age_ms(100);
+    let app2 = Issuer::new("app-2", sign);
+
+    let token = app1.mint(1000);
+    assert!(token.starts_with("app-1.1000."));
+    let claims = app2.validate(&token).unwrap();
+    assert_eq!((claims.server.as_str(), claims.issued_ms), ("app-1", 1000));
+
+    // Forged and malformed tokens.
+    let forged = token.replace("app-1", "app-2");
+    assert_eq!(
+        app2.validate(&forged),
+        Err(AffinityError::BadSignature(forged.clone()))
+    );
+    for malformed in &["", "app-1", "app-1.sig", ".1000.sig", "app-1.soon.sig"] {
+        assert_eq!(
+            app1.validate(malformed),
+            Err(AffinityError::Malformed(malformed.to_string()))
+        );
+    }
+
+    // A failover to app-2 issues its token, which it then keeps.
+    let mut request: Value =
+        serde_json::from_str(r#"{"jsonrpc": "2.0", "method": "f", "id": 1}"#).unwrap();
+    affinity::set(&mut request, token.clone());
+    let mut response: Value =
+        serde_json::from_str(r#"{"jsonrpc": "2.0", "result": 1, "id": 1}"#).unwrap();
+    assert_eq!(app2.issue(&request, &mut response, 5000), Ok(true));
+    assert!(affinity::affinity_of(&response)
+        .unwrap()
+        .starts_with("app-2.5000."));
+    affinity::set(&mut request, affinity::strip(&mut response).unwrap());
+    assert_eq!(app2.issue(&request, &mut response, 9000), Ok(false));
+    assert_eq!(affinity::affinity_of(&response), None);
+
+    // Renewed once older than max_age_ms.
+    affinity::set(&mut request, token.clone());
+    assert_eq!(app1.issue(&request, &mut response, 1099), Ok(false));
+    assert_eq!(app1.issue(&request, &mut response, 1100), Ok(true));
+
+    // Rejected tokens.
+    affinity::set(&mut request, forged.clone());
+    let err = app1.issue(&request, &mut response, 1100).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ServerError(AFFINITY_REJECTED));
+
+    // The client keeps the latest token of a batch and drops rejected ones.
+    let mut client = Sticky::new();
+    let batch = serde_json::json!([
+        {"jsonrpc": "2.0", "result": 1, "id": 1, "affinity": "app-1.1.aa"},
+        {"jsonrpc": "2.0", "result": 2, "id": 2, "affinity": "app-1.2.bb"},
+        {"jsonrpc": "2.0", "result": 3, "id": 3},
+    ]);
+    client.observe(&batch);
+    assert_eq!(client.token(), Some("app-1.2.bb"));
+
+    let mut requests = serde_json::json!([
+        {"jsonrpc": "2.0", "method": "f", "id": 4},
+        {"jsonrpc": "2.0", "method": "g"},
+    ]);
+    client.attach(&mut requests);
+    assert_eq!(affinity::affinity_of(&requests[0]), Some("app-1.2.bb"));
+    assert_eq!(affinity::affinity_of(&requests[1]), Some("app-1.2.bb"));
+
+    let error = err.into_error(Id::Int(4));
+    client.observe(&serde_json::to_value(&error).unwrap());
+    assert_eq!(client.token(), None);
+}
+
+#[test]
+fn test_glob_import_keeps_std_result() {
+    // `use jrpc::*` must not shadow `Result`: successful Responses are `Success<T>`.
+    fn parse(json: &str) -> Result<Success<u8>, String> {
+        serde_json::from_str(json).map_err(|err| err.to_string())
+    }
+    let success = parse(r#"{"jsonrpc": "2.0", "result": 1, "id": 1}"#).unwrap();
+    assert_eq!(success.result, 1);
+    assert!(parse("[").is_err());
+}
+
+#[test]
+fn test_error_details() {
+    let details = ErrorDetails::new("busy").retryable(true);
+    let error = ErrorObject::with_details(409, "Conflict", details.clone()).unwrap();
+    let json = serde_json::to_string(&error).unwrap();
+    assert_eq!(
+        json,
+        r#"{"code":409,"message":"Conflict","data":{"reason":"busy","retryable":true}}"#
+    );
+    assert!(ErrorObject::with_details(-32500, "Oops", details.clone()).is_err());
+
+    // Two-phase decode: inspect the code, then the data.
+    let error: ErrorObject<Value> = serde_json::from_str(&json).unwrap();
+    assert_eq!(error.code, ErrorCode::from(409));
+    let error = error.decode_data::<ErrorDetails>().unwrap();
+    assert_eq!(error.data, Some(details));
+    assert_eq!(error.message, "Conflict");
+
+    let error: ErrorObject<Value> = serde_json::from_str(r#"{"code": 1, "message": "m"}"#).unwrap();
+    assert_eq!(error.decode_data::<ErrorDetails>().unwrap().data, None);
+    let error = ErrorObject::<Value>::new(1, "m", Some(Value::from(5))).unwrap();
+    assert!(error.decode_data::<ErrorDetails>().is_err());
+
+    let error = ErrorObject::new(1, "m", Some(5)).unwrap();
+    let error = error.try_map_data(|n| if n > 0 { Ok(n * 2) } else { Err(n) });
+    assert_eq!(error.unwrap().data, Some(10));
+}
+
+#[test]
+fn test_error_display() {
+    fn call(fail: bool) -> Result<u8, Box<dyn std::error::Error>> {
+        if fail {
+            let data = Value::from("missing field `x`");
+            let error = ErrorObject::new(ErrorCode::InvalidParams, "Invalid params", Some(data));
+            return Err(error.unwrap().into());
+        }
+        Ok(1)
+    }
+    assert_eq!(call(false).unwrap(), 1);
+    assert_eq!(
+        call(true).unwrap_err().to_string(),
+        r#"Invalid params (-32602): "missing field `x`""#
+    );
+
+    // Errors are serialized with `to_json`.
+    let object = ErrorObject::new(409, "Conflict", None).unwrap();
+    let error: Error<Value> = (Id::from(5), object).into();
+    assert_eq!(error.id, Id::Int(5));
+    assert_eq!(format!("{}", error), "Conflict (409)");
+    assert_eq!(
+        error.to_json(),
+        r#"{"jsonrpc":"2.0","error":{"code":409,"message":"Conflict"},"id":5}"#
+    );
+    // The deprecated inherent `to_string` still returns the json.
+    #[allow(deprecated)]
+    let json = error.to_string();
+    assert_eq!(json, error.to_json());
+
+    let error = ErrorObject::<Value>::new(ErrorCode::MethodNotFound, "Method not found", None);
+    let error = error.unwrap().into_error(Id::from(3));
+    assert_eq!(error.id, Id::Int(3));
+    let error: &dyn std::error::Error = &error;
+    assert_eq!(error.to_string(), "Method not found (-32601)");
+}
+
+#[test]
+fn test_reserved_codes() {
+    assert_eq!(ErrorCode::from(-32768), ErrorCode::Reserved(-32768));
+    assert_eq!(ErrorCode::from(-32604), ErrorCode::Reserved(-32604));
+    assert_eq!(ErrorCode::from(-32100), ErrorCode::Reserved(-32100));
+    assert_eq!(ErrorCode::from(-32099), ErrorCode::ServerError(-32099));
+    assert_eq!(ErrorCode::from(-32000), ErrorCode::ServerError(-32000));
+    // Outside of the reserved range, e.g. the codes of the Language Server Protocol.
+    assert_eq!(ErrorCode::from(-32769), ErrorCode::ServerError(-32769));
+    assert_eq!(ErrorCode::from(-32800), ErrorCode::ServerError(-32800));
+    assert!(!ErrorCode::Reserved(-32650).is_valid());
+
+    let json = r#"{"code": -32650, "message": "Future error"}"#;
+    let error: ErrorObject<Value> = serde_json::from_str(json).unwrap();
+    assert_eq!(error.code, ErrorCode::Reserved(-32650));
+    assert_eq!(error.code.code(), -32650);
+    assert_eq!(
+        serde_json::to_string(&error).unwrap(),
+        r#"{"code":-32650,"message":"Future error"}"#
+    );
+    assert!(ErrorObject::<Value>::new(ErrorCode::Reserved(-32650), "Future error", None).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_cancel_notification() {
+    use jrpc::cancel::{self, CancelParams, CANCEL_METHOD};
+
+    let notification = Notification::cancel(Id::from("req-1"));
+    assert_eq!(notification.method, CANCEL_METHOD);
+    assert_eq!(
+        notification.params,
+        Some(CancelParams {
+            id: Id::from("req-1")
+        })
+    );
+
+    let message = |json: &str| -> Message { serde_json::from_str(json).unwrap() };
+    let json = notification.to_string();
+    assert_eq!(cancel::cancelled_id(&message(&json)), Some(Id::from("req-1")));
+    // By-position params.
+    let json = r#"{"jsonrpc": "2.0", "method": "$/cancelRequest", "params": [1]}"#;
+    assert_eq!(cancel::cancelled_id(&message(json)), Some(Id::from(1)));
+
+    // Only well formed cancellation Notifications are recognized.
+    for json in &[
+        r#"{"jsonrpc": "2.0", "method": "$/cancelRequest"}"#,
+        r#"{"jsonrpc": "2.0", "method": "$/cancelRequest", "params": {"id": 1.5}}"#,
+        r#"{"jsonrpc": "2.0", "method": "$/cancelRequest", "params": {"id": 1}, "id": 2}"#,
+        r#"{"jsonrpc": "2.0", "method": "$/progress", "params": {"id": 1}}"#,
+    ] {
+        assert_eq!(cancel::cancelled_id(&message(json)), None, "{}", json);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_progress_notification() {
+    use jrpc::progress::{self, ProgressParams, ProgressToken, PROGRESS_METHOD};
+
+    let token: ProgressToken = serde_json::from_str("7").unwrap();
+    assert_eq!(token, ProgressToken::Int(7));
+    let token: ProgressToken = serde_json::from_str(r#""seven""#).unwrap();
+    assert_eq!(token.to_string(), "seven");
+    assert!(serde_json::from_str::<ProgressToken>("null").is_err());
+    assert!(serde_json::from_str::<ProgressToken>("7.5").is_err());
+
+    let notification = Notification::progress(7, Value::from(50));
+    assert_eq!(notification.method, PROGRESS_METHOD);
+    let message: Message = serde_json::from_str(&notification.to_string()).unwrap();
+    assert_eq!(
+        progress::progress_of(&message),
+        Some(ProgressParams::new(7, Value::from(50)))
+    );
+    // The value must deserialize as the expected type.
+    assert_eq!(progress::progress_of::<String>(&message), None);
+
+    for json in &[
+        r#"{"jsonrpc": "2.0", "method": "$/progress", "params": {"value": 1}}"#,
+        r#"{"jsonrpc": "2.0", "method": "$/progress", "params": {"token": 1}}"#,
+        r#"{"jsonrpc": "2.0", "method": "$/cancelRequest", "params": {"token": 1, "value": 1}}"#,
+    ] {
+        let message: Message = serde_json::from_str(json).unwrap();
+        assert_eq!(progress::progress_of::<Value>(&message), None, "{}", json);
+    }
+}
+
+#[test]
+fn test_params_macro() {
+    let name = "foo".to_string();
+    let params = params![1, name, [true, false], None::<u8>,];
+    assert_eq!(
+        Value::from(params),
+        serde_json::json!([1, "foo", [true, false], null])
+    );
+    // `name` was only borrowed.
+    assert_eq!(name, "foo");
+    assert!(params![].is_empty());
+
+    let request: Request<String, params::Params> =
+        Request::with_params(Id::from(1), "GetFoo".to_string(), params![2, 3]);
+    let (a, b): (i64, i64) = request.params.unwrap().parse().unwrap();
+    assert_eq!(a + b, 5);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_call_expecting() {
+    let request = Request::with_params(Id::from("a"), "GetFoo".to_string(), vec![1, 2]);
+    let call: call::Call<Vec<i32>, Vec<u8>> = request.expecting();
+    assert_eq!(call.id(), &Id::from("a"));
+    assert_eq!(
+        call.parse_response(r#"{"jsonrpc": "2.0", "result": [1, 2], "id": "a"}"#),
+        Ok(vec![1, 2])
+    );
+
+    // The result type is checked.
+    let error = call
+        .parse_response(r#"{"jsonrpc": "2.0", "result": "foo", "id": "a"}"#)
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::ParseError);
+    assert_eq!(
+        call.parse_response("{").unwrap_err().code,
+        ErrorCode::ParseError
+    );
+
+    // Errors the Server could not attribute to a Request are passed through, others are not.
+    let json =
+        r#"{"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null}"#;
+    assert_eq!(
+        call.parse_response(json).unwrap_err().message,
+        "Parse error"
+    );
+    let json =
+        r#"{"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": "b"}"#;
+    assert_eq!(
+        call.parse_response(json).unwrap_err().code,
+        ErrorCode::InternalError
+    );
+
+    // A Null id only matches a Null id.
+    let call = Request::new(Id::Null, "GetFoo".to_string()).expecting::<u8>();
+    assert_eq!(
+        call.parse_response(r#"{"jsonrpc": "2.0", "result": 1, "id": null}"#),
+        Ok(1)
+    );
+    let json = r#"{"jsonrpc": "2.0", "result": 1, "id": 0}"#;
+    assert!(call.parse_response(json).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_grpc_extreme_codes() {
+    use std::collections::BTreeMap;
+    use jrpc::grpc::{GrpcMapping, GrpcStatus, METADATA_CODE};
+
+    let mapping = GrpcMapping::new();
+    for &code in &[i64::MAX, i64::MIN, -32017, 0] {
+        assert_eq!(mapping.to_grpc(ErrorCode::from(code)), GrpcStatus::Unknown);
+    }
+    assert_eq!(
+        mapping.to_grpc(ErrorCode::from(-32014)),
+        GrpcStatus::Unavailable
+    );
+
+    // A peer sending an out of range code in the metadata.
+    let mut metadata = BTreeMap::new();
+    metadata.insert(METADATA_CODE.to_string(), i64::MAX.to_string());
+    let error = mapping.from_status(GrpcStatus::Unknown, "Oops", &metadata);
+    assert_eq!(mapping.to_grpc(error.code), GrpcStatus::Unknown);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_lint_errors_serde() {
+    use jrpc::lint::{Case, Finding, LintError};
+
+    let errors = vec![
+        LintError::Empty,
+        LintError::TooLong { len: 80, max: 64 },
+        LintError::ReservedPrefix {
+            prefix: "rpc.".to_string(),
+        },
+        LintError::EmptySegment,
+        LintError::Case {
+            segment: "get_foo".to_string(),
+            expected: Case::Camel,
+        },
+    ];
+    for error in errors {
+        let finding = Finding {
+            method: "rpc.get_foo".to_string(),
+            error,
+        };
+        let json = serde_json::to_string(&finding).unwrap();
+        assert_eq!(serde_json::from_str::<Finding>(&json).unwrap(), finding);
+    }
+    assert_eq!(
+        serde_json::to_value(LintError::ReservedPrefix {
+            prefix: "rpc.".to_string()
+        })
+        .unwrap(),
+        serde_json::json!({"rule": "reserved_prefix", "prefix": "rpc."})
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_journal_collision() {
+    use jrpc::journal::{self, ResponseJournal};
+    use std::time::Duration;
+
+    let transfer = Request::with_params(Id::from(1), "Transfer".to_string(), vec![100]);
+    let mut journal = ResponseJournal::new(10, Duration::from_secs(60));
+    let response = Response::success(Id::from(1), "tx-1".to_string());
+    journal.record(&transfer, &response, 0);
+    assert_eq!(journal.replay(&transfer), Some(response));
+
+    // Swap the Requests of two entries, as if each had collided with the other Request.
+    let refund = Request::with_params(Id::from(1), "Refund".to_string(), vec![100]);
+    let mut json = serde_json::to_value(&journal).unwrap();
+    let hash = journal::content_hash(&transfer);
+    json["entries"][journal::content_hash(&refund)] = json["entries"][&hash].clone();
+    json["entries"][&hash]["request"] = serde_json::to_string(&refund).unwrap().into();
+    let journal: ResponseJournal = serde_json::from_value(json).unwrap();
+    assert_eq!(journal.replay::<_, String>(&transfer), None);
+    assert_eq!(journal.replay::<_, String>(&refund), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_journal_retention() {
+    use jrpc::journal::ResponseJournal;
+    use std::time::Duration;
+
+    let journal = ResponseJournal::new(10, Duration::new(2, 999_999_999));
+    assert_eq!(journal.retention(), Duration::from_millis(2999));
+    let journal = ResponseJournal::new(10, Duration::new(u64::MAX, 999_999_999));
+    assert_eq!(journal.retention(), Duration::from_millis(u64::MAX));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_webhook_at_most_once_duplicate() {
+    use jrpc::webhook::{Dedup, DeliveryPolicy, WebhookEnvelope};
+
+    let mut envelope = WebhookEnvelope::new("d-1", 1000).policy(DeliveryPolicy::AtMostOnce);
+    envelope.notify("order.shipped", &vec![7]);
+    assert_eq!(envelope.retry(2000), None);
+
+    // The same envelope delivered twice, e.g. replayed, is only processed once.
+    let json = envelope.to_string();
+    let mut dedup = Dedup::new(10);
+    assert!(dedup.first_delivery(&json.parse().unwrap()));
+    assert!(!dedup.first_delivery(&json.parse().unwrap()));
+
+    let other = WebhookEnvelope::new("d-2", 1000).policy(DeliveryPolicy::AtMostOnce);
+    assert!(dedup.first_delivery(&other));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_coalesce_key() {
+    use jrpc::coalesce::{CallKey, Coalescer};
+
+    let params = serde_json::json!({"b": 2, "a": 1});
+    let key = CallKey::new("get", Some(&params));
+    assert_eq!(key.0, r#"["get",{"a":1,"b":2}]"#);
+    assert_ne!(key, CallKey::new("get", None));
+
+    let mut coalescer = Coalescer::new();
+    let first = Request::with_params(Id::from(1), "get".to_string(), params.clone());
+    let other = Request::with_params(Id::from(2), "get".to_string(), serde_json::json!([1]));
+    assert!(coalescer.call(&first));
+    assert!(coalescer.call(&other));
+    assert_eq!(coalescer.waiting(&key), 1);
+    assert_eq!(coalescer.in_flight(), 2);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_stats_errors_order() {
+    use jrpc::stats::StatsCollector;
+
+    let mut collector = StatsCollector::new();
+    for &code in &[42, -32650, -32601, -32600, -32000, -32601] {
+        collector.record_error(code);
+    }
+    let stats = collector.snapshot();
+    let codes: Vec<i64> = stats.errors.iter().map(|e| e.code.code()).collect();
+    assert_eq!(codes, vec![-32650, -32601, -32600, -32000, 42]);
+    assert_eq!(stats.error_count(ErrorCode::MethodNotFound), 2);
+    assert_eq!(stats.errors[0].code, ErrorCode::Reserved(-32650));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_grpc_custom_codes_closest_to_zero() {
+    use jrpc::grpc::{GrpcMapping, GrpcStatus};
+
+    let mapping = GrpcMapping::new()
+        .with_code(500, GrpcStatus::Aborted)
+        .with_code(7, GrpcStatus::Aborted)
+        .with_code(-12, GrpcStatus::Aborted)
+        .with_code(i64::MIN, GrpcStatus::Aborted);
+    assert_eq!(mapping.from_grpc(GrpcStatus::Aborted), ErrorCode::from(7));
+    assert_eq!(mapping.to_grpc(500), GrpcStatus::Aborted);
+
+    let mapping = mapping.with_code(-7, GrpcStatus::Aborted);
+    assert_eq!(mapping.from_grpc(GrpcStatus::Aborted), ErrorCode::from(-7));
+}
@@ -0,0 +1,94 @@
+#![cfg(any(feature = "msgpack", feature = "cbor"))]
+#[macro_use]
+extern crate serde_json;
+extern crate jrpc;
+
+use jrpc::{Error, ErrorCode, Id, IdReq, Message, Notification, Request, Response, Value};
+
+/// Round trip every kind of message through the `to_vec` and `from_slice` of `$format`.
+macro_rules! round_trip {
+    ($format:ident) => {{
+        use jrpc::$format::{from_slice, to_vec};
+
+        let requests = vec![
+            Request::with_params(Id::from(1), "subtract".to_string(), json!([42, 23])),
+            Request::with_params(
+                Id::from("abc"),
+                "subtract".to_string(),
+                json!({"minuend": 42, "subtrahend": 23.5}),
+            ),
+            Request::with_params(Id::Null, "GetFoo".to_string(), json!([null, true])),
+        ];
+        for request in requests {
+            let bytes = to_vec(&request).unwrap();
+            assert_eq!(
+                from_slice::<Request<String, Value>>(&bytes).unwrap(),
+                request
+            );
+        }
+        let request = Request::new(IdReq::from(Id::from(7)), "GetFoo".to_string());
+        let bytes = to_vec(&request).unwrap();
+        assert_eq!(from_slice::<Request<String, ()>>(&bytes).unwrap(), request);
+
+        let notification = Notification::with_params("Log".to_string(), vec![1, 2]);
+        let bytes = to_vec(&notification).unwrap();
+        assert_eq!(
+            from_slice::<Notification<String, Vec<u8>>>(&bytes).unwrap(),
+            notification
+        );
+
+        let response: Response<Vec<u32>> = Response::success(Id::from(1), vec![1, 2]);
+        let bytes = to_vec(&response).unwrap();
+        assert_eq!(from_slice::<Response<Vec<u32>>>(&bytes).unwrap(), response);
+
+        for &code in &[-32700, -32601, -32000, -32099, -32050, -32768, 7, i64::MIN] {
+            let error: Error<Value> = Error::new(
+                Id::from(2),
+                ErrorCode::from(code),
+                "Oops",
+                Some(json!({"index": [4, 2]})),
+            );
+            let bytes = to_vec(&error).unwrap();
+            assert_eq!(from_slice::<Error<Value>>(&bytes).unwrap(), error);
+
+            let response: Response<u8> = Response::Err(error);
+            let bytes = to_vec(&response).unwrap();
+            assert_eq!(from_slice::<Response<u8>>(&bytes).unwrap(), response);
+        }
+        let error: Error<Value> = Error::new(Id::Null, ErrorCode::ParseError, "Parse error", None);
+        let bytes = to_vec(&error).unwrap();
+        assert_eq!(from_slice::<Error<Value>>(&bytes).unwrap(), error);
+
+        // Batches, and messages of any kind.
+        let batch = vec![
+            Request::with_params(Id::from(1), "sum".to_string(), vec![1, 2]),
+            Request::with_params(Id::from(2), "sum".to_string(), vec![3]),
+        ];
+        let bytes = to_vec(&batch).unwrap();
+        assert_eq!(
+            from_slice::<Vec<Request<String, Vec<u8>>>>(&bytes).unwrap(),
+            batch
+        );
+        let bytes = to_vec(&json!({"jsonrpc": "2.0", "method": "Log"})).unwrap();
+        match from_slice::<Message>(&bytes).unwrap() {
+            Message::Notification(notification) => assert_eq!(notification.method, "Log"),
+            other => panic!("expected a Notification, got {:?}", other),
+        }
+
+        // The version is still checked.
+        let bytes = to_vec(&json!({"jsonrpc": "1.0", "method": "Log", "id": 1})).unwrap();
+        assert!(from_slice::<Request<String, Value>>(&bytes).is_err());
+    }};
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_msgpack_round_trip() {
+    round_trip!(msgpack);
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_round_trip() {
+    round_trip!(cbor);
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "async")]
+
+extern crate jrpc;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use jrpc::cancel::{self, CancelToken};
+
+/// Pending until polled `n` times.
+struct Countdown(u32);
+
+impl Future for Countdown {
+    type Output = u32;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<u32> {
+        if self.0 == 0 {
+            Poll::Ready(7)
+        } else {
+            self.0 -= 1;
+            Poll::Pending
+        }
+    }
+}
+
+struct CountingWaker(AtomicUsize);
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_cancel_wakes_and_cleans_up_in_reverse() {
+    let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+    let waker = Waker::from(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    let token = CancelToken::new();
+    let (first, second) = (order.clone(), order.clone());
+    let mut handler = cancel::cancellable(Countdown(5), &token)
+        .on_cancel(move || first.lock().unwrap().push(1))
+        .on_cancel(move || second.lock().unwrap().push(2));
+    assert!(Pin::new(&mut handler).poll(&mut cx).is_pending());
+    assert!(Pin::new(&mut handler).poll(&mut cx).is_pending());
+
+    token.clone().cancel();
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+    assert!(Pin::new(&mut handler).poll(&mut cx).is_ready());
+    assert_eq!(*order.lock().unwrap(), vec![2, 1]);
+}
+
+#[test]
+fn test_cancel_on_drop() {
+    let mut cx = Context::from_waker(Waker::noop());
+    let cleaned = Arc::new(AtomicUsize::new(0));
+
+    let flag = cleaned.clone();
+    let mut handler = cancel::cancellable(Countdown(1), &CancelToken::new()).on_cancel(move || {
+        flag.fetch_add(1, Ordering::SeqCst);
+    });
+    assert!(Pin::new(&mut handler).poll(&mut cx).is_pending());
+    drop(handler);
+    assert_eq!(cleaned.load(Ordering::SeqCst), 1);
+
+    // Dropping a completed handler does not clean up.
+    let flag = cleaned.clone();
+    let mut handler = cancel::cancellable(Countdown(0), &CancelToken::new()).on_cancel(move || {
+        flag.fetch_add(1, Ordering::SeqCst);
+    });
+    assert!(Pin::new(&mut handler).poll(&mut cx).is_ready());
+    drop(handler);
+    assert_eq!(cleaned.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_cancel_run_to_completion() {
+    let mut cx = Context::from_waker(Waker::noop());
+
+    let token = CancelToken::new();
+    let mut handler = cancel::cancellable(Countdown(1), &token).run_to_completion();
+    assert!(Pin::new(&mut handler).poll(&mut cx).is_pending());
+    token.cancel();
+    match Pin::new(&mut handler).poll(&mut cx) {
+        Poll::Ready(Ok(7)) => {}
+        other => panic!("{:?}", other),
+    }
+
+    // Cancelled before it started.
+    let mut handler = cancel::cancellable(Countdown(1), &token).run_to_completion();
+    match Pin::new(&mut handler).poll(&mut cx) {
+        Poll::Ready(Err(error)) => assert_eq!(error.message, "request cancelled"),
+        other => panic!("{:?}", other),
+    }
+}
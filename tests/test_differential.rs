@@ -0,0 +1,62 @@
+#![cfg(feature = "differential")]
+extern crate jrpc;
+extern crate serde_json;
+
+use jrpc::differential::{self, Outcome};
+use jrpc::Value;
+
+#[test]
+fn test_reference_parser_agrees_with_serde_json() {
+    let samples = [
+        r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, -23.5e2], "id": 1}"#,
+        r#"[{"jsonrpc": "2.0", "method": "aé😀\n"}, {"id": 18446744073709551615}]"#,
+        r#" {"a": [true, false, null, {}, [], "", 0, -1, 1.5E-3]} "#,
+    ];
+    for sample in &samples {
+        let expected: Value = serde_json::from_str(sample).unwrap();
+        assert_eq!(differential::parse(sample).unwrap(), expected);
+        assert_eq!(differential::compare(sample), Outcome::Agree);
+    }
+    for invalid in &[
+        "",
+        "{",
+        "[1,]",
+        "01",
+        "1.",
+        "\"\t\"",
+        "{\"a\" 1}",
+        "nul",
+        "[1] 2",
+    ] {
+        assert!(differential::parse(invalid).is_err(), "{}", invalid);
+        assert_eq!(differential::compare(invalid), Outcome::BothReject);
+    }
+}
+
+#[test]
+fn test_differentials() {
+    let json = r#"[{"jsonrpc": "2.0", "id": 1, "id": 2, "method": "a"}]"#;
+    match differential::compare(json) {
+        Outcome::Disagree(d) => {
+            assert_eq!(d.len(), 1);
+            assert_eq!(d[0].index, 0);
+            assert_eq!(d[0].member, "id");
+            assert_eq!(d[0].serde_json, Some(Value::from(2)));
+            assert_eq!(d[0].reference, Some(Value::from(1)));
+        }
+        outcome => panic!("{:?}", outcome),
+    }
+
+    // serde_json reads `-0` as a float.
+    let zero = r#"{"jsonrpc": "2.0", "method": "a", "id": -0}"#;
+    assert!(!differential::compare(zero).is_consistent());
+
+    let lone = r#"{"jsonrpc": "2.0", "method": "\ud800"}"#;
+    match differential::compare(lone) {
+        Outcome::OnlyReference(_) => {}
+        outcome => panic!("{:?}", outcome),
+    }
+
+    let deep = format!("{}{}", "[".repeat(200), "]".repeat(200));
+    assert!(!differential::compare(&deep).is_consistent());
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+extern crate serde_json;
+
+use jrpc::examples_support::{self, Add, Calculator, Divide, Subtract, Sum};
+use jrpc::method::Method;
+use jrpc::{ErrorCode, Id, Value};
+
+#[test]
+fn test_calculator_calls() {
+    let mut calculator = Calculator::new();
+    assert_eq!(calculator.call::<Add>((1, 2)), Ok(3));
+    assert_eq!(calculator.call::<Subtract>((42, 23)), Ok(19));
+    assert_eq!(calculator.call::<Sum>(vec![]), Ok(0));
+
+    let error = calculator.call::<Add>((i64::MAX, 1)).unwrap_err();
+    assert_eq!(error.code, ErrorCode::from(examples_support::OVERFLOW));
+    assert!(!error.data.unwrap().retryable);
+    let error = calculator.call::<Divide>((i64::MIN, -1)).unwrap_err();
+    assert_eq!(error.code, ErrorCode::from(examples_support::OVERFLOW));
+
+    // The ids are sequential.
+    assert_eq!(
+        calculator.request::<Add>((0, 0)).id.to_id(),
+        Some(Id::Int(6))
+    );
+}
+
+#[test]
+fn test_calculator_server() {
+    let router = examples_support::router();
+    for method in &[Add::name(), Subtract::name(), Divide::name(), Sum::name()] {
+        assert!(router.contains(method));
+    }
+
+    // Other clients see the error details as json.
+    let json = r#"{"jsonrpc": "2.0", "method": "divide", "params": [1, 0], "id": "a"}"#;
+    let response: Value = serde_json::from_str(&router.handle(json).unwrap()).unwrap();
+    assert_eq!(
+        response["error"],
+        serde_json::json!({
+            "code": 1,
+            "message": "Division by zero",
+            "data": {"field": "1", "reason": "the divisor is zero"},
+        })
+    );
+
+    let json = r#"{"jsonrpc": "2.0", "method": "add", "params": [1], "id": 1}"#;
+    let response: Value = serde_json::from_str(&router.handle(json).unwrap()).unwrap();
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[test]
+fn test_calculator_batch() {
+    let mut calculator = Calculator::new();
+    let add = calculator.request::<Add>((1, 1));
+    let divide = calculator.request::<Divide>((1, 0));
+    let unknown = jrpc::Request::with_params(Id::from(99), "modulo".to_string(), Value::Null);
+
+    let batch = vec![add.clone(), divide.clone(), unknown.clone()];
+    let responses = calculator.batch(batch).unwrap();
+    assert_eq!(responses.responses().len(), 3);
+    assert_eq!(Calculator::decode::<Add>(&responses, &add), Ok(2));
+    let error = Calculator::decode::<Divide>(&responses, &divide).unwrap_err();
+    assert_eq!(
+        error.code,
+        ErrorCode::from(examples_support::DIVISION_BY_ZERO)
+    );
+    let error = Calculator::decode::<Add>(&responses, &unknown).unwrap_err();
+    assert_eq!(error.code, ErrorCode::MethodNotFound);
+
+    assert!(calculator.batch(vec![]).is_none());
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+extern crate serde_json;
+
+use jrpc::framing::{self, FrameDecoder, FrameError, StreamParser};
+use jrpc::{ErrorCode, Id, Request, Value};
+
+fn decode_all(decoder: &mut FrameDecoder) -> Vec<Value> {
+    let mut payloads = Vec::new();
+    while let Some(payload) = decoder.decode().unwrap() {
+        payloads.push(serde_json::from_slice(&payload).unwrap());
+    }
+    payloads
+}
+
+#[test]
+fn test_framing_chunks() {
+    let mut bytes = framing::encode(br#"{"a":1}"#);
+    bytes.extend(framing::encode_framed(&vec!["é"]).unwrap());
+    bytes.extend(framing::encode(b"null"));
+
+    // Any split of the stream yields the same payloads.
+    for chunk in 1..bytes.len() + 1 {
+        let mut decoder = FrameDecoder::new();
+        let mut payloads = Vec::new();
+        for bytes in bytes.chunks(chunk) {
+            decoder.push(bytes);
+            payloads.extend(decode_all(&mut decoder));
+        }
+        assert_eq!(
+            payloads,
+            vec![serde_json::json!({"a": 1}), serde_json::json!(["é"]), Value::Null],
+        );
+        assert_eq!(decoder.buffered(), 0);
+    }
+}
+
+#[test]
+fn test_framing_headers() {
+    let mut decoder = FrameDecoder::new();
+    decoder.push(b"content-length:  2\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8");
+    decoder.push(b"\r\n\r\n[]");
+    assert_eq!(decode_all(&mut decoder), vec![serde_json::json!([])]);
+
+    let error = |bytes: &[u8]| {
+        let mut decoder = FrameDecoder::new().max_content_length(10).max_header_length(64);
+        decoder.push(bytes);
+        decoder.decode().unwrap_err()
+    };
+    assert_eq!(error(b"Content-Type: x\r\n\r\n{}"), FrameError::MissingContentLength);
+    assert_eq!(
+        error(b"Content-Length: -1\r\n\r\n"),
+        FrameError::InvalidContentLength("-1".to_string())
+    );
+    assert_eq!(
+        error(b"Content-Length: 11\r\n\r\n"),
+        FrameError::ContentTooLong { len: 11, max: 10 }
+    );
+    assert_eq!(error(b"{}\r\n\r\n"), FrameError::InvalidHeader("{}".to_string()));
+    assert_eq!(error(&[b' '; 64]), FrameError::HeaderTooLong { max: 64 });
+}
+
+#[test]
+fn test_stream_chunks() {
+    let request = Request::with_params(Id::from(1), "a".to_string(), 1);
+    let mut bytes = framing::encode_line(&request).unwrap();
+    bytes.extend(b"\r\n  \n");
+    bytes.extend(br#"{"jsonrpc": "2.0", "result": "\u00e9", "id": 1}"#);
+    bytes.extend(b"\r\n");
+    bytes.extend(br#"{"jsonrpc": "2.0", "method": "b"}"#);
+
+    // Every split of the stream parses the same.
+    for size in 1..bytes.len() + 1 {
+        let mut parser = StreamParser::<Value>::new();
+        let mut messages = Vec::new();
+        for chunk in bytes.chunks(size) {
+            parser.push(chunk);
+            messages.extend(parser.by_ref().map(Result::unwrap));
+        }
+        assert_eq!(messages.len(), 2, "chunks of {}", size);
+        assert_eq!(messages[0], serde_json::to_value(&request).unwrap());
+        assert_eq!(messages[1]["result"], "é");
+        assert!(parser.buffered() > 0);
+        assert_eq!(parser.finish().unwrap().unwrap()["method"], "b");
+        assert_eq!(parser.buffered(), 0);
+        assert!(parser.finish().is_none());
+    }
+}
+
+#[test]
+fn test_stream_errors() {
+    let mut parser = StreamParser::<Request<String, Value>>::new().max_line_length(40);
+    parser.push(b"{\"jsonrpc\": \"2.0\", \"method\n[1, 2]\n");
+    let error = parser.next().unwrap().unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::ParseError);
+    assert_eq!(error.id, Id::Null);
+    let error = parser.next().unwrap().unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::InvalidRequest);
+    assert!(parser.next().is_none());
+
+    // An overlong line is reported once and skipped.
+    parser.push(&[b' '; 30]);
+    assert!(parser.next().is_none());
+    parser.push(&[b' '; 30]);
+    let error = parser.next().unwrap().unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::ParseError);
+    parser.push(&[b' '; 30]);
+    assert!(parser.next().is_none());
+    assert_eq!(parser.buffered(), 0);
+    parser.push(b" \n{\"jsonrpc\": \"2.0\", \"method\": \"c\"}\n");
+    assert_eq!(parser.next().unwrap().unwrap().method, "c");
+    assert!(parser.next().is_none());
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+
+const CORPUS: &[&str] = &[
+    r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#,
+    r#"{"jsonrpc": "2.0", "method": "update", "params": {"a": [1, 2, {"b": null}]}}"#,
+    r#"{"jsonrpc": "2.0", "method": "foobar", "id": "1"}"#,
+    r#"{"jsonrpc": "2.0", "method": "foobar", "id": null}"#,
+    r#"{"jsonrpc": "2.0", "method": 1, "params": "bar"}"#,
+    r#"{"jsonrpc": "2.0", "result": {"é": "ü"}, "id": 1}"#,
+    r#"{"jsonrpc": "2.0", "error": {"code": -32600, "message": "Invalid Request"}, "id": null}"#,
+    r#"[{"jsonrpc": "2.0", "method": "sum", "params": [1,2,4], "id": "1"}, {"foo": "boo"}]"#,
+    "{\"jsonrpc\": \"2.0\", \"method\": \"a\"} \n {\"jsonrpc\": \"2.0\", \"method\": \"b\"}",
+];
+
+#[test]
+fn test_fuzz_corpus_prefixes() {
+    // Every prefix of every sample, which covers truncated and unbalanced input.
+    for sample in CORPUS {
+        let data = sample.as_bytes();
+        for end in 0..=data.len() {
+            jrpc::fuzz::parse_never_panics(&data[..end]);
+            jrpc::fuzz::roundtrip(&data[..end]);
+        }
+    }
+}
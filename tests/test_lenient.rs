@@ -0,0 +1,34 @@
+#![cfg(feature = "lenient")]
+extern crate jrpc;
+
+use jrpc::lenient::normalize;
+
+#[test]
+fn test_normalize_strings() {
+    // Comment markers and trailing commas inside strings are left alone.
+    let json = r#"["a,]", "\"// still a string", "/* nope */",]"#;
+    assert_eq!(
+        normalize(json),
+        r#"["a,]", "\"// still a string", "/* nope */" ]"#
+    );
+}
+
+#[test]
+fn test_normalize_keeps_lines() {
+    let json = "{\n  /* one\n  two */ \"a\": 1, // trailing\n}";
+    let normalized = normalize(json);
+    assert_eq!(normalized.len(), json.len());
+    assert_eq!(normalized.lines().count(), json.lines().count());
+    assert_eq!(
+        jrpc::lenient::from_str::<jrpc::Value>(json).unwrap()["a"],
+        1
+    );
+}
+
+#[test]
+fn test_normalize_unterminated() {
+    // Left for the parser to report.
+    let json = "[1, /* 2";
+    assert_eq!(normalize(json), json);
+    assert!(jrpc::lenient::from_str::<jrpc::Value>(json).is_err());
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+extern crate serde_json;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+use jrpc::merkle::{self, InclusionProof};
+
+fn hash(data: &[u8]) -> Vec<u8> {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(data);
+    hasher.finish().to_be_bytes().to_vec()
+}
+
+#[test]
+fn test_merkle_proofs() {
+    for size in 1..20 {
+        let batch: Vec<u64> = (0..size).collect();
+        let root = merkle::root(&hash, &batch);
+        for index in 0..batch.len() {
+            let proof = merkle::prove(&hash, &batch, index).unwrap();
+            assert!(
+                proof.verify(&hash, &batch[index], &root),
+                "{}/{}",
+                index,
+                size
+            );
+            let other = (index + 1) % batch.len();
+            if other != index {
+                assert!(!proof.verify(&hash, &batch[other], &root));
+            }
+        }
+        assert!(merkle::prove(&hash, &batch, batch.len()).is_none());
+    }
+}
+
+#[test]
+fn test_merkle_proof_serde() {
+    let batch = vec!["a", "b", "c"];
+    let root = merkle::root(&hash, &batch);
+    let proof = merkle::prove(&hash, &batch, 2).unwrap();
+
+    let json = serde_json::to_string(&proof).unwrap();
+    let proof: InclusionProof = serde_json::from_str(&json).unwrap();
+    assert!(proof.verify(&hash, &"c", &root));
+
+    let mut wrong_size = proof.clone();
+    wrong_size.size = 4;
+    assert!(!wrong_size.verify(&hash, &"c", &root));
+}
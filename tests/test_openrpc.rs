@@ -0,0 +1,155 @@
+#![cfg(feature = "openrpc")]
+
+extern crate jrpc;
+#[cfg(feature = "schemars")]
+extern crate schemars;
+#[macro_use]
+extern crate serde_json;
+
+use jrpc::catalog::{ErrorCatalog, ErrorDoc, MethodDoc};
+use jrpc::openrpc::{ContentDescriptor, Document, ParamStructure, ServiceDescription};
+use jrpc::router::Router;
+use jrpc::Value;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+
+#[test]
+fn test_openrpc_params() {
+    let service = ServiceDescription::new("Calculator", "2.1.0")
+        .method(
+            MethodDoc::new("subtract")
+                .params_schema(json!({
+                    "type": "array",
+                    "prefixItems": [{"type": "integer"}, {"type": "integer"}],
+                }))
+                .result_schema(json!({"type": "integer"})),
+        )
+        .method(MethodDoc::new("mean").params_schema(json!({"type": "array"})))
+        .method(MethodDoc::new("ping"));
+    let document = service.document();
+    assert_eq!(document.openrpc, "1.2.6");
+
+    let subtract = &document.methods[0];
+    assert_eq!(subtract.param_structure, ParamStructure::ByPosition);
+    assert_eq!(
+        subtract.params,
+        vec![
+            ContentDescriptor::new("arg0", json!({"type": "integer"}), true),
+            ContentDescriptor::new("arg1", json!({"type": "integer"}), true),
+        ]
+    );
+    assert_eq!(subtract.result.schema, json!({"type": "integer"}));
+
+    let mean = &document.methods[1];
+    assert_eq!(mean.param_structure, ParamStructure::Either);
+    assert_eq!(
+        mean.params,
+        vec![ContentDescriptor::new(
+            "params",
+            json!({"type": "array"}),
+            true
+        )]
+    );
+    // Anything goes without a result schema.
+    assert_eq!(mean.result.schema, json!(true));
+
+    let ping = &document.methods[2];
+    assert!(ping.params.is_empty());
+}
+
+#[cfg(feature = "schemars")]
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Withdraw {
+    amount: u32,
+    memo: Option<String>,
+    account: Account,
+}
+
+#[cfg(feature = "schemars")]
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct Account {
+    number: String,
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_openrpc_schemars() {
+    let service = ServiceDescription::new("Bank", "1.0.0")
+        .method(
+            MethodDoc::new("withdraw")
+                .params_schema_for::<Withdraw>()
+                .result_schema_for::<bool>(),
+        )
+        .method(MethodDoc::new("subtract").params_schema_for::<(i64, i64)>());
+    let document = service.document();
+
+    let withdraw = &document.methods[0];
+    assert_eq!(withdraw.param_structure, ParamStructure::ByName);
+    let names: Vec<_> = withdraw.params.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["account", "amount", "memo"]);
+    let required: Vec<_> = withdraw.params.iter().map(|p| p.required).collect();
+    assert_eq!(required, vec![true, true, false]);
+    // The schema of the Account is inlined, not a reference to `$defs`.
+    assert_eq!(
+        withdraw.params[0].schema["properties"]["number"]["type"],
+        "string"
+    );
+    assert_eq!(withdraw.result.schema["type"], "boolean");
+    assert!(withdraw.result.schema.get("$schema").is_none());
+
+    let subtract = &document.methods[1];
+    assert_eq!(subtract.param_structure, ParamStructure::ByPosition);
+    assert_eq!(subtract.params.len(), 2);
+    assert_eq!(subtract.params[0].schema["type"], "integer");
+}
+
+#[test]
+fn test_openrpc_document() {
+    let catalog = ErrorCatalog::new()
+        .error(ErrorDoc::new(-32001, "Locked", "The account is locked."))
+        .method(
+            MethodDoc::new("withdraw")
+                .params_schema(json!({
+                    "type": "object",
+                    "properties": {"amount": {"type": "integer"}, "memo": {"type": "string"}},
+                    "required": ["amount"],
+                }))
+                .errors(&[-32001, -32002]),
+        );
+    let service = ServiceDescription::from_catalog("Bank", "1.0.0", catalog)
+        .description("Moves money around.");
+
+    let value = serde_json::to_value(service.document()).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "openrpc": "1.2.6",
+            "info": {"title": "Bank", "version": "1.0.0", "description": "Moves money around."},
+            "methods": [{
+                "name": "withdraw",
+                "params": [
+                    {"name": "amount", "schema": {"type": "integer"}, "required": true},
+                    {"name": "memo", "schema": {"type": "string"}},
+                ],
+                "result": {"name": "result", "schema": true},
+                "paramStructure": "by-name",
+                // Undocumented codes are left out.
+                "errors": [{"code": -32001, "message": "The account is locked."}],
+            }],
+        })
+    );
+    let document: Document = serde_json::from_value(value).unwrap();
+    assert_eq!(document, service.document());
+
+    // Served from rpc.discover.
+    let mut router = Router::new();
+    service.register(&mut router);
+    let json = r#"{"jsonrpc": "2.0", "method": "rpc.discover", "id": 1}"#;
+    let response: Value = serde_json::from_str(&router.handle(json).unwrap()).unwrap();
+    assert_eq!(
+        response["result"],
+        serde_json::to_value(service.document()).unwrap()
+    );
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "derive")]
+#[macro_use]
+extern crate serde_json;
+extern crate jrpc;
+
+use jrpc::method::Method;
+use jrpc::params::DualParams;
+use jrpc::{ErrorCode, Id, Params, Value};
+
+#[derive(Debug, PartialEq, Params)]
+struct Transfer {
+    from: String,
+    #[params(rename = "to")]
+    destination: String,
+    #[params(default)]
+    amount: u64,
+    #[params(default = "default_currency")]
+    currency: String,
+    memo: Option<String>,
+}
+
+fn default_currency() -> String {
+    "EUR".into()
+}
+
+fn transfer(from: &str, to: &str, amount: u64, currency: &str) -> Transfer {
+    Transfer {
+        from: from.into(),
+        destination: to.into(),
+        amount,
+        currency: currency.into(),
+        memo: None,
+    }
+}
+
+#[derive(Debug, PartialEq, Params)]
+#[params(positional)]
+struct Point {
+    x: i64,
+    y: i64,
+}
+
+struct TransferMethod;
+
+impl Method for TransferMethod {
+    type Params = Transfer;
+    type Result = ();
+    type ErrorData = Value;
+
+    fn name() -> std::borrow::Cow<'static, str> {
+        "transfer".into()
+    }
+}
+
+fn decode(params: Value) -> Result<Transfer, String> {
+    serde_json::from_value(params).map_err(|err| err.to_string())
+}
+
+#[test]
+fn test_params_positional_and_named() {
+    let expected = transfer("alice", "bob", 10, "USD");
+    assert_eq!(
+        decode(json!(["alice", "bob", 10, "USD"])).unwrap(),
+        expected
+    );
+    assert_eq!(
+        decode(json!({"from": "alice", "to": "bob", "amount": 10, "currency": "USD"})).unwrap(),
+        expected
+    );
+
+    // Defaults, and missing Options are None.
+    let expected = transfer("alice", "bob", 0, "EUR");
+    assert_eq!(decode(json!(["alice", "bob"])).unwrap(), expected);
+    assert_eq!(
+        decode(json!({"from": "alice", "to": "bob"})).unwrap(),
+        expected
+    );
+
+    // Serialized by name.
+    assert_eq!(
+        serde_json::to_value(&expected).unwrap(),
+        json!({"from": "alice", "to": "bob", "amount": 0, "currency": "EUR", "memo": null})
+    );
+}
+
+#[test]
+fn test_params_errors() {
+    assert_eq!(
+        decode(json!({"from": "alice"})).unwrap_err(),
+        "missing field `to`"
+    );
+    assert_eq!(decode(json!(["alice"])).unwrap_err(), "missing field `to`");
+    assert_eq!(
+        decode(json!(["a", "b", 1, "c", "d", "e"])).unwrap_err(),
+        "expected at most 5 positional params, found 6"
+    );
+    assert!(
+        decode(json!({"from": "alice", "to": "bob", "amount": "ten"}))
+            .unwrap_err()
+            .starts_with("invalid field `amount`")
+    );
+    assert!(decode(json!("alice")).is_err());
+
+    let json = r#"{"jsonrpc": "2.0", "method": "transfer", "params": ["alice"], "id": 1}"#;
+    let request = jrpc::parse_request::<String>(json).unwrap();
+    let error = TransferMethod::decode_params(request).unwrap_err();
+    assert_eq!(error.id, Id::from(1));
+    assert_eq!(error.error.code, ErrorCode::InvalidParams);
+    assert_eq!(error.error.message, "missing field `to`");
+}
+
+#[test]
+fn test_params_either_style() {
+    let expected = transfer("alice", "bob", 10, "USD");
+    assert_eq!(
+        Value::from(expected.to_positional().unwrap()),
+        json!(["alice", "bob", 10, "USD", null])
+    );
+    assert_eq!(
+        Value::from(expected.to_named().unwrap()),
+        json!({"from": "alice", "to": "bob", "amount": 10, "currency": "USD", "memo": null})
+    );
+    let positional = Value::from(expected.to_positional().unwrap());
+    assert_eq!(decode(positional).unwrap(), expected);
+
+    // Serialized by position, still decoded from both.
+    let point = Point { x: 1, y: -2 };
+    assert_eq!(serde_json::to_value(&point).unwrap(), json!([1, -2]));
+    assert_eq!(
+        Value::from(point.to_named().unwrap()),
+        json!({"x": 1, "y": -2})
+    );
+    let named: Point = serde_json::from_value(json!({"y": -2, "x": 1})).unwrap();
+    assert_eq!(named, point);
+}
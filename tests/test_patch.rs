@@ -0,0 +1,68 @@
+#![cfg(feature = "json-patch")]
+#[macro_use]
+extern crate serde_json;
+extern crate jrpc;
+
+use jrpc::patch::{self, PatchError, PatchOp};
+use jrpc::Value;
+
+fn ops(value: Value) -> Vec<PatchOp> {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn test_patch_rfc_examples() {
+    // RFC 6902, appendix A.
+    let mut doc = json!({"foo": ["bar", "baz"], "a~b": 1});
+    patch::apply(
+        &mut doc,
+        &ops(json!([
+            {"op": "add", "path": "/foo/1", "value": "qux"},
+            {"op": "add", "path": "/foo/-", "value": "end"},
+            {"op": "remove", "path": "/a~0b"},
+            {"op": "copy", "from": "/foo/0", "path": "/first"},
+            {"op": "move", "from": "/first", "path": "/moved"},
+            {"op": "test", "path": "/moved", "value": "bar"},
+            {"op": "replace", "path": "/foo/0", "value": "BAR"},
+        ])),
+    )
+    .unwrap();
+    assert_eq!(
+        doc,
+        json!({"foo": ["BAR", "qux", "baz", "end"], "moved": "bar"})
+    );
+}
+
+#[test]
+fn test_patch_atomic() {
+    let mut doc = json!({"a": 1});
+    let err = patch::apply(
+        &mut doc,
+        &ops(json!([
+            {"op": "replace", "path": "/a", "value": 2},
+            {"op": "test", "path": "/a", "value": 3},
+        ])),
+    )
+    .unwrap_err();
+    assert_eq!(err, PatchError::TestFailed("/a".into()));
+    assert_eq!(doc, json!({"a": 1}));
+}
+
+#[test]
+fn test_patch_validate() {
+    let err = patch::validate(&ops(json!([
+        {"op": "move", "from": "/a", "path": "/a/b"},
+    ])))
+    .unwrap_err();
+    assert_eq!(err, PatchError::MoveIntoChild("/a/b".into()));
+
+    let err = patch::validate(&ops(json!([{"op": "remove", "path": "a"}]))).unwrap_err();
+    assert_eq!(err, PatchError::InvalidPointer("a".into()));
+
+    let mut doc = json!([1, 2]);
+    let err = patch::apply(
+        &mut doc,
+        &ops(json!([{"op": "add", "path": "/01", "value": 0}])),
+    );
+    assert_eq!(err, Err(PatchError::InvalidIndex("/01".into())));
+}
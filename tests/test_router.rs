@@ -0,0 +1,90 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+extern crate serde_json;
+
+use jrpc::router::Router;
+use jrpc::{ErrorCode, Id, IdReq, Request, Response, Value};
+
+fn router() -> Router {
+    let mut router = Router::new();
+    router
+        .add::<(i64, i64), i64, _>("subtract", |(a, b)| Ok(a - b))
+        .add::<Option<String>, String, _>("greet", |name| {
+            Ok(format!(
+                "hello {}",
+                name.unwrap_or_else(|| "world".to_string())
+            ))
+        });
+    router
+}
+
+fn code(json: &str) -> ErrorCode {
+    let response: Response<Value> = serde_json::from_str(json).unwrap();
+    response.into_result().unwrap_err().code
+}
+
+#[test]
+fn test_router_errors() {
+    let router = router();
+    assert!(router.contains("greet"));
+    assert!(!router.contains("divide"));
+
+    let response = router.handle(r#"{"jsonrpc": "2.0", "method": "#).unwrap();
+    assert_eq!(code(&response), ErrorCode::ParseError);
+    assert_eq!(
+        code(&router.handle("[]").unwrap()),
+        ErrorCode::InvalidRequest
+    );
+    let response = router
+        .handle(r#"{"jsonrpc": "2.0", "method": 1, "id": 1}"#)
+        .unwrap();
+    assert_eq!(code(&response), ErrorCode::InvalidRequest);
+
+    let json = r#"{"jsonrpc": "2.0", "method": "subtract", "params": [1], "id": "a"}"#;
+    let response: Response<Value> = serde_json::from_str(&router.handle(json).unwrap()).unwrap();
+    assert_eq!(response.id(), &Id::from("a".to_string()));
+    assert_eq!(
+        response.into_result().unwrap_err().code,
+        ErrorCode::InvalidParams
+    );
+
+    // Unknown methods are not answered for Notifications either.
+    assert_eq!(
+        router.handle(r#"{"jsonrpc": "2.0", "method": "divide"}"#),
+        None
+    );
+    let json = r#"[{"jsonrpc": "2.0", "method": "greet"}, {"jsonrpc": "2.0", "method": "divide"}]"#;
+    assert_eq!(router.handle(json), None);
+}
+
+#[test]
+fn test_router_requests() {
+    let router = router();
+    let json = r#"[
+        {"jsonrpc": "2.0", "method": "greet", "id": null},
+        1,
+        {"jsonrpc": "2.0", "method": "greet", "params": "you", "id": 2}
+    ]"#;
+    let responses: Vec<Response<Value>> =
+        serde_json::from_str(&router.handle(json).unwrap()).unwrap();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(
+        responses[0],
+        Response::success(Id::Null, Value::from("hello world"))
+    );
+    assert_eq!(
+        code(&serde_json::to_string(&responses[1]).unwrap()),
+        ErrorCode::InvalidRequest
+    );
+    assert_eq!(
+        responses[2],
+        Response::success(Id::from(2), Value::from("hello you"))
+    );
+
+    let request =
+        Request::with_params(Id::from(3), "subtract".to_string(), Value::from(vec![5, 3]));
+    let response = router.handle_request(request).unwrap();
+    assert_eq!(response, Response::success(Id::from(3), Value::from(2)));
+    let request = Request::with_params(IdReq::Notification, "subtract".to_string(), Value::Null);
+    assert_eq!(router.handle_request(request), None);
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "std")]
+#[macro_use]
+extern crate serde_json;
+extern crate jrpc;
+
+use jrpc::catalog::MethodDoc;
+use jrpc::contract::{Contract, ContractError};
+use jrpc::schema::{self, Violation};
+use jrpc::shadow::{Enforcement, ShadowValidator};
+
+fn pointers(violations: Vec<Violation>) -> Vec<String> {
+    violations.into_iter().map(|v| v.pointer).collect()
+}
+
+#[test]
+fn test_schema_keywords() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "tags": {"type": "array", "items": {"type": "string", "maxLength": 3}, "maxItems": 2},
+            "kind": {"enum": ["a", "b"]},
+            "n": {"type": ["integer", "null"], "maximum": 10},
+            "a/b": {"const": 1},
+        },
+        "additionalProperties": false,
+    });
+    assert!(schema::is_valid(
+        &schema,
+        &json!({"tags": ["x"], "kind": "a", "n": null})
+    ));
+    assert!(schema::is_valid(&schema, &json!({"n": 10.0})));
+
+    let value = json!({
+        "tags": ["long", 1, "x"],
+        "kind": "c",
+        "n": 1.5,
+        "a/b": 2,
+        "extra": true,
+    });
+    assert_eq!(
+        pointers(schema::validate(&schema, &value)),
+        vec!["/a~1b", "/extra", "/kind", "/n", "/tags", "/tags/0", "/tags/1"]
+    );
+}
+
+#[test]
+fn test_schema_any_of() {
+    let schema = json!({"anyOf": [{"type": "string"}, {"type": "integer", "minimum": 0}]});
+    assert!(schema::is_valid(&schema, &json!("x")));
+    assert!(schema::is_valid(&schema, &json!(0)));
+    assert!(!schema::is_valid(&schema, &json!(-1)));
+    assert!(schema::is_valid(&json!(true), &json!(-1)));
+    assert!(!schema::is_valid(&json!(false), &json!(null)));
+}
+
+#[test]
+fn test_shadow_bounds() {
+    let contract = Contract::new("x")
+        .method(MethodDoc::new("tags").params_schema(json!({"items": {"type": "string"}})))
+        .method(MethodDoc::new("free"));
+    let mut validator = ShadowValidator::new(contract)
+        .max_samples(3)
+        .max_pointers(2)
+        .seed(7);
+
+    for i in 0..10 {
+        let params = json!(["ok", i, i]);
+        assert!(validator.check_params("tags", &params).is_ok());
+    }
+    assert!(validator.check_params("tags", &json!(["ok"])).is_ok());
+    assert!(validator.check_params("free", &json!(1)).is_ok());
+    assert_eq!(
+        validator.check_params("gone", &json!(1)),
+        Err(ContractError::UnknownMethod("gone".to_string()))
+    );
+
+    let report = validator.report();
+    assert_eq!(report.mode, Enforcement::Shadow);
+    assert_eq!(report.checked(), 12);
+    assert_eq!(report.violating(), 10);
+    let tags = &report.methods["tags"];
+    assert_eq!(tags.pointers["/1"], 10);
+    assert_eq!(tags.pointers["/2"], 10);
+    assert_eq!(tags.other_pointers, 0);
+    assert_eq!(tags.samples.len(), 3);
+    assert!((tags.violation_rate() - 10.0 / 11.0).abs() < 1e-9);
+    assert_eq!(report.methods["free"].violating, 0);
+    assert!(!report.methods.contains_key("gone"));
+
+    assert!(validator.check_params("tags", &json!([1, 2, 3])).is_ok());
+    assert_eq!(validator.report().methods["tags"].other_pointers, 1);
+
+    validator.reset();
+    assert!(validator.report().methods.is_empty());
+}
+
+#[test]
+fn test_examples_match_schema() {
+    use jrpc::examples;
+
+    let schemas = vec![
+        json!({"type": "object", "properties": {
+            "name": {"type": "string", "minLength": 8, "maxLength": 10},
+            "tags": {"type": "array", "items": {"type": "string"}, "minItems": 2},
+            "limit": {"type": ["null", "integer"], "minimum": 2.5, "maximum": 9},
+            "ratio": {"type": "number", "maximum": 0.5},
+            "kind": {"enum": ["a", "b"]},
+            "either": {"anyOf": [{"type": "boolean"}, {"type": "string"}]},
+        }, "required": ["name", "tags"]}),
+        json!({"type": "array", "items": {"type": "integer", "minimum": -3}, "maxItems": 0}),
+        json!(true),
+    ];
+    for schema in &schemas {
+        let example = examples::example(schema);
+        assert_eq!(schema::validate(schema, &example), vec![], "{}", example);
+    }
+    assert_eq!(examples::example(&schemas[0])["limit"], json!(3));
+}
+
+#[test]
+fn test_examples_are_bounded() {
+    use jrpc::examples::{self, MAX_EXAMPLE_LEN};
+
+    let huge = u64::MAX;
+    let example = examples::example(&json!({"type": "string", "minLength": huge}));
+    assert_eq!(example.as_str().unwrap().len(), MAX_EXAMPLE_LEN);
+    let example = examples::example(&json!({"type": "string", "minLength": 10}));
+    assert_eq!(example, json!("stringxxxx"));
+
+    let schema = json!({
+        "type": "array",
+        "minItems": huge,
+        "items": {"type": "array", "minItems": huge, "items": {"type": "string"}},
+    });
+    let example = examples::example(&schema);
+    assert_eq!(example.as_array().unwrap().len(), MAX_EXAMPLE_LEN);
+    assert_eq!(example[0].as_array().unwrap().len(), MAX_EXAMPLE_LEN);
+}
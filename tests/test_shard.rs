@@ -0,0 +1,34 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+
+use jrpc::shard::ShardRouter;
+
+fn router(shards: &[&str]) -> ShardRouter {
+    let mut router = ShardRouter::pointer("/0");
+    for shard in shards {
+        router.add_shard(shard);
+    }
+    router
+}
+
+#[test]
+fn test_shard_consistent_hashing() {
+    let keys: Vec<String> = (0..2000).map(|i| format!("key-{}", i)).collect();
+    let all = router(&["a", "b", "c", "d"]);
+    let without_c = router(&["a", "b", "d"]);
+
+    let mut counts = [0; 4];
+    for key in &keys {
+        let before = all.shard_for(key).unwrap();
+        let after = without_c.shard_for(key).unwrap();
+        // Only the keys of the removed shard move.
+        if before != "c" {
+            assert_eq!(before, after, "{} moved", key);
+        }
+        counts[(before.as_bytes()[0] - b'a') as usize] += 1;
+    }
+    // Each shard gets a reasonable share of the keys.
+    for count in &counts {
+        assert!(*count > 250 && *count < 750, "{:?}", counts);
+    }
+}
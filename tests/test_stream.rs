@@ -0,0 +1,147 @@
+#![cfg(feature = "async")]
+
+extern crate futures_core;
+extern crate futures_sink;
+extern crate jrpc;
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use jrpc::stream::{self, NotificationStream, Overflow, SinkError, LAGGED};
+use jrpc::{ErrorCode, ErrorObject, Value};
+
+fn next(stream: &mut NotificationStream<u32>) -> Poll<Option<Result<u32, ErrorObject<Value>>>> {
+    let mut cx = Context::from_waker(Waker::noop());
+    Pin::new(stream).poll_next(&mut cx)
+}
+
+#[test]
+fn test_stream_ends_with_publisher() {
+    let (publisher, mut stream) = stream::subscription::<u32>(4, Overflow::DropOldest);
+    publisher.push(Value::from(1));
+    publisher.push(Value::from("not a number"));
+    drop(publisher);
+
+    match next(&mut stream) {
+        Poll::Ready(Some(Ok(1))) => {}
+        other => panic!("{:?}", other),
+    }
+    match next(&mut stream) {
+        Poll::Ready(Some(Err(error))) => assert_eq!(error.code, ErrorCode::InvalidParams),
+        other => panic!("{:?}", other),
+    }
+    match next(&mut stream) {
+        Poll::Ready(None) => {}
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn test_stream_overflow() {
+    let (publisher, mut stream) = stream::subscription::<u32>(1, Overflow::DropNewest);
+    publisher.push(Value::from(1));
+    publisher.push(Value::from(2));
+    match next(&mut stream) {
+        Poll::Ready(Some(Err(error))) => assert_eq!(error.code, ErrorCode::ServerError(LAGGED)),
+        other => panic!("{:?}", other),
+    }
+    match next(&mut stream) {
+        Poll::Ready(Some(Ok(1))) => {}
+        other => panic!("{:?}", other),
+    }
+
+    let (publisher, mut stream) = stream::subscription::<u32>(1, Overflow::Close);
+    publisher.push(Value::from(1));
+    publisher.push(Value::from(2));
+    assert!(!publisher.push(Value::from(3)));
+    match next(&mut stream) {
+        Poll::Ready(Some(Err(error))) => assert_eq!(error.data, Some(Value::from(1))),
+        other => panic!("{:?}", other),
+    }
+    match next(&mut stream) {
+        Poll::Ready(None) => {}
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn test_stream_unsubscribe_on_drop() {
+    let unsubscribed = Arc::new(AtomicBool::new(false));
+    let flag = unsubscribed.clone();
+    let (publisher, stream) = stream::subscription::<u32>(1, Overflow::DropOldest);
+    let stream = stream.on_drop(move || flag.store(true, Ordering::SeqCst));
+    assert!(publisher.is_subscribed());
+    drop(stream);
+    assert!(unsubscribed.load(Ordering::SeqCst));
+    assert!(!publisher.is_subscribed());
+}
+
+#[test]
+fn test_sink_batches() {
+    let mut cx = Context::from_waker(Waker::noop());
+    let (mut sink, frames) = stream::sink::<u32>("tick", 2, 1);
+
+    assert!(Pin::new(&mut sink).poll_ready(&mut cx).is_ready());
+    Pin::new(&mut sink).start_send(1).unwrap();
+    assert_eq!(frames.next_frame(), None);
+    Pin::new(&mut sink).start_send(2).unwrap();
+
+    // One frame waits for the writer: not ready.
+    assert!(Pin::new(&mut sink).poll_ready(&mut cx).is_pending());
+    assert_eq!(
+        frames.next_frame().unwrap(),
+        r#"[{"jsonrpc":"2.0","method":"tick","params":1,"id":null},{"jsonrpc":"2.0","method":"tick","params":2,"id":null}]"#
+    );
+    assert!(Pin::new(&mut sink).poll_ready(&mut cx).is_ready());
+
+    // A flush sends a partial batch.
+    Pin::new(&mut sink).start_send(3).unwrap();
+    assert!(Pin::new(&mut sink).poll_flush(&mut cx).is_pending());
+    assert_eq!(
+        frames.next_frame().unwrap(),
+        r#"{"jsonrpc":"2.0","method":"tick","params":3,"id":null}"#
+    );
+    match Pin::new(&mut sink).poll_close(&mut cx) {
+        Poll::Ready(Ok(())) => {}
+        other => panic!("{:?}", other),
+    }
+    assert!(frames.is_finished());
+    assert_eq!(Pin::new(&mut sink).start_send(4), Err(SinkError::Closed));
+
+    drop(frames);
+    match Pin::new(&mut sink).poll_ready(&mut cx) {
+        Poll::Ready(Err(SinkError::Disconnected)) => {}
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn test_futures_traits() {
+    // Driven through the traits only, as by the combinators of `futures`.
+    fn next<S: Stream + Unpin>(stream: &mut S) -> Poll<Option<S::Item>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        Stream::poll_next(Pin::new(stream), &mut cx)
+    }
+    fn send<S: Sink<u32> + Unpin>(sink: &mut S, item: u32) -> Poll<Result<(), S::Error>> {
+        let mut cx = Context::from_waker(Waker::noop());
+        Sink::start_send(Pin::new(&mut *sink), item)?;
+        Sink::poll_flush(Pin::new(sink), &mut cx)
+    }
+
+    let (publisher, mut stream) = stream::subscription::<u32>(4, Overflow::DropOldest);
+    publisher.push(Value::from(1));
+    drop(publisher);
+    assert_eq!(next(&mut stream), Poll::Ready(Some(Ok(1))));
+    assert_eq!(next(&mut stream), Poll::Ready(None));
+
+    let (mut sink, frames) = stream::sink::<u32>("tick", 2, 1);
+    assert!(send(&mut sink, 1).is_pending());
+    assert_eq!(
+        frames.next_frame().unwrap(),
+        r#"{"jsonrpc":"2.0","method":"tick","params":1,"id":null}"#
+    );
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "std")]
+#[macro_use]
+extern crate serde_json;
+extern crate jrpc;
+
+use jrpc::catalog::MethodDoc;
+use jrpc::surface::{ApiSurface, Break, Bump};
+
+fn surface(params: serde_json::Value, result: serde_json::Value) -> ApiSurface {
+    ApiSurface::new("x").error(-32001).method(
+        MethodDoc::new("m")
+            .params_schema(params)
+            .result_schema(result),
+    )
+}
+
+fn reasons(breaks: Vec<Break>) -> Vec<String> {
+    breaks.iter().map(|b| b.to_string()).collect()
+}
+
+#[test]
+fn test_surface_params() {
+    let old = surface(
+        json!({"type": "object", "properties": {"n": {"type": "integer", "maximum": 10}, "k": {"enum": ["a", "b"]}}}),
+        json!(true),
+    );
+
+    // Widening params is compatible.
+    let new = surface(
+        json!({"type": ["object", "null"], "properties": {"n": {"type": "number"}, "k": {"enum": ["a", "b", "c"]}}}),
+        json!(true),
+    );
+    assert!(new.compatible_with(&old).is_empty());
+    assert_eq!(new.required_bump(&old), Bump::Minor);
+    assert_eq!(old.required_bump(&old), Bump::Patch);
+
+    let new = surface(
+        json!({"type": "object", "properties": {"n": {"type": "integer", "maximum": 5}, "k": {"enum": ["a"]}}, "additionalProperties": false}),
+        json!(true),
+    );
+    assert_eq!(
+        reasons(new.compatible_with(&old)),
+        vec![
+            "params of `m` narrowed: additional members are no longer accepted",
+            "params of `m` narrowed at /k: value \"b\" no longer accepted",
+            "params of `m` narrowed at /n: maximum tightened to 5",
+        ]
+    );
+}
+
+#[test]
+fn test_surface_results_and_errors() {
+    let old = surface(
+        json!(true),
+        json!({"type": "object", "required": ["id"], "properties": {"id": {"type": "string"}}}),
+    );
+    let new = ApiSurface::new("y").method(MethodDoc::new("m").result_schema(
+        json!({"type": "object", "properties": {"id": {"type": ["string", "null"]}}}),
+    ));
+    let breaks = new.compatible_with(&old);
+    assert_eq!(
+        reasons(breaks.clone()),
+        vec![
+            "result of `m` changed: member `id` is now required",
+            "result of `m` changed at /id: type null no longer accepted",
+            "error code -32001 removed",
+        ]
+    );
+    assert_eq!(new.required_bump(&old), Bump::Major);
+    assert_eq!(
+        serde_json::to_value(&breaks[2]).unwrap(),
+        json!({"kind": "error_removed", "code": -32001})
+    );
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "v1-compat")]
+extern crate jrpc;
+extern crate serde_json;
+
+use jrpc::negotiate::{Version, VersionSniffer};
+use jrpc::{v1, Id, Value};
+
+#[test]
+fn test_version_serde() {
+    assert_eq!(serde_json::to_string(&Version::V1_0).unwrap(), r#""1.0""#);
+    let version: Version = serde_json::from_str(r#""2.0""#).unwrap();
+    assert_eq!(version, Version::default());
+    assert_eq!(Version::from(jrpc::V2_0), Version::V2_0);
+    assert_eq!(Version::V1_0.to_string(), "1.0");
+    assert!(serde_json::from_str::<Version>(r#""1.1""#).is_err());
+}
+
+#[test]
+fn test_v1_roundtrip() {
+    let json = r#"{"method":"echo","params":["Hello"],"id":null}"#;
+    let request: v1::Request<String, Value> = serde_json::from_str(json).unwrap();
+    assert!(request.is_notification());
+    assert_eq!(serde_json::to_string(&request).unwrap(), json);
+    assert_eq!(
+        VersionSniffer::sniff(&serde_json::from_str(json).unwrap()),
+        Some(Version::V1_0)
+    );
+
+    let json = r#"{"result":"Hello","error":null,"id":1}"#;
+    let response: v1::Response<String> = serde_json::from_str(json).unwrap();
+    assert_eq!(response.id, Id::from(1));
+    assert_eq!(serde_json::to_string(&response).unwrap(), json);
+
+    let json = r#"{"result":null,"error":"busy","id":2}"#;
+    let response: v1::Response<String, String> = serde_json::from_str(json).unwrap();
+    assert_eq!(serde_json::to_string(&response).unwrap(), json);
+    assert_eq!(response.into_result(), Err(Some("busy".to_string())));
+}
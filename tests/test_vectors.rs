@@ -0,0 +1,34 @@
+#![cfg(feature = "std")]
+extern crate jrpc;
+extern crate serde_json;
+
+use jrpc::vectors::{self, Corpus, Expected, Kind, Vector};
+
+#[test]
+fn test_corpus_directories_match_outcomes() {
+    let corpus = vectors::corpus();
+    for (path, vector) in &corpus.files {
+        match vector.expected {
+            Expected::Accepted { .. } => assert!(path.contains("/valid/"), "{}", path),
+            Expected::Rejected { code } => {
+                assert!(path.contains("/invalid/"), "{}", path);
+                assert_eq!(code.is_some(), vector.kind == Kind::Request, "{}", path);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_corpus_roundtrip() {
+    let corpus = vectors::corpus();
+    let json = serde_json::to_string(&corpus).unwrap();
+    let read: Corpus = serde_json::from_str(&json).unwrap();
+    assert_eq!(read, corpus);
+
+    // A vector recorded with a stale outcome is reported.
+    let mut stale = Corpus::new();
+    let mut vector = Vector::new(Kind::Request, r#"{"jsonrpc": "2.0", "method": "a", "id": 1}"#);
+    vector.expected = Expected::Rejected { code: Some(-32600) };
+    stale.insert("request/stale.json", vector);
+    assert_eq!(stale.check(), vec!["request/stale.json"]);
+}